@@ -23,7 +23,8 @@ async fn make_insert(client: &Client, data: &[SomeRow]) -> Result<()> {
     for row in data {
         insert.write(row).await?;
     }
-    insert.end().await
+    insert.end().await?;
+    Ok(())
 }
 
 #[tokio::main]
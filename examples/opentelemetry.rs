@@ -16,13 +16,12 @@ struct Foo {
 
 #[tokio::main]
 async fn main() {
-    // The `TraceContextPropagator` is responsible for generating a trace ID
-    // that will then be forwarded to ClickHouse server for distributed tracing:
+    // The `TraceContextPropagator` is responsible for adding the `traceparent` and `tracestate`
+    // headers to requests to the ClickHouse HTTP interface for distributed tracing:
     // https://clickhouse.com/docs/operations/opentelemetry
     //
-    // Without this, OpenTelemetry spans will still be generated, but the `clickhouse` crate
-    // will not be able to add the `traceparent` and `tracestate` headers to requests
-    // to the ClickHouse HTTP interface.
+    // Without this, the `clickhouse` crate falls back to the same W3C Trace Context headers,
+    // but other libraries used by the application may not.
     opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
 
     // Configure export of OTLP spans over HTTP.
@@ -34,7 +34,8 @@ async fn insert(client: &Client) -> Result<()> {
         insert.write(&MyRow { no: i, name: "foo" }).await?;
     }
 
-    insert.end().await
+    insert.end().await?;
+    Ok(())
 }
 
 // This is a very basic example of using the `inserter` feature.
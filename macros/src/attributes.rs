@@ -2,6 +2,8 @@ use syn::meta::ParseNestedMeta;
 
 pub struct Attributes {
     pub crate_path: syn::Path,
+    /// `#[clickhouse(nested)]`
+    pub nested: bool,
 }
 
 impl Default for Attributes {
@@ -11,6 +13,7 @@ impl Default for Attributes {
             // it's possible that the user has renamed the `clickhouse` package,
             // but then aliased it back to `clickhouse` to fix the derive.
             crate_path: syn::parse_str("clickhouse").expect("BUG: crate_path should parse"),
+            nested: false,
         }
     }
 }
@@ -19,17 +22,15 @@ impl TryFrom<&[syn::Attribute]> for Attributes {
     type Error = syn::Error;
 
     fn try_from(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut out = Attributes::default();
+
         for attr in attrs {
             if attr.path().is_ident("clickhouse") {
-                let mut out = Attributes::default();
-
                 attr.parse_nested_meta(|meta| parse_nested_meta(meta, &mut out))?;
-
-                return Ok(out);
             }
         }
 
-        Ok(Self::default())
+        Ok(out)
     }
 }
 
@@ -44,9 +45,73 @@ fn parse_nested_meta(meta: ParseNestedMeta<'_>, out: &mut Attributes) -> syn::Re
             .parse::<syn::LitStr>()?
             // Parse the literal content as `Path`
             .parse()?;
+    }
+    // #[clickhouse(nested)]
+    else if meta.path.is_ident("nested") {
+        out.nested = true;
     } else {
         return Err(meta.error("unexpected `#[clickhouse(...)]` argument"));
     }
 
     Ok(())
 }
+
+/// Attributes of a single field: `#[clickhouse(...)]`.
+#[derive(Default)]
+pub struct FieldAttributes {
+    /// `#[clickhouse(rename = "<name>")]`
+    pub rename: Option<String>,
+    /// `#[clickhouse(skip_insert)]`
+    pub skip_insert: bool,
+    /// `#[clickhouse(nested)]`
+    pub nested: bool,
+    /// `#[clickhouse(flatten)]`
+    pub flatten: bool,
+}
+
+impl TryFrom<&[syn::Attribute]> for FieldAttributes {
+    type Error = syn::Error;
+
+    fn try_from(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut out = FieldAttributes::default();
+
+        for attr in attrs {
+            if attr.path().is_ident("clickhouse") {
+                attr.parse_nested_meta(|meta| parse_field_nested_meta(meta, &mut out))?;
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// Called for each meta-item inside the `#[clickhouse(...)]` attribute of a field.
+fn parse_field_nested_meta(
+    meta: ParseNestedMeta<'_>,
+    out: &mut FieldAttributes,
+) -> syn::Result<()> {
+    // #[clickhouse(rename = "<name>")]
+    if meta.path.is_ident("rename") {
+        let name = meta.value()?.parse::<syn::LitStr>()?;
+        if out.rename.is_some() {
+            return Err(syn::Error::new(name.span(), "duplicate `rename` argument"));
+        }
+        out.rename = Some(name.value());
+    }
+    // #[clickhouse(skip_insert)]
+    else if meta.path.is_ident("skip_insert") {
+        out.skip_insert = true;
+    }
+    // #[clickhouse(nested)]
+    else if meta.path.is_ident("nested") {
+        out.nested = true;
+    }
+    // #[clickhouse(flatten)]
+    else if meta.path.is_ident("flatten") {
+        out.flatten = true;
+    } else {
+        return Err(meta.error("unexpected `#[clickhouse(...)]` field argument"));
+    }
+
+    Ok(())
+}
@@ -1,19 +1,19 @@
-use crate::attributes::Attributes;
-use proc_macro2::{Span, TokenStream};
+use crate::attributes::{Attributes, FieldAttributes};
+use proc_macro2::{Span, TokenStream, TokenTree};
 use quote::quote;
 use serde_derive_internals::{
     Ctxt,
     attr::{Container, Default as SerdeDefault, Field},
 };
-use syn::{Data, DataStruct, DeriveInput, Error, Fields, Lifetime, Result, parse_macro_input};
+use syn::{
+    Data, DataStruct, DeriveInput, Error, Fields, GenericParam, Lifetime, Result, parse_macro_input,
+};
 
 mod attributes;
 
 #[cfg(test)]
 mod tests;
 
-// TODO: support wrappers `Wrapper(Inner)` and `Wrapper<T>(T)`.
-// TODO: support the `nested` attribute.
 #[proc_macro_derive(Row, attributes(clickhouse))]
 pub fn row(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -22,61 +22,490 @@ pub fn row(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         .into()
 }
 
-fn column_names(data: &DataStruct, cx: &Ctxt, container: &Container) -> Result<TokenStream> {
+struct ColumnNames {
+    /// All columns, used for both fetching and inserting.
+    all: TokenStream,
+    /// Only filled if some fields are marked with `#[clickhouse(skip_insert)]`.
+    insert: Option<InsertColumnNames>,
+    /// Columns of fields marked with `#[serde(default)]`, if any.
+    defaulted: Option<TokenStream>,
+    /// Whether names of some columns differ from serde names of fields.
+    remapped: Option<TokenStream>,
+    /// Types of `#[clickhouse(flatten)]` fields.
+    flattened: Vec<syn::Type>,
+    /// The inner type of a newtype `Wrapper(Inner)`, which all properties
+    /// of the row are delegated to.
+    newtype: Option<syn::Type>,
+}
+
+struct InsertColumnNames {
+    names: TokenStream,
+    /// Serde names of skipped fields.
+    skipped_fields: TokenStream,
+}
+
+#[derive(Clone)]
+enum ColumnNamesPart<'a> {
+    Plain(String),
+    /// `#[clickhouse(nested)]`: a prefix and the field type.
+    Nested(String, &'a syn::Type),
+    /// `#[clickhouse(flatten)]`: the field type.
+    Flatten(&'a syn::Type),
+}
+
+fn render_column_names(parts: &[ColumnNamesPart<'_>], crate_path: &syn::Path) -> TokenStream {
+    let has_plain_only = parts
+        .iter()
+        .all(|part| matches!(part, ColumnNamesPart::Plain(..)));
+
+    if has_plain_only {
+        let names = parts.iter().map(|part| match part {
+            ColumnNamesPart::Plain(name) => name,
+            _ => unreachable!(),
+        });
+        return quote! { &[#( #names,)*] };
+    }
+
+    // Names of nested and flattened columns are known only to their types,
+    // so the list is built by const evaluation.
+    let parts = parts.iter().map(|part| match part {
+        ColumnNamesPart::Plain(name) => {
+            quote! { #crate_path::_priv::ColumnNamesPart::Plain(#name) }
+        }
+        ColumnNamesPart::Nested(prefix, ty) => quote! {
+            #crate_path::_priv::ColumnNamesPart::Nested(
+                #prefix,
+                <#ty as #crate_path::_priv::NestedColumns>::COLUMN_NAMES,
+            )
+        },
+        ColumnNamesPart::Flatten(ty) => quote! {
+            #crate_path::_priv::ColumnNamesPart::Flatten(
+                <#ty as #crate_path::Row>::COLUMN_NAMES,
+            )
+        },
+    });
+
+    quote! {{
+        const PARTS: &[#crate_path::_priv::ColumnNamesPart] = &[#( #parts,)*];
+        const COUNT: usize = #crate_path::_priv::column_count(PARTS);
+        const LEN: usize = #crate_path::_priv::column_names_len(PARTS);
+        const BUF: &[u8] = &#crate_path::_priv::concat_column_names::<LEN>(PARTS);
+        const NAMES: &[&str] = &#crate_path::_priv::split_column_names::<COUNT>(BUF, PARTS);
+        NAMES
+    }}
+}
+
+fn column_names(
+    input: &DeriveInput,
+    data: &DataStruct,
+    cx: &Ctxt,
+    container: &Container,
+    crate_path: &syn::Path,
+) -> Result<ColumnNames> {
     Ok(match &data.fields {
         Fields::Named(fields) => {
-            let rename_rule = container.rename_all_rules().deserialize;
-            let column_names_iter = fields
-                .named
-                .iter()
-                .enumerate()
-                .map(|(index, field)| Field::from_ast(cx, index, field, None, &SerdeDefault::None))
-                .filter(|field| !field.skip_serializing() && !field.skip_deserializing())
-                .map(|field| {
-                    rename_rule
-                        .apply_to_field(field.name().serialize_name())
-                        .to_string()
-                });
-
-            quote! {
-                &[#( #column_names_iter,)*]
+            let mut all = Vec::with_capacity(fields.named.len());
+            let mut insert = Vec::with_capacity(fields.named.len());
+            let mut skipped_fields = Vec::new();
+            let mut defaulted = Vec::new();
+            let mut flattened = Vec::new();
+            let mut remapped = false;
+
+            for (index, ast) in fields.named.iter().enumerate() {
+                let mut field = Field::from_ast(cx, index, ast, None, &SerdeDefault::None);
+                if field.skip_serializing() || field.skip_deserializing() {
+                    continue;
+                }
+
+                // Not applied to fields renamed explicitly by `#[serde(rename)]`.
+                field.rename_by_rules(container.rename_all_rules());
+
+                if field.flatten() {
+                    let reason = "`#[serde(flatten)]` is not supported, \
+                                  consider `#[clickhouse(flatten)]` for columns of another `Row` \
+                                  or `#[clickhouse(nested)]` for `Nested` columns";
+                    return Err(Error::new_spanned(ast, reason));
+                }
+
+                let FieldAttributes {
+                    rename,
+                    skip_insert,
+                    nested,
+                    flatten,
+                } = ast.attrs[..].try_into()?;
+
+                let is_renamed = rename.is_some();
+                let column_name =
+                    rename.unwrap_or_else(|| field.name().deserialize_name().to_string());
+                remapped |= flatten || nested || column_name != field.name().deserialize_name();
+
+                let part = if flatten {
+                    check_flatten_field(input, ast, &field, is_renamed, nested)?;
+                    flattened.push(ast.ty.clone());
+                    ColumnNamesPart::Flatten(&ast.ty)
+                } else if nested {
+                    check_nested_field(input, ast, &field)?;
+                    ColumnNamesPart::Nested(column_name, &ast.ty)
+                } else {
+                    // Filled by serde if the column is absent in fetched rows.
+                    if !matches!(field.default(), SerdeDefault::None)
+                        || !matches!(container.default(), SerdeDefault::None)
+                    {
+                        defaulted.push(column_name.clone());
+                    }
+                    ColumnNamesPart::Plain(column_name)
+                };
+
+                if skip_insert {
+                    skipped_fields.push(field.name().serialize_name().to_string());
+                } else {
+                    insert.push(part.clone());
+                }
+
+                all.push(part);
+            }
+
+            ColumnNames {
+                all: render_column_names(&all, crate_path),
+                insert: (!skipped_fields.is_empty()).then(|| InsertColumnNames {
+                    names: render_column_names(&insert, crate_path),
+                    skipped_fields: quote! { &[#( #skipped_fields,)*] },
+                }),
+                defaulted: (!defaulted.is_empty()).then(|| quote! { &[#( #defaulted,)*] }),
+                remapped: remapped.then(|| quote! { true }),
+                flattened,
+                newtype: None,
             }
         }
-        Fields::Unnamed(_) => {
-            quote! { &[] }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let ast = &fields.unnamed[0];
+            if ast
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("clickhouse"))
+            {
+                let reason = "`#[clickhouse(...)]` attributes are not supported for newtypes";
+                return Err(Error::new_spanned(ast, reason));
+            }
+
+            let ty = &ast.ty;
+            ColumnNames {
+                all: quote! { <#ty as #crate_path::Row>::COLUMN_NAMES },
+                insert: Some(InsertColumnNames {
+                    names: quote! { <#ty as #crate_path::Row>::INSERT_COLUMN_NAMES },
+                    skipped_fields: quote! { <#ty as #crate_path::Row>::INSERT_SKIPPED_FIELDS },
+                }),
+                defaulted: Some(quote! { <#ty as #crate_path::Row>::DEFAULTED_COLUMNS }),
+                remapped: Some(quote! { <#ty as #crate_path::Row>::REMAPPED_COLUMNS }),
+                flattened: Vec::new(),
+                newtype: Some(ty.clone()),
+            }
         }
+        Fields::Unnamed(_) => ColumnNames {
+            all: quote! { &[] },
+            insert: None,
+            defaulted: None,
+            remapped: None,
+            flattened: Vec::new(),
+            newtype: None,
+        },
         Fields::Unit => unreachable!("checked by the caller"),
     })
 }
 
+/// Checks whether the type mentions any parameter of `generics`.
+///
+/// Column names of nested and flattened fields are built by const items,
+/// which cannot use generic parameters of the outer impl.
+fn uses_generic_params(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    fn visit(tokens: TokenStream, params: &[syn::Ident]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => params.contains(&ident),
+            TokenTree::Group(group) => visit(group.stream(), params),
+            TokenTree::Punct(_) | TokenTree::Literal(_) => false,
+        })
+    }
+
+    let params = generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Type(param) => param.ident.clone(),
+            GenericParam::Const(param) => param.ident.clone(),
+            GenericParam::Lifetime(param) => param.lifetime.ident.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    visit(quote! { #ty }, &params)
+}
+
+fn check_nested_field(input: &DeriveInput, ast: &syn::Field, field: &Field) -> Result<()> {
+    if uses_generic_params(&ast.ty, &input.generics) {
+        let reason = "`#[clickhouse(nested)]` fields cannot depend on generic parameters";
+        return Err(Error::new_spanned(ast, reason));
+    }
+
+    if field.serialize_with().is_none() || field.deserialize_with().is_none() {
+        let reason = "`#[clickhouse(nested)]` fields must be also marked with \
+                      `#[serde(with = \"clickhouse::serde::nested\")]`";
+        return Err(Error::new_spanned(ast, reason));
+    }
+
+    Ok(())
+}
+
+fn check_flatten_field(
+    input: &DeriveInput,
+    ast: &syn::Field,
+    field: &Field,
+    is_renamed: bool,
+    nested: bool,
+) -> Result<()> {
+    if uses_generic_params(&ast.ty, &input.generics) {
+        let reason = "`#[clickhouse(flatten)]` fields cannot depend on generic parameters";
+        return Err(Error::new_spanned(ast, reason));
+    }
+
+    if is_renamed || nested {
+        let reason = "`#[clickhouse(flatten)]` cannot be combined with `rename` or `nested`";
+        return Err(Error::new_spanned(ast, reason));
+    }
+
+    if field.serialize_with().is_some() || field.deserialize_with().is_some() {
+        let reason = "`#[clickhouse(flatten)]` cannot be combined with `#[serde(with)]`";
+        return Err(Error::new_spanned(ast, reason));
+    }
+
+    Ok(())
+}
+
+/// Returns type parameters bounded by the lifetime, e.g. `T: 'a`,
+/// either inline or in the `where` clause.
+fn type_params_outliving<'g>(generics: &'g syn::Generics, lt: &Lifetime) -> Vec<&'g syn::Ident> {
+    let is_outliving = |bound: &syn::TypeParamBound| matches!(bound, syn::TypeParamBound::Lifetime(bound) if bound == lt);
+
+    generics
+        .type_params()
+        .filter(|param| {
+            param.bounds.iter().any(is_outliving)
+                || generics.where_clause.iter().any(|clause| {
+                    clause.predicates.iter().any(|predicate| match predicate {
+                        syn::WherePredicate::Type(predicate) => {
+                            matches!(&predicate.bounded_ty, syn::Type::Path(ty) if ty.path.is_ident(&param.ident))
+                                && predicate.bounds.iter().any(is_outliving)
+                        }
+                        _ => false,
+                    })
+                })
+        })
+        .map(|param| &param.ident)
+        .collect()
+}
+
+/// Generates `impl Nested` for a struct marked with `#[clickhouse(nested)]`.
+fn nested_impl(
+    input: &DeriveInput,
+    data: &DataStruct,
+    cx: &Ctxt,
+    container: &Container,
+    crate_path: &syn::Path,
+) -> Result<TokenStream> {
+    let name = &input.ident;
+
+    if input.generics.params.iter().next().is_some() {
+        let reason = "`#[clickhouse(nested)]` cannot be used for generic structs";
+        return Err(Error::new_spanned(&input.generics, reason));
+    }
+
+    let Fields::Named(fields) = &data.fields else {
+        let reason = "`#[clickhouse(nested)]` can be used only for structs with named fields";
+        return Err(Error::new(name.span(), reason));
+    };
+
+    // Fields stored in columns.
+    let mut idents = Vec::new();
+    let mut types = Vec::new();
+    let mut columns = Vec::new();
+    // Fields filled with `Default::default()` while deserializing.
+    let mut skipped = Vec::new();
+
+    for (index, ast) in fields.named.iter().enumerate() {
+        let mut field = Field::from_ast(cx, index, ast, None, &SerdeDefault::None);
+        let ident = ast.ident.as_ref().expect("named field");
+
+        if field.skip_serializing() || field.skip_deserializing() {
+            skipped.push(ident);
+            continue;
+        }
+
+        let FieldAttributes {
+            rename,
+            skip_insert,
+            nested,
+            flatten,
+        } = ast.attrs[..].try_into()?;
+
+        if skip_insert || nested || flatten {
+            let reason =
+                "`skip_insert`, `nested` and `flatten` are not supported inside nested structures";
+            return Err(Error::new_spanned(ast, reason));
+        }
+
+        if field.serialize_with().is_some() || field.deserialize_with().is_some() || field.flatten()
+        {
+            let reason = "`#[serde(with)]` and `#[serde(flatten)]` are not supported \
+                          inside nested structures";
+            return Err(Error::new_spanned(ast, reason));
+        }
+
+        idents.push(ident);
+        types.push(&ast.ty);
+        field.rename_by_rules(container.rename_all_rules());
+        columns.push(rename.unwrap_or_else(|| field.name().deserialize_name().to_string()));
+    }
+
+    if idents.is_empty() {
+        let reason = "`#[clickhouse(nested)]` structs must have at least one serialized field";
+        return Err(Error::new(name.span(), reason));
+    }
+
+    let count = idents.len();
+    let vars = (0..count)
+        .map(|i| syn::Ident::new(&format!("__column{i}"), Span::call_site()))
+        .collect::<Vec<_>>();
+    let first = &vars[0];
+    let expected_lens = (0..count).map(|i| {
+        if i == 0 {
+            quote! { ::core::option::Option::None }
+        } else {
+            quote! { ::core::option::Option::Some(#first.len()) }
+        }
+    });
+
+    let serde = quote! { #crate_path::_priv::serde };
+    let serde_name = quote! { #crate_path::_priv::NESTED_SERDE_NAME };
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #crate_path::_priv::Nested for #name {
+            fn serialize_nested<__S>(
+                items: &[Self],
+                serializer: __S,
+            ) -> ::core::result::Result<__S::Ok, __S::Error>
+            where
+                __S: #serde::Serializer,
+            {
+                use #serde::ser::SerializeTupleStruct as _;
+
+                let mut state = serializer.serialize_tuple_struct(#serde_name, #count)?;
+                #(
+                    state.serialize_field(
+                        &#crate_path::_priv::NestedColumn::new(items, |item| &item.#idents)
+                    )?;
+                )*
+                state.end()
+            }
+
+            fn deserialize_nested<'de, __D>(
+                deserializer: __D,
+            ) -> ::core::result::Result<::std::vec::Vec<Self>, __D::Error>
+            where
+                __D: #serde::Deserializer<'de>,
+            {
+                struct __Visitor;
+
+                impl<'de> #serde::de::Visitor<'de> for __Visitor {
+                    type Value = ::std::vec::Vec<#name>;
+
+                    fn expecting(
+                        &self,
+                        formatter: &mut ::core::fmt::Formatter<'_>,
+                    ) -> ::core::fmt::Result {
+                        formatter.write_str(concat!("nested ", stringify!(#name)))
+                    }
+
+                    fn visit_seq<__A>(
+                        self,
+                        mut seq: __A,
+                    ) -> ::core::result::Result<Self::Value, __A::Error>
+                    where
+                        __A: #serde::de::SeqAccess<'de>,
+                    {
+                        #(
+                            let #vars: ::std::vec::Vec<#types> =
+                                #crate_path::_priv::next_nested_column(
+                                    &mut seq,
+                                    #columns,
+                                    #expected_lens,
+                                )?;
+                        )*
+                        let len = #first.len();
+                        #( let mut #vars = #vars.into_iter(); )*
+
+                        ::core::result::Result::Ok((0..len)
+                            .map(|_| #name {
+                                #( #idents: #vars.next().unwrap(), )*
+                                #( #skipped: ::core::default::Default::default(), )*
+                            })
+                            .collect())
+                    }
+                }
+
+                deserializer.deserialize_tuple_struct(#serde_name, #count, __Visitor)
+            }
+        }
+    })
+}
+
 fn row_impl(input: DeriveInput) -> Result<TokenStream> {
-    let cx = Ctxt::new();
+    // Parsed before creating `Ctxt`, which panics if dropped unchecked.
+    let Attributes { crate_path, nested } = input.attrs[..].try_into()?;
 
-    let Attributes { crate_path } = input.attrs[..].try_into()?;
+    let cx = Ctxt::new();
 
     let container = Container::from_ast(&cx, &input);
-    let name = input.ident;
+    let name = &input.ident;
 
     let result = match &input.data {
         Data::Struct(data) if data.fields.is_empty() => {
             let reason = "`Row` cannot be derived for unit or empty structs";
             Err(Error::new(name.span(), reason))
         }
-        Data::Struct(data) => column_names(data, &cx, &container),
+        Data::Struct(data) => column_names(&input, data, &cx, &container, &crate_path),
         Data::Enum(_) | Data::Union(_) => {
             let reason = "`Row` can only be derived for structs";
             Err(Error::new(name.span(), reason))
         }
     };
 
+    let nested_impl = match &input.data {
+        Data::Struct(data) if nested && result.is_ok() => {
+            Some(nested_impl(&input, data, &cx, &container, &crate_path))
+        }
+        _ => None,
+    };
+
     cx.check()?;
     let column_names = result?;
+    let nested_impl = nested_impl.transpose()?;
+
+    let mut generics = input.generics.clone();
 
     let value = match input.generics.lifetimes().count() {
         // An owned row: `struct Row { .. }`
         0 => quote! { Self },
         // A borrowed row: `struct Row<'a> { .. }`
         1 => {
+            // `Value<'__v>` must be valid for any `'__v`, so parameters bounded
+            // by the lifetime, e.g. `T: 'a`, are required to be `'static`.
+            let lt = &input.generics.lifetimes().next().unwrap().lifetime;
+            for ident in type_params_outliving(&input.generics, lt) {
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote! { #ident: 'static });
+            }
+
             // Replace the lifetime with `__v` to set `Value<'__v> = ..`.
             let mut cloned = input.generics.clone();
             let param = cloned.lifetimes_mut().next().unwrap();
@@ -92,17 +521,106 @@ fn row_impl(input: DeriveInput) -> Result<TokenStream> {
         }
     };
 
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let ColumnNames {
+        all: column_names,
+        insert,
+        defaulted,
+        remapped,
+        flattened,
+        newtype,
+    } = column_names;
+
+    // Newtypes are (de)serialized as their inner type, so they're the same row.
+    let (column_count, kind) = match &newtype {
+        Some(ty) => {
+            // Only bounded if required, because `T<'a>: Row` for a borrowed
+            // row would restrict `Value<'__v>` to the same lifetime.
+            let mut type_params = input.generics.clone();
+            type_params.params = (type_params.params.into_iter())
+                .filter(|param| !matches!(param, GenericParam::Lifetime(_)))
+                .collect();
+
+            if uses_generic_params(ty, &type_params) {
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote! { #ty: #crate_path::Row });
+            }
+
+            (
+                quote! { <#ty as #crate_path::Row>::COLUMN_COUNT },
+                quote! { <#ty as #crate_path::Row>::KIND },
+            )
+        }
+        None => (
+            quote! { <Self as #crate_path::Row>::COLUMN_NAMES.len() },
+            quote! { #crate_path::_priv::RowKind::Struct },
+        ),
+    };
+
+    // Fields of flattened rows are written by the same serializer,
+    // so `skip_insert` of their fields would be applied to the wrong fields.
+    let flatten_checks = (!flattened.is_empty()).then(|| {
+        quote! {
+            const _: () = {
+                #(
+                    assert!(
+                        matches!(
+                            <#flattened as #crate_path::Row>::KIND,
+                            #crate_path::_priv::RowKind::Struct,
+                        ),
+                        "`#[clickhouse(flatten)]` fields must be structs deriving `Row`",
+                    );
+                    assert!(
+                        <#flattened as #crate_path::Row>::INSERT_SKIPPED_FIELDS.is_empty(),
+                        "`#[clickhouse(skip_insert)]` is not supported inside flattened structs",
+                    );
+                )*
+            };
+        }
+    });
+
+    let insert_consts = insert.map(|insert| {
+        let InsertColumnNames {
+            names,
+            skipped_fields,
+        } = insert;
+
+        quote! {
+            const INSERT_COLUMN_NAMES: &'static [&'static str] = #names;
+            const INSERT_SKIPPED_FIELDS: &'static [&'static str] = #skipped_fields;
+        }
+    });
+
+    let defaulted_const = defaulted.map(|defaulted| {
+        quote! {
+            const DEFAULTED_COLUMNS: &'static [&'static str] = #defaulted;
+        }
+    });
+
+    let remapped_const = remapped.map(|remapped| {
+        quote! {
+            const REMAPPED_COLUMNS: bool = #remapped;
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     Ok(quote! {
         #[automatically_derived]
         impl #impl_generics #crate_path::Row for #name #ty_generics #where_clause {
             const NAME: &'static str = stringify!(#name);
             const COLUMN_NAMES: &'static [&'static str] = #column_names;
-            const COLUMN_COUNT: usize = <Self as #crate_path::Row>::COLUMN_NAMES.len();
-            const KIND: #crate_path::_priv::RowKind = #crate_path::_priv::RowKind::Struct;
+            const COLUMN_COUNT: usize = #column_count;
+            const KIND: #crate_path::_priv::RowKind = #kind;
+            #insert_consts
+            #defaulted_const
+            #remapped_const
 
             type Value<'__v> = #value;
         }
+
+        #flatten_checks
+        #nested_impl
     })
 }
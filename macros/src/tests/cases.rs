@@ -120,6 +120,30 @@ fn serde_skip_deserializing() {
     }
 }
 
+#[test]
+fn serde_default() {
+    render! {
+        #[derive(Row)]
+        struct Sample {
+            a: u32,
+            #[serde(default)]
+            b: u32,
+            #[serde(default = "default_c", rename = "cc")]
+            c: u32,
+        }
+    }
+
+    render! {
+        #[derive(Row)]
+        #[serde(default)]
+        struct Sample {
+            a: u32,
+            #[clickhouse(rename = "b.column")]
+            b: u32,
+        }
+    }
+}
+
 #[test]
 fn crate_attribute() {
     render! {
@@ -131,3 +155,104 @@ fn crate_attribute() {
         }
     }
 }
+
+#[test]
+fn clickhouse_rename() {
+    render! {
+        #[derive(Row)]
+        #[serde(rename_all = "camelCase")]
+        struct Sample {
+            some_a: u32,
+            #[clickhouse(rename = "b.column")]
+            some_b: u32,
+        }
+    }
+}
+
+#[test]
+fn clickhouse_skip_insert() {
+    render! {
+        #[derive(Row)]
+        #[serde(rename_all = "camelCase")]
+        struct Sample {
+            a: u32,
+            #[clickhouse(skip_insert)]
+            some_b: u32,
+            #[clickhouse(skip_insert, rename = "c.column")]
+            #[serde(rename = "cc")]
+            c: u32,
+        }
+    }
+}
+
+#[test]
+fn nested() {
+    render! {
+        #[derive(Row)]
+        #[clickhouse(nested)]
+        struct Goal {
+            id: u32,
+            #[clickhouse(rename = "Serial")]
+            serial: String,
+            #[serde(skip)]
+            cached: u64,
+        }
+    }
+}
+
+#[test]
+fn nested_field() {
+    render! {
+        #[derive(Row)]
+        struct Sample {
+            no: u32,
+            #[clickhouse(nested)]
+            #[serde(with = "clickhouse::serde::nested")]
+            goals: Vec<Goal>,
+        }
+    }
+}
+
+#[test]
+fn flatten_field() {
+    render! {
+        #[derive(Row)]
+        struct Event {
+            id: u64,
+            #[clickhouse(flatten)]
+            audit: Audit,
+            name: String,
+        }
+    }
+}
+
+#[test]
+fn generic_flatten_field() {
+    render! {
+        #[derive(Row)]
+        struct Event<'a, T: 'a + Clone> {
+            name: &'a str,
+            payload: T,
+            #[clickhouse(flatten)]
+            audit: Audit,
+        }
+    }
+}
+
+#[test]
+fn newtype() {
+    render! {
+        #[derive(Row)]
+        struct Wrapper(Inner);
+    }
+
+    render! {
+        #[derive(Row)]
+        struct Wrapper<T>(T);
+    }
+
+    render! {
+        #[derive(Row)]
+        struct Wrapper<'a>(Inner<'a>);
+    }
+}
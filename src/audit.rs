@@ -0,0 +1,260 @@
+//! Auditing of state-changing statements, see [`Client::with_audit_sink`].
+//!
+//! Every `INSERT`, `ALTER`, `CREATE`, `DROP`, `TRUNCATE`, `RENAME`, `DELETE`
+//! and `UPDATE` statement sent by the client is reported to the configured
+//! [`AuditSink`] once it's finished, either successfully or not.
+//! Other statements (e.g. `SELECT`) are not reported.
+//!
+//! Audited statements always have a `query_id`: if it's not provided using
+//! the `query_id` setting, a random one is generated and sent to the server,
+//! so records can be matched with `system.query_log`.
+//!
+//! Besides, every statement (including `SELECT`) can be reported before
+//! it's sent by [`Client::with_statement_hook`], with sensitive literals
+//! redacted by [`Client::with_statement_redaction`].
+//!
+//! [`Client::with_audit_sink`]: crate::Client::with_audit_sink
+//! [`Client::with_statement_hook`]: crate::Client::with_statement_hook
+//! [`Client::with_statement_redaction`]: crate::Client::with_statement_redaction
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use url::Url;
+
+use crate::{error::Error, observer::QueryKind, sql::split};
+
+pub(crate) type StatementHook = Arc<dyn Fn(&StatementInfo<'_>) + Send + Sync>;
+pub(crate) type Redaction = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// A destination of [`AuditRecord`]s.
+///
+/// It's implemented for closures, so a separate type isn't required.
+pub trait AuditSink: Send + Sync + 'static {
+    /// Called once a state-changing statement is finished.
+    ///
+    /// It's called synchronously by the client, so it must not block,
+    /// e.g. send records to a channel if they're written somewhere.
+    fn record(&self, record: &AuditRecord<'_>);
+}
+
+impl<F> AuditSink for F
+where
+    F: Fn(&AuditRecord<'_>) + Send + Sync + 'static,
+{
+    fn record(&self, record: &AuditRecord<'_>) {
+        self(record)
+    }
+}
+
+/// A kind of an audited statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StatementKind {
+    Insert,
+    Alter,
+    Create,
+    Drop,
+    Truncate,
+    Rename,
+    Delete,
+    Update,
+}
+
+/// A finished state-changing statement reported to an [`AuditSink`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct AuditRecord<'a> {
+    /// The kind of the statement.
+    pub kind: StatementKind,
+    /// The SQL sent to the server, including bound arguments.
+    pub sql: &'a str,
+    /// The `query_id` of the statement, either provided or generated.
+    pub query_id: &'a str,
+    /// The user the client is authenticated as.
+    ///
+    /// `None` if the server's default user or JWT authentication is used.
+    pub user: Option<&'a str>,
+    /// The database configured for the client, if any.
+    pub database: Option<&'a str>,
+    /// The affected table as written in the statement (e.g. `db.events`),
+    /// if it can be determined.
+    pub table: Option<&'a str>,
+    /// Time when the statement was started.
+    pub started_at: SystemTime,
+    /// Time since the statement was started until it's finished.
+    pub elapsed: Duration,
+    /// The number of written rows, if known.
+    pub rows: Option<u64>,
+    /// The error the statement has failed with, if any.
+    pub error: Option<&'a Error>,
+}
+
+/// A statement about to be sent to the server,
+/// reported to [`Client::with_statement_hook`].
+///
+/// [`Client::with_statement_hook`]: crate::Client::with_statement_hook
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct StatementInfo<'a> {
+    /// The kind of the statement.
+    pub kind: QueryKind,
+    /// The SQL sent to the server, including bound arguments redacted
+    /// by [`Client::with_statement_redaction`], if configured.
+    ///
+    /// [`Client::with_statement_redaction`]: crate::Client::with_statement_redaction
+    pub sql: &'a str,
+    /// The number of arguments bound by [`Query::bind`].
+    ///
+    /// [`Query::bind`]: crate::query::Query::bind
+    pub bound_args: usize,
+    /// Settings sent along with the statement in order, including
+    /// `database`, `query_id` and server-side parameters (`param_*`),
+    /// whose values are redacted as bound arguments.
+    pub settings: &'a [(String, String)],
+}
+
+/// Returns settings sent to `url` along with the statement,
+/// with values of server-side parameters redacted by `redaction`, if any.
+pub(crate) fn redacted_settings(url: &Url, redaction: Option<&Redaction>) -> Vec<(String, String)> {
+    url.query_pairs()
+        // Inserts are sent with the statement in the URL.
+        .filter(|(name, _)| name != crate::settings::QUERY)
+        .map(|(name, value)| {
+            let value = match redaction {
+                Some(redact) if name.starts_with("param_") => redact(&value),
+                _ => value.into_owned(),
+            };
+            (name.into_owned(), value)
+        })
+        .collect()
+}
+
+/// Returns the kind and the affected table of the statement,
+/// or `None` if the statement is not audited.
+pub(crate) fn classify(sql: &str) -> Option<(StatementKind, Option<&str>)> {
+    let mut words = split::leading_words(sql);
+
+    let kind = match words.next()?.to_ascii_uppercase().as_str() {
+        "INSERT" => StatementKind::Insert,
+        "ALTER" => StatementKind::Alter,
+        "CREATE" => StatementKind::Create,
+        "DROP" => StatementKind::Drop,
+        "TRUNCATE" => StatementKind::Truncate,
+        "RENAME" => StatementKind::Rename,
+        "DELETE" => StatementKind::Delete,
+        "UPDATE" => StatementKind::Update,
+        _ => return None,
+    };
+
+    // `TRUNCATE [TABLE] t` and `UPDATE t SET ...`.
+    let mut is_table_next = matches!(kind, StatementKind::Truncate | StatementKind::Update);
+
+    for word in words {
+        match word.to_ascii_uppercase().as_str() {
+            "TEMPORARY" | "OR" | "REPLACE" | "MATERIALIZED" | "IF" | "NOT" | "EXISTS" => {}
+            "TABLE" | "VIEW" | "DICTIONARY" | "INTO" | "FROM" => is_table_next = true,
+            // E.g. `INSERT INTO FUNCTION` or `CREATE DATABASE`.
+            _ if !is_table_next || word.eq_ignore_ascii_case("FUNCTION") => break,
+            _ => return Some((kind, Some(word))),
+        }
+    }
+
+    Some((kind, None))
+}
+
+/// Generates a random `query_id` formatted as UUID v4.
+pub(crate) fn generate_query_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    // `RandomState` is randomly seeded, the counter guarantees different
+    // values within the process even if seeds are cached.
+    let random = |salt: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u64(salt);
+        hasher.finish()
+    };
+
+    let high = (random(0) & !0xf000) | 0x4000;
+    let low = (random(1) & !(0b11 << 62)) | (0b10 << 62);
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_classifies_statements() {
+        use StatementKind::*;
+
+        for (sql, expected) in [
+            ("SELECT 1", None),
+            ("  select * FROM t", None),
+            ("EXPLAIN DROP TABLE t", None),
+            (
+                "INSERT INTO db.t(a, b) FORMAT RowBinary",
+                Some((Insert, Some("db.t"))),
+            ),
+            (
+                "insert into table `t` VALUES (1)",
+                Some((Insert, Some("`t`"))),
+            ),
+            (
+                "INSERT INTO FUNCTION s3('path') SELECT 1",
+                Some((Insert, None)),
+            ),
+            ("ALTER TABLE t DELETE WHERE 1", Some((Alter, Some("t")))),
+            (
+                "CREATE OR REPLACE TABLE t (a UInt8)",
+                Some((Create, Some("t"))),
+            ),
+            (
+                "CREATE TABLE IF NOT EXISTS t (a UInt8)",
+                Some((Create, Some("t"))),
+            ),
+            (
+                "CREATE MATERIALIZED VIEW mv TO t AS SELECT 1",
+                Some((Create, Some("mv"))),
+            ),
+            ("CREATE DATABASE db", Some((Create, None))),
+            ("-- c\nDROP TABLE IF EXISTS t", Some((Drop, Some("t")))),
+            ("TRUNCATE t", Some((Truncate, Some("t")))),
+            ("TRUNCATE TABLE IF EXISTS t", Some((Truncate, Some("t")))),
+            ("RENAME TABLE a TO b", Some((Rename, Some("a")))),
+            ("DELETE FROM t WHERE 1", Some((Delete, Some("t")))),
+            ("UPDATE t SET a = 1 WHERE 1", Some((Update, Some("t")))),
+        ] {
+            assert_eq!(classify(sql), expected, "{sql}");
+        }
+    }
+
+    #[test]
+    fn it_generates_query_ids() {
+        let a = generate_query_id();
+        let b = generate_query_id();
+        assert_ne!(a, b);
+
+        for id in [a, b] {
+            assert_eq!(id.len(), 36, "{id}");
+            assert_eq!(&id[14..15], "4", "{id}");
+            assert!(matches!(&id[19..20], "8" | "9" | "a" | "b"), "{id}");
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Refreshable access tokens, see [`Client::with_access_token_provider`].
+//!
+//! [`Client::with_access_token_provider`]: crate::Client::with_access_token_provider
+
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+/// Provides JWT access tokens, e.g. to refresh them before they expire.
+///
+/// It's implemented for closures returning futures, so a separate type isn't
+/// required.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use tokio::sync::RwLock;
+///
+/// // Updated by a background task before the token expires.
+/// let token = Arc::new(RwLock::new(String::from("initial")));
+///
+/// let client = clickhouse::Client::default().with_access_token_provider(move || {
+///     let token = token.clone();
+///     async move { token.read().await.clone() }
+/// });
+/// ```
+pub trait AccessTokenProvider: Send + Sync + 'static {
+    /// Returns a token to authenticate the next request.
+    ///
+    /// It's called before every request, so it should return a cached token
+    /// and refresh it only if it's about to expire.
+    fn token(&self) -> Pin<Box<dyn Future<Output = String> + Send + '_>>;
+}
+
+impl<F, Fut> AccessTokenProvider for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = String> + Send + 'static,
+{
+    fn token(&self) -> Pin<Box<dyn Future<Output = String> + Send + '_>> {
+        Box::pin(self())
+    }
+}
+
+/// A shared provider, compared by identity.
+#[derive(Clone)]
+pub(crate) struct SharedProvider(pub(crate) Arc<dyn AccessTokenProvider>);
+
+impl fmt::Debug for SharedProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedProvider")
+    }
+}
+
+impl PartialEq for SharedProvider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use bytes::Bytes;
+    use futures_channel::oneshot;
+    use hyper::{Request, Response};
+
+    use super::*;
+    use crate::{Client, test};
+
+    struct RecordAuthorization;
+
+    impl test::sealed::Sealed for RecordAuthorization {}
+
+    impl test::Handler for RecordAuthorization {
+        type Control = oneshot::Receiver<Option<String>>;
+
+        fn make(self) -> (test::HandlerFn, Self::Control) {
+            let (tx, rx) = oneshot::channel();
+            let h = Box::new(move |request: Request<Bytes>| -> Response<Bytes> {
+                let value = request
+                    .headers()
+                    .get("Authorization")
+                    .map(|value| value.to_str().unwrap().to_string());
+                let _ = tx.send(value);
+                Response::new(Bytes::new())
+            });
+            (h, rx)
+        }
+    }
+
+    #[tokio::test]
+    async fn refreshes_tokens() {
+        let mock = test::Mock::new();
+        let calls = Arc::new(AtomicU32::new(0));
+        let client = Client::default()
+            .with_mock(&mock)
+            .with_access_token_provider({
+                let calls = calls.clone();
+                move || {
+                    let n = calls.fetch_add(1, Ordering::Relaxed) + 1;
+                    async move { format!("token-{n}") }
+                }
+            });
+
+        for expected in ["Bearer token-1", "Bearer token-2"] {
+            let recorded = mock.add(RecordAuthorization);
+            client.query("SELECT 1").execute().await.unwrap();
+            assert_eq!(recorded.await.unwrap().as_deref(), Some(expected));
+        }
+
+        // Inserts are authenticated too.
+        let recorded = mock.add(RecordAuthorization);
+        let mut insert = client.insert_formatted_with("INSERT INTO t FORMAT CSV");
+        insert.send(Bytes::from_static(b"1\n")).await.unwrap();
+        insert.end().await.unwrap();
+        assert_eq!(recorded.await.unwrap().as_deref(), Some("Bearer token-3"));
+
+        // A static token replaces the provider.
+        let client = client.with_access_token("static");
+        let recorded = mock.add(RecordAuthorization);
+        client.query("SELECT 1").execute().await.unwrap();
+        assert_eq!(recorded.await.unwrap().as_deref(), Some("Bearer static"));
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+}
@@ -10,15 +10,32 @@ pub(crate) struct BytesExt {
     // `Cell` allows us to mutate this value while keeping references to `bytes`.
     // Also, the dedicated counter is faster than using `Bytes::advance()`.
     cursor: Cell<usize>,
+
+    // The minimal capacity reserved once chunks are joined,
+    // so the same allocation is reused for subsequent joins.
+    buffer_size: usize,
 }
 
 impl BytesExt {
+    pub(crate) fn new(buffer_size: usize) -> Self {
+        Self {
+            buffer_size,
+            ..Self::default()
+        }
+    }
+
     /// Returns a remaining slice of bytes.
     #[inline(always)]
     pub(crate) fn slice(&self) -> &[u8] {
         &self.bytes[self.cursor.get()..]
     }
 
+    /// Returns the whole buffer, which [`BytesExt::slice`] is a part of.
+    #[inline(always)]
+    pub(crate) fn source(&self) -> &Bytes {
+        &self.bytes
+    }
+
     /// Returns the number of remaining bytes.
     #[inline(always)]
     pub(crate) fn remaining(&self) -> usize {
@@ -38,6 +55,14 @@ impl BytesExt {
         *self.cursor.get_mut() += n;
     }
 
+    /// Makes the following joins of chunks reserve capacity for `n` remaining
+    /// bytes at once, e.g. for a compressed block whose size is known from
+    /// its header, so the block is copied once without reallocations.
+    #[inline(always)]
+    pub(crate) fn reserve_remaining(&mut self, n: usize) {
+        self.buffer_size = n;
+    }
+
     /// Adds the provided chunk into available bytes.
     #[inline(always)]
     pub(crate) fn extend(&mut self, chunk: Bytes) {
@@ -57,6 +82,10 @@ impl BytesExt {
 
         // Try to reuse the capacity, if possible.
         let mut remaining = BytesMut::from(remaining);
+        let additional = chunk
+            .len()
+            .max(self.buffer_size.saturating_sub(remaining.len()));
+        remaining.reserve(additional);
         remaining.extend_from_slice(&chunk);
 
         self.bytes = remaining.freeze();
@@ -118,4 +147,37 @@ mod tests_miri {
         assert_eq!(bytes.slice(), b"l");
         assert_eq!(bytes.remaining(), 1);
     }
+
+    #[test]
+    fn reuses_buffer() {
+        let mut bytes = BytesExt::new(64);
+
+        bytes.extend(Bytes::from_static(b"hello"));
+        bytes.advance(3);
+        bytes.extend(Bytes::from_static(b"l"));
+        assert_eq!(bytes.slice(), b"lol");
+        let ptr = bytes.slice().as_ptr();
+
+        // The joined buffer is unique, so it's reused.
+        bytes.advance(2);
+        bytes.extend(Bytes::from_static(b"ly"));
+        assert_eq!(bytes.slice(), b"lly");
+        assert_eq!(bytes.slice().as_ptr(), ptr);
+    }
+
+    #[test]
+    fn reserves_remaining() {
+        let mut bytes = BytesExt::default();
+
+        bytes.extend(Bytes::from_static(b"he"));
+        bytes.reserve_remaining(11);
+        bytes.extend(Bytes::from_static(b"llo"));
+        let ptr = bytes.slice().as_ptr();
+
+        // Chunks are appended without reallocations.
+        bytes.extend(Bytes::from_static(b" wor"));
+        bytes.extend(Bytes::from_static(b"ld"));
+        assert_eq!(bytes.slice(), b"hello world");
+        assert_eq!(bytes.slice().as_ptr(), ptr);
+    }
 }
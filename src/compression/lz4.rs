@@ -56,7 +56,13 @@ where
 
             match self.meta.take() {
                 Some(meta) => break meta,
-                None => self.meta = Some(self.read_meta()?),
+                None => {
+                    let meta = self.read_meta()?;
+                    // A block spanning multiple chunks is joined into a buffer
+                    // allocated once, instead of growing it by every chunk.
+                    self.bytes.reserve_remaining(meta.total_size());
+                    self.meta = Some(meta);
+                }
             };
         };
 
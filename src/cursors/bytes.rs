@@ -1,12 +1,21 @@
-use crate::{cursors::RawCursor, error::Result, query_summary::QuerySummary, response::Response};
+use crate::{
+    cursors::RawCursor,
+    error::{Error, ErrorContext, Result},
+    observer::Observation,
+    query_summary::QuerySummary,
+    response::Response,
+    response_headers::ResponseHeaders,
+};
 use bytes::{Buf, Bytes, BytesMut};
 use futures_util::TryFutureExt;
 use std::{
     io::Result as IoResult,
-    pin::Pin,
+    path::Path,
+    pin::{Pin, pin},
+    sync::Arc,
     task::{Context, Poll, ready},
 };
-use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
 use tracing::Instrument;
 
 /// A cursor over raw bytes of the response returned by [`Query::fetch_bytes`].
@@ -32,23 +41,54 @@ use tracing::Instrument;
 /// Note: methods of these traits use [`std::io::Error`] for errors.
 /// To get an original error from this crate, use `From` conversion.
 ///
+/// # Dropping
+///
+/// Similar to [`RowCursor`], see [`Client::with_cursor_drain_limit`].
+///
+/// [`Client::with_cursor_drain_limit`]: crate::Client::with_cursor_drain_limit
 /// [`RowCursor`]: crate::query::RowCursor
 /// [`Query::fetch_bytes`]: crate::query::Query::fetch_bytes
 pub struct BytesCursor {
     raw: RawCursor,
     bytes: Bytes,
     span: tracing::Span,
+    /// Reported once the first error is returned or the cursor is dropped.
+    observation: Option<Observation>,
+    /// Attached to returned errors, see [`Client::with_error_context`].
+    ///
+    /// [`Client::with_error_context`]: crate::Client::with_error_context
+    error_context: Option<Arc<ErrorContext>>,
 }
 
 // TODO: what if any next/poll_* called AFTER error returned?
 
 impl BytesCursor {
-    pub(crate) fn new(response: Response, span: tracing::Span) -> Self {
+    pub(crate) fn new(
+        response: Response,
+        span: tracing::Span,
+        observation: Option<Observation>,
+        error_context: Option<Arc<ErrorContext>>,
+    ) -> Self {
         Self {
             raw: RawCursor::new(response),
             bytes: Bytes::default(),
             span,
+            observation,
+            error_context,
+        }
+    }
+
+    /// Reports the error and attaches the context to it, if any.
+    #[cold]
+    fn observe_error(
+        observation: &mut Option<Observation>,
+        error_context: Option<&Arc<ErrorContext>>,
+        err: Error,
+    ) -> Error {
+        if let Some(observation) = observation.take() {
+            observation.finish(Some(&err));
         }
+        err.with_context(error_context)
     }
 
     /// Emits the next bytes chunk.
@@ -67,6 +107,9 @@ impl BytesCursor {
             .inspect_err(|e| tracing::debug!(error=?e, "error from BytesCursor::next()"))
             .instrument(self.span.clone())
             .await
+            .map_err(|err| {
+                Self::observe_error(&mut self.observation, self.error_context.as_ref(), err)
+            })
     }
 
     /// Poll for the next chunk of bytes.
@@ -79,7 +122,11 @@ impl BytesCursor {
 
         let _guard = self.span.enter();
 
-        self.raw.poll_next(cx)
+        let result = ready!(self.raw.poll_next(cx)).map_err(|err| {
+            Self::observe_error(&mut self.observation, self.error_context.as_ref(), err)
+        });
+
+        Poll::Ready(result)
     }
 
     /// Collects the whole response into a single [`Bytes`].
@@ -111,6 +158,36 @@ impl BytesCursor {
         Ok(collected.freeze())
     }
 
+    /// Writes the whole response to `writer` chunk by chunk and flushes it.
+    ///
+    /// Returns the number of written bytes.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is NOT cancellation safe.
+    /// If cancelled, a chunk can be partially written.
+    pub async fn write_to(&mut self, writer: impl AsyncWrite) -> Result<u64> {
+        let mut writer = pin!(writer);
+        let mut written = 0;
+
+        while let Some(chunk) = self.next().await? {
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Writes the whole response to a file at `path`, creating it or
+    /// truncating an existing one, see [`BytesCursor::write_to`].
+    ///
+    /// If an error occurs, the file is left partially written.
+    pub async fn write_to_file(&mut self, path: impl AsRef<Path>) -> Result<u64> {
+        let file = tokio::fs::File::create(path).await?;
+        self.write_to(file).await
+    }
+
     #[cold]
     fn poll_refill(&mut self, cx: &mut Context<'_>) -> Poll<IoResult<bool>> {
         debug_assert_eq!(self.bytes.len(), 0);
@@ -126,6 +203,8 @@ impl BytesCursor {
                 Ok(None) => return Poll::Ready(Ok(false)),
                 Err(e) => {
                     tracing::debug!(error=?e, "error reading from cursor");
+                    let e =
+                        Self::observe_error(&mut self.observation, self.error_context.as_ref(), e);
                     return Poll::Ready(Err(e.into()));
                 }
             }
@@ -161,6 +240,14 @@ impl BytesCursor {
         self.raw.summary()
     }
 
+    /// Returns the `X-ClickHouse-*` response headers, e.g. the query ID or
+    /// the server timezone. Available once the response headers have been
+    /// received.
+    #[inline]
+    pub fn headers(&self) -> Option<&ResponseHeaders> {
+        self.raw.headers()
+    }
+
     #[inline]
     #[doc(hidden)]
     pub fn _priv_span(&self) -> &tracing::Span {
@@ -221,6 +308,10 @@ impl Drop for BytesCursor {
         );
 
         tracing::debug!("finished raw query");
+
+        if let Some(observation) = self.observation.take() {
+            observation.finish(None);
+        }
     }
 }
 
@@ -266,7 +357,11 @@ impl futures_util::stream::Stream for BytesCursor {
 
         let _guard = this.span.enter();
 
-        this.raw.poll_next(cx).map(Result::transpose)
+        let result = ready!(this.raw.poll_next(cx)).map_err(|err| {
+            Self::observe_error(&mut this.observation, this.error_context.as_ref(), err)
+        });
+
+        Poll::Ready(result.transpose())
     }
 }
 
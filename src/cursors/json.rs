@@ -0,0 +1,112 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use tokio::io::AsyncBufReadExt;
+
+use crate::{
+    cursors::BytesCursor,
+    error::{Error, Result},
+    query_summary::QuerySummary,
+    response_headers::ResponseHeaders,
+};
+
+/// A cursor that emits rows of `JSONEachRow`, returned by [`Query::fetch_json`].
+///
+/// Unlike [`RowCursor`], it requires only `T: DeserializeOwned` and uses JSON
+/// representation of values, e.g. `Date` is a string, not a number of days.
+/// Fields of structs are matched to columns by names, and columns without
+/// fields are ignored, so `T` doesn't have to follow the schema strictly.
+///
+/// # Dropping
+///
+/// Similar to [`RowCursor`], see [`Client::with_cursor_drain_limit`].
+///
+/// [`Query::fetch_json`]: crate::query::Query::fetch_json
+/// [`RowCursor`]: crate::query::RowCursor
+/// [`Client::with_cursor_drain_limit`]: crate::Client::with_cursor_drain_limit
+#[must_use]
+pub struct JsonCursor<T> {
+    bytes: BytesCursor,
+    line: Vec<u8>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> JsonCursor<T> {
+    pub(crate) fn new(bytes: BytesCursor) -> Self {
+        Self {
+            bytes,
+            line: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Emits the next row.
+    ///
+    /// The result is unspecified if it's called after `Err` is returned.
+    pub async fn next(&mut self) -> Result<Option<T>> {
+        loop {
+            self.line.clear();
+            if self.bytes.read_until(b'\n', &mut self.line).await? == 0 {
+                return Ok(None);
+            }
+
+            let line = self.line.strip_suffix(b"\n").unwrap_or(&self.line);
+            if !line.is_empty() {
+                return parse(line).map(Some);
+            }
+        }
+    }
+
+    /// Returns the `X-ClickHouse-*` headers of the response, see [`BytesCursor::headers`].
+    pub fn headers(&self) -> Option<&ResponseHeaders> {
+        self.bytes.headers()
+    }
+
+    /// Returns the summary of the query, see [`BytesCursor::summary`].
+    pub fn summary(&self) -> Option<&QuerySummary> {
+        self.bytes.summary()
+    }
+
+    /// Returns the total size in bytes received from the CH server since
+    /// the cursor was created, see [`BytesCursor::received_bytes`].
+    pub fn received_bytes(&self) -> u64 {
+        self.bytes.received_bytes()
+    }
+
+    /// Returns the total size in bytes decompressed since the cursor was
+    /// created, see [`BytesCursor::decoded_bytes`].
+    pub fn decoded_bytes(&self) -> u64 {
+        self.bytes.decoded_bytes()
+    }
+}
+
+fn parse<T: DeserializeOwned>(line: &[u8]) -> Result<T> {
+    serde_json::from_slice(line).map_err(|err| Error::BadResponse(format!("invalid row: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Partial {
+        id: u64,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn it_ignores_unknown_columns() {
+        let line = br#"{"id":42,"name":"foo","tags":["a","b"],"day":"2024-01-01"}"#;
+        assert_eq!(
+            parse::<Partial>(line).unwrap(),
+            Partial {
+                id: 42,
+                tags: vec!["a".into(), "b".into()],
+            }
+        );
+
+        let err = parse::<Partial>(br#"{"id":"42","tags":[]}"#).unwrap_err();
+        assert!(err.to_string().contains("invalid row"), "{err}");
+    }
+}
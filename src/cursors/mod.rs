@@ -1,6 +1,15 @@
-pub(crate) use self::raw::RawCursor;
-pub use self::{bytes::BytesCursor, row::RowCursor};
+pub use self::{
+    bytes::BytesCursor,
+    json::JsonCursor,
+    row::{CursorStats, RowCursor, RowError},
+    text::TextCursor,
+    totals::{Extremes, TotalsCursor},
+};
+pub(crate) use self::{raw::RawCursor, text::TextFormat};
 
 mod bytes;
+mod json;
 mod raw;
 mod row;
+mod text;
+mod totals;
@@ -2,6 +2,7 @@ use crate::{
     error::Result,
     query_summary::QuerySummary,
     response::{Chunks, Response, ResponseFuture},
+    response_headers::ResponseHeaders,
 };
 use bytes::Bytes;
 use futures_util::Stream;
@@ -21,9 +22,11 @@ enum RawCursorState {
 
 struct RawCursorLoading {
     chunks: Chunks,
-    summary: Option<Box<QuerySummary>>,
+    headers: Option<Box<ResponseHeaders>>,
     net_size: u64,
     data_size: u64,
+    chunk_count: u64,
+    max_chunk_size: u64,
 }
 
 impl RawCursor {
@@ -43,6 +46,8 @@ impl RawCursor {
                 Some(chunk) => {
                     state.net_size += chunk.net_size as u64;
                     state.data_size += chunk.data.len() as u64;
+                    state.chunk_count += 1;
+                    state.max_chunk_size = state.max_chunk_size.max(chunk.net_size as u64);
                     Ok(Some(chunk.data))
                 }
                 None => Ok(None),
@@ -65,19 +70,21 @@ impl RawCursor {
         // in order to provide proper fused behavior of the cursor.
         let res = ready!(future.as_mut().poll(cx));
         let mut chunks = Chunks::empty();
-        let mut summary = None;
+        let mut headers = None;
         let res = res
-            .map(|(c, s)| {
+            .map(|(c, h)| {
                 chunks = c;
-                summary = s;
+                headers = Some(h);
             })
             .inspect_err(|e| e.record_in_current_span("response error"));
 
         self.0 = RawCursorState::Loading(RawCursorLoading {
             chunks,
-            summary,
+            headers,
             net_size: 0,
             data_size: 0,
+            chunk_count: 0,
+            max_chunk_size: 0,
         });
 
         Poll::Ready(res)
@@ -97,13 +104,32 @@ impl RawCursor {
         }
     }
 
-    pub(crate) fn summary(&self) -> Option<&QuerySummary> {
+    pub(crate) fn chunk_count(&self) -> u64 {
+        match &self.0 {
+            RawCursorState::Loading(state) => state.chunk_count,
+            RawCursorState::Waiting(_) => 0,
+        }
+    }
+
+    /// Returns the size of the largest chunk before decompression.
+    pub(crate) fn max_chunk_size(&self) -> u64 {
+        match &self.0 {
+            RawCursorState::Loading(state) => state.max_chunk_size,
+            RawCursorState::Waiting(_) => 0,
+        }
+    }
+
+    pub(crate) fn headers(&self) -> Option<&ResponseHeaders> {
         match &self.0 {
-            RawCursorState::Loading(state) => state.summary.as_deref(),
+            RawCursorState::Loading(state) => state.headers.as_deref(),
             RawCursorState::Waiting(_) => None,
         }
     }
 
+    pub(crate) fn summary(&self) -> Option<&QuerySummary> {
+        self.headers()?.summary()
+    }
+
     #[cfg(feature = "futures03")]
     pub(crate) fn is_terminated(&self) -> bool {
         match &self.0 {
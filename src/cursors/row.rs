@@ -1,48 +1,116 @@
 #[cfg(feature = "futures03")]
 use crate::RowOwned;
+use crate::observer::Observation;
 use crate::row_metadata::RowMetadata;
 use crate::{
-    RowRead,
+    DecodeLimits, Row, RowRead,
     bytes_ext::BytesExt,
     cursors::RawCursor,
-    error::{Error, Result},
+    error::{Error, ErrorContext, Result},
     query_summary::QuerySummary,
-    response::Response,
+    response::{MAX_EXCEPTION_SIZE, Response},
+    response_headers::ResponseHeaders,
     rowbinary,
 };
-use bytes::Buf;
+use bytes::{Buf, Bytes};
 use clickhouse_types::error::TypesError;
 use clickhouse_types::parse_rbwnat_columns_header;
 use polonius_the_crab::prelude::*;
+use std::fmt;
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll, ready};
+use std::time::{Duration, Instant};
 
 /// A cursor that emits rows deserialized as structures from RowBinary.
+///
+/// RowBinary doesn't carry `WITH TOTALS` and extremes rows, use
+/// [`Query::fetch_with_totals`] to get them.
+///
+/// # Dropping
+///
+/// If the cursor is dropped before all rows are emitted, the request is
+/// aborted and the connection is closed. Alternatively, the rest of the
+/// response can be drained in the background to reuse the connection,
+/// see [`Client::with_cursor_drain_limit`].
+///
+/// [`Client::with_cursor_drain_limit`]: crate::Client::with_cursor_drain_limit
+/// [`Query::fetch_with_totals`]: crate::query::Query::fetch_with_totals
 #[must_use]
 pub struct RowCursor<T> {
     raw: RawCursor,
     bytes: BytesExt,
     validation: bool,
+    strict_utc: bool,
+    /// See [`crate::query::Query::allow_extra_columns`].
+    allow_extra_columns: bool,
+    /// See [`crate::Client::with_strict_decode`].
+    decode_limits: Option<DecodeLimits>,
     /// [`None`] until the first call to [`RowCursor::next()`],
     /// as [`RowCursor::new`] is not `async`, so it loads lazily.
     row_metadata: Option<RowMetadata>,
     span: tracing::Span,
     returned_rows: u64,
+    /// `Some` if invalid rows are skipped, see [`RowCursor::skip_invalid_rows`].
+    row_errors: Option<Vec<RowError>>,
+    skipped_rows: u64,
+    /// The time spent deserializing rows, see [`RowCursor::stats`].
+    decode_time: Duration,
+    /// Reported once the first error is returned or the cursor is dropped.
+    observation: Option<Observation>,
+    /// Attached to returned errors, see [`Client::with_error_context`].
+    ///
+    /// [`Client::with_error_context`]: crate::Client::with_error_context
+    error_context: Option<Arc<ErrorContext>>,
+    /// See [`RowCursor::poll_deferred_error`].
+    deferred_error: Option<Box<DeferredError>>,
     _marker: PhantomData<fn() -> T>,
 }
 
 impl<T> RowCursor<T> {
-    pub(crate) fn new(response: Response, validation: bool, span: tracing::Span) -> Self {
+    pub(crate) fn new(
+        response: Response,
+        validation: bool,
+        strict_utc: bool,
+        span: tracing::Span,
+        observation: Option<Observation>,
+        error_context: Option<Arc<ErrorContext>>,
+        read_buffer_size: usize,
+    ) -> Self {
         Self {
             _marker: PhantomData,
             raw: RawCursor::new(response),
-            bytes: BytesExt::default(),
+            bytes: BytesExt::new(read_buffer_size),
             row_metadata: None,
             validation,
+            strict_utc,
+            allow_extra_columns: false,
+            decode_limits: None,
             span,
             returned_rows: 0,
+            row_errors: None,
+            skipped_rows: 0,
+            decode_time: Duration::ZERO,
+            observation,
+            error_context,
+            deferred_error: None,
+        }
+    }
+
+    /// Reports the error and attaches the context to it, if any.
+    #[cold]
+    fn observe_error(
+        observation: &mut Option<Observation>,
+        error_context: Option<&Arc<ErrorContext>>,
+        rows: u64,
+        err: Error,
+    ) -> Error {
+        if let Some(mut observation) = observation.take() {
+            observation.set_rows(rows);
+            observation.finish(Some(&err));
         }
+        err.with_context(error_context)
     }
 
     #[cold]
@@ -61,7 +129,11 @@ impl<T> RowCursor<T> {
                 match parse_rbwnat_columns_header(&mut slice) {
                     Ok(columns) if !columns.is_empty() => {
                         self.bytes.set_remaining(slice.len());
-                        let row_metadata = RowMetadata::new_for_cursor::<T>(columns)?;
+                        let mut row_metadata = RowMetadata::new_for_cursor_with::<T>(
+                            columns,
+                            self.allow_extra_columns,
+                        )?;
+                        row_metadata.strict_utc = self.strict_utc;
                         self.row_metadata = Some(row_metadata);
                         return Poll::Ready(Ok(()));
                     }
@@ -93,6 +165,40 @@ impl<T> RowCursor<T> {
         }
     }
 
+    /// Makes the cursor skip rows that cannot be deserialized into `T`
+    /// instead of failing, e.g. because of unexpected values.
+    /// Errors of skipped rows can be taken by [`RowCursor::take_row_errors`].
+    ///
+    /// Rows are skipped based on column types provided by the server,
+    /// so it works only if validation is enabled (see [`Client::with_validation`]),
+    /// and not for columns of `Dynamic`, `JSON` and `AggregateFunction` types.
+    /// Otherwise, the cursor fails as usual. Network and server errors
+    /// are returned as usual too.
+    ///
+    /// [`Client::with_validation`]: crate::Client::with_validation
+    pub fn skip_invalid_rows(mut self) -> Self {
+        self.row_errors.get_or_insert_with(Vec::new);
+        self
+    }
+
+    pub(crate) fn allow_extra_columns(mut self, allow: bool) -> Self {
+        self.allow_extra_columns = allow;
+        self
+    }
+
+    pub(crate) fn strict_decode(mut self, limits: Option<DecodeLimits>) -> Self {
+        self.decode_limits = limits;
+        self
+    }
+
+    /// Takes errors of rows skipped so far, see [`RowCursor::skip_invalid_rows`].
+    pub fn take_row_errors(&mut self) -> Vec<RowError> {
+        self.row_errors
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
     /// Emits the next row.
     ///
     /// The result is unspecified if it's called after `Err` is returned.
@@ -101,49 +207,180 @@ impl<T> RowCursor<T> {
     ///
     /// This method is cancellation safe.
     pub async fn next(&mut self) -> Result<Option<T::Value<'_>>>
+    where
+        T: RowRead,
+    {
+        Ok(Next::new(self).await?.map(|(value, _)| value))
+    }
+
+    /// Emits the next row along with its raw `RowBinary` encoding,
+    /// e.g. to persist rows for replaying or to calculate checksums.
+    ///
+    /// The raw slice borrows the cursor's buffer, so no copying is involved.
+    /// It doesn't include the columns header sent if validation is enabled.
+    ///
+    /// The result is unspecified if it's called after `Err` is returned.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancellation safe.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// # #[derive(clickhouse::Row, serde::Deserialize)]
+    /// # struct MyRow { no: u32 }
+    /// # let client = clickhouse::Client::default();
+    /// let mut cursor = client.query("SELECT ?fields FROM some").fetch::<MyRow>()?;
+    ///
+    /// while let Some((row, raw)) = cursor.next_with_bytes().await? {
+    ///     println!("row #{} is encoded as {raw:?}", row.no);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub async fn next_with_bytes(&mut self) -> Result<Option<(T::Value<'_>, &[u8])>>
     where
         T: RowRead,
     {
         Next::new(self).await
     }
 
+    #[cfg(feature = "futures03")]
     #[inline]
     fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<T::Value<'_>>>>
+    where
+        T: RowRead,
+    {
+        self.poll_next_with_bytes(cx)
+            .map_ok(|row| row.map(|(value, _)| value))
+    }
+
+    #[inline]
+    fn poll_next_with_bytes(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<RowWithBytes<'_, T>>>>
     where
         T: RowRead,
     {
         if self.validation && self.row_metadata.is_none() {
-            ready!(self.poll_read_columns(cx))?;
+            if let Err(err) = ready!(self.poll_read_columns(cx)) {
+                let err = Self::observe_error(
+                    &mut self.observation,
+                    self.error_context.as_ref(),
+                    self.returned_rows,
+                    err,
+                );
+                return Poll::Ready(Err(err));
+            }
             debug_assert!(self.row_metadata.is_some());
         }
 
         let _span = self.span.enter();
 
+        if self.deferred_error.is_some() {
+            let err = ready!(Self::poll_deferred_error(
+                &mut self.raw,
+                &mut self.deferred_error,
+                cx
+            ));
+            let err = Self::observe_error(
+                &mut self.observation,
+                self.error_context.as_ref(),
+                self.returned_rows,
+                err,
+            );
+            return Poll::Ready(Err(err));
+        }
+
         let mut bytes = &mut self.bytes;
 
         loop {
-            polonius!(|bytes| -> Poll<Result<Option<T::Value<'polonius>>>> {
-                if bytes.remaining() > 0 {
-                    let mut slice = bytes.slice();
-                    let result = rowbinary::deserialize_row::<T::Value<'_>>(
-                        &mut slice,
-                        self.row_metadata.as_ref(),
-                    );
+            polonius!(
+                |bytes| -> Poll<Result<Option<RowWithBytes<'polonius, T>>>> {
+                    while bytes.remaining() > 0 {
+                        let raw = bytes.slice();
+                        let mut slice = raw;
+                        let started_at = Instant::now();
+                        let result = match self.row_metadata.as_ref() {
+                            // Rows of the same fixed size are validated only once.
+                            Some(metadata)
+                                if self.returned_rows > 0
+                                    && let Some(row_size) = metadata.fixed_row_size =>
+                            {
+                                rowbinary::deserialize_fixed_row::<T::Value<'_>>(
+                                    &mut slice, row_size,
+                                )
+                            }
+                            metadata => rowbinary::deserialize_row_from::<T::Value<'_>>(
+                                &mut slice,
+                                metadata,
+                                Some(bytes.source()),
+                                self.decode_limits,
+                            ),
+                        };
+                        self.decode_time += started_at.elapsed();
 
-                    match result {
-                        Ok(value) => {
-                            self.returned_rows += 1;
-                            bytes.set_remaining(slice.len());
-                            polonius_return!(Poll::Ready(Ok(Some(value))))
-                        }
-                        Err(Error::NotEnoughData) => {}
-                        Err(err) => {
-                            tracing::debug!(error=?err, "error deserializing row");
-                            polonius_return!(Poll::Ready(Err(err)))
+                        match result {
+                            Ok(value) => {
+                                self.returned_rows += 1;
+                                bytes.set_remaining(slice.len());
+                                let raw = &raw[..raw.len() - slice.len()];
+                                polonius_return!(Poll::Ready(Ok(Some((value, raw)))))
+                            }
+                            Err(Error::NotEnoughData) => {}
+                            Err(error) => {
+                                let row_len = self
+                                    .row_errors
+                                    .as_ref()
+                                    .and(self.row_metadata.as_ref())
+                                    .map(|metadata| {
+                                        rowbinary::row_len(bytes.slice(), &metadata.columns)
+                                    });
+
+                                match row_len {
+                                    Some(Ok(row_len)) => {
+                                        tracing::debug!(error=?error, "skipping invalid row");
+                                        let row_errors = self.row_errors.as_mut().unwrap();
+                                        row_errors.push(RowError {
+                                            index: self.returned_rows + self.skipped_rows,
+                                            raw: Bytes::copy_from_slice(&bytes.slice()[..row_len]),
+                                            error,
+                                        });
+                                        self.skipped_rows += 1;
+                                        bytes.set_remaining(bytes.remaining() - row_len);
+                                        continue;
+                                    }
+                                    // Wait for the rest of the row to skip it.
+                                    Some(Err(Error::NotEnoughData)) => {}
+                                    _ => {
+                                        tracing::debug!(error=?error, "error deserializing row");
+                                        self.deferred_error =
+                                            Some(Box::new(DeferredError { error, drained: 0 }));
+                                    }
+                                }
+                            }
                         }
+
+                        break;
                     }
                 }
-            });
+            );
+
+            if self.deferred_error.is_some() {
+                let err = ready!(Self::poll_deferred_error(
+                    &mut self.raw,
+                    &mut self.deferred_error,
+                    cx
+                ));
+                let err = Self::observe_error(
+                    &mut self.observation,
+                    self.error_context.as_ref(),
+                    self.returned_rows,
+                    err,
+                );
+                return Poll::Ready(Err(err));
+            }
 
             match ready!(self.raw.poll_next(cx)) {
                 Ok(Some(chunk)) => bytes.extend(chunk),
@@ -155,19 +392,59 @@ impl<T> RowCursor<T> {
                             bytes_remaining = bytes.remaining(),
                             "incomplete read from cursor"
                         );
-                        Poll::Ready(Err(Error::NotEnoughData))
+                        let err = Self::observe_error(
+                            &mut self.observation,
+                            self.error_context.as_ref(),
+                            self.returned_rows,
+                            Error::NotEnoughData,
+                        );
+                        Poll::Ready(Err(err))
                     } else {
                         Poll::Ready(Ok(None))
                     };
                 }
                 Err(e) => {
                     tracing::debug!(error=?e, "error from raw cursor");
+                    let e = Self::observe_error(
+                        &mut self.observation,
+                        self.error_context.as_ref(),
+                        self.returned_rows,
+                        e,
+                    );
                     return Poll::Ready(Err(e));
                 }
             }
         }
     }
 
+    /// Reads the rest of the response (at most [`MAX_EXCEPTION_SIZE`] bytes)
+    /// after a row decoding error, because the server can send an exception in
+    /// the middle of the response, which is detected only once fully received.
+    /// If so, the exception is returned instead of the decoding error.
+    #[cold]
+    #[inline(never)]
+    fn poll_deferred_error(
+        raw: &mut RawCursor,
+        deferred_error: &mut Option<Box<DeferredError>>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Error> {
+        let deferred = deferred_error.as_mut().expect("no deferred error");
+
+        while deferred.drained <= MAX_EXCEPTION_SIZE {
+            match ready!(raw.poll_next(cx)) {
+                Ok(Some(chunk)) => deferred.drained += chunk.len(),
+                Err(err @ Error::ServerException { .. }) => {
+                    *deferred_error = None;
+                    return Poll::Ready(err);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let deferred = deferred_error.take().expect("no deferred error");
+        Poll::Ready(deferred.error)
+    }
+
     /// Returns the total size in bytes received from the CH server since
     /// the cursor was created.
     ///
@@ -199,6 +476,29 @@ impl<T> RowCursor<T> {
     pub fn summary(&self) -> Option<&QuerySummary> {
         self.raw.summary()
     }
+
+    /// Returns statistics of the cursor so far, which are updated while
+    /// rows are fetched, e.g. to report the throughput of a long fetch.
+    #[inline]
+    pub fn stats(&self) -> CursorStats {
+        CursorStats {
+            received_bytes: self.raw.received_bytes(),
+            decoded_bytes: self.raw.decoded_bytes(),
+            rows: self.returned_rows,
+            skipped_rows: self.skipped_rows,
+            blocks: self.raw.chunk_count(),
+            max_block_size: self.raw.max_chunk_size(),
+            decode_time: self.decode_time,
+        }
+    }
+
+    /// Returns the `X-ClickHouse-*` response headers, e.g. the query ID or
+    /// the server timezone. Available once the response headers have been
+    /// received.
+    #[inline]
+    pub fn headers(&self) -> Option<&ResponseHeaders> {
+        self.raw.headers()
+    }
 }
 
 impl<T> Drop for RowCursor<T> {
@@ -213,9 +513,74 @@ impl<T> Drop for RowCursor<T> {
         );
 
         tracing::debug!("finished typed query");
+
+        if let Some(mut observation) = self.observation.take() {
+            observation.set_rows(self.returned_rows);
+            observation.finish(None);
+        }
+    }
+}
+
+/// Statistics of a [`RowCursor`], see [`RowCursor::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CursorStats {
+    /// The number of bytes received from the server, see [`RowCursor::received_bytes`].
+    pub received_bytes: u64,
+    /// The number of bytes after decompression, see [`RowCursor::decoded_bytes`].
+    pub decoded_bytes: u64,
+    /// The number of rows returned so far.
+    pub rows: u64,
+    /// The number of rows skipped so far, see [`RowCursor::skip_invalid_rows`].
+    pub skipped_rows: u64,
+    /// The number of received blocks: compressed blocks if compression is
+    /// enabled, otherwise chunks of the HTTP response.
+    pub blocks: u64,
+    /// The size of the largest received block before decompression.
+    ///
+    /// A compressed block is decompressed once it's received completely,
+    /// because its checksum covers the whole block, so it's the maximum
+    /// number of bytes buffered by the cursor to decompress a block.
+    /// Blocks are limited by `max_compress_block_size` on the server.
+    pub max_block_size: u64,
+    /// The time spent deserializing rows, excluding waiting for data.
+    pub decode_time: Duration,
+}
+
+/// A row skipped because it cannot be deserialized,
+/// see [`RowCursor::skip_invalid_rows`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RowError {
+    /// The index of the row in the result, counting both returned and skipped rows.
+    pub index: u64,
+    /// The row encoded in `RowBinary`.
+    pub raw: Bytes,
+    /// Why the row cannot be deserialized.
+    pub error: Error,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid row #{}: {}", self.index, self.error)
+    }
+}
+
+impl std::error::Error for RowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
     }
 }
 
+/// A deserialized row along with its `RowBinary` encoding.
+type RowWithBytes<'a, T> = (<T as Row>::Value<'a>, &'a [u8]);
+
+struct DeferredError {
+    error: Error,
+    // The number of bytes read after the error.
+    drained: usize,
+}
+
 #[cfg(feature = "futures03")]
 impl<T> futures_util::stream::Stream for RowCursor<T>
 where
@@ -247,7 +612,7 @@ impl<'a, T> std::future::Future for Next<'a, T>
 where
     T: RowRead,
 {
-    type Output = Result<Option<T::Value<'a>>>;
+    type Output = Result<Option<RowWithBytes<'a, T>>>;
 
     #[inline]
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
@@ -255,12 +620,14 @@ where
         // the correct lifetime `'a` rather than the unnamed lifetime of `&mut self`.
         let mut cursor = self.cursor.take().expect("Future polled after completion");
 
-        polonius!(|cursor| -> Poll<Result<Option<T::Value<'polonius>>>> {
-            match cursor.poll_next(cx) {
-                Poll::Ready(value) => polonius_return!(Poll::Ready(value)),
-                Poll::Pending => {}
+        polonius!(
+            |cursor| -> Poll<Result<Option<RowWithBytes<'polonius, T>>>> {
+                match cursor.poll_next_with_bytes(cx) {
+                    Poll::Ready(value) => polonius_return!(Poll::Ready(value)),
+                    Poll::Pending => {}
+                }
             }
-        });
+        );
 
         self.cursor = Some(cursor);
         Poll::Pending
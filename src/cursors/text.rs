@@ -0,0 +1,652 @@
+use std::{marker::PhantomData, str::FromStr, vec};
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use tokio::io::AsyncBufReadExt;
+
+use crate::{
+    cursors::BytesCursor,
+    error::{Error, Result},
+    query_summary::QuerySummary,
+    response_headers::ResponseHeaders,
+};
+
+/// A cursor that emits rows of `CSVWithNames` or `TSVWithNames`,
+/// returned by [`Query::fetch_csv`] and [`Query::fetch_tsv`].
+///
+/// Unlike [`RowCursor`], it requires `T: DeserializeOwned` and uses text
+/// representation of values: numbers and booleans are parsed, `NULL` is
+/// deserialized as `None`, and other values (e.g. `Date`, `UUID`, `Decimal`)
+/// are strings. Fields of structs are matched to columns by serde names.
+/// Nested values (e.g. `Array` and `Tuple`) aren't supported.
+///
+/// # Dropping
+///
+/// Similar to [`RowCursor`], see [`Client::with_cursor_drain_limit`].
+///
+/// [`Query::fetch_csv`]: crate::query::Query::fetch_csv
+/// [`Query::fetch_tsv`]: crate::query::Query::fetch_tsv
+/// [`RowCursor`]: crate::query::RowCursor
+/// [`Client::with_cursor_drain_limit`]: crate::Client::with_cursor_drain_limit
+#[must_use]
+pub struct TextCursor<T> {
+    bytes: BytesCursor,
+    format: TextFormat,
+    record: Vec<u8>,
+    names: Option<Vec<String>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TextFormat {
+    Csv,
+    Tsv,
+}
+
+impl<T: DeserializeOwned> TextCursor<T> {
+    pub(crate) fn new(bytes: BytesCursor, format: TextFormat) -> Self {
+        Self {
+            bytes,
+            format,
+            record: Vec::new(),
+            names: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Emits the next row.
+    ///
+    /// The result is unspecified if it's called after `Err` is returned.
+    pub async fn next(&mut self) -> Result<Option<T>> {
+        loop {
+            let Some(fields) = self.read_record().await? else {
+                return Ok(None);
+            };
+
+            let Some(names) = &self.names else {
+                self.names = Some(parse_names(fields)?);
+                continue;
+            };
+
+            return T::deserialize(RecordDeserializer::new(names, fields)).map(Some);
+        }
+    }
+
+    /// Returns the `X-ClickHouse-*` headers of the response, see [`BytesCursor::headers`].
+    pub fn headers(&self) -> Option<&ResponseHeaders> {
+        self.bytes.headers()
+    }
+
+    /// Returns the summary of the query, see [`BytesCursor::summary`].
+    pub fn summary(&self) -> Option<&QuerySummary> {
+        self.bytes.summary()
+    }
+
+    /// Reads lines until they form a whole record,
+    /// because quoted CSV values can contain line breaks.
+    async fn read_record(&mut self) -> Result<Option<Vec<Option<String>>>> {
+        self.record.clear();
+        loop {
+            let is_eof = self.bytes.read_until(b'\n', &mut self.record).await? == 0;
+            if is_eof && self.record.is_empty() {
+                return Ok(None);
+            }
+
+            if let Some(fields) = self.format.split(&self.record, is_eof)? {
+                return Ok(Some(fields));
+            }
+        }
+    }
+}
+
+fn parse_names(fields: Vec<Option<String>>) -> Result<Vec<String>> {
+    fields
+        .into_iter()
+        .map(|name| name.ok_or_else(|| Error::BadResponse("NULL in column names".into())))
+        .collect()
+}
+
+// === Parsing ===
+
+impl TextFormat {
+    /// Splits a record into fields, `None` if it isn't complete yet.
+    fn split(self, record: &[u8], is_eof: bool) -> Result<Option<Vec<Option<String>>>> {
+        match self {
+            Self::Csv => split_csv(record, is_eof),
+            Self::Tsv => split_tsv(record).map(Some),
+        }
+    }
+}
+
+const NULL: &[u8] = b"\\N";
+
+fn split_tsv(record: &[u8]) -> Result<Vec<Option<String>>> {
+    let record = record.strip_suffix(b"\n").unwrap_or(record);
+
+    record
+        .split(|&b| b == b'\t')
+        .map(|field| {
+            if field == NULL {
+                return Ok(None);
+            }
+
+            let mut value = Vec::with_capacity(field.len());
+            let mut bytes = field.iter();
+            while let Some(&b) = bytes.next() {
+                if b != b'\\' {
+                    value.push(b);
+                    continue;
+                }
+
+                value.push(match bytes.next() {
+                    Some(b'b') => b'\x08',
+                    Some(b'f') => b'\x0c',
+                    Some(b'r') => b'\r',
+                    Some(b'n') => b'\n',
+                    Some(b't') => b'\t',
+                    Some(b'0') => b'\0',
+                    Some(&b) => b,
+                    None => b'\\',
+                });
+            }
+
+            Ok(Some(
+                String::from_utf8(value).map_err(|err| err.utf8_error())?,
+            ))
+        })
+        .collect()
+}
+
+fn split_csv(record: &[u8], is_eof: bool) -> Result<Option<Vec<Option<String>>>> {
+    let mut fields = Vec::new();
+    let mut rest = record;
+
+    loop {
+        let (value, is_quoted) = if let Some(quoted) = rest.strip_prefix(b"\"") {
+            let mut value = Vec::new();
+            let mut pos = 0;
+            loop {
+                match quoted[pos..].iter().position(|&b| b == b'"') {
+                    Some(len) if quoted.get(pos + len + 1) == Some(&b'"') => {
+                        value.extend_from_slice(&quoted[pos..=pos + len]);
+                        pos += len + 2;
+                    }
+                    Some(len) => {
+                        value.extend_from_slice(&quoted[pos..pos + len]);
+                        rest = &quoted[pos + len + 1..];
+                        break;
+                    }
+                    None if is_eof => {
+                        return Err(Error::BadResponse("unterminated quoted CSV value".into()));
+                    }
+                    None => return Ok(None),
+                }
+            }
+            (value, true)
+        } else {
+            let len = rest
+                .iter()
+                .position(|&b| b == b',' || b == b'\n')
+                .unwrap_or(rest.len());
+            let value = rest[..len].strip_suffix(b"\r").unwrap_or(&rest[..len]);
+            let value = value.to_vec();
+            rest = &rest[len..];
+            (value, false)
+        };
+
+        fields.push(if !is_quoted && value == NULL {
+            None
+        } else {
+            Some(String::from_utf8(value).map_err(|err| err.utf8_error())?)
+        });
+
+        match rest.split_first() {
+            Some((b',', tail)) => rest = tail,
+            Some((b'\r', tail)) if tail.is_empty() || tail == b"\n" => return Ok(Some(fields)),
+            Some((b'\n', _)) | None => return Ok(Some(fields)),
+            Some((&b, _)) => {
+                return Err(Error::BadResponse(format!(
+                    "unexpected {:?} after quoted CSV value",
+                    char::from(b)
+                )));
+            }
+        }
+    }
+}
+
+// === RecordDeserializer ===
+
+/// Deserializes a row from fields of a record, structs are matched by names.
+struct RecordDeserializer<'a> {
+    names: std::slice::Iter<'a, String>,
+    fields: vec::IntoIter<Option<String>>,
+}
+
+impl<'a> RecordDeserializer<'a> {
+    fn new(names: &'a [String], fields: Vec<Option<String>>) -> Self {
+        Self {
+            names: names.iter(),
+            fields: fields.into_iter(),
+        }
+    }
+
+    /// Returns the only field of a record, e.g. for `fetch_csv::<u64>()`.
+    fn single(mut self) -> Result<FieldDeserializer> {
+        match (self.fields.next(), self.fields.next()) {
+            (Some(field), None) => Ok(FieldDeserializer(field)),
+            _ => Err(Error::SchemaMismatch(
+                "expected exactly one column for a non-struct row".into(),
+            )),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for RecordDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_map(self)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_option
+        deserialize_unit deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.single()?.deserialize_unit(visitor)
+    }
+}
+
+impl<'de> MapAccess<'de> for RecordDeserializer<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.fields.len() == 0 {
+            return Ok(None);
+        }
+
+        let Some(name) = self.names.next() else {
+            return Err(Error::BadResponse("more values than columns".into()));
+        };
+
+        seed.deserialize(name.as_str().into_deserializer())
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let field = self
+            .fields
+            .next()
+            .expect("next_key_seed must be called first");
+        seed.deserialize(FieldDeserializer(field))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+impl<'de> SeqAccess<'de> for RecordDeserializer<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        self.fields
+            .next()
+            .map(|field| seed.deserialize(FieldDeserializer(field)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+// === FieldDeserializer ===
+
+/// Deserializes a single value, `None` is `NULL`.
+struct FieldDeserializer(Option<String>);
+
+impl FieldDeserializer {
+    fn value(self) -> Result<String> {
+        self.0
+            .ok_or_else(|| Error::Custom("unexpected NULL, use `Option<_>`".into()))
+    }
+
+    fn parse<T>(self, type_name: &str) -> Result<T>
+    where
+        T: FromStr<Err: std::fmt::Display>,
+    {
+        let value = self.value()?;
+        value
+            .parse()
+            .map_err(|err| Error::Custom(format!("invalid {type_name} {value:?}: {err}")))
+    }
+}
+
+macro_rules! impl_parse {
+    ($($method:ident => $visit:ident: $ty:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.parse::<$ty>(stringify!($ty))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for FieldDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Some(value) => visitor.visit_string(value),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value()?.as_str() {
+            "true" | "1" => visitor.visit_bool(true),
+            "false" | "0" => visitor.visit_bool(false),
+            value => Err(Error::Custom(format!("invalid bool {value:?}"))),
+        }
+    }
+
+    impl_parse! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+        deserialize_char => visit_char: char,
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.value()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.value()?)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.value()?)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_byte_buf(self.value()?.into_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_byte_buf(self.value()?.into_bytes())
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Some(value) => visitor.visit_some(Self(Some(value))),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let value: de::value::StringDeserializer<Error> = self.value()?.into_deserializer();
+        visitor.visit_enum(value)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(unsupported_nested())
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(unsupported_nested())
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value> {
+        Err(unsupported_nested())
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(unsupported_nested())
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value> {
+        Err(unsupported_nested())
+    }
+}
+
+fn unsupported_nested() -> Error {
+    Error::Unsupported("nested values aren't supported in text formats".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Sample {
+        id: u64,
+        name: String,
+        score: Option<f64>,
+        active: bool,
+    }
+
+    fn parse_all<T: DeserializeOwned>(format: TextFormat, body: &str) -> Result<Vec<T>> {
+        let mut lines = body.split_inclusive('\n');
+        let mut names: Option<Vec<String>> = None;
+        let mut rows = Vec::new();
+        let mut record = Vec::new();
+
+        while let Some(line) = lines.next() {
+            record.extend_from_slice(line.as_bytes());
+            let is_eof = lines.clone().next().is_none();
+            let Some(fields) = format.split(&record, is_eof)? else {
+                continue;
+            };
+            record.clear();
+
+            match &names {
+                Some(names) => rows.push(T::deserialize(RecordDeserializer::new(names, fields))?),
+                None => names = Some(parse_names(fields)?),
+            }
+        }
+
+        Ok(rows)
+    }
+
+    #[test]
+    fn it_splits_tsv() {
+        assert_eq!(
+            split_tsv(b"1\ta\\tb\\\\c\\n\t\\N\t\n").unwrap(),
+            [
+                Some("1".into()),
+                Some("a\tb\\c\n".into()),
+                None,
+                Some(String::new())
+            ]
+        );
+    }
+
+    #[test]
+    fn it_splits_csv() {
+        assert_eq!(
+            split_csv(b"1,\"a,\"\"b\"\"\",\\N,\"\\N\",\r\n", false).unwrap(),
+            Some(vec![
+                Some("1".into()),
+                Some("a,\"b\"".into()),
+                None,
+                Some("\\N".into()),
+                Some(String::new()),
+            ])
+        );
+
+        // Quoted values can contain line breaks.
+        assert_eq!(split_csv(b"1,\"a\n", false).unwrap(), None);
+        assert_eq!(
+            split_csv(b"1,\"a\nb\"\n", false).unwrap(),
+            Some(vec![Some("1".into()), Some("a\nb".into())])
+        );
+        assert!(split_csv(b"1,\"a\n", true).is_err());
+        assert!(split_csv(b"\"a\"b\n", false).is_err());
+    }
+
+    #[test]
+    fn it_deserializes_structs_by_names() {
+        let expected = vec![
+            Sample {
+                id: 1,
+                name: "a\nb".into(),
+                score: Some(0.5),
+                active: true,
+            },
+            Sample {
+                id: 2,
+                name: "c".into(),
+                score: None,
+                active: false,
+            },
+        ];
+
+        let tsv = "name\tactive\tid\tscore\na\\nb\ttrue\t1\t0.5\nc\tfalse\t2\t\\N\n";
+        assert_eq!(parse_all::<Sample>(TextFormat::Tsv, tsv).unwrap(), expected);
+
+        let csv = "\"name\",\"active\",\"id\",\"score\"\n\"a\nb\",true,1,0.5\n\"c\",false,2,\\N\n";
+        assert_eq!(parse_all::<Sample>(TextFormat::Csv, csv).unwrap(), expected);
+    }
+
+    #[test]
+    fn it_deserializes_tuples_and_primitives() {
+        let csv = "\"id\",\"name\"\n1,\"a\"\n2,\"b\"";
+        assert_eq!(
+            parse_all::<(u32, String)>(TextFormat::Csv, csv).unwrap(),
+            [(1, "a".into()), (2, "b".into())]
+        );
+
+        let tsv = "number\n1\n2\n";
+        assert_eq!(parse_all::<u64>(TextFormat::Tsv, tsv).unwrap(), [1, 2]);
+        assert!(parse_all::<u64>(TextFormat::Tsv, "a\tb\n1\t2\n").is_err());
+    }
+
+    #[test]
+    fn it_reports_invalid_values() {
+        let err = parse_all::<u64>(TextFormat::Tsv, "number\nfoo\n").unwrap_err();
+        assert!(err.to_string().contains("invalid u64 \"foo\""), "{err}");
+
+        let err = parse_all::<u64>(TextFormat::Tsv, "number\n\\N\n").unwrap_err();
+        assert!(err.to_string().contains("unexpected NULL"), "{err}");
+
+        let err = parse_all::<Vec<Vec<u8>>>(TextFormat::Tsv, "arr\n[1]\n").unwrap_err();
+        assert!(matches!(err, Error::Unsupported(_)), "{err}");
+    }
+}
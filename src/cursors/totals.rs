@@ -0,0 +1,252 @@
+use crate::{
+    cursors::BytesCursor,
+    error::{Error, Result},
+    query_summary::QuerySummary,
+    response_headers::ResponseHeaders,
+};
+use serde::de::DeserializeOwned;
+use tokio::io::AsyncBufReadExt;
+
+/// A cursor that emits rows of an aggregation query along with its `WITH TOTALS`
+/// and extremes rows, returned by [`Query::fetch_with_totals`].
+///
+/// Rows are read from `JSONCompactEachRowWithNamesAndTypes`, where these rows
+/// are written after the main data, each block separated by an empty line.
+/// Thus, unlike [`RowCursor`], it requires `T: DeserializeOwned` and uses JSON
+/// representation of values, e.g. `Date` is a string, not a number of days.
+///
+/// [`totals`](Self::totals) and [`extremes`](Self::extremes) are available
+/// once [`next`](Self::next) returns `None`.
+///
+/// # Dropping
+///
+/// Similar to [`RowCursor`], see [`Client::with_cursor_drain_limit`].
+///
+/// [`Query::fetch_with_totals`]: crate::query::Query::fetch_with_totals
+/// [`RowCursor`]: crate::query::RowCursor
+/// [`Client::with_cursor_drain_limit`]: crate::Client::with_cursor_drain_limit
+#[must_use]
+pub struct TotalsCursor<T> {
+    bytes: BytesCursor,
+    line: Vec<u8>,
+    parser: Parser<T>,
+}
+
+/// The `min` and `max` rows calculated if the `extremes` setting is enabled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extremes<T> {
+    pub min: T,
+    pub max: T,
+}
+
+impl<T: DeserializeOwned> TotalsCursor<T> {
+    pub(crate) fn new(bytes: BytesCursor) -> Self {
+        Self {
+            bytes,
+            line: Vec::new(),
+            parser: Parser::new(),
+        }
+    }
+
+    /// Emits the next row of the main data.
+    ///
+    /// The result is unspecified if it's called after `Err` is returned.
+    pub async fn next(&mut self) -> Result<Option<T>> {
+        while self.parser.state != State::Finished {
+            self.line.clear();
+            if self.bytes.read_until(b'\n', &mut self.line).await? == 0 {
+                self.parser.end()?;
+                break;
+            }
+
+            let line = self.line.strip_suffix(b"\n").unwrap_or(&self.line);
+            if let Some(row) = self.parser.line(line)? {
+                return Ok(Some(row));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the `WITH TOTALS` row, if the query has it.
+    ///
+    /// Available once [`next`](Self::next) returns `None`.
+    pub fn totals(&self) -> Option<&T> {
+        self.parser.totals.as_ref()
+    }
+
+    /// Returns the extremes, if the `extremes` setting is enabled.
+    ///
+    /// Available once [`next`](Self::next) returns `None`.
+    pub fn extremes(&self) -> Option<&Extremes<T>> {
+        self.parser.extremes.as_ref()
+    }
+
+    /// Returns the totals and extremes, consuming the cursor.
+    pub fn into_parts(self) -> (Option<T>, Option<Extremes<T>>) {
+        (self.parser.totals, self.parser.extremes)
+    }
+
+    /// Returns the `X-ClickHouse-*` headers of the response, see [`BytesCursor::headers`].
+    pub fn headers(&self) -> Option<&ResponseHeaders> {
+        self.bytes.headers()
+    }
+
+    /// Returns the summary of the query, see [`BytesCursor::summary`].
+    pub fn summary(&self) -> Option<&QuerySummary> {
+        self.bytes.summary()
+    }
+}
+
+// === Parser ===
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// The number of header lines (names and types) left to skip.
+    Header(u8),
+    Rows,
+    /// Blocks after the main data: totals and extremes.
+    Blocks,
+    Finished,
+}
+
+struct Parser<T> {
+    state: State,
+    /// Rows of the current block after the main data.
+    block: Vec<T>,
+    totals: Option<T>,
+    extremes: Option<Extremes<T>>,
+}
+
+impl<T: DeserializeOwned> Parser<T> {
+    fn new() -> Self {
+        Self {
+            state: State::Header(2),
+            block: Vec::new(),
+            totals: None,
+            extremes: None,
+        }
+    }
+
+    /// Handles a line without the trailing `\n`, returns a row of the main data.
+    fn line(&mut self, line: &[u8]) -> Result<Option<T>> {
+        match self.state {
+            State::Header(left) if line.is_empty() => {
+                return Err(Error::BadResponse(format!(
+                    "expected {left} more header lines"
+                )));
+            }
+            State::Header(1) => self.state = State::Rows,
+            State::Header(left) => self.state = State::Header(left - 1),
+            State::Rows if line.is_empty() => self.state = State::Blocks,
+            State::Rows => return parse(line).map(Some),
+            State::Blocks if line.is_empty() => self.finish_block()?,
+            State::Blocks => self.block.push(parse(line)?),
+            State::Finished => {}
+        }
+        Ok(None)
+    }
+
+    fn end(&mut self) -> Result<()> {
+        self.finish_block()?;
+        self.state = State::Finished;
+        Ok(())
+    }
+
+    /// A block of one row is totals, a block of two rows is extremes.
+    fn finish_block(&mut self) -> Result<()> {
+        let mut block = std::mem::take(&mut self.block).into_iter();
+        match (block.next(), block.next(), block.next()) {
+            (None, _, _) => {}
+            (Some(totals), None, _) if self.totals.is_none() && self.extremes.is_none() => {
+                self.totals = Some(totals);
+            }
+            (Some(min), Some(max), None) if self.extremes.is_none() => {
+                self.extremes = Some(Extremes { min, max });
+            }
+            _ => {
+                return Err(Error::BadResponse(
+                    "unexpected block after the main data".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse<T: DeserializeOwned>(line: &[u8]) -> Result<T> {
+    serde_json::from_slice(line).map_err(|err| Error::BadResponse(format!("invalid row: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Agg {
+        key: String,
+        sum: u64,
+    }
+
+    fn check(body: &str) -> (Vec<Agg>, Option<Agg>, Option<Extremes<Agg>>) {
+        let mut parser = Parser::<Agg>::new();
+        let mut rows = Vec::new();
+        for line in body.strip_suffix('\n').unwrap_or(body).split('\n') {
+            rows.extend(parser.line(line.as_bytes()).unwrap());
+        }
+        parser.end().unwrap();
+        (rows, parser.totals, parser.extremes)
+    }
+
+    fn agg(key: &str, sum: u64) -> Agg {
+        Agg {
+            key: key.into(),
+            sum,
+        }
+    }
+
+    const HEADER: &str = "[\"key\", \"sum\"]\n[\"String\", \"UInt64\"]\n";
+
+    #[test]
+    fn rows_only() {
+        let body = format!("{HEADER}[\"a\", 1]\n[\"b\", 2]\n");
+        let (rows, totals, extremes) = check(&body);
+        assert_eq!(rows, [agg("a", 1), agg("b", 2)]);
+        assert_eq!(totals, None);
+        assert_eq!(extremes, None);
+    }
+
+    #[test]
+    fn totals_and_extremes() {
+        let body =
+            format!("{HEADER}[\"a\", 1]\n[\"b\", 2]\n\n[\"\", 3]\n\n[\"a\", 1]\n[\"b\", 2]\n");
+        let (rows, totals, extremes) = check(&body);
+        assert_eq!(rows, [agg("a", 1), agg("b", 2)]);
+        assert_eq!(totals, Some(agg("", 3)));
+        assert_eq!(
+            extremes,
+            Some(Extremes {
+                min: agg("a", 1),
+                max: agg("b", 2)
+            })
+        );
+    }
+
+    #[test]
+    fn extremes_only() {
+        let body = format!("{HEADER}[\"a\", 1]\n\n[\"a\", 1]\n[\"a\", 1]\n");
+        let (rows, totals, extremes) = check(&body);
+        assert_eq!(rows, [agg("a", 1)]);
+        assert_eq!(totals, None);
+        assert!(extremes.is_some());
+    }
+
+    #[test]
+    fn empty() {
+        let (rows, totals, extremes) = check(HEADER);
+        assert!(rows.is_empty());
+        assert_eq!(totals, None);
+        assert_eq!(extremes, None);
+    }
+}
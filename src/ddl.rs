@@ -0,0 +1,303 @@
+//! Cluster-aware DDL, see [`Client::ddl`].
+//!
+//! [`Client::ddl`]: crate::Client::ddl
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{
+    Client,
+    error::{Error, Result},
+    settings,
+    sql::{escape, split},
+};
+
+const DISTRIBUTED_DDL_OUTPUT_MODE: &str = "distributed_ddl_output_mode";
+const DISTRIBUTED_DDL_TASK_TIMEOUT: &str = "distributed_ddl_task_timeout";
+const OUTPUT_FORMAT_JSON_QUOTE_64BIT_INTEGERS: &str = "output_format_json_quote_64bit_integers";
+
+/// A DDL statement, optionally executed on a cluster.
+///
+/// Created by [`Client::ddl`].
+///
+/// [`Client::ddl`]: crate::Client::ddl
+#[must_use]
+#[derive(Clone)]
+pub struct Ddl {
+    client: Client,
+    sql: String,
+    cluster: Option<String>,
+}
+
+impl Ddl {
+    pub(crate) fn new(client: &Client, sql: &str) -> Self {
+        Self {
+            client: client.clone(),
+            sql: sql.into(),
+            cluster: None,
+        }
+    }
+
+    /// Executes the statement on all hosts of the cluster by adding
+    /// `ON CLUSTER <cluster>` after the name of the affected object.
+    ///
+    /// Supported statements are `CREATE`, `ALTER`, `DROP`, `TRUNCATE`,
+    /// `OPTIMIZE`, `ATTACH`, `DETACH`, `RENAME` and `EXCHANGE`.
+    /// Otherwise, or if the statement already contains `ON CLUSTER`,
+    /// [`Ddl::execute`] fails with [`Error::InvalidParams`].
+    pub fn on_cluster(mut self, cluster: impl Into<String>) -> Self {
+        self.cluster = Some(cluster.into());
+        self
+    }
+
+    /// Waits until the statement is executed on all hosts of the cluster
+    /// and reports their statuses even if some of them have failed.
+    ///
+    /// By default, the server stops waiting at the first failed host
+    /// and returns an exception, or returns once the statement is queued
+    /// if `distributed_ddl_output_mode` is set to `none`.
+    ///
+    /// Use [`Ddl::with_task_timeout`] to limit how long to wait.
+    pub fn wait_all_replicas(mut self) -> Self {
+        self.client.set_setting(settings::WAIT_END_OF_QUERY, "1");
+        self.client
+            .set_setting(DISTRIBUTED_DDL_OUTPUT_MODE, "never_throw");
+        self
+    }
+
+    /// Sets `distributed_ddl_task_timeout`, how long to wait for hosts
+    /// to execute the statement. Hosts not finished in time are reported
+    /// with [`DdlHostStatus::status`] set to `None`.
+    ///
+    /// The timeout is rounded up to seconds. By default, it's 180 seconds.
+    pub fn with_task_timeout(mut self, timeout: Duration) -> Self {
+        let secs = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+        self.client
+            .set_setting(DISTRIBUTED_DDL_TASK_TIMEOUT, secs.to_string());
+        self
+    }
+
+    /// Similar to [`Client::with_setting`], but for this statement only.
+    ///
+    /// [`Client::with_setting`]: crate::Client::with_setting
+    pub fn with_setting(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.client.set_setting(name, value);
+        self
+    }
+
+    /// Executes the statement and returns statuses of hosts reported by
+    /// the server, which are empty if the statement is not executed on a cluster.
+    ///
+    /// Note that it succeeds even if the statement has failed on some hosts,
+    /// check [`DdlReport::is_success`] or [`DdlReport::failed_hosts`].
+    pub async fn execute(self) -> Result<DdlReport> {
+        let sql = match &self.cluster {
+            Some(cluster) => add_on_cluster(&self.sql, cluster)?,
+            None => self.sql,
+        };
+
+        let raw = self
+            .client
+            .query(&sql.replace('?', "??"))
+            .with_setting(OUTPUT_FORMAT_JSON_QUOTE_64BIT_INTEGERS, "0")
+            .fetch_bytes("JSONEachRow")?
+            .collect()
+            .await?;
+
+        let hosts = raw
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                serde_json::from_slice(line).map_err(|err| {
+                    Error::BadResponse(format!("invalid status of a DDL task: {err}"))
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(DdlReport { hosts })
+    }
+}
+
+/// Statuses of hosts executed a DDL statement, see [`Ddl::execute`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DdlReport {
+    /// Statuses in the order returned by the server.
+    pub hosts: Vec<DdlHostStatus>,
+}
+
+impl DdlReport {
+    /// Returns `true` if the statement has succeeded on all reported hosts.
+    pub fn is_success(&self) -> bool {
+        self.hosts.iter().all(DdlHostStatus::is_success)
+    }
+
+    /// Returns hosts the statement has failed or timed out on.
+    pub fn failed_hosts(&self) -> impl Iterator<Item = &DdlHostStatus> {
+        self.hosts.iter().filter(|host| !host.is_success())
+    }
+}
+
+/// A status of a host executed a DDL statement.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[non_exhaustive]
+pub struct DdlHostStatus {
+    /// The host name, empty for databases with the `Replicated` engine.
+    #[serde(default)]
+    pub host: String,
+    /// The port, zero for databases with the `Replicated` engine.
+    #[serde(default)]
+    pub port: u16,
+    /// The error code, `0` if succeeded or `None` if not finished in time.
+    pub status: Option<i64>,
+    /// The error message, if failed.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub error: Option<String>,
+}
+
+impl DdlHostStatus {
+    /// Returns `true` if the statement has succeeded on the host.
+    pub fn is_success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+fn empty_as_none<'de, D: serde::Deserializer<'de>>(de: D) -> Result<Option<String>, D::Error> {
+    let error = Option::<String>::deserialize(de)?;
+    Ok(error.filter(|error| !error.is_empty()))
+}
+
+/// Adds `ON CLUSTER` to the statement after the name of the affected object
+/// or at the end of `RENAME` and `EXCHANGE` statements.
+fn add_on_cluster(sql: &str, cluster: &str) -> Result<String> {
+    let invalid = |msg: &str| Error::InvalidParams(format!("{msg}: {sql}").into());
+
+    let mut words = split::leading_words(sql);
+    let verb = words.next().unwrap_or_default().to_ascii_uppercase();
+
+    let mut prev_word = "";
+    for word in split::leading_words(sql) {
+        if prev_word.eq_ignore_ascii_case("ON") && word.eq_ignore_ascii_case("CLUSTER") {
+            return Err(invalid("the statement already contains ON CLUSTER"));
+        }
+        prev_word = word;
+    }
+
+    let pos = match verb.as_str() {
+        // `RENAME TABLE a TO b, c TO d [ON CLUSTER cluster]`.
+        "RENAME" | "EXCHANGE" => sql.trim_end().trim_end_matches(';').trim_end().len(),
+        "CREATE" | "ALTER" | "DROP" | "TRUNCATE" | "OPTIMIZE" | "ATTACH" | "DETACH" => words
+            .find(|word| {
+                !matches!(
+                    word.to_ascii_uppercase().as_str(),
+                    "TEMPORARY"
+                        | "OR"
+                        | "REPLACE"
+                        | "MATERIALIZED"
+                        | "IF"
+                        | "NOT"
+                        | "EXISTS"
+                        | "TABLE"
+                        | "VIEW"
+                        | "DICTIONARY"
+                        | "DATABASE"
+                        | "FUNCTION"
+                        | "USER"
+                        | "ROLE"
+                )
+            })
+            // `leading_words()` returns subslices of `sql`.
+            .map(|name| name.as_ptr() as usize - sql.as_ptr() as usize + name.len())
+            .ok_or_else(|| invalid("cannot find the name of the object"))?,
+        _ => return Err(invalid("ON CLUSTER is not supported for the statement")),
+    };
+
+    let mut result = String::with_capacity(sql.len() + cluster.len() + 16);
+    result.push_str(&sql[..pos]);
+    result.push_str(" ON CLUSTER ");
+    escape::identifier(cluster, &mut result).expect("impossible error");
+    result.push_str(&sql[pos..]);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_adds_on_cluster() {
+        for (sql, expected) in [
+            (
+                "CREATE TABLE db.t (a UInt8) ENGINE = ReplicatedMergeTree ORDER BY a",
+                "CREATE TABLE db.t ON CLUSTER `c` (a UInt8) ENGINE = ReplicatedMergeTree ORDER BY a",
+            ),
+            (
+                "create table if not exists `t`(a UInt8)",
+                "create table if not exists `t` ON CLUSTER `c`(a UInt8)",
+            ),
+            (
+                "ALTER TABLE t ADD COLUMN b String",
+                "ALTER TABLE t ON CLUSTER `c` ADD COLUMN b String",
+            ),
+            (
+                "DROP TABLE IF EXISTS t SYNC",
+                "DROP TABLE IF EXISTS t ON CLUSTER `c` SYNC",
+            ),
+            ("DROP DATABASE db", "DROP DATABASE db ON CLUSTER `c`"),
+            (
+                "-- comment\nTRUNCATE t",
+                "-- comment\nTRUNCATE t ON CLUSTER `c`",
+            ),
+            (
+                "OPTIMIZE TABLE t FINAL",
+                "OPTIMIZE TABLE t ON CLUSTER `c` FINAL",
+            ),
+            (
+                "RENAME TABLE a TO b, c TO d;\n",
+                "RENAME TABLE a TO b, c TO d ON CLUSTER `c`;\n",
+            ),
+        ] {
+            assert_eq!(add_on_cluster(sql, "c").unwrap(), expected, "{sql}");
+        }
+
+        for sql in [
+            "SELECT 1",
+            "",
+            "DROP TABLE",
+            "ALTER TABLE t ON CLUSTER c DELETE WHERE 1",
+        ] {
+            let err = add_on_cluster(sql, "c").unwrap_err();
+            assert!(matches!(err, Error::InvalidParams(_)), "{sql}: {err}");
+        }
+    }
+
+    #[test]
+    fn it_parses_host_statuses() {
+        let status = |raw: &str| serde_json::from_str::<DdlHostStatus>(raw).unwrap();
+
+        let ok =
+            status(r#"{"host":"a","port":9000,"status":0,"error":"","num_hosts_remaining":1}"#);
+        assert!(ok.is_success());
+        assert_eq!(ok.error, None);
+
+        let failed = status(r#"{"host":"b","port":9000,"status":60,"error":"Code: 60"}"#);
+        assert!(!failed.is_success());
+        assert_eq!(failed.error.as_deref(), Some("Code: 60"));
+
+        let timed_out = status(r#"{"host":"c","port":9000,"status":null,"error":null}"#);
+        assert!(!timed_out.is_success());
+
+        let report = DdlReport {
+            hosts: vec![ok, failed, timed_out],
+        };
+        assert!(!report.is_success());
+        assert_eq!(
+            report
+                .failed_hosts()
+                .map(|host| host.host.as_str())
+                .collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+    }
+}
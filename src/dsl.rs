@@ -0,0 +1,380 @@
+//! A minimal typed builder of `SELECT` queries.
+//!
+//! It isn't an ORM, it just covers simple queries without string
+//! concatenation: columns are taken from a [`Row`] definition, identifiers
+//! are escaped, and values are bound as SQL literals the same way as
+//! [`Query::bind`] does.
+//!
+//! # Example
+//!
+//! ```
+//! # async fn example() -> clickhouse::error::Result<()> {
+//! use clickhouse::{Row, dsl::{Select, col}};
+//! use serde::Deserialize;
+//!
+//! #[derive(Row, Deserialize)]
+//! struct Event {
+//!     id: u64,
+//!     name: String,
+//! }
+//!
+//! let client = clickhouse::Client::default().with_url("http://localhost:8123");
+//!
+//! let select = Select::from("events")
+//!     .columns::<Event>()
+//!     .filter(col("id").gt(10).and(col("name").ne("")))
+//!     .order_by(col("id").desc())
+//!     .limit(100);
+//!
+//! assert_eq!(
+//!     select.to_sql()?,
+//!     "SELECT `id`,`name` FROM `events` WHERE (`id` > 10 AND `name` != '') \
+//!      ORDER BY `id` DESC LIMIT 100",
+//! );
+//!
+//! let events = select.build(&client)?.fetch_all::<Event>().await?;
+//! # Ok(()) }
+//! ```
+
+use std::fmt::Write;
+
+use crate::{
+    Client,
+    error::{Error, Result},
+    query::Query,
+    row::{self, Row},
+    sql::{Bind, escape},
+};
+
+type Rendered = std::result::Result<String, String>;
+
+// === Select ===
+
+/// A builder of a `SELECT` query, see the [module-level docs](self).
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Select {
+    table: Rendered,
+    columns: Option<Rendered>,
+    filter: Option<Expr>,
+    order_by: Vec<OrderBy>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl Select {
+    /// Starts a query selecting from `table`.
+    ///
+    /// The table name is escaped as a single identifier. To query a table
+    /// in another database, use [`Client::with_database`].
+    pub fn from(table: &str) -> Self {
+        Self {
+            table: identifier(table),
+            columns: None,
+            filter: None,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// Selects columns corresponding to fields of `T`, like `?fields` does.
+    ///
+    /// By default, all columns are selected (`*`).
+    pub fn columns<T: Row>(mut self) -> Self {
+        self.columns = Some(
+            row::join_column_names::<T>().ok_or_else(|| format!("{} is not a struct", T::NAME)),
+        );
+        self
+    }
+
+    /// Adds a `WHERE` condition. Multiple conditions are combined by `AND`.
+    pub fn filter(mut self, expr: Expr) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(filter) => filter.and(expr),
+            None => expr,
+        });
+        self
+    }
+
+    /// Adds an `ORDER BY` key, see [`Column::asc`] and [`Column::desc`].
+    pub fn order_by(mut self, order: OrderBy) -> Self {
+        self.order_by.push(order);
+        self
+    }
+
+    /// Sets `LIMIT`.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets `OFFSET`.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Renders the query.
+    ///
+    /// Returns [`Error::InvalidParams`] if any identifier or value
+    /// cannot be rendered.
+    pub fn to_sql(&self) -> Result<String> {
+        self.render()
+            .map_err(|err| Error::InvalidParams(format!("failed to build a query: {err}").into()))
+    }
+
+    /// Renders the query and creates a [`Query`] to execute it.
+    ///
+    /// Values are already bound, so the query has no `?` placeholders.
+    pub fn build(&self, client: &Client) -> Result<Query> {
+        // Bound values can contain `?`, which must not be treated as placeholders.
+        let sql = self.to_sql()?.replace('?', "??");
+        Ok(client.query(&sql))
+    }
+
+    fn render(&self) -> Rendered {
+        let mut sql = String::from("SELECT ");
+
+        match &self.columns {
+            Some(columns) => sql.push_str(columns.as_ref()?),
+            None => sql.push('*'),
+        }
+
+        sql.push_str(" FROM ");
+        sql.push_str(self.table.as_ref()?);
+
+        if let Some(filter) = &self.filter {
+            sql.push_str(" WHERE ");
+            sql.push_str(filter.0.as_ref()?);
+        }
+
+        for (i, order) in self.order_by.iter().enumerate() {
+            sql.push_str(if i == 0 { " ORDER BY " } else { ", " });
+            sql.push_str(order.0.as_ref()?);
+        }
+
+        if let Some(limit) = self.limit {
+            write!(sql, " LIMIT {limit}").map_err(|err| err.to_string())?;
+        }
+
+        if let Some(offset) = self.offset {
+            write!(sql, " OFFSET {offset}").map_err(|err| err.to_string())?;
+        }
+
+        Ok(sql)
+    }
+}
+
+// === Column ===
+
+/// Refers to a column, see [`Column`].
+pub fn col(name: &str) -> Column {
+    Column(identifier(name))
+}
+
+/// A column used to build [`Expr`] and [`OrderBy`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Column(Rendered);
+
+impl Column {
+    /// `column = value`
+    pub fn eq(self, value: impl Bind) -> Expr {
+        self.binary("=", value)
+    }
+
+    /// `column != value`
+    pub fn ne(self, value: impl Bind) -> Expr {
+        self.binary("!=", value)
+    }
+
+    /// `column < value`
+    pub fn lt(self, value: impl Bind) -> Expr {
+        self.binary("<", value)
+    }
+
+    /// `column <= value`
+    pub fn le(self, value: impl Bind) -> Expr {
+        self.binary("<=", value)
+    }
+
+    /// `column > value`
+    pub fn gt(self, value: impl Bind) -> Expr {
+        self.binary(">", value)
+    }
+
+    /// `column >= value`
+    pub fn ge(self, value: impl Bind) -> Expr {
+        self.binary(">=", value)
+    }
+
+    /// `column LIKE pattern`
+    pub fn like(self, pattern: &str) -> Expr {
+        self.binary("LIKE", pattern)
+    }
+
+    /// `column IN (values...)`, or `false` if there are no values.
+    pub fn is_in<V: Bind>(self, values: impl IntoIterator<Item = V>) -> Expr {
+        Expr((|| {
+            let mut list = String::new();
+            for (i, value) in values.into_iter().enumerate() {
+                if i > 0 {
+                    list.push_str(", ");
+                }
+                value.write(&mut list)?;
+            }
+
+            if list.is_empty() {
+                return Ok("false".into());
+            }
+
+            Ok(format!("{} IN ({list})", self.0?))
+        })())
+    }
+
+    /// `column IS NULL`
+    pub fn is_null(self) -> Expr {
+        Expr(self.0.map(|column| format!("{column} IS NULL")))
+    }
+
+    /// `column IS NOT NULL`
+    pub fn is_not_null(self) -> Expr {
+        Expr(self.0.map(|column| format!("{column} IS NOT NULL")))
+    }
+
+    /// Ascending order by this column.
+    pub fn asc(self) -> OrderBy {
+        OrderBy(self.0.map(|column| format!("{column} ASC")))
+    }
+
+    /// Descending order by this column.
+    pub fn desc(self) -> OrderBy {
+        OrderBy(self.0.map(|column| format!("{column} DESC")))
+    }
+
+    fn binary(self, op: &str, value: impl Bind) -> Expr {
+        Expr(self.0.and_then(|mut sql| {
+            write!(sql, " {op} ").map_err(|err| err.to_string())?;
+            value.write(&mut sql)?;
+            Ok(sql)
+        }))
+    }
+}
+
+// === Expr ===
+
+/// A boolean expression used in [`Select::filter`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Expr(Rendered);
+
+impl Expr {
+    /// `(self AND other)`
+    pub fn and(self, other: Expr) -> Expr {
+        self.combine("AND", other)
+    }
+
+    /// `(self OR other)`
+    pub fn or(self, other: Expr) -> Expr {
+        self.combine("OR", other)
+    }
+
+    /// `NOT (self)`
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Expr {
+        Expr(self.0.map(|expr| format!("NOT ({expr})")))
+    }
+
+    fn combine(self, op: &str, other: Expr) -> Expr {
+        Expr(
+            self.0
+                .and_then(|lhs| Ok(format!("({lhs} {op} {})", other.0?))),
+        )
+    }
+}
+
+// === OrderBy ===
+
+/// An `ORDER BY` key, see [`Column::asc`] and [`Column::desc`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct OrderBy(Rendered);
+
+fn identifier(name: &str) -> Rendered {
+    let mut escaped = String::new();
+    escape::identifier(name, &mut escaped).map_err(|err| err.to_string())?;
+    Ok(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(crate::Row)]
+    #[clickhouse(crate = "crate")]
+    #[expect(dead_code)]
+    struct Event {
+        id: u64,
+        name: String,
+    }
+
+    #[test]
+    fn select_all() {
+        assert_eq!(Select::from("t").to_sql().unwrap(), "SELECT * FROM `t`");
+    }
+
+    #[test]
+    fn select() {
+        let sql = Select::from("events")
+            .columns::<Event>()
+            .filter(col("id").ge(10u64).or(col("id").is_in([1u64, 2])))
+            .filter(col("name").like("a'?%").not())
+            .filter(col("deleted").is_null())
+            .order_by(col("name").asc())
+            .order_by(col("id").desc())
+            .limit(10)
+            .offset(20)
+            .to_sql()
+            .unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT `id`,`name` FROM `events` \
+             WHERE (((`id` >= 10 OR `id` IN (1, 2)) AND NOT (`name` LIKE 'a\\'?%')) \
+             AND `deleted` IS NULL) \
+             ORDER BY `name` ASC, `id` DESC LIMIT 10 OFFSET 20"
+        );
+    }
+
+    #[test]
+    fn empty_in() {
+        let sql = Select::from("t")
+            .filter(col("id").is_in(Vec::<u32>::new()))
+            .to_sql()
+            .unwrap();
+        assert_eq!(sql, "SELECT * FROM `t` WHERE false");
+    }
+
+    #[test]
+    fn invalid_columns() {
+        let err = Select::from("t").columns::<u64>().to_sql().unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidParams(msg) if msg.to_string().contains("not a struct")),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn question_marks_are_escaped() {
+        let client = Client::default();
+        let query = Select::from("t")
+            .filter(col("name").eq("?"))
+            .build(&client)
+            .unwrap();
+        assert_eq!(
+            query.sql_display().to_string(),
+            "SELECT * FROM `t` WHERE `name` = '?'"
+        );
+    }
+}
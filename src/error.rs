@@ -1,7 +1,10 @@
 //! Contains [`Error`] and corresponding [`Result`].
 
 use serde::{de, ser};
-use std::{error::Error as StdError, fmt, io, result, str::Utf8Error};
+use std::{error::Error as StdError, fmt, io, result, str::Utf8Error, sync::Arc};
+use url::Url;
+
+use crate::{Client, sql::split};
 
 /// A result with a specified [`Error`] type.
 pub type Result<T, E = Error> = result::Result<T, E>;
@@ -23,6 +26,10 @@ pub enum Error {
     Decompression(#[source] BoxedError),
     #[error("no rows returned by a query that expected to return at least one row")]
     RowNotFound,
+    #[error("more than one row returned by a query that expected to return a single row")]
+    TooManyRows,
+    #[error("duplicate key returned by a query collected into a map: {0}")]
+    DuplicateKey(String),
     #[error("sequences must have a known size ahead of time")]
     SequenceMustHaveLength,
     #[error("`deserialize_any` is not supported")]
@@ -39,16 +46,104 @@ pub enum Error {
     Custom(String),
     #[error("bad response: {0}")]
     BadResponse(String),
+    /// An exception sent by the server in the middle of a response,
+    /// e.g. if `max_execution_time` is exceeded after some rows are sent.
+    /// `code` is parsed from the message, if possible.
+    #[error("server exception: {message}")]
+    ServerException { code: Option<u32>, message: String },
     #[error("timeout expired")]
     TimedOut,
+    #[error("timeout expired while waiting for a free slot to send a request")]
+    RequestQueueTimedOut,
+    /// No data has been accepted by the socket for longer than
+    /// [`Insert::with_stall_timeout`], e.g. because the server froze.
+    ///
+    /// [`Insert::with_stall_timeout`]: crate::insert::Insert::with_stall_timeout
+    #[error("insert stalled: no data has been written to the socket for too long")]
+    WriteStalled,
     #[error("error while parsing columns header from the response: {0}")]
     InvalidColumnsHeader(#[source] BoxedError),
     #[error("schema mismatch: {0}")]
     SchemaMismatch(String),
     #[error("unsupported: {0}")]
     Unsupported(String),
+    #[error("migration error: {0}")]
+    Migration(String),
+    /// Malformed `RowBinary` data detected in the strict decoding mode,
+    /// see [`Client::with_strict_decode`].
+    /// `offset` is the position in the row where the invalid value starts.
+    #[error("corrupt data at offset {offset} of the row: {reason}")]
+    CorruptData { offset: usize, reason: String },
     #[error("{0}")]
     Other(BoxedError),
+    /// An error of a request with its context attached,
+    /// see [`Client::with_error_context`].
+    #[error("{error} ({context})")]
+    WithContext {
+        #[source]
+        error: Box<Error>,
+        context: Arc<ErrorContext>,
+    },
+}
+
+/// Information to reproduce a failed request, attached to errors
+/// if [`Client::with_error_context`] is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ErrorContext {
+    /// The SQL sent to the server, with string literals replaced by `'?'`.
+    pub sql: String,
+    /// Settings sent to the server along with the query, including ones
+    /// set for the client. Values of query parameters (`param_*`) are
+    /// replaced by `?`.
+    pub settings: Vec<(String, String)>,
+    /// The server's URL without credentials and the path, e.g. `https://host:8443`.
+    pub server: String,
+}
+
+impl ErrorContext {
+    /// Returns `None` if error contexts are disabled for the client.
+    pub(crate) fn new(client: &Client, url: &Url, sql: &str) -> Option<Arc<Self>> {
+        if !client.error_context {
+            return None;
+        }
+
+        let settings = url
+            .query_pairs()
+            .filter(|(name, _)| name != crate::settings::QUERY)
+            .map(|(name, value)| {
+                let value = if name.starts_with("param_") {
+                    "?".into()
+                } else {
+                    value.into_owned()
+                };
+                (name.into_owned(), value)
+            })
+            .collect();
+
+        Some(Arc::new(Self {
+            sql: split::redact_literals(sql),
+            settings,
+            server: url.origin().ascii_serialization(),
+        }))
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "server: {}, query: {:?}, settings: ",
+            self.server, self.sql
+        )?;
+
+        f.write_str("{")?;
+        for (i, (name, value)) in self.settings.iter().enumerate() {
+            let sep = if i > 0 { ", " } else { "" };
+            write!(f, "{sep}{name}={value}")?;
+        }
+        f.write_str("}")
+    }
 }
 
 impl From<clickhouse_types::error::TypesError> for Error {
@@ -114,7 +209,7 @@ impl From<io::Error> for Error {
 
 impl Error {
     /// https://opentelemetry.io/docs/specs/semconv/registry/attributes/error/#error-type
-    #[cfg(feature = "opentelemetry")]
+    #[cfg(any(feature = "opentelemetry", feature = "metrics"))]
     pub(crate) fn error_type(&self) -> &str {
         match self {
             Error::InvalidParams(_) => "InvalidParams",
@@ -122,6 +217,8 @@ impl Error {
             Error::Compression(_) => "Compression",
             Error::Decompression(_) => "Decompression",
             Error::RowNotFound => "RowNotFound",
+            Error::TooManyRows => "TooManyRows",
+            Error::DuplicateKey(_) => "DuplicateKey",
             Error::SequenceMustHaveLength => "SequenceMustHaveLength",
             Error::DeserializeAnyNotSupported => "DeserializeAnyNotSupported",
             Error::NotEnoughData => "NotEnoughData",
@@ -130,11 +227,54 @@ impl Error {
             Error::VariantDiscriminatorIsOutOfBound(_) => "VariantDiscriminatorIsOutOfBound",
             Error::Custom(_) => "Custom",
             Error::BadResponse(_) => "BadResponse",
+            Error::ServerException { .. } => "ServerException",
             Error::TimedOut => "TimedOut",
+            Error::RequestQueueTimedOut => "RequestQueueTimedOut",
+            Error::WriteStalled => "WriteStalled",
             Error::InvalidColumnsHeader(_) => "InvalidColumnsHeader",
             Error::SchemaMismatch(_) => "SchemaMismatch",
             Error::Unsupported(_) => "Unsupported",
+            Error::Migration(_) => "Migration",
+            Error::CorruptData { .. } => "CorruptData",
             Error::Other(_) => "Other",
+            Error::WithContext { error, .. } => error.error_type(),
+        }
+    }
+
+    /// Returns the context of the failed request,
+    /// if [`Client::with_error_context`] is enabled.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without the context attached by
+    /// [`Client::with_error_context`], e.g. to match it.
+    ///
+    /// # Example
+    /// ```
+    /// # use clickhouse::error::{Error, Result};
+    /// fn is_timeout<T>(result: &Result<T>) -> bool {
+    ///     matches!(result, Err(err) if matches!(err.without_context(), Error::TimedOut))
+    /// }
+    /// ```
+    pub fn without_context(&self) -> &Error {
+        match self {
+            Error::WithContext { error, .. } => error,
+            _ => self,
+        }
+    }
+
+    /// Attaches the context, if any and not attached yet.
+    pub(crate) fn with_context(self, context: Option<&Arc<ErrorContext>>) -> Self {
+        match context {
+            Some(context) if self.context().is_none() => Error::WithContext {
+                error: Box::new(self),
+                context: context.clone(),
+            },
+            _ => self,
         }
     }
 
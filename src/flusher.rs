@@ -0,0 +1,189 @@
+//! Support for [`Inserter::with_flush_interval`].
+//!
+//! While the inserter is idle, i.e. between calls, the `INSERT` of the current
+//! batch is parked in a slot shared with a background task. If it stays parked
+//! longer than the interval, the task takes and ends it. The inserter takes
+//! the `INSERT` back on the next call and reports the result of the flush.
+//!
+//! [`Inserter::with_flush_interval`]: crate::inserter::Inserter::with_flush_interval
+
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use hyper_util::client::legacy::connect::CaptureConnection;
+use tokio::{sync::Notify, task::JoinHandle, time::Duration};
+
+use crate::{
+    error::Result,
+    insert::{Insert, WriteStats},
+    ticks::Instant,
+};
+
+/// An ended `INSERT`, either by the inserter or by the flusher.
+pub(crate) struct EndedInsert {
+    pub(crate) stats: Result<WriteStats>,
+    pub(crate) started_at: Instant,
+    pub(crate) ended_at: Instant,
+    pub(crate) connection: Option<CaptureConnection>,
+}
+
+impl EndedInsert {
+    pub(crate) async fn end<T>(insert: Insert<T>) -> Self {
+        let connection = insert.connection().cloned();
+        let started_at = Instant::now();
+        let stats = insert.end().await;
+
+        Self {
+            stats,
+            started_at,
+            ended_at: Instant::now(),
+            connection,
+        }
+    }
+}
+
+/// Returned by [`IdleFlusher::unpark`].
+pub(crate) struct Unparked<T> {
+    /// `None` if the parked `INSERT` has been taken by the flusher.
+    pub(crate) insert: Option<Insert<T>>,
+    /// Results of background flushes not reported yet, in order.
+    pub(crate) ended: Vec<EndedInsert>,
+}
+
+pub(crate) struct IdleFlusher<T> {
+    shared: Arc<Shared<T>>,
+    task: Option<JoinHandle<()>>,
+}
+
+struct Shared<T> {
+    slot: Mutex<Slot<T>>,
+    notify: Notify,
+}
+
+struct Slot<T> {
+    interval: Duration,
+    insert: Option<Insert<T>>,
+    parked_at: Instant,
+    ended: Vec<EndedInsert>,
+    closed: bool,
+}
+
+impl<T: 'static> IdleFlusher<T> {
+    /// Spawns the background task.
+    ///
+    /// # Panics
+    /// If called outside of a Tokio runtime.
+    pub(crate) fn spawn(interval: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            slot: Mutex::new(Slot {
+                interval,
+                insert: None,
+                parked_at: Instant::now(),
+                ended: Vec::new(),
+                closed: false,
+            }),
+            notify: Notify::new(),
+        });
+
+        Self {
+            task: Some(tokio::spawn(run(shared.clone()))),
+            shared,
+        }
+    }
+}
+
+impl<T> IdleFlusher<T> {
+    pub(crate) fn set_interval(&self, interval: Duration) {
+        self.shared.slot.lock().unwrap().interval = interval;
+        self.shared.notify.notify_one();
+    }
+
+    /// Leaves the `INSERT` to be ended by the flusher if the inserter is idle.
+    pub(crate) fn park(&self, insert: Insert<T>) {
+        let mut slot = self.shared.slot.lock().unwrap();
+        debug_assert!(slot.insert.is_none());
+        slot.insert = Some(insert);
+        slot.parked_at = Instant::now();
+        drop(slot);
+
+        self.shared.notify.notify_one();
+    }
+
+    pub(crate) fn unpark(&self) -> Unparked<T> {
+        let mut slot = self.shared.slot.lock().unwrap();
+        Unparked {
+            insert: slot.insert.take(),
+            ended: mem::take(&mut slot.ended),
+        }
+    }
+
+    /// Calls `f` for the parked `INSERT`, if any.
+    pub(crate) fn with_parked<R>(&self, f: impl FnOnce(&mut Insert<T>) -> R) -> Option<R> {
+        self.shared.slot.lock().unwrap().insert.as_mut().map(f)
+    }
+
+    /// Stops the task, waiting for the background flush in progress.
+    pub(crate) async fn close(mut self) -> Unparked<T> {
+        self.shared.slot.lock().unwrap().closed = true;
+        self.shared.notify.notify_one();
+
+        if let Some(task) = self.task.take() {
+            // The task panics only if the `INSERT` does.
+            let _ = task.await;
+        }
+
+        self.unpark()
+    }
+}
+
+impl<T> Drop for IdleFlusher<T> {
+    fn drop(&mut self) {
+        // Aborts the background flush in progress, like dropping the inserter
+        // aborts the current `INSERT`.
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+async fn run<T>(shared: Arc<Shared<T>>) {
+    loop {
+        let deadline = {
+            let slot = shared.slot.lock().unwrap();
+            if slot.closed {
+                return;
+            }
+            slot.insert.as_ref().map(|_| slot.parked_at + slot.interval)
+        };
+
+        let now = Instant::now();
+        match deadline {
+            Some(deadline) if deadline <= now => {
+                let insert = {
+                    let mut slot = shared.slot.lock().unwrap();
+                    if slot.closed {
+                        return;
+                    }
+                    slot.insert.take()
+                };
+
+                // Could be taken back by the inserter in the meantime.
+                if let Some(insert) = insert {
+                    tracing::debug!("flushing idle insert");
+                    let ended = EndedInsert::end(insert).await;
+                    shared.slot.lock().unwrap().ended.push(ended);
+                }
+            }
+            Some(deadline) => {
+                let delay = deadline.saturating_duration_since(now);
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = shared.notify.notified() => {}
+                }
+            }
+            None => shared.notify.notified().await,
+        }
+    }
+}
@@ -1,8 +1,14 @@
-use crate::{Authentication, ProductInfo};
-use hyper::header::{AUTHORIZATION, USER_AGENT};
+use crate::error::{Error, Result};
+use crate::request_body::RequestBody;
+use crate::{Authentication, Client, ProductInfo, proxy};
+use hyper::body::Incoming;
+use hyper::header::{AUTHORIZATION, HeaderValue, PROXY_AUTHORIZATION, USER_AGENT};
 use hyper::http::request::Builder;
-use std::collections::HashMap;
+use hyper::{Request, Response};
 use std::env::consts::OS;
+use std::future::Future;
+
+pub(crate) const QUOTA_KEY: &str = "X-ClickHouse-Quota";
 
 fn get_user_agent(products_info: &[ProductInfo]) -> String {
     // See https://doc.rust-lang.org/cargo/reference/environment-variables.html#environment-variables-cargo-sets-for-crates
@@ -23,31 +29,104 @@ fn get_user_agent(products_info: &[ProductInfo]) -> String {
 }
 
 #[inline]
-pub(crate) fn with_request_headers(
-    mut builder: Builder,
-    headers: &HashMap<String, String>,
-    products_info: &[ProductInfo],
-) -> Builder {
-    // Inject the OpenTelemetry trace context if the feature is enabled
+pub(crate) fn with_request_headers(mut builder: Builder, client: &Client) -> Builder {
+    // Inject the OpenTelemetry trace context if the feature is enabled.
+    // Will only be `None` if there's already an error in building the request,
+    // in which case injecting the headers would be redundant anyway.
     #[cfg(feature = "opentelemetry")]
+    if client.trace_context_propagation
+        && let Some(headers) = builder.headers_mut()
+    {
+        inject_trace_context(headers);
+    }
+
+    for (name, value) in &client.headers {
+        builder = builder.header(name, value);
+    }
+
+    let matcher = client.pool.as_ref().and_then(|pool| pool.proxy.as_deref());
+    if let Some(matcher) = matcher
+        && let Some(uri) = builder.uri_ref()
+        && let Some(auth) = proxy::authorization(matcher, uri)
+    {
+        builder = builder.header(PROXY_AUTHORIZATION, auth);
+    }
+
+    builder = builder.header(
+        USER_AGENT.to_string(),
+        get_user_agent(&client.products_info),
+    );
+    builder
+}
+
+/// Sends the request, requesting an access token first if
+/// [`Client::with_access_token_provider`] is used.
+///
+/// The returned future doesn't borrow the client, and the token isn't
+/// requested until it's polled.
+pub(crate) fn send_request(
+    client: &Client,
+    mut request: Request<RequestBody>,
+) -> impl Future<Output = Result<Response<Incoming>>> + Send + 'static {
+    let http = client.http.clone();
+    let provider = match &client.authentication {
+        Authentication::JwtProvider(provider) => Some(provider.0.clone()),
+        _ => None,
+    };
+
+    async move {
+        if let Some(provider) = provider {
+            let bearer = format!("Bearer {}", provider.token().await);
+            let mut value =
+                HeaderValue::from_str(&bearer).map_err(|err| Error::InvalidParams(err.into()))?;
+            value.set_sensitive(true);
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+
+        Ok(http.request(request).await?)
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+fn inject_trace_context(headers: &mut hyper::HeaderMap) {
+    use opentelemetry::{Context, trace::TraceContextExt};
+    use opentelemetry_http::HeaderInjector;
+
     opentelemetry::global::get_text_map_propagator(|propagator| {
-        use opentelemetry_http::HeaderInjector;
+        // Prefer the configured propagator, if any.
+        if propagator.fields().next().is_some() {
+            // Note that `HeaderInjector` skips headers with invalid names, as of writing.
+            propagator.inject(&mut HeaderInjector(headers));
+            return;
+        }
 
-        // Will only be `None` if there's already an error in building the request,
-        // in which case injecting the headers would be redundant anyway.
-        let Some(headers) = builder.headers_mut() else {
+        // Otherwise, fall back to W3C Trace Context, which is the only format
+        // supported by ClickHouse anyway.
+        // See https://www.w3.org/TR/trace-context/#traceparent-header
+        let context = Context::current();
+        let span = context.span();
+        let span_context = span.span_context();
+        if !span_context.is_valid() {
             return;
-        };
+        }
 
-        // Note that `HeaderInjector` skips headers with invalid names, as of writing.
-        propagator.inject(&mut HeaderInjector(headers));
-    });
+        let traceparent = format!(
+            "00-{}-{}-{:02x}",
+            span_context.trace_id(),
+            span_context.span_id(),
+            span_context.trace_flags(),
+        );
+        if let Ok(value) = HeaderValue::from_str(&traceparent) {
+            headers.insert("traceparent", value);
+        }
 
-    for (name, value) in headers {
-        builder = builder.header(name, value);
-    }
-    builder = builder.header(USER_AGENT.to_string(), get_user_agent(products_info));
-    builder
+        let tracestate = span_context.trace_state().header();
+        if !tracestate.is_empty()
+            && let Ok(value) = HeaderValue::from_str(&tracestate)
+        {
+            headers.insert("tracestate", value);
+        }
+    });
 }
 
 #[inline]
@@ -57,6 +136,8 @@ pub(crate) fn with_authentication(mut builder: Builder, auth: &Authentication) -
             let bearer = format!("Bearer {access_token}");
             builder = builder.header(AUTHORIZATION, bearer);
         }
+        // Requested asynchronously, see `send_request`.
+        Authentication::JwtProvider(_) => {}
         Authentication::Credentials { user, password } => {
             if let Some(user) = &user {
                 builder = builder.header("X-ClickHouse-User", user);
@@ -68,3 +149,52 @@ pub(crate) fn with_authentication(mut builder: Builder, auth: &Authentication) -
     }
     builder
 }
+
+#[cfg(all(test, feature = "opentelemetry"))]
+mod tests {
+    use super::*;
+    use opentelemetry::{
+        Context,
+        trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+    };
+
+    fn traced_headers(client: &Client) -> hyper::HeaderMap {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::from_key_value([("foo", "bar")]).unwrap(),
+        );
+        let _guard = Context::new()
+            .with_remote_span_context(span_context)
+            .attach();
+
+        let builder = with_request_headers(Builder::new(), client);
+        builder.body(()).unwrap().headers().clone()
+    }
+
+    #[test]
+    fn it_injects_trace_context() {
+        let headers = traced_headers(&Client::default());
+        assert_eq!(
+            headers["traceparent"],
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        assert_eq!(headers["tracestate"], "foo=bar");
+
+        let headers = traced_headers(&Client::default().with_trace_context_propagation(false));
+        assert!(!headers.contains_key("traceparent"));
+        assert!(!headers.contains_key("tracestate"));
+    }
+
+    #[test]
+    fn it_skips_invalid_trace_context() {
+        let headers = with_request_headers(Builder::new(), &Client::default())
+            .body(())
+            .unwrap()
+            .headers()
+            .clone();
+        assert!(!headers.contains_key("traceparent"));
+    }
+}
@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use hyper::{Method, Request};
+use hyper_util::client::legacy::connect::capture_connection;
+use url::Url;
+
+use crate::{
+    Client, Compression,
+    error::{Error, Result},
+    headers::{send_request, with_request_headers},
+    request_body::RequestBody,
+    response::{Response, ResponseOptions},
+};
+
+/// The result of a successful health check,
+/// see [`Client::ping`] and [`Client::ready`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Health {
+    /// The time between sending the request and receiving the whole response,
+    /// including connecting to the server if there is no idle connection.
+    pub latency: Duration,
+    /// The time spent by the server on the query, if reported.
+    ///
+    /// Always `None` for [`Client::ping`].
+    pub server_elapsed: Option<Duration>,
+}
+
+pub(crate) async fn ping(client: &Client) -> Result<Health> {
+    let mut url = Url::parse(&client.url).map_err(|err| Error::InvalidParams(Box::new(err)))?;
+    url.set_query(None);
+    url.path_segments_mut()
+        .map_err(|()| Error::InvalidParams("invalid URL".into()))?
+        .pop_if_empty()
+        .push("ping");
+
+    let mut builder = Request::builder().method(Method::GET).uri(url.as_str());
+    builder = with_request_headers(builder, client);
+
+    let mut request = builder
+        .body(RequestBody::full(String::new()))
+        .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+
+    // Health checks aren't limited by `Client::with_max_concurrent_requests`,
+    // because they shouldn't fail if the client is just busy.
+    let pool_metrics = client.pool_metrics();
+    let options = ResponseOptions {
+        permit: None,
+        timeouts: client.timeouts,
+        connection: (pool_metrics.is_some() || client.timeouts.connect.is_some())
+            .then(|| capture_connection(&mut request)),
+        pool_metrics,
+        drain_limit: client.cursor_drain_limit,
+    };
+
+    let started_at = Instant::now();
+    let future = send_request(client, request);
+    Response::new(future, Compression::None, options)
+        .finish()
+        .await?;
+
+    Ok(Health {
+        latency: started_at.elapsed(),
+        server_elapsed: None,
+    })
+}
+
+pub(crate) async fn ready(client: &Client, timeout: Duration) -> Result<Health> {
+    let started_at = Instant::now();
+    let query = client.query("SELECT 1").execute_with_headers();
+    let headers = tokio::time::timeout(timeout, query)
+        .await
+        .map_err(|_| Error::TimedOut)??;
+
+    Ok(Health {
+        latency: started_at.elapsed(),
+        server_elapsed: headers
+            .summary()
+            .and_then(|summary| summary.elapsed_ns())
+            .map(Duration::from_nanos),
+    })
+}
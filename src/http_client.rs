@@ -1,15 +1,22 @@
-use std::time::Duration;
+use std::{error::Error as StdError, future::Future, sync::Arc};
 
-use hyper::Request;
+use hyper::{
+    Request, Uri,
+    rt::{Read, Write},
+};
 use hyper_util::{
     client::legacy::{
         Client, Client as HyperClient, ResponseFuture,
-        connect::{Connect, HttpConnector},
+        connect::{Connect, Connection, HttpConnector},
     },
     rt::TokioExecutor,
 };
 
-use crate::request_body::RequestBody;
+use crate::{
+    pool::{CountingConnector, PoolMetrics, PoolOptions, Resolver},
+    proxy::{Matcher, ProxyConnector},
+    request_body::RequestBody,
+};
 
 /// A trait for underlying HTTP client.
 ///
@@ -20,6 +27,10 @@ use crate::request_body::RequestBody;
 /// Secondly, although it's stable in terms of semver, it will be changed in the
 /// future (e.g. to support more runtimes, not only tokio). Thus, prefer to open
 /// a feature request instead of implementing this trait manually.
+///
+/// To customize only how connections are established (e.g. to use a Unix
+/// domain socket or a custom TLS setup), use [`crate::Client::with_connector`]
+/// instead, which keeps the connection pool of the default client.
 pub trait HttpClient: sealed::Sealed + Send + Sync + 'static {
     fn request(&self, req: Request<RequestBody>) -> ResponseFuture;
 }
@@ -37,50 +48,77 @@ impl<C> sealed::Sealed for Client<C, RequestBody> {}
 
 // === Default ===
 
-const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+/// Whether the default connector supports `https` URLs.
+pub(crate) const TLS_ENABLED: bool = cfg!(any(
+    feature = "native-tls",
+    feature = "rustls-tls-aws-lc",
+    feature = "rustls-tls-ring",
+));
 
-// ClickHouse uses 3s by default.
-// See https://github.com/ClickHouse/ClickHouse/blob/368cb74b4d222dc5472a7f2177f6bb154ebae07a/programs/server/config.xml#L201
-const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+pub(crate) fn default(
+    options: &PoolOptions,
+    metrics: Arc<PoolMetrics>,
+    proxy: Option<Arc<Matcher>>,
+) -> impl HttpClient {
+    // Hosts are resolved on every new connection, see `PoolOptions::dns_refresh_interval`.
+    let mut connector = HttpConnector::new_with_resolver(Resolver::new(options));
 
-pub(crate) fn default() -> impl HttpClient {
-    let mut connector = HttpConnector::new();
+    connector.set_keepalive(options.tcp_keepalive);
+    connector.set_happy_eyeballs_timeout(options.happy_eyeballs_timeout);
 
-    // TODO: make configurable in `Client::builder()`.
-    connector.set_keepalive(Some(TCP_KEEPALIVE));
+    connector.enforce_http(!TLS_ENABLED);
 
-    connector.enforce_http(!cfg!(any(
-        feature = "native-tls",
-        feature = "rustls-tls-aws-lc",
-        feature = "rustls-tls-ring",
-    )));
+    let connector = ProxyConnector::new(connector, proxy);
 
     #[cfg(feature = "native-tls")]
     let connector = hyper_tls::HttpsConnector::new_with_connector(connector);
 
     #[cfg(all(feature = "rustls-tls-aws-lc", not(feature = "native-tls")))]
-    let connector =
-        prepare_hyper_rustls_connector(connector, rustls::crypto::aws_lc_rs::default_provider());
+    let connector = prepare_hyper_rustls_connector(
+        connector,
+        rustls::crypto::aws_lc_rs::default_provider(),
+        options.http2,
+    );
 
     #[cfg(all(
         feature = "rustls-tls-ring",
         not(feature = "rustls-tls-aws-lc"),
         not(feature = "native-tls"),
     ))]
-    let connector =
-        prepare_hyper_rustls_connector(connector, rustls::crypto::ring::default_provider());
+    let connector = prepare_hyper_rustls_connector(
+        connector,
+        rustls::crypto::ring::default_provider(),
+        options.http2,
+    );
+
+    build(CountingConnector::new(connector, metrics, options), options)
+}
+
+fn build<C>(connector: C, options: &PoolOptions) -> impl HttpClient
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let mut builder = HyperClient::builder(TokioExecutor::new());
+    builder
+        .pool_idle_timeout(options.idle_timeout)
+        .pool_max_idle_per_host(options.max_idle_per_host);
+
+    // Large responses are streamed over a single stream, so let the window
+    // grow according to the measured bandwidth instead of the fixed 64 KiB.
+    #[cfg(feature = "http2")]
+    builder.http2_adaptive_window(true);
 
-    HyperClient::builder(TokioExecutor::new())
-        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
-        .build(connector)
+    builder.build(connector)
 }
 
 #[cfg(not(feature = "native-tls"))]
 #[cfg(any(feature = "rustls-tls-aws-lc", feature = "rustls-tls-ring"))]
+#[cfg_attr(not(feature = "http2"), allow(unused_variables))]
 fn prepare_hyper_rustls_connector(
-    connector: HttpConnector,
+    connector: ProxyConnector<HttpConnector<Resolver>>,
     provider: rustls::crypto::CryptoProvider,
-) -> hyper_rustls::HttpsConnector<HttpConnector> {
+    http2: bool,
+) -> hyper_rustls::HttpsConnector<ProxyConnector<HttpConnector<Resolver>>> {
     #[cfg(not(feature = "rustls-tls-webpki-roots"))]
     #[cfg(not(feature = "rustls-tls-native-roots"))]
     compile_error!(
@@ -101,12 +139,143 @@ fn prepare_hyper_rustls_connector(
         .with_provider_and_webpki_roots(provider)
         .unwrap();
 
-    builder
-        .https_or_http()
-        .enable_http1()
-        .wrap_connector(connector)
+    let builder = builder.https_or_http();
+
+    // Both versions are offered, so HTTP/1.1 is used if the server
+    // doesn't support HTTP/2.
+    #[cfg(feature = "http2")]
+    if http2 {
+        return builder.enable_all_versions().wrap_connector(connector);
+    }
+
+    builder.enable_http1().wrap_connector(connector)
+}
+
+// === Custom ===
+
+type MakeHttpClient = dyn Fn(&PoolOptions, Arc<PoolMetrics>) -> Arc<dyn HttpClient> + Send + Sync;
+
+/// A connector provided by [`crate::Client::with_connector`], kept to build
+/// a new HTTP client once pool options are changed.
+#[derive(Clone)]
+pub(crate) struct CustomConnector(Arc<MakeHttpClient>);
+
+impl CustomConnector {
+    pub(crate) fn new<C>(connector: C) -> Self
+    where
+        C: tower_service::Service<Uri> + Clone + Send + Sync + 'static,
+        C::Response: Read + Write + Connection + Unpin + Send + 'static,
+        C::Future: Send + 'static,
+        C::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        Self(Arc::new(move |options, metrics| {
+            let connector = CountingConnector::new(connector.clone(), metrics, options);
+            Arc::new(build(connector, options))
+        }))
+    }
+
+    pub(crate) fn build(
+        &self,
+        options: &PoolOptions,
+        metrics: Arc<PoolMetrics>,
+    ) -> Arc<dyn HttpClient> {
+        (self.0)(options, metrics)
+    }
+}
+
+// === Unix ===
+
+/// Connects to a Unix domain socket regardless of the requested URI,
+/// see [`crate::Client::with_unix_socket`].
+#[cfg(unix)]
+#[derive(Clone)]
+pub(crate) struct UnixConnector {
+    path: Arc<std::path::Path>,
+}
+
+#[cfg(unix)]
+impl UnixConnector {
+    pub(crate) fn new(path: &std::path::Path) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(unix)]
+impl tower_service::Service<Uri> for UnixConnector {
+    type Response = hyper_util::rt::TokioIo<tokio::net::UnixStream>;
+    type Error = std::io::Error;
+    type Future = std::pin::Pin<Box<dyn Future<Output = std::io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _dst: Uri) -> Self::Future {
+        let path = self.path.clone();
+        Box::pin(async move {
+            let stream = tokio::net::UnixStream::connect(&*path).await?;
+            Ok(hyper_util::rt::TokioIo::new(stream))
+        })
+    }
 }
 
 mod sealed {
     pub trait Sealed {}
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::UnixListener,
+    };
+
+    use crate::Client;
+
+    #[tokio::test]
+    async fn unix_socket() {
+        let dir = std::env::temp_dir().join(format!("clickhouse-rs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("unix_socket.sock");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            // Only headers are required, the body can be ignored.
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                assert_ne!(n, 0, "unexpected EOF");
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\n42\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let client = Client::default()
+            .with_unix_socket(&path)
+            .with_url("http://localhost")
+            .with_pool_idle_timeout(None); // the connector is kept
+        let bytes = client
+            .query("SELECT 42")
+            .fetch_bytes("TSV")
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"42\n");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /?"), "{request}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
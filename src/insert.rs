@@ -2,15 +2,19 @@ use crate::insert_formatted::{BufInsertFormatted, InsertFormatted};
 use crate::row_metadata::RowMetadata;
 use crate::rowbinary::{serialize_row_binary, serialize_with_validation};
 use crate::{
-    Client, RowWrite,
+    Client, ResponseHeaders, RowWrite, Settings,
     error::Result,
     formats,
     row::{self, Row},
 };
 use clickhouse_types::put_rbwnat_columns_header;
+#[cfg(feature = "inserter")]
+use hyper_util::client::legacy::connect::CaptureConnection;
 use std::num::Saturating;
 use std::{future::Future, marker::PhantomData, time::Duration};
 
+pub use crate::insert_group::InsertGroup;
+
 // The desired max frame size.
 const BUFFER_SIZE: usize = 256 * 1024;
 // Threshold to send a chunk. Should be slightly less than `BUFFER_SIZE`
@@ -18,9 +22,99 @@ const BUFFER_SIZE: usize = 256 * 1024;
 const MIN_CHUNK_SIZE: usize = const {
     // to use the whole buffer's capacity
     assert!(BUFFER_SIZE.is_power_of_two());
-    BUFFER_SIZE - 2048
+    min_chunk_size(BUFFER_SIZE)
 };
 
+const fn min_chunk_size(buffer_size: usize) -> usize {
+    buffer_size - buffer_size / 128
+}
+
+// Limits the size of uncompressed chunks for [`Insert::with_target_block_bytes`]
+// in case of highly compressible data, e.g. mostly zeros.
+const MAX_ADAPTIVE_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Defines when an asynchronous `INSERT` is considered finished,
+/// see [`Insert::with_async_insert`].
+///
+/// See [asynchronous inserts](https://clickhouse.com/docs/optimize/asynchronous-inserts)
+/// for details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AsyncInsertWait {
+    /// Waits until the data is flushed from the server's buffer to the table,
+    /// so flushing errors are reported. Recommended for most cases.
+    ///
+    /// Sets `async_insert = 1` and `wait_for_async_insert = 1`.
+    Flush,
+    /// Similar to [`AsyncInsertWait::Flush`], but fails if the data isn't
+    /// flushed within the timeout (rounded up to seconds).
+    ///
+    /// Also sets `wait_for_async_insert_timeout`.
+    FlushWithTimeout(Duration),
+    /// Doesn't wait until the data is flushed, only until it's buffered.
+    /// Flushing errors aren't reported, so the data can be lost silently.
+    ///
+    /// Sets `async_insert = 1` and `wait_for_async_insert = 0`.
+    Enqueue,
+}
+
+impl AsyncInsertWait {
+    pub(crate) fn settings(self) -> Settings {
+        let settings = Settings::new()
+            .async_insert(true)
+            .wait_for_async_insert(self != Self::Enqueue);
+
+        match self {
+            Self::FlushWithTimeout(timeout) => {
+                let seconds = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+                settings.set("wait_for_async_insert_timeout", seconds.to_string())
+            }
+            Self::Flush | Self::Enqueue => settings,
+        }
+    }
+}
+
+/// Statistics of an ended `INSERT`, returned by [`Insert::end`]
+/// and [`InsertFormatted::end`].
+///
+/// [`InsertFormatted::end`]: crate::insert_formatted::InsertFormatted::end
+///
+/// Rows, bytes and the duration are provided by the server in the
+/// `X-ClickHouse-Summary` header, so they're `None` if it's absent.
+/// For asynchronous inserts (see [`Insert::with_async_insert`]),
+/// they're meaningful only if flushing is awaited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WriteStats {
+    /// The number of rows written by the server.
+    pub rows: Option<u64>,
+    /// The number of uncompressed bytes written by the server.
+    pub bytes: Option<u64>,
+    /// The number of bytes sent to the server, after compression if enabled.
+    pub compressed_bytes: u64,
+    /// The time the server spent executing the query.
+    pub duration: Option<Duration>,
+    /// The ID of the query, either provided by the client or generated by the server.
+    pub query_id: Option<String>,
+}
+
+impl WriteStats {
+    pub(crate) fn new(headers: Option<&ResponseHeaders>, compressed_bytes: u64) -> Self {
+        let summary = headers.and_then(|headers| headers.summary());
+        Self {
+            rows: summary.and_then(|s| s.written_rows()),
+            bytes: summary.and_then(|s| s.written_bytes()),
+            compressed_bytes,
+            duration: summary
+                .and_then(|s| s.elapsed_ns())
+                .map(Duration::from_nanos),
+            query_id: headers
+                .and_then(|headers| headers.query_id())
+                .map(Into::into),
+        }
+    }
+}
+
 /// Performs one `INSERT`.
 ///
 /// The [`Insert::end`] must be called to finalize the `INSERT`.
@@ -42,6 +136,9 @@ pub struct Insert<T> {
     insert: BufInsertFormatted,
     row_metadata: Option<RowMetadata>,
     sent_rows: Saturating<u64>,
+    min_chunk_size: usize,
+    /// Set by [`Insert::with_target_block_bytes`].
+    target_block_bytes: Option<usize>,
     _marker: PhantomData<fn() -> T>, // TODO: test contravariance.
 }
 
@@ -50,7 +147,7 @@ impl<T> Insert<T> {
     where
         T: Row,
     {
-        let fields = row::join_column_names::<T>()
+        let fields = row::join_insert_column_names::<T>()
             .expect("the row type must be a struct or a wrapper around it");
 
         let format = if row_metadata.is_some() {
@@ -65,10 +162,74 @@ impl<T> Insert<T> {
                 .buffered_with_capacity(BUFFER_SIZE),
             row_metadata,
             sent_rows: Saturating(0),
+            min_chunk_size: MIN_CHUNK_SIZE,
+            target_block_bytes: None,
             _marker: PhantomData,
         }
     }
 
+    /// Sets the capacity of the buffer used to accumulate rows before
+    /// sending them to the server as a single chunk.
+    ///
+    /// A chunk is sent once the buffer is almost full, so a smaller capacity
+    /// reduces memory usage per `INSERT`, while a larger one results in
+    /// fewer (and better compressed) chunks. The buffer is allocated once
+    /// and reused for all chunks of this `INSERT`.
+    ///
+    /// By default, it's 256 KiB.
+    ///
+    /// # Panics
+    /// If `capacity` is zero.
+    pub fn with_write_buffer_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "write buffer capacity must be positive");
+        self.insert.set_capacity(capacity);
+        if self.target_block_bytes.is_none() {
+            self.min_chunk_size = min_chunk_size(capacity);
+        }
+        self
+    }
+
+    /// Sets the target size of chunks sent to the server, after compression
+    /// if it's enabled, e.g. 1 MiB.
+    ///
+    /// The size of chunks is adapted to the compression ratio of already
+    /// sent chunks, so very large inserts are split into chunks of roughly
+    /// the same compressed size regardless of how well rows are compressed,
+    /// and the server decompresses and parses them in parallel. Until the
+    /// first chunk is sent, `bytes` of serialized rows are collected.
+    ///
+    /// Overrides the chunk size defined by [`Insert::with_write_buffer_capacity`].
+    ///
+    /// # Panics
+    /// If `bytes` is zero.
+    pub fn with_target_block_bytes(mut self, bytes: usize) -> Self {
+        assert!(bytes > 0, "target block size must be positive");
+        self.insert.set_capacity(bytes);
+        self.min_chunk_size = bytes;
+        self.target_block_bytes = Some(bytes);
+        self
+    }
+
+    /// Compresses chunks of serialized rows on the blocking thread pool
+    /// (see [`tokio::task::spawn_blocking`]) instead of the task calling
+    /// [`Insert::write`], so compressing large chunks (e.g. with
+    /// [`Insert::with_write_buffer_capacity`] of several MiB) doesn't block
+    /// a worker thread of the runtime.
+    ///
+    /// Rows are still serialized by [`Insert::write`], because they're
+    /// borrowed, but the next chunk is filled while the previous one is
+    /// compressed. Chunks are sent in order.
+    ///
+    /// Has no effect if compression is disabled, see [`Client::with_compression`].
+    ///
+    /// By default, it's disabled.
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "lz4", feature = "zstd"))))]
+    pub fn with_offload_serialization(mut self, offload: bool) -> Self {
+        self.insert.set_offload_compression(offload);
+        self
+    }
+
     /// Sets timeouts for different operations.
     ///
     /// `send_timeout` restricts time on sending a data chunk to a socket.
@@ -92,6 +253,50 @@ impl<T> Insert<T> {
         self
     }
 
+    /// Sets how many times the request is started again if it fails with
+    /// a network error while rows are written, e.g. because of a broken
+    /// connection.
+    ///
+    /// Written rows are kept in memory to be sent again, but only until they
+    /// exceed 512 KiB, so no rows are inserted twice. Larger `INSERT`s fail
+    /// as usual once more rows are written. Failures after [`Insert::end`]
+    /// is called aren't retried, because the server could have inserted
+    /// the rows already.
+    ///
+    /// It relies on the server not writing blocks smaller than the default
+    /// `min_insert_block_size_rows` and `min_insert_block_size_bytes`, so
+    /// retries are disabled if they or `max_insert_block_size` are set on
+    /// the client or the `INSERT`. Don't enable retries if these settings are
+    /// lowered in settings profiles of the server, otherwise rows can be
+    /// inserted twice.
+    ///
+    /// By default, it's `0`, i.e. failed requests aren't started again.
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.insert.set_retries(max_retries);
+        self
+    }
+
+    /// Sets how long writes to the socket can be blocked before the `INSERT`
+    /// fails with [`Error::WriteStalled`], e.g. because the server froze
+    /// and stopped reading rows.
+    ///
+    /// Unlike `send_timeout` of [`Insert::with_timeouts`], it's also checked
+    /// by [`Insert::end`], but only while there are rows not written to the
+    /// socket yet, so slow processing of the `INSERT` by the server isn't
+    /// considered a stall. The stalled connection is closed instead of being
+    /// returned to the pool, releasing rows buffered for it.
+    ///
+    /// It's applied only to connections of the default HTTP client and ones
+    /// set by [`Client::with_connector`].
+    ///
+    /// `None` disables the detection, it's a default.
+    ///
+    /// [`Error::WriteStalled`]: crate::error::Error::WriteStalled
+    pub fn with_stall_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.set_stall_timeout(timeout);
+        self
+    }
+
     /// Configure the [roles] to use when executing `INSERT` statements.
     ///
     /// Overrides any roles previously set by this method, [`Insert::with_setting`],
@@ -134,6 +339,19 @@ impl<T> Insert<T> {
         self
     }
 
+    /// Similar to [`Client::with_header`], but for this particular INSERT
+    /// statement only. Overrides the header with the same name set on the [`Client`].
+    ///
+    /// # Panics
+    /// If called after the request is started, e.g., after [`Insert::write`].
+    #[track_caller]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.insert
+            .expect_client_mut()
+            .set_header(name.into(), value.into());
+        self
+    }
+
     /// Similar to [`Client::with_setting`], but for this particular INSERT
     /// statement only.
     ///
@@ -145,6 +363,49 @@ impl<T> Insert<T> {
         self
     }
 
+    /// Similar to [`Client::with_settings`], but for this particular INSERT
+    /// statement only.
+    ///
+    /// # Panics
+    /// If called after the request is started, e.g., after [`Insert::write`].
+    #[track_caller]
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        let client = self.insert.expect_client_mut();
+        for (name, value) in settings.into_pairs() {
+            client.set_setting(name, value);
+        }
+        self
+    }
+
+    /// Similar to [`Query::with_tag`], but for this particular INSERT
+    /// statement.
+    ///
+    /// # Panics
+    /// If called after the request is started, e.g., after [`Insert::write`].
+    ///
+    /// [`Query::with_tag`]: crate::query::Query::with_tag
+    #[track_caller]
+    pub fn with_tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.insert
+            .expect_client_mut()
+            .add_tag(key.into(), value.into());
+        self
+    }
+
+    /// Makes this `INSERT` asynchronous: the server buffers data of concurrent
+    /// inserts and flushes it to the table in larger parts.
+    ///
+    /// It's preferable for many small inserts, e.g. from many clients.
+    /// [`WriteStats`] returned by [`Insert::end`] include the number of written
+    /// rows, but they're meaningful only if flushing is awaited.
+    ///
+    /// # Panics
+    /// If called after the request is started, e.g., after [`Insert::write`].
+    #[track_caller]
+    pub fn with_async_insert(self, wait: AsyncInsertWait) -> Self {
+        self.with_settings(wait.settings())
+    }
+
     pub(crate) fn set_timeouts(
         &mut self,
         send_timeout: Option<Duration>,
@@ -153,6 +414,10 @@ impl<T> Insert<T> {
         self.insert.set_timeouts(send_timeout, end_timeout);
     }
 
+    pub(crate) fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.insert.set_stall_timeout(timeout);
+    }
+
     /// Serializes the provided row into an internal buffer.
     /// Once the buffer is full, it's sent to a background task writing to the
     /// socket.
@@ -183,8 +448,9 @@ impl<T> Insert<T> {
 
         async move {
             result?;
-            if self.insert.buf_len() >= MIN_CHUNK_SIZE {
-                self.insert.flush().await?;
+            if self.insert.buf_len() >= self.min_chunk_size {
+                self.insert.start_flush().await?;
+                self.adapt_chunk_size();
             }
 
             self.sent_rows += 1;
@@ -199,9 +465,13 @@ impl<T> Insert<T> {
     where
         T: RowWrite,
     {
-        // We don't want to wait for the buffer to be full before we start the request,
-        // in the event of an error.
-        self.init_request_if_required()?;
+        // The first row is serialized (and validated) before starting the request,
+        // so a schema mismatch is reported without sending anything to the server.
+        let fresh_request = self.insert.is_not_started();
+        if fresh_request && let Some(metadata) = &self.row_metadata {
+            put_rbwnat_columns_header(&metadata.columns, self.insert.buffer_mut())
+                .inspect_err(|_| self.abort())?;
+        }
 
         // The following calls need an `impl BufMut`
         let buffer = self.insert.buffer_mut();
@@ -213,12 +483,19 @@ impl<T> Insert<T> {
         };
         let written = buffer.len() - old_buf_size;
 
-        if let Err(e) = &result {
-            e.record_in_current_span("error serializing row");
+        if let Err(err) = result {
+            err.record_in_current_span("error serializing row");
             self.abort();
+            return Err(err);
         }
 
-        result.and(Ok(written))
+        // We don't want to wait for the buffer to be full before we start the request,
+        // in the event of an error.
+        if fresh_request {
+            self.insert.init_request_if_required()?;
+        }
+
+        Ok(written)
     }
 
     /// Ends `INSERT`, the server starts processing the data.
@@ -227,25 +504,73 @@ impl<T> Insert<T> {
     /// successfully, including all materialized views and quorum writes.
     ///
     /// NOTE: If it isn't called, the whole `INSERT` is aborted.
-    pub async fn end(mut self) -> Result<()> {
+    ///
+    /// Returns [`WriteStats`] reported by the server.
+    pub async fn end(mut self) -> Result<WriteStats> {
+        self.record_sent_rows();
+        self.insert.end().await
+    }
+
+    fn record_sent_rows(&mut self) {
         // `InsertFormatted::end()` will add `sent_bytes` and `encoded_bytes` to the span.
         tracing::record_all!(
             self.insert._priv_span(),
             clickhouse.request.sent_rows = self.sent_rows.0,
         );
+        self.insert.set_observed_rows(self.sent_rows.0);
+    }
 
-        self.insert.end().await
+    #[cfg(feature = "inserter")]
+    pub(crate) fn connection(&self) -> Option<&CaptureConnection> {
+        self.insert.connection()
     }
 
-    fn init_request_if_required(&mut self) -> Result<()> {
-        let fresh_request = self.insert.init_request_if_required()?;
+    /// Sends the buffered data if there is enough of it for a chunk,
+    /// like [`Insert::write`] does, or if the estimated compressed size
+    /// reaches `max_compressed_bytes`, so it's measured exactly.
+    #[cfg(feature = "inserter")]
+    pub(crate) async fn flush_compressed(&mut self, max_compressed_bytes: u64) -> Result<()> {
+        if self.insert.buf_len() >= self.min_chunk_size
+            || self.compressed_size_estimate() >= max_compressed_bytes
+        {
+            self.insert.flush().await?;
+            self.adapt_chunk_size();
+        }
+        Ok(())
+    }
 
-        if fresh_request && let Some(metadata) = &self.row_metadata {
-            put_rbwnat_columns_header(&metadata.columns, self.insert.buffer_mut())
-                .inspect_err(|_| self.abort())?;
+    /// Returns the estimated size of written data after compression.
+    ///
+    /// Sent chunks are counted exactly, while the compression ratio of them
+    /// is applied to the buffered data. If nothing is sent yet or compression
+    /// is disabled, buffered data is counted as is.
+    #[cfg(feature = "inserter")]
+    pub(crate) fn compressed_size_estimate(&self) -> u64 {
+        let buffered = self.insert.buf_len() as u64;
+
+        match self.insert.sent_sizes() {
+            Some((sent, encoded)) if encoded > 0 => {
+                let ratio = sent as f64 / encoded as f64;
+                sent + (buffered as f64 * ratio).ceil() as u64
+            }
+            _ => buffered,
         }
+    }
 
-        Ok(())
+    /// Updates the chunk size to reach [`Insert::with_target_block_bytes`]
+    /// according to the compression ratio of sent chunks.
+    fn adapt_chunk_size(&mut self) {
+        let Some(target) = self.target_block_bytes else {
+            return;
+        };
+
+        if let Some((sent, encoded)) = self.insert.sent_sizes()
+            && sent > 0
+        {
+            let ratio = encoded as f64 / sent as f64;
+            let chunk_size = (target as f64 * ratio) as usize;
+            self.min_chunk_size = chunk_size.clamp(1, MAX_ADAPTIVE_CHUNK_SIZE.max(target));
+        }
     }
 
     fn abort(&mut self) {
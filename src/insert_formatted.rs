@@ -1,17 +1,21 @@
-use crate::headers::{with_authentication, with_request_headers};
+use crate::headers::{send_request, with_authentication, with_request_headers};
 use crate::{
-    Client, Compression,
-    error::{Error, Result},
+    Client, Compression, ResponseHeaders,
+    error::{Error, ErrorContext, Result},
+    insert::WriteStats,
+    observer::{Observation, QueryKind},
+    pool::{self, WriteWatch},
     request_body::{ChunkSender, RequestBody},
-    response::Response,
+    response::{Response, ResponseOptions},
     settings,
 };
 use bytes::{Bytes, BytesMut};
 use hyper::{self, Request};
+use hyper_util::client::legacy::connect::{CaptureConnection, capture_connection};
 use std::ops::ControlFlow;
 use std::task::{Context, Poll, ready};
-use std::{cmp, future::Future, io, mem, panic, pin::Pin, time::Duration};
-use tokio::io::AsyncWrite;
+use std::{cmp, future::Future, io, mem, panic, pin::Pin, sync::Arc, time::Duration};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::{
     task::JoinHandle,
     time::{Instant, Sleep},
@@ -24,6 +28,18 @@ pub use compression::CompressedData;
 // The desired max frame size.
 const BUFFER_SIZE: usize = 256 * 1024;
 
+// The max size of data kept to send the request again, see `InsertFormatted::with_retries()`.
+// Even one-byte rows of such data don't reach the default `min_insert_block_size_rows`,
+// so the server doesn't write any part of it until the whole request is received.
+const MAX_RETAINED_SIZE: u64 = 512 * 1024;
+
+// Settings changing sizes of written blocks, retries are disabled if any is set.
+const BLOCK_SIZE_SETTINGS: [&str; 3] = [
+    settings::MIN_INSERT_BLOCK_SIZE_ROWS,
+    settings::MIN_INSERT_BLOCK_SIZE_BYTES,
+    settings::MAX_INSERT_BLOCK_SIZE,
+];
+
 /// Performs one `INSERT`, sending pre-formatted data.
 ///
 /// The [`InsertFormatted::end`] method must be called to finalize the `INSERT`.
@@ -50,8 +66,62 @@ pub struct InsertFormatted {
     // Also, `tokio::time::timeout()` significantly increases a future's size.
     sleep: Pin<Box<Sleep>>,
     span: tracing::Span,
+    /// Started with the request, reported once the `INSERT` is ended.
+    observation: Option<Observation>,
+    /// Captured when the request is started, used to recycle idle connections.
+    connection: Option<CaptureConnection>,
+    /// Attached to returned errors, see [`Client::with_error_context`].
+    error_context: Option<Arc<ErrorContext>>,
+    /// Provided by the server once the `INSERT` is ended.
+    headers: Option<Box<ResponseHeaders>>,
+    /// The number of bytes sent before the `INSERT` is ended.
+    sent_bytes: u64,
+    /// `Some` if failed requests are started again, see [`InsertFormatted::with_retries`].
+    retries: Option<Box<Retries>>,
+    /// `Some` if stalled writes are detected, see [`InsertFormatted::with_stall_timeout`].
+    stall_watchdog: Option<Box<StallWatchdog>>,
+}
+
+/// A state of [`InsertFormatted::with_retries`].
+struct Retries {
+    max_retries: u32,
+    retries: u32,
+    /// The client and the query to start the request again.
+    request: Option<(Box<Client>, String)>,
+    /// Chunks sent by the current request with their original sizes,
+    /// `None` once they exceed [`MAX_RETAINED_SIZE`].
+    chunks: Option<Vec<(Bytes, u64)>>,
+    retained_size: u64,
+    /// The number of chunks sent again by the restarted request.
+    resent: usize,
+}
+
+impl Retries {
+    fn retain(&mut self, chunk: Bytes, original_size: u64) {
+        self.retained_size += original_size;
+        match &mut self.chunks {
+            Some(_) if self.retained_size > MAX_RETAINED_SIZE => self.chunks = None,
+            Some(chunks) => chunks.push((chunk, original_size)),
+            None => {}
+        }
+    }
+
+    fn next_to_resend(&self) -> Option<(Bytes, u64)> {
+        self.chunks.as_ref()?.get(self.resent).cloned()
+    }
+}
+
+/// A state of [`InsertFormatted::with_stall_timeout`].
+struct StallWatchdog {
+    timeout: Duration,
+    /// Found once the request is assigned a connection.
+    writes: Option<Arc<WriteWatch>>,
+    // A separate timer, because it runs along with `send_timeout` and `end_timeout`.
+    sleep: Pin<Box<Sleep>>,
 }
 
+type ResponseHandle = JoinHandle<Result<Option<Box<ResponseHeaders>>>>;
+
 struct Timeout {
     duration: Duration,
     is_set: bool,
@@ -64,12 +134,12 @@ enum InsertState {
     },
     Active {
         sender: ChunkSender,
-        handle: JoinHandle<Result<()>>,
+        handle: ResponseHandle,
         sent_bytes: u64,
         encoded_bytes: u64,
     },
     Terminated {
-        handle: JoinHandle<Result<()>>,
+        handle: ResponseHandle,
     },
     Completed,
 }
@@ -87,7 +157,7 @@ impl InsertState {
         }
     }
 
-    fn handle(&mut self) -> Option<&mut JoinHandle<Result<()>>> {
+    fn handle(&mut self) -> Option<&mut ResponseHandle> {
         match self {
             InsertState::Active { handle, .. } | InsertState::Terminated { handle } => Some(handle),
             _ => None,
@@ -148,11 +218,8 @@ impl InsertFormatted {
                 otel.kind = cfg!(feature = "opentelemetry").then_some("client"),
                 error.type = tracing::field::Empty,
                 db.system.name = cfg!(feature = "opentelemetry").then_some("clickhouse"),
-                // Only log full query text at TRACE level
-                // Important that this is taken before client-side parameters are populated
-                // FIXME: we can't use `enabled!` due to https://github.com/tokio-rs/tracing/issues/2448
-                // but we don't want to log the full query at all verbosity levels.
-                // db.query.text = tracing::enabled!(tracing::Level::TRACE).then_some(&sql),
+                // Recorded with the `tracing` feature only.
+                db.query.text = tracing::field::Empty,
                 // TODO: generate summary
                 db.query.summary = tracing::field::Empty,
                 db.operation.name = "INSERT",
@@ -163,6 +230,7 @@ impl InsertFormatted {
                 clickhouse.request.sent_rows = tracing::field::Empty,
                 clickhouse.request.sent_bytes = tracing::field::Empty,
                 clickhouse.request.encoded_bytes = tracing::field::Empty,
+                clickhouse.query.duration = tracing::field::Empty,
             ),
             state: InsertState::NotStarted {
                 client: Box::new(client.clone()),
@@ -173,6 +241,13 @@ impl InsertFormatted {
             send_timeout: None,
             end_timeout: None,
             sleep: Box::pin(tokio::time::sleep(Duration::new(0, 0))),
+            observation: None,
+            connection: None,
+            error_context: None,
+            headers: None,
+            sent_bytes: 0,
+            retries: None,
+            stall_watchdog: None,
         }
     }
 
@@ -241,6 +316,19 @@ impl InsertFormatted {
         self
     }
 
+    /// Similar to [`Client::with_header`], but for this particular INSERT
+    /// statement only. Overrides the header with the same name set on the [`Client`].
+    ///
+    /// # Panics
+    /// If called after the request is started, e.g., after [`InsertFormatted::send`].
+    #[track_caller]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.state
+            .expect_client_mut()
+            .set_header(name.into(), value.into());
+        self
+    }
+
     /// Similar to [`Client::with_setting`], but for this particular INSERT
     /// statement only.
     ///
@@ -252,6 +340,21 @@ impl InsertFormatted {
         self
     }
 
+    /// Similar to [`Query::with_tag`], but for this particular INSERT
+    /// statement.
+    ///
+    /// # Panics
+    /// If called after the request is started, e.g., after [`InsertFormatted::send`].
+    ///
+    /// [`Query::with_tag`]: crate::query::Query::with_tag
+    #[track_caller]
+    pub fn with_tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.state
+            .expect_client_mut()
+            .add_tag(key.into(), value.into());
+        self
+    }
+
     /// Similar to [`Client::with_product_info()`], but for this `INSERT` statement only.
     pub fn with_product_info(
         mut self,
@@ -264,6 +367,49 @@ impl InsertFormatted {
         self
     }
 
+    /// Sets how many times the request is started again if it fails with
+    /// a network error while data is sent, e.g. because of a broken connection.
+    ///
+    /// Sent chunks are kept in memory to be sent again, but only until they
+    /// exceed 512 KiB (before compression), which is less than the minimal
+    /// size of blocks written by the server (`min_insert_block_size_rows`),
+    /// so no data is written twice. Larger `INSERT`s fail as usual once
+    /// more data is sent.
+    ///
+    /// It relies on default sizes of blocks, so retries are disabled if
+    /// `min_insert_block_size_rows`, `min_insert_block_size_bytes` or
+    /// `max_insert_block_size` is set on the client or the `INSERT`.
+    /// Don't enable retries if they're lowered in settings profiles of the
+    /// server, otherwise data can be written twice.
+    ///
+    /// Failures after the `INSERT` is ended (e.g. by [`InsertFormatted::end`])
+    /// aren't retried, because the server could have written the data already.
+    ///
+    /// By default, it's `0`, i.e. failed requests aren't started again.
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.set_retries(max_retries);
+        self
+    }
+
+    /// Sets how long writes to the socket can be blocked before the `INSERT`
+    /// fails with [`Error::WriteStalled`], e.g. because the server froze
+    /// and stopped reading the data.
+    ///
+    /// Unlike `send_timeout` of [`InsertFormatted::with_timeouts`], it's
+    /// also checked while the `INSERT` is ended, but only while there is data
+    /// not written to the socket yet, so slow processing of the `INSERT`
+    /// by the server isn't considered a stall. The stalled connection is
+    /// closed instead of being returned to the pool.
+    ///
+    /// It's applied only to connections of the default HTTP client and ones
+    /// set by [`Client::with_connector`].
+    ///
+    /// `None` disables the detection, it's a default.
+    pub fn with_stall_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.set_stall_timeout(timeout);
+        self
+    }
+
     pub(crate) fn set_timeouts(
         &mut self,
         send_timeout: Option<Duration>,
@@ -273,10 +419,59 @@ impl InsertFormatted {
         self.end_timeout = Timeout::new_opt(end_timeout);
     }
 
+    pub(crate) fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.stall_watchdog = timeout.map(|timeout| {
+            Box::new(StallWatchdog {
+                timeout,
+                writes: None,
+                sleep: Box::pin(tokio::time::sleep(Duration::new(0, 0))),
+            })
+        });
+    }
+
+    pub(crate) fn set_retries(&mut self, max_retries: u32) {
+        self.retries = (max_retries > 0).then(|| {
+            Box::new(Retries {
+                max_retries,
+                retries: 0,
+                request: None,
+                chunks: Some(Vec::new()),
+                retained_size: 0,
+                resent: 0,
+            })
+        });
+    }
+
     pub(crate) fn span(&self) -> &tracing::Span {
         &self.span
     }
 
+    /// Returns the connection used by the request, if it's started.
+    #[cfg(feature = "inserter")]
+    pub(crate) fn connection(&self) -> Option<&CaptureConnection> {
+        self.connection.as_ref()
+    }
+
+    /// Returns the number of sent (possibly compressed) bytes and the number
+    /// of bytes they were encoded from, if the request is active.
+    pub(crate) fn sent_sizes(&self) -> Option<(u64, u64)> {
+        match &self.state {
+            InsertState::Active {
+                sent_bytes,
+                encoded_bytes,
+                ..
+            } => Some((*sent_bytes, *encoded_bytes)),
+            _ => None,
+        }
+    }
+
+    /// Sets the number of rows reported to the query observer, if any.
+    pub(crate) fn set_observed_rows(&mut self, rows: u64) {
+        if let Some(observation) = &mut self.observation {
+            observation.set_rows(rows);
+        }
+    }
+
     /// Wrap this `InsertFormatted` with a buffer of a default size.
     ///
     /// The returned type also implements [`AsyncWrite`].
@@ -319,6 +514,40 @@ impl InsertFormatted {
         self.send_inner(data, original_size).await
     }
 
+    /// Reads data from `reader` until EOF and sends it in chunks of 256 KiB.
+    ///
+    /// Returns the number of bytes read.
+    ///
+    /// If compression is enabled, chunks are compressed before sending.
+    pub async fn send_from_reader(&mut self, reader: impl AsyncRead) -> Result<u64> {
+        let mut reader = std::pin::pin!(reader);
+        let mut buffer = vec![0; BUFFER_SIZE];
+        let mut total = 0;
+
+        loop {
+            // Fill the whole buffer to avoid sending small chunks.
+            let mut filled = 0;
+            while filled < buffer.len() {
+                let mut read_buf = ReadBuf::new(&mut buffer[filled..]);
+                std::future::poll_fn(|cx| reader.as_mut().poll_read(cx, &mut read_buf)).await?;
+
+                match read_buf.filled().len() {
+                    0 => break,
+                    n => filled += n,
+                }
+            }
+
+            if filled > 0 {
+                total += to_u64_saturating(filled);
+                self.send(Bytes::copy_from_slice(&buffer[..filled])).await?;
+            }
+
+            if filled < buffer.len() {
+                return Ok(total);
+            }
+        }
+    }
+
     async fn send_inner(&mut self, mut data: Bytes, original_size: u64) -> Result<()> {
         if self.state.is_not_started() {
             self.init_request()?;
@@ -357,17 +586,30 @@ impl InsertFormatted {
         match sender.poll_ready(cx) {
             Poll::Ready(true) => {
                 Timeout::reset_opt(self.send_timeout.as_mut());
+                if self.retries.is_some() {
+                    return self.poll_resend(cx);
+                }
                 Poll::Ready(Ok(()))
             }
+            Poll::Ready(false) if self.retries.is_some() => {
+                ready!(self.poll_restart(cx))?;
+                self.poll_ready(cx)
+            }
             Poll::Ready(false) => Poll::Ready(Err(Error::Network("channel closed".into()))),
             Poll::Pending => {
+                if let Poll::Ready(err) = self.poll_stalled(cx) {
+                    self.abort();
+                    return Poll::Ready(Err(err.with_context(self.error_context.as_ref())));
+                }
+
                 ready!(Timeout::poll_opt(
                     self.send_timeout.as_mut(),
                     self.sleep.as_mut(),
                     cx
                 ));
                 self.abort();
-                Poll::Ready(Err(Error::TimedOut))
+                let err = Error::TimedOut.with_context(self.error_context.as_ref());
+                Poll::Ready(Err(err))
             }
         }
     }
@@ -377,6 +619,32 @@ impl InsertFormatted {
         &mut self,
         bytes: Bytes,
         original_size: u64,
+    ) -> ControlFlow<Result<()>, Bytes> {
+        if self.retries.is_none() {
+            return self.try_send_chunk(bytes, original_size);
+        }
+
+        let retained = bytes.clone();
+        let result = self.try_send_chunk(bytes, original_size);
+        let retries = self.retries.as_deref_mut().unwrap(); // checked above
+        if !matches!(result, ControlFlow::Continue(_)) {
+            retries.retain(retained, original_size);
+            retries.resent += 1;
+        }
+
+        match result {
+            // The request is failed, but the chunk will be sent again once
+            // the request is restarted by `poll_ready()`.
+            ControlFlow::Break(Err(_)) if retries.chunks.is_some() => ControlFlow::Break(Ok(())),
+            result => result,
+        }
+    }
+
+    #[inline(always)]
+    fn try_send_chunk(
+        &mut self,
+        bytes: Bytes,
+        original_size: u64,
     ) -> ControlFlow<Result<()>, Bytes> {
         let InsertState::Active {
             sender,
@@ -406,11 +674,29 @@ impl InsertFormatted {
     /// successfully, including all materialized views and quorum writes.
     ///
     /// NOTE: If this isn't called, the whole `INSERT` is aborted.
-    pub async fn end(mut self) -> Result<()> {
-        std::future::poll_fn(|cx| self.poll_end(cx)).await
+    ///
+    /// Returns [`WriteStats`] reported by the server.
+    pub async fn end(mut self) -> Result<WriteStats> {
+        std::future::poll_fn(|cx| self.poll_end(cx)).await?;
+        Ok(self.write_stats())
+    }
+
+    fn write_stats(&self) -> WriteStats {
+        WriteStats::new(self.headers.as_deref(), self.sent_bytes)
     }
 
     pub(crate) fn poll_end(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        if self.retries.is_some() && self.state.sender().is_some() {
+            // Sends chunks again if the request has been restarted.
+            ready!(self.poll_ready(cx))?;
+        }
+
+        if let Some((sent_bytes, encoded_bytes)) = self.sent_sizes() {
+            self.sent_bytes = sent_bytes;
+            if let Some(observation) = &mut self.observation {
+                observation.set_bytes(encoded_bytes);
+            }
+        }
         self.state.terminated(&self.span);
         self.poll_wait_handle(cx)
     }
@@ -421,20 +707,31 @@ impl InsertFormatted {
         };
 
         let Poll::Ready(res) = Pin::new(&mut *handle).poll(cx) else {
-            ready!(Timeout::poll_opt(
-                self.end_timeout.as_mut(),
-                self.sleep.as_mut(),
-                cx
-            ));
+            let err = match self.poll_stalled(cx) {
+                Poll::Ready(err) => err,
+                Poll::Pending => {
+                    ready!(Timeout::poll_opt(
+                        self.end_timeout.as_mut(),
+                        self.sleep.as_mut(),
+                        cx
+                    ));
+                    tracing::debug!("insert timed out");
+                    Error::TimedOut
+                }
+            };
 
             // We can do nothing useful here, so just shut down the background task.
-            handle.abort();
-            tracing::debug!("insert timed out");
-            return Poll::Ready(Err(Error::TimedOut));
+            if let Some(handle) = self.state.handle() {
+                handle.abort();
+            }
+            if let Some(observation) = self.observation.take() {
+                observation.finish(Some(&err));
+            }
+            return Poll::Ready(Err(err.with_context(self.error_context.as_ref())));
         };
 
         let res = match res {
-            Ok(res) => res,
+            Ok(res) => res.map(|headers| self.headers = headers),
             Err(err) if err.is_panic() => panic::resume_unwind(err.into_panic()),
             Err(err) => Err(Error::Custom(format!("unexpected error: {err}"))),
         };
@@ -443,9 +740,91 @@ impl InsertFormatted {
 
         tracing::trace!("finished insert");
 
+        if let Some(observation) = self.observation.take() {
+            observation.finish(res.as_ref().err());
+        }
+
+        let res = res.map_err(|err| err.with_context(self.error_context.as_ref()));
         Poll::Ready(res.inspect_err(|e| e.record_in_current_span("error from insert query")))
     }
 
+    /// Returns [`Error::WriteStalled`] once writes to the connection have been
+    /// blocked longer than [`InsertFormatted::with_stall_timeout`].
+    fn poll_stalled(&mut self, cx: &mut Context<'_>) -> Poll<Error> {
+        let Some(watchdog) = self.stall_watchdog.as_deref_mut() else {
+            return Poll::Pending;
+        };
+
+        ready!(watchdog.poll(self.connection.as_ref(), cx));
+        tracing::debug!(timeout = ?watchdog.timeout, "insert stalled");
+
+        if let Some(connection) = &self.connection {
+            pool::poison(connection);
+        }
+        Poll::Ready(Error::WriteStalled)
+    }
+
+    /// Sends chunks of the failed request again, once it's restarted.
+    fn poll_resend(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        loop {
+            let Some(retries) = self.retries.as_deref_mut() else {
+                return Poll::Ready(Ok(()));
+            };
+            let Some((chunk, original_size)) = retries.next_to_resend() else {
+                return Poll::Ready(Ok(()));
+            };
+
+            match self.try_send_chunk(chunk, original_size) {
+                ControlFlow::Break(Ok(())) => {
+                    if let Some(retries) = self.retries.as_deref_mut() {
+                        retries.resent += 1;
+                    }
+                }
+                ControlFlow::Break(Err(_)) => ready!(self.poll_restart(cx))?,
+                ControlFlow::Continue(_) => {
+                    let Some(sender) = self.state.sender() else {
+                        return Poll::Ready(Err(Error::Network("channel closed".into())));
+                    };
+                    if !ready!(sender.poll_ready(cx)) {
+                        ready!(self.poll_restart(cx))?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Waits for the error of the failed request and starts it again,
+    /// if the error is transient and all sent chunks are retained.
+    #[cold]
+    #[inline(never)]
+    fn poll_restart(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let err = match ready!(self.poll_wait_handle(cx)) {
+            Ok(()) => Error::Network("channel closed".into()),
+            Err(err) => err,
+        };
+
+        let Some(retries) = self.retries.as_deref_mut() else {
+            return Poll::Ready(Err(err));
+        };
+
+        let can_retry = matches!(err.without_context(), Error::Network(_))
+            && retries.retries < retries.max_retries
+            && retries.chunks.is_some();
+        let Some((client, sql)) = retries.request.as_ref().filter(|_| can_retry) else {
+            return Poll::Ready(Err(err));
+        };
+
+        retries.retries += 1;
+        retries.resent = 0;
+        tracing::debug!(error = ?err, retry = retries.retries, "starting insert again");
+
+        self.state = InsertState::NotStarted {
+            client: client.clone(),
+            sql: sql.clone(),
+        };
+        Poll::Ready(self.init_request())
+    }
+
     #[cold]
     #[track_caller]
     #[inline(never)]
@@ -453,6 +832,18 @@ impl InsertFormatted {
         debug_assert!(matches!(self.state, InsertState::NotStarted { .. }));
         let (client, sql) = self.state.client_with_sql().unwrap(); // checked above
 
+        if BLOCK_SIZE_SETTINGS
+            .iter()
+            .any(|name| client.get_setting(name).is_some())
+        {
+            // Parts of smaller blocks could be written before a failure.
+            self.retries = None;
+        } else if let Some(retries) = self.retries.as_deref_mut()
+            && retries.request.is_none()
+        {
+            retries.request = Some((Box::new(client.clone()), sql.into()));
+        }
+
         let _span = self.span.enter();
 
         tracing::trace!("beginning insert");
@@ -475,24 +866,47 @@ impl InsertFormatted {
             pairs.append_pair(name, value);
         }
 
+        let observation = Observation::start(client, QueryKind::Insert, sql);
+        if let Some(query_id) = observation.as_ref().and_then(|o| o.generated_query_id()) {
+            pairs.append_pair(settings::QUERY_ID, query_id);
+        }
+
         drop(pairs);
 
+        if let Some(observation) = &observation {
+            observation.sent(&url, None, 0);
+        }
+
+        let error_context = ErrorContext::new(client, &url, sql);
+
         let mut builder = Request::post(url.as_str());
-        builder = with_request_headers(builder, &client.headers, &client.products_info);
+        builder = with_request_headers(builder, client);
         builder = with_authentication(builder, &client.authentication);
 
         let (sender, body) = RequestBody::chunked();
 
-        let request = builder.body(body).map_err(|err| {
+        let mut request = builder.body(body).map_err(|err| {
             let err = Error::InvalidParams(Box::new(err));
             err.record_in_current_span("invalid params in insert request");
-            err
+            err.with_context(error_context.as_ref())
         })?;
 
-        let future = client.http.request(request);
+        self.connection = Some(capture_connection(&mut request));
+        if let Some(watchdog) = self.stall_watchdog.as_deref_mut() {
+            watchdog.writes = None;
+        }
+        let future = send_request(client, request);
+        self.observation = observation;
+        self.error_context = error_context;
 
         // Ensure the span created internally is captured as a child of the current span.
-        let mut response = Response::new(future, Compression::None);
+        let options = ResponseOptions {
+            permit: client.pending_permit(),
+            pool_metrics: client.pool_metrics(),
+            connection: self.connection.clone(),
+            ..ResponseOptions::default()
+        };
+        let mut response = Response::new(future, Compression::None, options);
 
         // TODO: introduce `Executor` to allow bookkeeping of spawned tasks.
         let handle = tokio::spawn(async move { response.finish().await });
@@ -529,6 +943,14 @@ pub struct BufInsertFormatted {
     buffer: BytesMut,
     /// Nominal capacity, stored separately because [`Self::write_buffered()`] can grow the buffer.
     nominal_capacity: usize,
+    /// Compress chunks on the blocking pool, see [`Insert::with_offload_serialization`].
+    ///
+    /// [`Insert::with_offload_serialization`]: crate::insert::Insert::with_offload_serialization
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    offload: bool,
+    /// The previous chunk, which is compressed on the blocking pool, if offloaded.
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    pending: Option<compression::PendingChunk>,
 }
 
 impl BufInsertFormatted {
@@ -537,6 +959,10 @@ impl BufInsertFormatted {
             insert,
             buffer: BytesMut::with_capacity(capacity),
             nominal_capacity: capacity,
+            #[cfg(any(feature = "lz4", feature = "zstd"))]
+            offload: false,
+            #[cfg(any(feature = "lz4", feature = "zstd"))]
+            pending: None,
         }
     }
 
@@ -567,6 +993,21 @@ impl BufInsertFormatted {
         self.nominal_capacity
     }
 
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        if self.buffer.is_empty() {
+            self.buffer = BytesMut::with_capacity(capacity);
+        } else {
+            self.buffer
+                .reserve(capacity.saturating_sub(self.buffer.len()));
+        }
+        self.nominal_capacity = capacity;
+    }
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    pub(crate) fn set_offload_compression(&mut self, offload: bool) {
+        self.offload = offload;
+    }
+
     #[inline(always)]
     pub(crate) fn buffer_mut(&mut self) -> &mut BytesMut {
         &mut self.buffer
@@ -584,11 +1025,32 @@ impl BufInsertFormatted {
         self.insert.set_timeouts(send_timeout, end_timeout);
     }
 
+    pub(crate) fn set_retries(&mut self, max_retries: u32) {
+        self.insert.set_retries(max_retries);
+    }
+
+    pub(crate) fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.insert.set_stall_timeout(timeout);
+    }
+
     #[doc(hidden)]
     pub fn _priv_span(&self) -> &tracing::Span {
         self.insert.span()
     }
 
+    pub(crate) fn set_observed_rows(&mut self, rows: u64) {
+        self.insert.set_observed_rows(rows);
+    }
+
+    #[cfg(feature = "inserter")]
+    pub(crate) fn connection(&self) -> Option<&CaptureConnection> {
+        self.insert.connection()
+    }
+
+    pub(crate) fn sent_sizes(&self) -> Option<(u64, u64)> {
+        self.insert.sent_sizes()
+    }
+
     /// Write data to the buffer without waiting for it to be flushed.
     ///
     /// May cause the buffer to resize to fit the data.
@@ -622,7 +1084,7 @@ impl BufInsertFormatted {
         // Capacity calculations change a little bit from those in, e.g., `tokio::io::BufWriter`
         // since we always need to copy into the buffer to send chunks on the connection.
         if self.buffer.len() >= self.nominal_capacity {
-            ready!(self.poll_flush_inner(cx))?;
+            ready!(self.poll_start_flush(cx))?;
             debug_assert!(self.buffer.is_empty());
         }
 
@@ -650,12 +1112,34 @@ impl BufInsertFormatted {
         std::future::poll_fn(|cx| self.poll_flush_inner(cx)).await
     }
 
+    /// Starts flushing the buffer without waiting for the chunk to be sent
+    /// if it's compressed on the blocking pool.
+    pub(crate) async fn start_flush(&mut self) -> Result<()> {
+        std::future::poll_fn(|cx| self.poll_start_flush(cx)).await
+    }
+
     #[inline(always)]
     fn poll_flush_inner(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.poll_start_flush(cx))?;
+        self.poll_pending(cx)
+    }
+
+    #[inline(always)]
+    fn poll_start_flush(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        // Chunks must be sent in order, so the previous one is waited for.
+        ready!(self.poll_pending(cx))?;
+
         if self.buffer.is_empty() {
             return Poll::Ready(Ok(()));
         }
 
+        #[cfg(any(feature = "lz4", feature = "zstd"))]
+        if self.offload && self.insert.compression.is_enabled() {
+            let data = self.buffer.split().freeze();
+            self.spawn_compression(data);
+            return Poll::Ready(Ok(()));
+        }
+
         ready!(self.insert.poll_ready(cx))?;
 
         let data = self.buffer.split().freeze();
@@ -680,16 +1164,15 @@ impl BufInsertFormatted {
     ///
     /// Cancel-safe.
     #[inline(always)]
-    pub async fn end(&mut self) -> Result<()> {
-        std::future::poll_fn(|cx| self.poll_end(cx)).await
+    pub async fn end(&mut self) -> Result<WriteStats> {
+        std::future::poll_fn(|cx| self.poll_end(cx)).await?;
+        Ok(self.insert.write_stats())
     }
 
     #[inline(always)]
     fn poll_end(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
-        if !self.buffer.is_empty() {
-            ready!(self.poll_flush_inner(cx))?;
-            debug_assert!(self.buffer.is_empty());
-        }
+        ready!(self.poll_flush_inner(cx))?;
+        debug_assert!(self.buffer.is_empty());
 
         self.insert.poll_end(cx)
     }
@@ -697,6 +1180,10 @@ impl BufInsertFormatted {
     /// Returns `Ok(true)` if the request was freshly started, `Err(...)` on error,
     /// or `Ok(false)` otherwise.
     #[inline]
+    pub(crate) fn is_not_started(&self) -> bool {
+        self.insert.state.is_not_started()
+    }
+
     pub(crate) fn init_request_if_required(&mut self) -> Result<bool> {
         if self.insert.state.is_not_started() {
             self.insert.init_request().map(|_| true)
@@ -706,8 +1193,18 @@ impl BufInsertFormatted {
     }
 
     pub(crate) fn abort(&mut self) {
+        #[cfg(any(feature = "lz4", feature = "zstd"))]
+        {
+            self.pending = None;
+        }
         self.insert.abort();
     }
+
+    #[cfg(not(any(feature = "lz4", feature = "zstd")))]
+    #[inline(always)]
+    fn poll_pending(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
 }
 
 impl AsyncWrite for BufInsertFormatted {
@@ -778,6 +1275,35 @@ impl Timeout {
     }
 }
 
+impl StallWatchdog {
+    /// Returns `Poll::Ready` once writes have been blocked for `timeout`,
+    /// failing the blocked write, so the connection is closed.
+    fn poll(&mut self, connection: Option<&CaptureConnection>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.writes.is_none() {
+            self.writes = connection.and_then(WriteWatch::of);
+        }
+
+        let blocked_since = self.writes.as_ref().and_then(|w| w.blocked_since());
+        let now = Instant::now();
+        let deadline = match blocked_since {
+            Some(since) if now.saturating_duration_since(since) >= self.timeout => {
+                if let Some(writes) = &self.writes {
+                    writes.stall();
+                }
+                return Poll::Ready(());
+            }
+            Some(since) => since + self.timeout,
+            // Checked again later, writes can be blocked in the meantime.
+            None => now + self.timeout,
+        };
+
+        self.sleep.as_mut().reset(deadline);
+        // The deadline is in the future, so it only registers the waker.
+        let _ = self.sleep.as_mut().poll(cx);
+        Poll::Pending
+    }
+}
+
 fn to_u64_saturating(n: usize) -> u64 {
     n.try_into().unwrap_or(u64::MAX)
 }
@@ -787,8 +1313,12 @@ fn to_u64_saturating(n: usize) -> u64 {
 mod compression {
     use crate::Compression;
     use crate::error::{Error, Result};
-    use crate::insert_formatted::{InsertFormatted, to_u64_saturating};
+    use crate::insert_formatted::{BufInsertFormatted, InsertFormatted, to_u64_saturating};
     use bytes::Bytes;
+    use std::ops::ControlFlow;
+    use std::task::{Context, Poll, ready};
+    use std::{future::Future, panic, pin::Pin};
+    use tokio::task::JoinHandle;
 
     /// A chunk of pre-compressed data.
     #[cfg_attr(docsrs, doc(cfg(any(feature = "lz4", feature = "zstd"))))]
@@ -864,4 +1394,57 @@ mod compression {
             self.send_inner(data.compressed, data.original_size).await
         }
     }
+    /// A chunk compressed on the blocking pool, see [`BufInsertFormatted::spawn_compression`].
+    pub(crate) enum PendingChunk {
+        Compressing(JoinHandle<Result<CompressedData>>),
+        Compressed(CompressedData),
+    }
+
+    impl BufInsertFormatted {
+        /// Starts compressing `data` on the blocking pool,
+        /// it's sent by [`BufInsertFormatted::poll_pending`].
+        pub(super) fn spawn_compression(&mut self, data: Bytes) {
+            debug_assert!(self.pending.is_none());
+            let compression = self.insert.compression;
+            let task = tokio::task::spawn_blocking(move || CompressedData::new(&data, compression));
+            self.pending = Some(PendingChunk::Compressing(task));
+        }
+
+        /// Waits for the pending chunk to be compressed and sends it, if any.
+        pub(super) fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            loop {
+                match &mut self.pending {
+                    None => return Poll::Ready(Ok(())),
+                    Some(PendingChunk::Compressing(task)) => {
+                        let result = ready!(Pin::new(task).poll(cx));
+                        self.pending = None;
+                        match result {
+                            Ok(Ok(data)) => self.pending = Some(PendingChunk::Compressed(data)),
+                            Ok(Err(err)) => {
+                                self.abort();
+                                return Poll::Ready(Err(err));
+                            }
+                            Err(err) if err.is_panic() => panic::resume_unwind(err.into_panic()),
+                            Err(err) => return Poll::Ready(Err(Error::Other(err.into()))),
+                        }
+                    }
+                    Some(PendingChunk::Compressed(_)) => {
+                        ready!(self.insert.poll_ready(cx))?;
+
+                        let Some(PendingChunk::Compressed(data)) = self.pending.take() else {
+                            unreachable!("BUG: the chunk is checked above");
+                        };
+
+                        let ControlFlow::Break(res) =
+                            self.insert.try_send(data.compressed, data.original_size)
+                        else {
+                            unreachable!("BUG: we just checked that `ChunkSender` was ready")
+                        };
+
+                        return Poll::Ready(res);
+                    }
+                }
+            }
+        }
+    }
 }
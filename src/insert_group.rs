@@ -0,0 +1,133 @@
+//! Support for [`Client::insert_group`].
+//!
+//! All inserts of a group are executed in one HTTP session, which is started
+//! with `BEGIN TRANSACTION`. If the server doesn't support transactions
+//! (they're experimental and disabled by default), the group falls back to
+//! inserting in the session without one, so only each `INSERT` is atomic.
+//! Other errors of `BEGIN TRANSACTION`, e.g. authentication ones, are returned.
+//!
+//! [`Client::insert_group`]: crate::Client::insert_group
+
+use crate::{
+    Client,
+    audit::generate_query_id,
+    error::{Error, Result},
+    insert::Insert,
+    row::Row,
+    settings, sql,
+};
+
+/// Reports unsupported queries instead of executing them outside of the transaction,
+/// e.g. inserts into tables with engines other than `MergeTree`.
+const THROW_ON_UNSUPPORTED: &str = "throw_on_unsupported_query_inside_transaction";
+
+/// Codes of server errors meaning that transactions are unsupported or disabled,
+/// `NOT_IMPLEMENTED` and `SUPPORT_IS_DISABLED`. Other errors aren't ignored.
+const TRANSACTIONS_UNSUPPORTED: [u32; 2] = [48, 344];
+
+/// Inserts into multiple related tables (e.g. facts and dimensions) in one
+/// session, committed together where the server supports transactions,
+/// see [`Client::insert_group`].
+///
+/// The session allows only one query at a time, so end each [`Insert`]
+/// created by [`InsertGroup::insert`] before starting the next one and before
+/// calling [`InsertGroup::commit`].
+///
+/// # Dropping
+///
+/// If neither [`InsertGroup::commit`] nor [`InsertGroup::rollback`] is called,
+/// the transaction is rolled back by the server once the session expires
+/// (`session_timeout`, 60 seconds by default).
+///
+/// [`Client::insert_group`]: crate::Client::insert_group
+#[must_use]
+pub struct InsertGroup {
+    client: Client,
+    transactional: bool,
+}
+
+impl InsertGroup {
+    pub(crate) async fn begin(client: &Client) -> Result<Self> {
+        let client = client
+            .clone()
+            .with_setting(settings::SESSION_ID, generate_query_id())
+            .with_setting(THROW_ON_UNSUPPORTED, "1");
+
+        let transactional = match client.query("BEGIN TRANSACTION").execute().await {
+            Ok(()) => true,
+            // Rejected by the server, e.g. `allow_experimental_transactions` is disabled.
+            Err(err) if is_unsupported(&err) => {
+                tracing::debug!(error = %err, "inserting without a transaction");
+                false
+            }
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            client,
+            transactional,
+        })
+    }
+
+    /// Returns `true` if inserts are executed in a transaction.
+    ///
+    /// Otherwise, the server doesn't support transactions, and rows of ended
+    /// inserts are visible immediately and cannot be rolled back.
+    pub fn is_transactional(&self) -> bool {
+        self.transactional
+    }
+
+    /// Returns the client bound to the session of the group, e.g. to run
+    /// `SELECT` queries seeing rows inserted in the transaction.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Starts a new `INSERT` in the session, see [`Client::insert`].
+    ///
+    /// [`Client::insert`]: crate::Client::insert
+    pub async fn insert<'a, T: Row>(
+        &self,
+        table: impl Into<sql::QualifiedTable<'a>>,
+    ) -> Result<Insert<T>> {
+        self.client.insert(table).await
+    }
+
+    /// Commits the transaction, making rows of all inserts visible at once.
+    ///
+    /// Does nothing if the group isn't transactional.
+    pub async fn commit(self) -> Result<()> {
+        if self.transactional {
+            self.client.query("COMMIT").execute().await?;
+        }
+        Ok(())
+    }
+
+    /// Rolls back the transaction, discarding rows of all inserts.
+    ///
+    /// Returns [`Error::Unsupported`] if the group isn't transactional,
+    /// because rows of ended inserts are already stored.
+    pub async fn rollback(self) -> Result<()> {
+        if !self.transactional {
+            return Err(Error::Unsupported(
+                "inserts cannot be rolled back without transaction support".into(),
+            ));
+        }
+
+        self.client.query("ROLLBACK").execute().await
+    }
+}
+
+fn is_unsupported(err: &Error) -> bool {
+    let code = match err.without_context() {
+        // `Code: <code>. DB::Exception: ...` or `Code: <code>` if the body is empty.
+        Error::BadResponse(reason) => reason
+            .strip_prefix("Code: ")
+            .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .and_then(|code| code.parse().ok()),
+        Error::ServerException { code, .. } => *code,
+        _ => None,
+    };
+
+    code.is_some_and(|code| TRANSACTIONS_UNSUPPORTED.contains(&code))
+}
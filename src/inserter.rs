@@ -1,40 +1,81 @@
-use std::mem;
+use std::{collections::VecDeque, mem};
 
+use hyper_util::client::legacy::connect::CaptureConnection;
 use tokio::time::Duration;
 
 use crate::{
-    Client,
+    Client, Settings,
     error::Result,
-    insert::Insert,
+    flusher::{EndedInsert, IdleFlusher, Unparked},
+    insert::{AsyncInsertWait, Insert, WriteStats},
+    rate_limit::TokenBucket,
     row::{Row, RowWrite},
-    ticks::Ticks,
+    ticks::{Instant, Ticks},
 };
 
+pub use crate::sink::{InsertSink, SinkSender};
+
+// ClickHouse uses 3s by default.
+// See https://github.com/ClickHouse/ClickHouse/blob/368cb74b4d222dc5472a7f2177f6bb154ebae07a/programs/server/config.xml#L201
+const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(3);
+const DEFAULT_KEEP_ALIVE_MARGIN: Duration = Duration::from_secs(1);
+const DEFAULT_RATE_LIMIT_BURST: Duration = Duration::from_secs(1);
+
 /// Performs multiple consecutive `INSERT`s.
 ///
 /// By default, it **doesn't** end the current active `INSERT` automatically.
-/// Use `with_max_bytes`, `with_max_rows` and `with_period` to set limits.
+/// Use `with_max_bytes`, `with_max_compressed_bytes`, `with_max_rows`,
+/// `with_period` and `with_max_batch_age` to set limits, and
+/// `with_flush_interval` to end it in background while the inserter is idle.
 /// Alternatively, call `force_commit` to forcibly end an active `INSERT`.
 ///
 /// Rows are being sent progressively to spread network load.
 ///
 /// All rows written by [`Inserter::write()`] between [`Inserter::commit()`]
 /// calls are sent in one `INSERT` statement.
+///
+/// `K` is the type of batch tokens, see [`Inserter::on_committed`].
 #[must_use]
-pub struct Inserter<T> {
+pub struct Inserter<T, K = ()> {
     client: Client,
     table: String,
     max_bytes: u64,
     max_rows: u64,
+    max_compressed_bytes: u64,
+    max_batch_age: Option<Duration>,
     send_timeout: Option<Duration>,
     end_timeout: Option<Duration>,
+    stall_timeout: Option<Duration>,
+    keep_alive: Option<Duration>,
+    keep_alive_margin: Duration,
+    rows_per_sec: u64,
+    bytes_per_sec: u64,
+    rate_limit_burst: Duration,
+    rows_limit: Option<TokenBucket>,
+    bytes_limit: Option<TokenBucket>,
     insert: Option<Insert<T>>,
+    idle_connection: Option<IdleConnection>,
     ticks: Ticks,
     pending: Quantities,
     in_transaction: bool,
+    /// When the first pending row was written.
+    batch_started_at: Option<Instant>,
+    /// How long the last `INSERT` took to end.
+    last_end_duration: Duration,
+    last_batch_age: Option<Duration>,
+    flusher: Option<IdleFlusher<T>>,
+    /// Whether `insert` is parked in the flusher.
+    parked: bool,
+    /// Quantities, starts and tokens of batches ended by the flusher,
+    /// until their results are reported.
+    flushing: VecDeque<(Quantities, Option<Instant>, Option<K>)>,
+    /// The token of the current batch, see [`Inserter::set_batch_token`].
+    batch_token: Option<K>,
 
     #[allow(clippy::type_complexity)]
     on_commit: Option<Box<dyn FnMut(&Quantities) + Send + 'static>>,
+    #[allow(clippy::type_complexity)]
+    on_committed: Option<Box<dyn FnMut(&Quantities, Option<K>) + Send + 'static>>,
 }
 
 /// Statistics about pending or inserted data.
@@ -46,6 +87,10 @@ pub struct Quantities {
     pub rows: u64,
     /// The number of nonempty transactions (calls of [`Inserter::commit`]).
     pub transactions: u64,
+    /// Statistics reported by the server for the ended `INSERT`.
+    ///
+    /// `None` for pending data and if no `INSERT` has been ended.
+    pub stats: Option<WriteStats>,
 }
 
 impl Quantities {
@@ -54,10 +99,17 @@ impl Quantities {
         bytes: 0,
         rows: 0,
         transactions: 0,
+        stats: None,
     };
 }
 
-impl<T> Inserter<T>
+/// A connection used by the last ended `INSERT`, which is idle in the pool now.
+struct IdleConnection {
+    connection: CaptureConnection,
+    since: Instant,
+}
+
+impl<T, K> Inserter<T, K>
 where
     T: Row,
 {
@@ -67,13 +119,32 @@ where
             table: table.into(),
             max_bytes: u64::MAX,
             max_rows: u64::MAX,
+            max_compressed_bytes: u64::MAX,
+            max_batch_age: None,
             send_timeout: None,
             end_timeout: None,
+            stall_timeout: None,
+            keep_alive: Some(DEFAULT_KEEP_ALIVE),
+            keep_alive_margin: DEFAULT_KEEP_ALIVE_MARGIN,
+            rows_per_sec: u64::MAX,
+            bytes_per_sec: u64::MAX,
+            rate_limit_burst: DEFAULT_RATE_LIMIT_BURST,
+            rows_limit: None,
+            bytes_limit: None,
             insert: None,
+            idle_connection: None,
             ticks: Ticks::default(),
             pending: Quantities::ZERO,
             in_transaction: false,
+            batch_started_at: None,
+            last_end_duration: Duration::ZERO,
+            last_batch_age: None,
+            flusher: None,
+            parked: false,
+            flushing: VecDeque::new(),
+            batch_token: None,
             on_commit: None,
+            on_committed: None,
         }
     }
 
@@ -90,6 +161,67 @@ where
         self
     }
 
+    /// See [`Insert::with_stall_timeout()`].
+    ///
+    /// It's also applied to [`Inserter::commit()`] ending an `INSERT`,
+    /// so a flush to a frozen server fails instead of holding the batch.
+    pub fn with_stall_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.set_stall_timeout(timeout);
+        self
+    }
+
+    /// Sets the server's [`keep_alive_timeout`] and a safety margin for it.
+    ///
+    /// If the time between the end of the previous `INSERT` and the beginning
+    /// of the next one exceeds `keep_alive - margin`, the connection used by
+    /// the previous `INSERT` isn't reused, because the server can close it at
+    /// the same moment the next `INSERT` starts writing to it. It leads to
+    /// errors like "broken pipe" or "connection reset" and lost batches.
+    ///
+    /// It matters mostly for custom HTTP clients (see [`Client::with_http_client`])
+    /// having an idle timeout of their pools greater than the server's one.
+    ///
+    /// `None` disables recycling of idle connections.
+    ///
+    /// By default, `keep_alive` is 3s (the server's default) and `margin` is 1s.
+    ///
+    /// [`keep_alive_timeout`]: https://clickhouse.com/docs/operations/server-configuration-parameters/settings#keep_alive_timeout
+    pub fn with_keep_alive(mut self, keep_alive: Option<Duration>, margin: Duration) -> Self {
+        self.set_keep_alive(keep_alive, margin);
+        self
+    }
+
+    /// Limits the rate of written rows and uncompressed bytes per second.
+    ///
+    /// [`Inserter::write()`] waits if the limit has been exceeded by previous
+    /// writes, which smooths ingestion from bursty sources. Since batches are
+    /// limited by size and age, it also reduces the rate of `INSERT`s and,
+    /// thus, the number of created parts (e.g. to avoid `TOO_MANY_PARTS`).
+    ///
+    /// Up to one second worth of rows and bytes can be written at once,
+    /// see [`Inserter::with_rate_limit_burst()`].
+    ///
+    /// Unlimited (`u64::MAX`) by default.
+    ///
+    /// # Panics
+    /// If any of the limits is zero.
+    pub fn with_rate_limit(mut self, rows_per_sec: u64, bytes_per_sec: u64) -> Self {
+        self.set_rate_limit(rows_per_sec, bytes_per_sec);
+        self
+    }
+
+    /// Sets how long the rate limit can be exceeded after being idle, i.e.
+    /// how many rows and bytes can be written at once without waiting.
+    ///
+    /// For instance, with `with_rate_limit(1000, u64::MAX)` and 5s burst,
+    /// up to 5000 rows can be written immediately after 5s of inactivity.
+    ///
+    /// 1s by default.
+    pub fn with_rate_limit_burst(mut self, burst: Duration) -> Self {
+        self.set_rate_limit_burst(burst);
+        self
+    }
+
     /// The maximum number of uncompressed bytes in one `INSERT` statement.
     ///
     /// This is the soft limit, which can be exceeded if rows between
@@ -106,6 +238,27 @@ where
         self
     }
 
+    /// The maximum number of bytes sent in one `INSERT` statement after
+    /// compression, see [`Client::with_compression`].
+    ///
+    /// Unlike [`Inserter::with_max_bytes()`], it limits the actual network
+    /// and server load, which varies a lot for the same number of uncompressed
+    /// bytes depending on the data. To measure it, rows are compressed and
+    /// sent progressively once this limit is set. The size of the data not yet
+    /// sent is estimated based on the compression ratio of already sent data.
+    ///
+    /// If compression is disabled, it's the same as the number of uncompressed
+    /// bytes.
+    ///
+    /// This is the soft limit, which can be exceeded if rows between
+    /// [`Inserter::commit()`] calls are larger than set value.
+    ///
+    /// Unlimited (`u64::MAX`) by default.
+    pub fn with_max_compressed_bytes(mut self, threshold: u64) -> Self {
+        self.set_max_compressed_bytes(threshold);
+        self
+    }
+
     /// The maximum number of rows in one `INSERT` statement.
     ///
     /// In order to reduce overhead of merging small parts by ClickHouse, use
@@ -150,6 +303,61 @@ where
         self
     }
 
+    /// The maximum time a row can stay uncommitted, measured from the moment
+    /// the first row of the current batch is written.
+    ///
+    /// Unlike [`Inserter::with_period()`], it's independent of when the previous
+    /// `INSERT` was ended, so delays under load don't shift the deadline.
+    /// The batch is committed earlier by the time the previous `INSERT` took
+    /// to end (but at most half of `max_age`), so ending the `INSERT` fits
+    /// the deadline too.
+    ///
+    /// As with the period, checks are performed only on [`Inserter::commit()`],
+    /// so it must be called in time, see [`Inserter::time_left()`].
+    ///
+    /// Unlimited (`None`) by default.
+    pub fn with_max_batch_age(mut self, max_age: Option<Duration>) -> Self {
+        self.set_max_batch_age(max_age);
+        self
+    }
+
+    /// Ends the current `INSERT` in background if nothing has been written
+    /// for `interval`, so pending rows aren't stuck in the inserter while
+    /// the source is idle, and the request isn't dropped by the server
+    /// because of its receive timeout.
+    ///
+    /// Unlike [`Inserter::with_period()`] and [`Inserter::with_max_batch_age()`],
+    /// which are checked only on [`Inserter::commit()`], it spawns a task
+    /// to end the `INSERT` without any calls.
+    ///
+    /// Rows written by [`Inserter::write()`] and not committed yet are also
+    /// flushed, so use [`Inserter::write()`] and [`Inserter::commit()`]
+    /// without awaiting anything else between them if rows must be inserted
+    /// in one transaction.
+    ///
+    /// The result of the background flush is reported on the next call of
+    /// [`Inserter::write()`], [`Inserter::commit()`], [`Inserter::force_commit()`]
+    /// or [`Inserter::end()`]: the commit callback is invoked and errors are
+    /// returned. Until then, [`Inserter::pending()`] still includes flushed rows.
+    ///
+    /// [`Inserter::end()`] waits for the background flush in progress.
+    /// Dropping the inserter aborts it, as well as the current `INSERT`.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Panics
+    /// If called outside of a Tokio runtime.
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self
+    where
+        T: 'static,
+    {
+        match &self.flusher {
+            Some(flusher) => flusher.set_interval(interval),
+            None => self.flusher = Some(IdleFlusher::spawn(interval)),
+        }
+        self
+    }
+
     /// Adds a bias to the period, so actual period is in the following range:
     ///
     /// ```text
@@ -232,6 +440,29 @@ where
         self
     }
 
+    /// Similar to [`Client::with_settings`], but for the INSERT statements
+    /// generated by this [`Inserter`] only.
+    ///
+    /// # Note
+    /// This does not take effect until the next `INSERT` statement begins
+    /// if one is already in-progress.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        for (name, value) in settings.into_pairs() {
+            self.client.set_setting(name, value);
+        }
+        self
+    }
+
+    /// Makes the INSERT statements generated by this [`Inserter`] asynchronous,
+    /// see [`Insert::with_async_insert`] for details.
+    ///
+    /// # Note
+    /// This does not take effect until the next `INSERT` statement begins
+    /// if one is already in-progress.
+    pub fn with_async_insert(self, wait: AsyncInsertWait) -> Self {
+        self.with_settings(wait.settings())
+    }
+
     /// See [`Inserter::with_timeouts()`].
     pub fn set_timeouts(&mut self, send_timeout: Option<Duration>, end_timeout: Option<Duration>) {
         self.send_timeout = send_timeout;
@@ -239,6 +470,39 @@ where
         if let Some(insert) = &mut self.insert {
             insert.set_timeouts(self.send_timeout, self.end_timeout);
         }
+        if let Some(flusher) = &self.flusher {
+            flusher.with_parked(|insert| insert.set_timeouts(send_timeout, end_timeout));
+        }
+    }
+
+    /// See [`Inserter::with_stall_timeout()`].
+    pub fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.stall_timeout = timeout;
+        if let Some(insert) = &mut self.insert {
+            insert.set_stall_timeout(timeout);
+        }
+        if let Some(flusher) = &self.flusher {
+            flusher.with_parked(|insert| insert.set_stall_timeout(timeout));
+        }
+    }
+
+    /// See [`Inserter::with_keep_alive()`].
+    pub fn set_keep_alive(&mut self, keep_alive: Option<Duration>, margin: Duration) {
+        self.keep_alive = keep_alive;
+        self.keep_alive_margin = margin;
+    }
+
+    /// See [`Inserter::with_rate_limit()`].
+    pub fn set_rate_limit(&mut self, rows_per_sec: u64, bytes_per_sec: u64) {
+        self.rows_per_sec = rows_per_sec;
+        self.bytes_per_sec = bytes_per_sec;
+        self.reset_rate_limits();
+    }
+
+    /// See [`Inserter::with_rate_limit_burst()`].
+    pub fn set_rate_limit_burst(&mut self, burst: Duration) {
+        self.rate_limit_burst = burst;
+        self.reset_rate_limits();
     }
 
     /// See [`Inserter::with_max_bytes()`].
@@ -246,11 +510,21 @@ where
         self.max_bytes = threshold;
     }
 
+    /// See [`Inserter::with_max_compressed_bytes()`].
+    pub fn set_max_compressed_bytes(&mut self, threshold: u64) {
+        self.max_compressed_bytes = threshold;
+    }
+
     /// See [`Inserter::with_max_rows()`].
     pub fn set_max_rows(&mut self, threshold: u64) {
         self.max_rows = threshold;
     }
 
+    /// See [`Inserter::with_max_batch_age()`].
+    pub fn set_max_batch_age(&mut self, max_age: Option<Duration>) {
+        self.max_batch_age = max_age;
+    }
+
     /// See [`Inserter::with_period()`].
     pub fn set_period(&mut self, period: Option<Duration>) {
         self.ticks.set_period(period);
@@ -276,11 +550,161 @@ where
         self
     }
 
-    /// How much time we have until the next tick.
+    /// Registers a callback invoked after each successful batch commit with
+    /// the committed [`Quantities`] and the token of the batch set by
+    /// [`Inserter::set_batch_token`], if any.
+    ///
+    /// Like [`Inserter::with_commit_callback`], it's invoked only after the
+    /// server acknowledged the batch, so it can be used to commit offsets of
+    /// consumed messages (e.g. Kafka offsets) for at-least-once delivery.
+    /// Tokens of failed batches are dropped along with their rows.
+    ///
+    /// It sets the type of tokens accepted by [`Inserter::set_batch_token`],
+    /// so they're checked at compile time. Tokens set before with another
+    /// type are dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use clickhouse::{Client, Row};
+    /// # #[derive(Row, serde::Serialize)]
+    /// # struct Event { offset: i64 }
+    /// # async fn example(client: Client, events: Vec<Event>) -> clickhouse::error::Result<()> {
+    /// let mut inserter = client
+    ///     .inserter::<Event>("events")
+    ///     .with_max_rows(10_000)
+    ///     .on_committed(|quantities, offset: Option<i64>| {
+    ///         // Commit `offset` upstream.
+    ///         println!("{} rows, offset {offset:?}", quantities.rows);
+    ///     });
     ///
-    /// `None` if the period isn't configured.
+    /// for event in events {
+    ///     inserter.write(&event).await?;
+    ///     inserter.set_batch_token(event.offset);
+    ///     inserter.commit().await?;
+    /// }
+    /// inserter.end().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_committed<K2>(
+        self,
+        callback: impl FnMut(&Quantities, Option<K2>) + Send + 'static,
+    ) -> Inserter<T, K2> {
+        let Self {
+            client,
+            table,
+            max_bytes,
+            max_rows,
+            max_compressed_bytes,
+            max_batch_age,
+            send_timeout,
+            end_timeout,
+            stall_timeout,
+            keep_alive,
+            keep_alive_margin,
+            rows_per_sec,
+            bytes_per_sec,
+            rate_limit_burst,
+            rows_limit,
+            bytes_limit,
+            insert,
+            idle_connection,
+            ticks,
+            pending,
+            in_transaction,
+            batch_started_at,
+            last_end_duration,
+            last_batch_age,
+            flusher,
+            parked,
+            on_commit,
+            flushing,
+            batch_token: _,
+            on_committed: _,
+        } = self;
+
+        Inserter {
+            client,
+            table,
+            max_bytes,
+            max_rows,
+            max_compressed_bytes,
+            max_batch_age,
+            send_timeout,
+            end_timeout,
+            stall_timeout,
+            keep_alive,
+            keep_alive_margin,
+            rows_per_sec,
+            bytes_per_sec,
+            rate_limit_burst,
+            rows_limit,
+            bytes_limit,
+            insert,
+            idle_connection,
+            ticks,
+            pending,
+            in_transaction,
+            batch_started_at,
+            last_end_duration,
+            last_batch_age,
+            flusher,
+            parked,
+            on_commit,
+            flushing: flushing
+                .into_iter()
+                .map(|(quantities, started_at, _)| (quantities, started_at, None))
+                .collect(),
+            batch_token: None,
+            on_committed: Some(Box::new(callback)),
+        }
+    }
+
+    /// Sets the token of the current batch, e.g. the offset of the last
+    /// consumed message, passed to the [`Inserter::on_committed`] callback
+    /// once the batch is committed.
+    ///
+    /// It replaces the token set before for the same batch, so it can be called
+    /// after each [`Inserter::write()`] with the latest position. If there are
+    /// no pending rows, the token is passed with the next committed batch.
+    pub fn set_batch_token(&mut self, token: K) {
+        self.batch_token = Some(token);
+    }
+
+    /// How much time we have until the next tick or until the current batch
+    /// must be committed to meet [`Inserter::with_max_batch_age()`].
+    ///
+    /// `None` if the period isn't configured and there are no pending rows
+    /// limited by the max batch age.
     pub fn time_left(&mut self) -> Option<Duration> {
-        self.ticks.time_left()
+        let ticks = self.ticks.time_left();
+        let deadline = self
+            .batch_deadline()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+        match (ticks, deadline) {
+            (Some(ticks), Some(deadline)) => Some(ticks.min(deadline)),
+            (ticks, deadline) => ticks.or(deadline),
+        }
+    }
+
+    /// Returns how long the oldest pending row has been waiting for a commit.
+    ///
+    /// `None` if there are no pending rows.
+    pub fn batch_age(&self) -> Option<Duration> {
+        self.batch_started_at
+            .map(|started_at| Instant::now().saturating_duration_since(started_at))
+    }
+
+    /// Returns how long the oldest row of the last committed batch waited
+    /// until the batch was committed, including ending the `INSERT`.
+    ///
+    /// It's the upper bound of the delay introduced by the inserter,
+    /// which can be reported as a metric after every commit.
+    ///
+    /// `None` if nothing has been committed yet.
+    pub fn last_batch_age(&self) -> Option<Duration> {
+        self.last_batch_age
     }
 
     /// Returns statistics about data not yet inserted into ClickHouse.
@@ -288,11 +712,27 @@ where
         &self.pending
     }
 
+    /// Returns the number of pending bytes after compression.
+    ///
+    /// It's exact for data already sent to ClickHouse and estimated for
+    /// buffered data, see [`Inserter::with_max_compressed_bytes()`].
+    pub fn pending_compressed_bytes(&self) -> u64 {
+        match (&self.insert, &self.flusher) {
+            (Some(insert), _) => insert.compressed_size_estimate(),
+            (None, Some(flusher)) => flusher
+                .with_parked(|insert| insert.compressed_size_estimate())
+                .unwrap_or(0),
+            (None, None) => 0,
+        }
+    }
+
     /// Serializes the provided row into an internal buffer.
     ///
     /// To check the limits and send the data to ClickHouse, call
     /// [`Inserter::commit()`].
     ///
+    /// Waits before writing if [`Inserter::with_rate_limit()`] is exceeded.
+    ///
     /// # Panics
     /// If called after the previous call that returned an error.
     #[inline]
@@ -300,15 +740,50 @@ where
     where
         T: RowWrite,
     {
+        if self.flusher.is_none() {
+            return self.write_impl(row).await;
+        }
+
+        self.unpark()?;
+        let result = self.write_impl(row).await;
+        self.park();
+        result
+    }
+
+    async fn write_impl(&mut self, row: &T::Value<'_>) -> Result<()>
+    where
+        T: RowWrite,
+    {
+        self.throttle().await;
+
         if self.insert.is_none() {
             self.init_insert().await?;
         }
 
-        match self.insert.as_mut().unwrap().do_write(row) {
+        let insert = self.insert.as_mut().unwrap();
+        let mut result = insert.do_write(row);
+
+        // Rows are sent progressively only if the compressed size is needed.
+        if self.max_compressed_bytes != u64::MAX
+            && result.is_ok()
+            && let Err(err) = insert.flush_compressed(self.max_compressed_bytes).await
+        {
+            result = Err(err);
+        }
+
+        match result {
             Ok(bytes) => {
+                self.batch_started_at.get_or_insert_with(Instant::now);
                 self.pending.bytes += bytes as u64;
                 self.pending.rows += 1;
 
+                if let Some(limit) = &mut self.rows_limit {
+                    limit.consume(1);
+                }
+                if let Some(limit) = &mut self.bytes_limit {
+                    limit.consume(bytes as u64);
+                }
+
                 if !self.in_transaction {
                     self.pending.transactions += 1;
                     self.in_transaction = true;
@@ -318,6 +793,8 @@ where
             }
             Err(err) => {
                 self.pending = Quantities::ZERO;
+                self.batch_started_at = None;
+                self.batch_token = None;
                 Err(err)
             }
         }
@@ -325,8 +802,11 @@ where
 
     /// Checks limits and ends the current `INSERT` if they are reached.
     pub async fn commit(&mut self) -> Result<Quantities> {
+        self.unpark()?;
+
         if !self.limits_reached() {
             self.in_transaction = false;
+            self.park();
             return Ok(Quantities::ZERO);
         }
 
@@ -335,6 +815,7 @@ where
 
     /// Ends the current `INSERT` unconditionally.
     pub async fn force_commit(&mut self) -> Result<Quantities> {
+        self.unpark()?;
         let quantities = self.insert().await?;
         self.ticks.reschedule();
         Ok(quantities)
@@ -344,21 +825,192 @@ where
     ///
     /// If it isn't called, the current `INSERT` is aborted.
     pub async fn end(mut self) -> Result<Quantities> {
+        if let Some(flusher) = self.flusher.take() {
+            let unparked = flusher.close().await;
+            self.report_unparked(unparked)?;
+        }
+
         self.insert().await
     }
 
+    /// Takes the `INSERT` back from the flusher and reports its background
+    /// flush, if any.
+    fn unpark(&mut self) -> Result<()> {
+        let Some(flusher) = &self.flusher else {
+            return Ok(());
+        };
+
+        let unparked = flusher.unpark();
+        self.report_unparked(unparked)
+    }
+
+    fn report_unparked(&mut self, unparked: Unparked<T>) -> Result<()> {
+        if mem::take(&mut self.parked) {
+            debug_assert!(self.insert.is_none());
+
+            match unparked.insert {
+                Some(insert) => self.insert = Some(insert),
+                // Rows of the batch are being inserted by the flusher.
+                None => {
+                    self.in_transaction = false;
+                    let quantities = mem::replace(&mut self.pending, Quantities::ZERO);
+                    let batch_started_at = self.batch_started_at.take();
+                    let token = self.batch_token.take();
+                    self.flushing
+                        .push_back((quantities, batch_started_at, token));
+                }
+            }
+        }
+
+        if unparked.ended.is_empty() {
+            return Ok(());
+        }
+
+        self.ticks.reschedule();
+
+        // Report all results, but return the first error.
+        let mut result = Ok(());
+        for ended in unparked.ended {
+            let (quantities, batch_started_at, token) =
+                self.flushing
+                    .pop_front()
+                    .unwrap_or((Quantities::ZERO, None, None));
+
+            let completed = self.complete(quantities, batch_started_at, Some(ended), token);
+            if let (Ok(()), Err(err)) = (&result, completed) {
+                result = Err(err);
+            }
+        }
+        result
+    }
+
+    /// Leaves the current `INSERT` to the flusher while the inserter is idle.
+    fn park(&mut self) {
+        let Some(flusher) = &self.flusher else {
+            return;
+        };
+
+        if let Some(insert) = self.insert.take() {
+            flusher.park(insert);
+            self.parked = true;
+        }
+    }
+
     fn limits_reached(&self) -> bool {
         self.pending.rows >= self.max_rows
             || self.pending.bytes >= self.max_bytes
+            || self.pending_compressed_bytes() >= self.max_compressed_bytes
             || self.ticks.reached()
+            || self
+                .batch_deadline()
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Waits until rows and bytes written so far fit into the rate limit.
+    async fn throttle(&mut self) {
+        let delay = [&mut self.rows_limit, &mut self.bytes_limit]
+            .into_iter()
+            .flatten()
+            .map(|limit| limit.delay())
+            .max()
+            .unwrap_or_default();
+
+        if !delay.is_zero() {
+            tracing::trace!(?delay, "throttling writes");
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn reset_rate_limits(&mut self) {
+        let bucket =
+            |rate| (rate != u64::MAX).then(|| TokenBucket::new(rate, self.rate_limit_burst));
+        self.rows_limit = bucket(self.rows_per_sec);
+        self.bytes_limit = bucket(self.bytes_per_sec);
+    }
+
+    /// Returns when the current batch must be committed, if it's limited by age.
+    fn batch_deadline(&self) -> Option<Instant> {
+        let max_age = self.max_batch_age?;
+        let started_at = self.batch_started_at?;
+
+        // Leave time to end the `INSERT`, based on the previous one.
+        let margin = self.last_end_duration.min(max_age / 2);
+        Some(started_at + (max_age - margin))
     }
 
     async fn insert(&mut self) -> Result<Quantities> {
         self.in_transaction = false;
         let quantities = mem::replace(&mut self.pending, Quantities::ZERO);
+        let batch_started_at = self.batch_started_at.take();
+        // Kept for the next batch if there are no rows to commit.
+        let token = if quantities.transactions > 0 {
+            self.batch_token.take()
+        } else {
+            None
+        };
 
-        if let Some(insert) = self.insert.take() {
-            insert.end().await?;
+        let ended = match self.insert.take() {
+            Some(insert) => {
+                self.idle_connection = None;
+                #[cfg(feature = "tracing")]
+                let ended = Self::end_traced(&self.table, insert, &quantities).await;
+                #[cfg(not(feature = "tracing"))]
+                let ended = EndedInsert::end(insert).await;
+                Some(ended)
+            }
+            None => None,
+        };
+
+        self.complete(quantities, batch_started_at, ended, token)
+    }
+
+    /// Ends the `INSERT` in the `clickhouse.inserter.commit` span.
+    ///
+    /// Doesn't borrow `self`, which isn't `Sync`, to keep the future `Send`.
+    #[cfg(feature = "tracing")]
+    async fn end_traced(table: &str, insert: Insert<T>, quantities: &Quantities) -> EndedInsert {
+        use tracing::Instrument;
+
+        let span = tracing::info_span!(
+            "clickhouse.inserter.commit",
+            db.collection.name = table,
+            clickhouse.inserter.rows = quantities.rows,
+            clickhouse.inserter.bytes = quantities.bytes,
+            clickhouse.inserter.transactions = quantities.transactions,
+            clickhouse.query.duration = tracing::field::Empty,
+            otel.status_code = tracing::field::Empty,
+            error.type = tracing::field::Empty,
+        );
+
+        let ended = EndedInsert::end(insert).instrument(span.clone()).await;
+        let duration = ended.ended_at.saturating_duration_since(ended.started_at);
+        span.record("clickhouse.query.duration", duration.as_secs_f64());
+        if let Err(err) = &ended.stats {
+            span.in_scope(|| err.record_in_current_span("error committing insert"));
+        }
+        ended
+    }
+
+    fn complete(
+        &mut self,
+        mut quantities: Quantities,
+        batch_started_at: Option<Instant>,
+        ended: Option<EndedInsert>,
+        token: Option<K>,
+    ) -> Result<Quantities> {
+        if let Some(ended) = ended {
+            quantities.stats = Some(ended.stats?);
+            let now = ended.ended_at;
+
+            self.last_end_duration = now.saturating_duration_since(ended.started_at);
+            if let Some(batch_started_at) = batch_started_at {
+                self.last_batch_age = Some(now.saturating_duration_since(batch_started_at));
+            }
+
+            self.idle_connection = ended.connection.map(|connection| IdleConnection {
+                connection,
+                since: now,
+            });
         }
 
         if let Some(cb) = &mut self.on_commit
@@ -367,6 +1019,12 @@ where
             (cb)(&quantities);
         }
 
+        if let Some(cb) = &mut self.on_committed
+            && quantities.transactions > 0
+        {
+            (cb)(&quantities, token);
+        }
+
         Ok(quantities)
     }
 
@@ -376,9 +1034,33 @@ where
         debug_assert!(self.insert.is_none());
         debug_assert_eq!(self.pending, Quantities::ZERO);
 
+        self.recycle_idle_connection();
+
         let mut new_insert: Insert<T> = self.client.insert(&self.table).await?;
         new_insert.set_timeouts(self.send_timeout, self.end_timeout);
+        new_insert.set_stall_timeout(self.stall_timeout);
         self.insert = Some(new_insert);
         Ok(())
     }
+
+    /// Prevents reusing the connection of the previous `INSERT` if it has been
+    /// idle for too long, so the pool opens a new one for the next `INSERT`.
+    fn recycle_idle_connection(&mut self) {
+        let Some(idle) = self.idle_connection.take() else {
+            return;
+        };
+        let Some(keep_alive) = self.keep_alive else {
+            return;
+        };
+
+        let idle_time = Instant::now().saturating_duration_since(idle.since);
+        if idle_time < keep_alive.saturating_sub(self.keep_alive_margin) {
+            return;
+        }
+
+        if let Some(connected) = idle.connection.connection_metadata().as_ref() {
+            tracing::debug!(?idle_time, "recycling idle connection");
+            connected.poison();
+        }
+    }
 }
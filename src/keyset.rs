@@ -0,0 +1,278 @@
+//! Support for [`Query::fetch_keyset`].
+//!
+//! The query is fetched page by page, each page is requested as
+//! `SELECT * FROM (<query>) WHERE <key> > <last key> ORDER BY <key> LIMIT <page size>`,
+//! so only the last key is required to continue after a failure.
+//!
+//! [`KeysetStream::follow`] turns it into a [`Subscription`], which polls
+//! for new rows once the last page is reached instead of finishing.
+//!
+//! [`Query::fetch_keyset`]: crate::query::Query::fetch_keyset
+
+use std::time::Duration;
+#[cfg(feature = "futures03")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cursors::RowCursor,
+    error::{Error, Result},
+    query::Query,
+    row::{RowOwned, RowRead},
+    sql::ser,
+};
+
+pub(crate) const DEFAULT_MAX_RETRIES: u32 = 3;
+pub(crate) const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+type KeyFn<T> = Box<dyn Fn(&T) -> Result<String> + Send + Sync>;
+
+/// A position of [`KeysetStream`] in the result, see [`KeysetStream::checkpoint`].
+///
+/// It can be persisted (e.g. as JSON) to resume the stream later
+/// using [`KeysetStream::with_checkpoint`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeysetCheckpoint {
+    /// The key of the last returned row as a SQL literal,
+    /// or `None` if no rows have been returned yet.
+    pub last_key: Option<String>,
+    /// The number of rows returned so far.
+    pub rows: u64,
+}
+
+/// Rows of a query fetched page by page using keyset pagination,
+/// see [`Query::fetch_keyset`].
+///
+/// Pages failed because of network errors or timeouts are requested again
+/// starting after the last returned row, see [`KeysetStream::with_max_retries`].
+///
+/// [`Query::fetch_keyset`]: crate::query::Query::fetch_keyset
+#[must_use]
+pub struct KeysetStream<T> {
+    query: Query,
+    key: String,
+    key_of: KeyFn<T>,
+    page_size: u64,
+    max_retries: u32,
+    retry_delay: Duration,
+    checkpoint: KeysetCheckpoint,
+    cursor: Option<RowCursor<T>>,
+    page_rows: u64,
+    retries: u32,
+    is_finished: bool,
+    /// `Some` if the stream is turned into a [`Subscription`].
+    poll_interval: Option<Duration>,
+}
+
+impl<T> KeysetStream<T> {
+    pub(crate) fn new<K: Serialize>(
+        query: Query,
+        key: &str,
+        page_size: u64,
+        key_of: impl Fn(&T) -> K + Send + Sync + 'static,
+    ) -> Self {
+        assert!(page_size > 0, "page size must be positive");
+
+        Self {
+            query,
+            key: key.into(),
+            key_of: Box::new(move |row| {
+                let mut literal = String::new();
+                ser::write_arg(&mut literal, &key_of(row))
+                    .map_err(|err| Error::InvalidParams(format!("invalid key: {err}").into()))?;
+                Ok(literal)
+            }),
+            page_size,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            checkpoint: KeysetCheckpoint::default(),
+            cursor: None,
+            page_rows: 0,
+            retries: 0,
+            is_finished: false,
+            poll_interval: None,
+        }
+    }
+
+    /// Resumes the stream after the provided checkpoint,
+    /// e.g. one persisted by a previous run.
+    pub fn with_checkpoint(mut self, checkpoint: KeysetCheckpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// Sets how many times a page is requested again in a row after failing
+    /// because of a network error or a timeout.
+    ///
+    /// Successfully returned rows reset the counter.
+    /// By default, it's `3`.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before requesting a failed page again.
+    ///
+    /// By default, it's 1 second.
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Returns the position after the last returned row.
+    pub fn checkpoint(&self) -> &KeysetCheckpoint {
+        &self.checkpoint
+    }
+
+    /// Turns the stream into an endless [`Subscription`]: once the last page
+    /// is reached, the query is executed again every `interval` to fetch rows
+    /// with keys greater than the last returned one.
+    ///
+    /// Note that rows inserted later with lower keys are never returned,
+    /// so the key should increase monotonically, e.g. an insertion timestamp.
+    pub fn follow(mut self, interval: Duration) -> Subscription<T>
+    where
+        T: RowOwned + RowRead + Send,
+    {
+        self.poll_interval = Some(interval);
+        Subscription::new(self)
+    }
+
+    /// Emits the next row, requesting the next page if needed.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancellation safe. If cancelled, the current page
+    /// is requested again starting after the last returned row.
+    pub async fn next(&mut self) -> Result<Option<T>>
+    where
+        T: RowOwned + RowRead,
+    {
+        loop {
+            if self.is_finished {
+                return Ok(None);
+            }
+
+            // `None` once a page is over or failed.
+            let mut cursor = match self.cursor.take() {
+                Some(cursor) => cursor,
+                None => {
+                    self.page_rows = 0;
+                    self.page_query().fetch::<T>()?
+                }
+            };
+
+            match cursor.next().await {
+                Ok(Some(row)) => {
+                    self.checkpoint.last_key = Some((self.key_of)(&row)?);
+                    self.checkpoint.rows += 1;
+                    self.page_rows += 1;
+                    self.retries = 0;
+                    self.cursor = Some(cursor);
+                    return Ok(Some(row));
+                }
+                Ok(None) if self.page_rows == self.page_size => {}
+                // A partial page is the last one for now.
+                Ok(None) => match self.poll_interval {
+                    Some(interval) => tokio::time::sleep(interval).await,
+                    None => self.is_finished = true,
+                },
+                Err(err) if is_retryable(&err) && self.retries < self.max_retries => {
+                    tracing::debug!(error=?err, "retrying keyset page");
+                    self.retries += 1;
+                    drop(cursor);
+                    tokio::time::sleep(self.retry_delay).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn page_query(&self) -> Query {
+        let mut filter = String::new();
+        if let Some(last_key) = &self.checkpoint.last_key {
+            filter = format!(" WHERE {} > {last_key}", self.key);
+        }
+
+        let suffix = format!("){filter} ORDER BY {} LIMIT {}", self.key, self.page_size);
+        self.query.clone().wrap("SELECT * FROM (", &suffix)
+    }
+}
+
+pub(crate) fn is_retryable(err: &Error) -> bool {
+    matches!(err.without_context(), Error::Network(_) | Error::TimedOut)
+}
+
+/// New rows of a query polled periodically, see [`KeysetStream::follow`]
+/// and [`Client::subscribe`].
+///
+/// It's never finished. Errors are returned as items, and polling continues
+/// after them from the last returned row.
+///
+/// Besides [`Subscription::next`], it implements [`futures_util::Stream`]
+/// (requires the `futures03` feature).
+///
+/// [`Client::subscribe`]: crate::Client::subscribe
+#[must_use]
+pub struct Subscription<T> {
+    rows: BoxStream<'static, (Result<T>, KeysetCheckpoint)>,
+    checkpoint: KeysetCheckpoint,
+}
+
+impl<T: RowOwned + RowRead + Send> Subscription<T> {
+    fn new(stream: KeysetStream<T>) -> Self {
+        let checkpoint = stream.checkpoint().clone();
+        let rows = stream::unfold(stream, |mut keyset| async move {
+            let row = keyset.next().await.transpose()?;
+            let checkpoint = keyset.checkpoint().clone();
+            Some(((row, checkpoint), keyset))
+        });
+
+        Self {
+            rows: rows.boxed(),
+            checkpoint,
+        }
+    }
+
+    /// Waits for the next row.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancellation safe.
+    pub async fn next(&mut self) -> Result<T> {
+        match self.rows.next().await {
+            Some((row, checkpoint)) => {
+                self.checkpoint = checkpoint;
+                row
+            }
+            None => unreachable!("subscription is never finished"),
+        }
+    }
+}
+
+impl<T> Subscription<T> {
+    /// Returns the position after the last returned row, see [`KeysetCheckpoint`].
+    pub fn checkpoint(&self) -> &KeysetCheckpoint {
+        &self.checkpoint
+    }
+}
+
+#[cfg(feature = "futures03")]
+impl<T> futures_util::stream::Stream for Subscription<T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        this.rows.poll_next_unpin(cx).map(|item| {
+            item.map(|(row, checkpoint)| {
+                this.checkpoint = checkpoint;
+                row
+            })
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::sync::Semaphore;
+
+use crate::error::{Error, Result};
+
+/// Limits the number of concurrent requests sent by clones of the same [`Client`].
+///
+/// [`Client`]: crate::Client
+pub(crate) struct Limiter {
+    // `tokio`'s semaphore is fair, so requests are queued in FIFO order.
+    semaphore: Semaphore,
+    max: usize,
+    queued: AtomicUsize,
+}
+
+/// Statistics about requests limited by [`Client::with_max_concurrent_requests`].
+///
+/// [`Client::with_max_concurrent_requests`]: crate::Client::with_max_concurrent_requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConcurrencyStats {
+    /// The maximum number of concurrent requests.
+    pub max: usize,
+    /// The number of requests being executed right now.
+    pub active: usize,
+    /// The number of requests waiting for a free slot.
+    pub queued: usize,
+}
+
+impl Limiter {
+    pub(crate) fn new(max: usize) -> Self {
+        assert!(
+            (1..=Semaphore::MAX_PERMITS).contains(&max),
+            "max concurrent requests must be in range [1, {}]",
+            Semaphore::MAX_PERMITS
+        );
+
+        Self {
+            semaphore: Semaphore::new(max),
+            max,
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ConcurrencyStats {
+        ConcurrencyStats {
+            max: self.max,
+            active: self.max - self.semaphore.available_permits(),
+            queued: self.queued.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A request waiting for a free slot, created before the request is sent.
+pub(crate) struct PendingPermit {
+    pub(crate) limiter: Arc<Limiter>,
+    pub(crate) queue_timeout: Option<Duration>,
+}
+
+impl PendingPermit {
+    pub(crate) async fn acquire(self) -> Result<RequestPermit> {
+        let limiter = self.limiter;
+
+        // Decrements the counter even if the future is cancelled.
+        struct Queued<'a>(&'a AtomicUsize);
+
+        impl Drop for Queued<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+
+        let acquired = {
+            limiter.queued.fetch_add(1, Ordering::Relaxed);
+            let _queued = Queued(&limiter.queued);
+            let acquire = limiter.semaphore.acquire();
+
+            match self.queue_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, acquire)
+                    .await
+                    .map_err(|_| Error::RequestQueueTimedOut)?,
+                None => acquire.await,
+            }
+        };
+
+        // The semaphore is never closed.
+        acquired.expect("semaphore is closed").forget();
+        Ok(RequestPermit(limiter))
+    }
+}
+
+/// Occupies a slot until the response is fully read or dropped.
+pub(crate) struct RequestPermit(Arc<Limiter>);
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        self.0.semaphore.add_permits(1);
+    }
+}
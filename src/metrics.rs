@@ -0,0 +1,115 @@
+//! Support for the `metrics` feature.
+//!
+//! Every finished query is recorded by [`Observation::finish`] via the
+//! [`metrics`] facade, so nothing is collected unless the application
+//! installs a recorder, e.g. a Prometheus exporter.
+//!
+//! [`Observation::finish`]: crate::observer::Observation::finish
+
+use std::sync::Once;
+
+use metrics::{Label, Unit, counter, describe_counter, describe_gauge, describe_histogram, gauge};
+
+use crate::{
+    error::Error,
+    observer::{QueryEvent, QueryKind},
+    pool::PoolStats,
+};
+
+const QUERIES: &str = "clickhouse_client_queries_total";
+const ERRORS: &str = "clickhouse_client_errors_total";
+const DURATION: &str = "clickhouse_client_query_duration_seconds";
+const INSERTED_ROWS: &str = "clickhouse_client_inserted_rows_total";
+const INSERTED_BYTES: &str = "clickhouse_client_inserted_bytes_total";
+const FETCHED_ROWS: &str = "clickhouse_client_fetched_rows_total";
+const POOL_OPEN: &str = "clickhouse_client_pool_open_connections";
+const POOL_IN_FLIGHT: &str = "clickhouse_client_pool_in_flight_requests";
+
+/// Labels of a query: `operation`, and `database` and `table` if known.
+///
+/// The table is known only for state-changing statements, e.g. inserts.
+pub(crate) struct QueryLabels(Vec<Label>);
+
+impl QueryLabels {
+    pub(crate) fn new(kind: QueryKind, database: Option<&str>, table: Option<&str>) -> Self {
+        let operation = match kind {
+            QueryKind::Execute => "execute",
+            QueryKind::Fetch => "fetch",
+            QueryKind::Insert => "insert",
+        };
+
+        let mut labels = vec![Label::new("operation", operation)];
+        if let Some(database) = database {
+            labels.push(Label::new("database", database.to_string()));
+        }
+        if let Some(table) = table {
+            // Escaped names are unquoted, e.g. `db`.`t` becomes db.t.
+            labels.push(Label::new("table", table.replace(['`', '"'], "")));
+        }
+        Self(labels)
+    }
+}
+
+pub(crate) fn record_query(labels: &QueryLabels, outcome: &QueryEvent<'_>) {
+    describe();
+
+    let labels = &labels.0;
+    counter!(QUERIES, labels.iter()).increment(1);
+    metrics::histogram!(DURATION, labels.iter()).record(outcome.elapsed.as_secs_f64());
+
+    if let Some(error) = outcome.error {
+        let mut labels = labels.clone();
+        labels.push(Label::new("error", error.error_type().to_string()));
+        if let Error::ServerException {
+            code: Some(code), ..
+        } = error.without_context()
+        {
+            labels.push(Label::new("code", code.to_string()));
+        }
+        counter!(ERRORS, labels).increment(1);
+    }
+
+    match outcome.kind {
+        QueryKind::Insert => {
+            if let Some(rows) = outcome.rows {
+                counter!(INSERTED_ROWS, labels.iter()).increment(rows);
+            }
+            if let Some(bytes) = outcome.bytes {
+                counter!(INSERTED_BYTES, labels.iter()).increment(bytes);
+            }
+        }
+        QueryKind::Fetch => {
+            if let Some(rows) = outcome.rows {
+                counter!(FETCHED_ROWS, labels.iter()).increment(rows);
+            }
+        }
+        QueryKind::Execute => {}
+    }
+}
+
+pub(crate) fn record_pool(stats: &PoolStats) {
+    gauge!(POOL_OPEN).set(stats.open as f64);
+    gauge!(POOL_IN_FLIGHT).set(stats.in_flight as f64);
+}
+
+fn describe() {
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        describe_counter!(QUERIES, "The number of finished queries.");
+        describe_counter!(ERRORS, "The number of failed queries by the error type.");
+        describe_histogram!(DURATION, Unit::Seconds, "Time of queries until finished.");
+        describe_counter!(INSERTED_ROWS, Unit::Count, "The number of inserted rows.");
+        describe_counter!(
+            INSERTED_BYTES,
+            Unit::Bytes,
+            "The number of inserted bytes before compression."
+        );
+        describe_counter!(FETCHED_ROWS, Unit::Count, "The number of fetched rows.");
+        describe_gauge!(POOL_OPEN, "The number of open connections in the pool.");
+        describe_gauge!(
+            POOL_IN_FLIGHT,
+            "The number of requests being sent or whose responses are being read."
+        );
+    });
+}
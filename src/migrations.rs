@@ -0,0 +1,286 @@
+//! Versioned schema migrations.
+//!
+//! [`Migrator`] applies [`Migration`]s in the order of their versions and
+//! records applied ones in a table (`schema_migrations` by default), so
+//! running it again applies only new migrations.
+//!
+//! Checksums of applied migrations are verified on every run, so changing
+//! an already applied migration is detected instead of being silently ignored.
+//!
+//! # Example
+//!
+//! ```
+//! # async fn example() -> clickhouse::error::Result<()> {
+//! use clickhouse::migrations::{Migration, Migrator};
+//!
+//! let client = clickhouse::Client::default().with_url("http://localhost:8123");
+//!
+//! let applied = Migrator::new(&client, [
+//!     Migration::new(1, "create_events", "
+//!         CREATE TABLE events (id UInt64, payload String)
+//!         ENGINE = MergeTree ORDER BY id;
+//!     "),
+//!     Migration::new(2, "add_created_at", "
+//!         ALTER TABLE events ADD COLUMN created_at DateTime DEFAULT now();
+//!     "),
+//! ])
+//! .run()
+//! .await?;
+//!
+//! println!("applied migrations: {applied:?}");
+//! # Ok(()) }
+//! ```
+
+use crate::{
+    Client,
+    error::{Error, Result},
+    sql::Identifier,
+};
+
+const DEFAULT_TABLE: &str = "schema_migrations";
+
+/// A versioned migration, a script of one or more SQL statements.
+///
+/// See [`Client::execute_batch`] for details on how the script is executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    version: u64,
+    name: String,
+    sql: String,
+}
+
+impl Migration {
+    /// Creates a new migration.
+    ///
+    /// `sql` can be embedded into the binary using `include_str!`.
+    pub fn new(version: u64, name: impl Into<String>, sql: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            sql: sql.into(),
+        }
+    }
+
+    /// Returns the version of the migration.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the name of the migration.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the checksum of the migration's SQL, stored once it's applied.
+    ///
+    /// It's the 64-bit FNV-1a hash of the SQL as is, so any change
+    /// (including whitespaces) leads to a different checksum.
+    pub fn checksum(&self) -> u64 {
+        fnv1a(self.sql.as_bytes())
+    }
+}
+
+/// Applies [`Migration`]s, see [the module documentation](self) for details.
+///
+/// Note: concurrent runs of migrators for the same database are not
+/// coordinated, so run migrations from a single place, e.g. a deploy job.
+#[must_use]
+#[derive(Clone)]
+pub struct Migrator {
+    client: Client,
+    migrations: Vec<Migration>,
+    table: String,
+    cluster: Option<String>,
+    dry_run: bool,
+}
+
+impl Migrator {
+    /// Creates a migrator for the given migrations, which can be provided
+    /// in any order, but must have unique versions.
+    pub fn new(client: &Client, migrations: impl IntoIterator<Item = Migration>) -> Self {
+        let mut migrations = migrations.into_iter().collect::<Vec<_>>();
+        migrations.sort_by_key(|migration| migration.version);
+
+        Self {
+            client: client.clone(),
+            migrations,
+            table: DEFAULT_TABLE.into(),
+            cluster: None,
+            dry_run: false,
+        }
+    }
+
+    /// Sets the table to record applied migrations in.
+    ///
+    /// `schema_migrations` by default.
+    pub fn with_table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Creates the table of applied migrations `ON CLUSTER` using the
+    /// `ReplicatedReplacingMergeTree` engine, so every replica sees the same
+    /// applied migrations. Default replica path settings of the server are used.
+    ///
+    /// Note: statements of migrations are executed as is,
+    /// so they must specify `ON CLUSTER` themselves.
+    pub fn with_cluster(mut self, cluster: impl Into<String>) -> Self {
+        self.cluster = Some(cluster.into());
+        self
+    }
+
+    /// If enabled, [`Migrator::run`] only verifies applied migrations and
+    /// returns pending ones without applying them or creating any tables.
+    ///
+    /// Disabled by default.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Applies pending migrations in the order of their versions and
+    /// returns their versions.
+    ///
+    /// Fails without applying anything if:
+    /// * versions of migrations are not unique,
+    /// * an applied migration is missing or its checksum has changed.
+    ///
+    /// If a migration fails, the next ones are not applied.
+    /// Since ClickHouse doesn't support transactional DDL, statements of the failed
+    /// migration executed before the error are not rolled back and must be
+    /// reverted manually or made idempotent (e.g. `CREATE TABLE IF NOT EXISTS`).
+    pub async fn run(&self) -> Result<Vec<u64>> {
+        if let Some(pair) = self
+            .migrations
+            .windows(2)
+            .find(|w| w[0].version == w[1].version)
+        {
+            return Err(Error::Migration(format!(
+                "duplicate version {} of migrations {:?} and {:?}",
+                pair[0].version, pair[0].name, pair[1].name
+            )));
+        }
+
+        let applied = if self.dry_run {
+            if self.table_exists().await? {
+                self.fetch_applied().await?
+            } else {
+                Vec::new()
+            }
+        } else {
+            self.create_table().await?;
+            self.fetch_applied().await?
+        };
+
+        for (version, name, checksum) in &applied {
+            let Some(migration) = self.migrations.iter().find(|m| m.version == *version) else {
+                return Err(Error::Migration(format!(
+                    "applied migration {version} ({name:?}) is missing"
+                )));
+            };
+
+            if migration.checksum() != *checksum {
+                return Err(Error::Migration(format!(
+                    "migration {version} ({name:?}) has been changed after being applied"
+                )));
+            }
+        }
+
+        let pending = self
+            .migrations
+            .iter()
+            .filter(|m| !applied.iter().any(|(version, _, _)| *version == m.version));
+
+        let mut versions = Vec::new();
+        for migration in pending {
+            if !self.dry_run {
+                self.apply(migration).await?;
+            }
+            versions.push(migration.version);
+        }
+
+        Ok(versions)
+    }
+
+    async fn table_exists(&self) -> Result<bool> {
+        let exists = self
+            .client
+            .query("EXISTS TABLE ?")
+            .bind(Identifier(&self.table))
+            .fetch_one::<u8>()
+            .await?;
+
+        Ok(exists != 0)
+    }
+
+    async fn create_table(&self) -> Result<()> {
+        let (on_cluster, engine) = match &self.cluster {
+            Some(_) => (" ON CLUSTER ?", "ReplicatedReplacingMergeTree"),
+            None => ("", "ReplacingMergeTree"),
+        };
+
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS ?{on_cluster} (
+                version UInt64,
+                name String,
+                checksum UInt64,
+                applied_at DateTime64(3) DEFAULT now64(3)
+            )
+            ENGINE = {engine}
+            ORDER BY version"
+        );
+
+        let mut query = self.client.query(&sql).bind(Identifier(&self.table));
+        if let Some(cluster) = &self.cluster {
+            query = query.bind(Identifier(cluster));
+        }
+
+        query.execute().await
+    }
+
+    async fn fetch_applied(&self) -> Result<Vec<(u64, String, u64)>> {
+        self.client
+            .query("SELECT version, name, checksum FROM ? FINAL ORDER BY version")
+            .bind(Identifier(&self.table))
+            .fetch_all()
+            .await
+    }
+
+    async fn apply(&self, migration: &Migration) -> Result<()> {
+        self.client.execute_batch(&migration.sql).await?;
+
+        self.client
+            .query("INSERT INTO ? (version, name, checksum) VALUES (?, ?, ?)")
+            .bind(Identifier(&self.table))
+            .bind(migration.version)
+            .bind(&migration.name)
+            .bind(migration.checksum())
+            .execute()
+            .await
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_calculates_checksum() {
+        // Reference values of FNV-1a, must never change.
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(
+            Migration::new(1, "foo", "foobar").checksum(),
+            0x85944171f73967e8
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Support for `#[clickhouse(nested)]` fields.
+//!
+//! ClickHouse stores `Nested(a T1, b T2)` as separate `Array` columns
+//! `name.a` and `name.b` of the same length. A `Vec<S>` field is transposed
+//! into these columns while serializing and back while deserializing.
+//!
+//! The `RowBinary` (de)serializers detect nested fields by [`SERDE_NAME`]
+//! passed to `serialize_tuple_struct` and `deserialize_tuple_struct`.
+
+use serde::{
+    Deserialize, Serialize, Serializer,
+    de::{Deserializer, Error as _, SeqAccess},
+};
+
+use crate::Row;
+
+pub const SERDE_NAME: &str = concat!(module_path!(), "::Nested");
+
+/// Implemented by `#[derive(Row)]` for structs marked with `#[clickhouse(nested)]`.
+pub trait Nested: Row + Sized {
+    fn serialize_nested<S: Serializer>(items: &[Self], serializer: S) -> Result<S::Ok, S::Error>;
+
+    fn deserialize_nested<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Self>, D::Error>;
+}
+
+/// Provides column names of a `#[clickhouse(nested)]` field, e.g. `Vec<S>`.
+pub trait NestedColumns {
+    const COLUMN_NAMES: &'static [&'static str];
+}
+
+impl<T: Nested> NestedColumns for Vec<T> {
+    const COLUMN_NAMES: &'static [&'static str] = T::COLUMN_NAMES;
+}
+
+/// A single column of a nested structure, serialized as a sequence.
+pub struct NestedColumn<'a, T, F> {
+    items: &'a [T],
+    get: F,
+}
+
+impl<'a, T, F, V> NestedColumn<'a, T, F>
+where
+    F: Fn(&'a T) -> &'a V,
+    V: Serialize + 'a,
+{
+    pub fn new(items: &'a [T], get: F) -> Self {
+        Self { items, get }
+    }
+}
+
+impl<'a, T, F, V> Serialize for NestedColumn<'a, T, F>
+where
+    F: Fn(&'a T) -> &'a V,
+    V: Serialize + 'a,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.items.iter().map(&self.get))
+    }
+}
+
+/// Reads the next column of a nested structure,
+/// checking that it has the same length as the first one.
+pub fn next_nested_column<'de, A, V>(
+    seq: &mut A,
+    column: &str,
+    expected_len: Option<usize>,
+) -> Result<Vec<V>, A::Error>
+where
+    A: SeqAccess<'de>,
+    V: Deserialize<'de>,
+{
+    let values: Vec<V> = seq
+        .next_element()?
+        .ok_or_else(|| A::Error::custom(format!("missing nested column {column}")))?;
+
+    match expected_len {
+        Some(len) if len != values.len() => Err(A::Error::custom(format!(
+            "nested column {column} has {} elements, expected {len}",
+            values.len()
+        ))),
+        _ => Ok(values),
+    }
+}
+
+/// A part of a struct's column list, used to expand nested fields at compile time.
+pub enum ColumnNamesPart {
+    /// A regular column.
+    Plain(&'static str),
+    /// A nested field: a prefix and names of the nested structure's columns.
+    Nested(&'static str, &'static [&'static str]),
+    /// A flattened field: names of the embedded row's columns as is.
+    Flatten(&'static [&'static str]),
+}
+
+/// Returns the total number of columns.
+pub const fn column_count(parts: &[ColumnNamesPart]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        count += match &parts[i] {
+            ColumnNamesPart::Plain(_) => 1,
+            ColumnNamesPart::Nested(_, names) | ColumnNamesPart::Flatten(names) => names.len(),
+        };
+        i += 1;
+    }
+    count
+}
+
+/// Returns the total length of all column names in bytes.
+pub const fn column_names_len(parts: &[ColumnNamesPart]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        match &parts[i] {
+            ColumnNamesPart::Plain(name) => len += name.len(),
+            ColumnNamesPart::Nested(prefix, names) => {
+                let mut j = 0;
+                while j < names.len() {
+                    // `prefix.name`
+                    len += prefix.len() + 1 + names[j].len();
+                    j += 1;
+                }
+            }
+            ColumnNamesPart::Flatten(names) => {
+                let mut j = 0;
+                while j < names.len() {
+                    len += names[j].len();
+                    j += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+    len
+}
+
+/// Concatenates all column names into a single buffer of length `LEN`,
+/// which must be calculated by [`column_names_len`].
+pub const fn concat_column_names<const LEN: usize>(parts: &[ColumnNamesPart]) -> [u8; LEN] {
+    const fn put(buf: &mut [u8], mut pos: usize, s: &str) -> usize {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            buf[pos] = bytes[i];
+            pos += 1;
+            i += 1;
+        }
+        pos
+    }
+
+    let mut buf = [0; LEN];
+    let mut pos = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        match &parts[i] {
+            ColumnNamesPart::Plain(name) => pos = put(&mut buf, pos, name),
+            ColumnNamesPart::Nested(prefix, names) => {
+                let mut j = 0;
+                while j < names.len() {
+                    pos = put(&mut buf, pos, prefix);
+                    pos = put(&mut buf, pos, ".");
+                    pos = put(&mut buf, pos, names[j]);
+                    j += 1;
+                }
+            }
+            ColumnNamesPart::Flatten(names) => {
+                let mut j = 0;
+                while j < names.len() {
+                    pos = put(&mut buf, pos, names[j]);
+                    j += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+    assert!(pos == LEN, "invalid length of column names");
+    buf
+}
+
+/// Splits the buffer produced by [`concat_column_names`] into `COUNT` names,
+/// which must be calculated by [`column_count`].
+pub const fn split_column_names<const COUNT: usize>(
+    mut buf: &'static [u8],
+    parts: &[ColumnNamesPart],
+) -> [&'static str; COUNT] {
+    const fn take(buf: &mut &'static [u8], len: usize) -> &'static str {
+        let (head, tail) = buf.split_at(len);
+        *buf = tail;
+        match std::str::from_utf8(head) {
+            Ok(name) => name,
+            Err(_) => panic!("column names must be valid UTF-8"),
+        }
+    }
+
+    let mut names = [""; COUNT];
+    let mut idx = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        match &parts[i] {
+            ColumnNamesPart::Plain(name) => {
+                names[idx] = take(&mut buf, name.len());
+                idx += 1;
+            }
+            ColumnNamesPart::Nested(prefix, inner) => {
+                let mut j = 0;
+                while j < inner.len() {
+                    names[idx] = take(&mut buf, prefix.len() + 1 + inner[j].len());
+                    idx += 1;
+                    j += 1;
+                }
+            }
+            ColumnNamesPart::Flatten(inner) => {
+                let mut j = 0;
+                while j < inner.len() {
+                    names[idx] = take(&mut buf, inner[j].len());
+                    idx += 1;
+                    j += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+    assert!(idx == COUNT, "invalid number of column names");
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_expands_column_names() {
+        use ColumnNamesPart::*;
+
+        const PARTS: &[ColumnNamesPart] = &[
+            Plain("no"),
+            Nested("goals", &["id", "serial"]),
+            Plain("x"),
+            Nested("empty", &[]),
+            Flatten(&["created_at", "updated_at"]),
+        ];
+        const COUNT: usize = column_count(PARTS);
+        const LEN: usize = column_names_len(PARTS);
+        const BUF: &[u8] = &concat_column_names::<LEN>(PARTS);
+        const NAMES: &[&str] = &split_column_names::<COUNT>(BUF, PARTS);
+
+        assert_eq!(
+            NAMES,
+            [
+                "no",
+                "goals.id",
+                "goals.serial",
+                "x",
+                "created_at",
+                "updated_at"
+            ]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Tracking of queries from start to finish, see [`Observation`].
+//!
+//! Every query is reported to all configured consumers from one place:
+//! [`Client::with_statement_hook`] before it's sent, and
+//! [`Client::with_query_observer`], [`Client::with_audit_sink`], the `metrics`
+//! and `tracing` features once it's finished, all built on [`QueryEvent`].
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use url::Url;
+
+use crate::{
+    Authentication, Client,
+    audit::{self, AuditRecord, AuditSink, Redaction, StatementHook, StatementInfo, StatementKind},
+    error::Error,
+    settings,
+};
+
+pub(crate) type Observer = Arc<dyn Fn(&QueryEvent<'_>) + Send + Sync>;
+
+/// A kind of a query reported to [`Client::with_query_observer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QueryKind {
+    /// [`Query::execute`](crate::query::Query::execute).
+    Execute,
+    /// [`Query::fetch`](crate::query::Query::fetch) and other `fetch*` methods.
+    Fetch,
+    /// [`Insert`](crate::insert::Insert), [`InsertFormatted`](crate::insert_formatted::InsertFormatted)
+    /// and every commit of [`Inserter`](crate::inserter::Inserter).
+    Insert,
+}
+
+/// A finished query reported to [`Client::with_query_observer`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct QueryEvent<'a> {
+    /// The kind of the query.
+    pub kind: QueryKind,
+    /// The SQL sent to the server, including bound arguments.
+    pub sql: &'a str,
+    /// The `query_id` setting, if provided.
+    pub query_id: Option<&'a str>,
+    /// Time since the query was started until it's finished.
+    ///
+    /// For fetches, it includes the time spent on reading the response,
+    /// so the event is reported only after the cursor is dropped.
+    pub elapsed: Duration,
+    /// The number of rows fetched or written, if known.
+    pub rows: Option<u64>,
+    /// The number of inserted bytes before compression, if known.
+    pub bytes: Option<u64>,
+    /// The error the query has failed with, if any.
+    pub error: Option<&'a Error>,
+}
+
+/// Tracks a single query until it's finished and reports it to the statement
+/// hook once it's sent, and to the observer, the audit sink (if the statement
+/// is state-changing), metrics and the span once it's finished.
+pub(crate) struct Observation {
+    observer: Option<Observer>,
+    audit: Option<Audit>,
+    statement_hook: Option<StatementHook>,
+    redaction: Option<Redaction>,
+    kind: QueryKind,
+    /// Only copied if it's reported, see [`Observation::start`].
+    sql: Option<String>,
+    query_id: Option<String>,
+    started_at: Instant,
+    rows: Option<u64>,
+    bytes: Option<u64>,
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
+    /// The span of the query, which is current once the query is started.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+#[cfg(feature = "metrics")]
+struct Metrics {
+    labels: crate::metrics::QueryLabels,
+    pool: Option<Arc<crate::pool::PoolMetrics>>,
+}
+
+struct Audit {
+    sink: Arc<dyn AuditSink>,
+    kind: StatementKind,
+    table: Option<String>,
+    user: Option<String>,
+    database: Option<String>,
+    started_at: SystemTime,
+    // Set if the `query_id` isn't provided and must be sent to the server.
+    query_id_generated: bool,
+}
+
+impl Observation {
+    /// Returns `None` if neither an observer, a statement hook nor an audit
+    /// sink (for state-changing statements) is configured for the client,
+    /// unless the `metrics` or `tracing` feature is enabled.
+    pub(crate) fn start(client: &Client, kind: QueryKind, sql: &str) -> Option<Self> {
+        let mut query_id = client.get_setting(settings::QUERY_ID).map(String::from);
+
+        // Classified once for both the audit sink and labels of metrics.
+        let classified = if client.audit_sink.is_some() || cfg!(feature = "metrics") {
+            audit::classify(sql)
+        } else {
+            None
+        };
+
+        let audit = client.audit_sink.as_ref().and_then(|sink| {
+            let (kind, table) = classified?;
+            let user = match &client.authentication {
+                Authentication::Credentials { user, .. } => user.clone(),
+                Authentication::Jwt { .. } | Authentication::JwtProvider(_) => None,
+            };
+
+            Some(Audit {
+                sink: sink.clone(),
+                kind,
+                table: table.map(Into::into),
+                user,
+                database: client.database.clone(),
+                started_at: SystemTime::now(),
+                query_id_generated: query_id.is_none(),
+            })
+        });
+
+        #[cfg(not(any(feature = "metrics", feature = "tracing")))]
+        if client.query_observer.is_none() && client.statement_hook.is_none() && audit.is_none() {
+            return None;
+        }
+
+        if audit.as_ref().is_some_and(|audit| audit.query_id_generated) {
+            query_id = Some(audit::generate_query_id());
+        }
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::Span::current();
+        #[cfg(feature = "tracing")]
+        if let Some(query_id) = &query_id {
+            span.record("clickhouse.request.query_id", query_id.as_str());
+        }
+
+        // Metrics don't need the SQL, so it's copied only if it's reported.
+        let keeps_sql =
+            client.query_observer.is_some() || client.statement_hook.is_some() || audit.is_some();
+        #[cfg(feature = "tracing")]
+        let keeps_sql = keeps_sql || !span.is_disabled();
+
+        Some(Self {
+            observer: client.query_observer.clone(),
+            audit,
+            statement_hook: client.statement_hook.clone(),
+            redaction: client.statement_redaction.clone(),
+            kind,
+            sql: keeps_sql.then(|| sql.to_string()),
+            query_id,
+            started_at: Instant::now(),
+            rows: None,
+            bytes: None,
+            #[cfg(feature = "metrics")]
+            metrics: Metrics {
+                labels: crate::metrics::QueryLabels::new(
+                    kind,
+                    client.database.as_deref(),
+                    classified.and_then(|(_, table)| table),
+                ),
+                pool: client.pool_metrics(),
+            },
+            #[cfg(feature = "tracing")]
+            span,
+        })
+    }
+
+    /// Returns `true` if the SQL is reported before it's sent,
+    /// so bound arguments must be redacted if configured.
+    pub(crate) fn reports_sql(client: &Client) -> bool {
+        cfg!(feature = "tracing") || client.statement_hook.is_some()
+    }
+
+    /// Reports the statement about to be sent to `url`.
+    ///
+    /// `redacted_sql` is the SQL with bound arguments redacted
+    /// by [`Client::with_statement_redaction`], if configured.
+    pub(crate) fn sent(&self, url: &Url, redacted_sql: Option<&str>, bound_args: usize) {
+        let sql = redacted_sql.or(self.sql.as_deref()).unwrap_or_default();
+
+        #[cfg(feature = "tracing")]
+        self.span.record("db.query.text", sql);
+
+        if let Some(hook) = &self.statement_hook {
+            let settings = audit::redacted_settings(url, self.redaction.as_ref());
+            hook(&StatementInfo {
+                kind: self.kind,
+                sql,
+                bound_args,
+                settings: &settings,
+            });
+        }
+    }
+
+    /// Returns the `query_id` generated for auditing, which must be sent
+    /// to the server along with the query.
+    pub(crate) fn generated_query_id(&self) -> Option<&str> {
+        self.audit
+            .as_ref()
+            .filter(|audit| audit.query_id_generated)
+            .and(self.query_id.as_deref())
+    }
+
+    pub(crate) fn set_rows(&mut self, rows: u64) {
+        self.rows = Some(rows);
+    }
+
+    /// Sets the number of inserted bytes before compression.
+    pub(crate) fn set_bytes(&mut self, bytes: u64) {
+        self.bytes = Some(bytes);
+    }
+
+    pub(crate) fn finish(self, error: Option<&Error>) {
+        let event = QueryEvent {
+            kind: self.kind,
+            sql: self.sql.as_deref().unwrap_or_default(),
+            query_id: self.query_id.as_deref(),
+            elapsed: self.started_at.elapsed(),
+            rows: self.rows,
+            bytes: self.bytes,
+            error,
+        };
+
+        if let Some(observer) = &self.observer {
+            observer(&event);
+        }
+
+        if let Some(audit) = &self.audit {
+            audit.record(&event);
+        }
+
+        #[cfg(feature = "metrics")]
+        {
+            crate::metrics::record_query(&self.metrics.labels, &event);
+
+            if let Some(pool) = &self.metrics.pool {
+                crate::metrics::record_pool(&pool.stats());
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        {
+            let span = &self.span;
+            span.record("clickhouse.query.duration", event.elapsed.as_secs_f64());
+            // Fetched and inserted rows are recorded by cursors and inserts.
+            if let (QueryKind::Execute, Some(rows)) = (event.kind, event.rows) {
+                span.record("clickhouse.response.written_rows", rows);
+            }
+        }
+    }
+}
+
+impl Audit {
+    fn record(&self, event: &QueryEvent<'_>) {
+        self.sink.record(&AuditRecord {
+            kind: self.kind,
+            sql: event.sql,
+            query_id: event.query_id.unwrap_or_default(),
+            user: self.user.as_deref(),
+            database: self.database.as_deref(),
+            table: self.table.as_deref(),
+            started_at: self.started_at,
+            elapsed: event.elapsed,
+            // Fetched rows are not written ones.
+            rows: event.rows.filter(|_| event.kind != QueryKind::Fetch),
+            error: event.error,
+        });
+    }
+}
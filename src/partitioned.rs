@@ -0,0 +1,192 @@
+//! Support for [`Query::fetch_partitioned`].
+//!
+//! The query is split into partitions by a key, each partition is requested
+//! as `SELECT * FROM (<query>) WHERE <filter>` concurrently with others,
+//! and rows are emitted in the order they're received.
+//!
+//! [`Query::fetch_partitioned`]: crate::query::Query::fetch_partitioned
+
+#[cfg(feature = "futures03")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+
+use crate::{
+    error::{Error, Result},
+    query::Query,
+    row::{RowOwned, RowRead},
+};
+
+/// How [`Query::fetch_partitioned`] splits the query into partitions.
+///
+/// [`Query::fetch_partitioned`]: crate::query::Query::fetch_partitioned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Partitioning {
+    /// Splits the range between the minimum and maximum values of a numeric
+    /// key (integers, dates or timestamps) into equal ranges. The bounds are
+    /// requested by an additional query before fetching.
+    ///
+    /// Ranges are filtered as `toInt128(<key>) >= <start> AND toInt128(<key>) < <end>`,
+    /// so the primary key index is used if `key` is a prefix of it.
+    /// However, partitions are imbalanced if values are distributed unevenly.
+    ///
+    /// Values must fit into `Int128`. If the difference between the bounds
+    /// doesn't, [`Error::InvalidParams`] is returned.
+    Range(String),
+    /// Splits rows by the hash of a key of any type, e.g. `user_id`,
+    /// as `cityHash64(<key>) % <partitions> = <index>`.
+    ///
+    /// Partitions are balanced, but every query reads the whole table.
+    Hash(String),
+}
+
+impl Partitioning {
+    /// Creates [`Partitioning::Range`] by the provided SQL expression.
+    pub fn range(key: impl Into<String>) -> Self {
+        Self::Range(key.into())
+    }
+
+    /// Creates [`Partitioning::Hash`] by the provided SQL expression.
+    pub fn hash(key: impl Into<String>) -> Self {
+        Self::Hash(key.into())
+    }
+}
+
+/// Rows of a query fetched by several concurrent queries,
+/// see [`Query::fetch_partitioned`].
+///
+/// Rows are emitted in the order they're received, so the order of the query
+/// isn't preserved. An error of one partition doesn't stop other ones: it's
+/// returned, and remaining rows can still be fetched. Drop the stream to
+/// cancel all queries.
+///
+/// Besides [`PartitionedStream::next`], it implements [`futures_util::Stream`]
+/// (requires the `futures03` feature).
+///
+/// [`Query::fetch_partitioned`]: crate::query::Query::fetch_partitioned
+#[must_use]
+pub struct PartitionedStream<T> {
+    rows: BoxStream<'static, Result<T>>,
+}
+
+impl<T> PartitionedStream<T>
+where
+    T: RowOwned + RowRead + Send,
+{
+    pub(crate) fn new(query: Query, partitions: u64, partitioning: Partitioning) -> Self {
+        assert!(partitions > 0, "the number of partitions must be positive");
+
+        // Fields are bound in advance, because bounds of ranges are requested
+        // by another row type.
+        let query = query.bind_fields::<T>();
+
+        let filters = stream::once(async move {
+            let filters = match partitioning {
+                Partitioning::Range(key) => range_filters(&query, &key, partitions).await?,
+                Partitioning::Hash(key) => (0..partitions)
+                    .map(|index| format!("cityHash64({key}) % {partitions} = {index}"))
+                    .collect(),
+            };
+            Ok::<_, Error>((query, filters))
+        });
+
+        let rows = filters
+            .map(|result| match result {
+                Ok((query, filters)) => stream::select_all(
+                    filters
+                        .into_iter()
+                        .map(|filter| partition::<T>(&query, &filter)),
+                )
+                .boxed(),
+                Err(err) => stream::once(async { Err(err) }).boxed(),
+            })
+            .flatten();
+
+        Self { rows: rows.boxed() }
+    }
+
+    /// Emits the next row of any partition.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancellation safe.
+    pub async fn next(&mut self) -> Result<Option<T>> {
+        self.rows.next().await.transpose()
+    }
+}
+
+/// Requests bounds of `key` and splits them into `partitions` ranges.
+async fn range_filters(query: &Query, key: &str, partitions: u64) -> Result<Vec<String>> {
+    let (min, max, count) = query
+        .clone()
+        .wrap(
+            &format!("SELECT toInt128(min({key})), toInt128(max({key})), count() FROM ("),
+            ")",
+        )
+        .fetch_one::<(i128, i128, u64)>()
+        .await?;
+
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let step = max
+        .checked_sub(min)
+        .and_then(|span| (span / i128::from(partitions)).checked_add(1))
+        .ok_or_else(|| {
+            Error::InvalidParams(format!("the range of `{key}` is too wide to be split").into())
+        })?;
+
+    let mut filters = Vec::new();
+    let mut start = min;
+    while filters.len() < partitions as usize && start <= max {
+        let Some(end) = start.checked_add(step) else {
+            // The last range ends at `i128::MAX`, which can't be excluded.
+            filters.push(format!("toInt128({key}) >= {start}"));
+            break;
+        };
+
+        filters.push(format!(
+            "toInt128({key}) >= {start} AND toInt128({key}) < {end}"
+        ));
+        start = end;
+    }
+
+    Ok(filters)
+}
+
+fn partition<T>(query: &Query, filter: &str) -> BoxStream<'static, Result<T>>
+where
+    T: RowOwned + RowRead + Send,
+{
+    let cursor = query
+        .clone()
+        .wrap("SELECT * FROM (", &format!(") WHERE {filter}"))
+        .fetch::<T>();
+
+    match cursor {
+        Ok(cursor) => stream::unfold(Some(cursor), |cursor| async move {
+            let mut cursor = cursor?;
+            match cursor.next().await {
+                Ok(Some(row)) => Some((Ok(row), Some(cursor))),
+                Ok(None) => None,
+                // The cursor is fused after an error.
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+        .boxed(),
+        Err(err) => stream::once(async { Err(err) }).boxed(),
+    }
+}
+
+#[cfg(feature = "futures03")]
+impl<T> futures_util::stream::Stream for PartitionedStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rows.poll_next_unpin(cx)
+    }
+}
@@ -0,0 +1,693 @@
+//! Connection pooling.
+//!
+//! HTTP connections of [`Client`] are pooled internally and shared by its
+//! clones, see [`Client::pool_stats`]. Sessions (`session_id`) cannot be
+//! shared that way, because the server executes only one query at a time
+//! in a session. With the `deadpool` feature, clients bound to sessions
+//! can be pooled by `SessionManager`.
+//!
+//! [`Client`]: crate::Client
+//! [`Client::pool_stats`]: crate::Client::pool_stats
+
+use std::{
+    collections::HashMap,
+    error::Error as StdError,
+    fmt,
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "deadpool")]
+pub use self::session::{PooledSession, SessionManager, SessionPool};
+use crate::{
+    error::Error,
+    http_client::CustomConnector,
+    proxy::{Matcher, ProxyOptions},
+};
+use hyper::{
+    Uri,
+    rt::{Read, ReadBufCursor, Write},
+};
+use hyper_util::client::legacy::connect::{
+    CaptureConnection, Connected, Connection,
+    dns::{GaiResolver, Name},
+};
+
+#[cfg(feature = "deadpool")]
+mod session;
+
+// ClickHouse uses 3s by default.
+// See https://github.com/ClickHouse/ClickHouse/blob/368cb74b4d222dc5472a7f2177f6bb154ebae07a/programs/server/config.xml#L201
+const IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+// Typical TTL of DNS records of cloud endpoints.
+const DNS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+// The same as hyper uses by default.
+const HAPPY_EYEBALLS_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Options of the connection pool of the default HTTP client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PoolOptions {
+    pub(crate) max_idle_per_host: usize,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) tcp_keepalive: Option<Duration>,
+    pub(crate) dns_refresh_interval: Option<Duration>,
+    pub(crate) ip_preference: IpPreference,
+    pub(crate) happy_eyeballs_timeout: Option<Duration>,
+    /// Whether HTTP/2 is offered via ALPN for TLS connections.
+    pub(crate) http2: bool,
+    pub(crate) proxy: Option<ProxyOptions>,
+    /// `None` if hosts are resolved by the system resolver.
+    pub(crate) resolver: Option<SharedResolver>,
+    pub(crate) refresh_dns_on_error: bool,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: usize::MAX,
+            idle_timeout: Some(IDLE_TIMEOUT),
+            tcp_keepalive: Some(TCP_KEEPALIVE),
+            dns_refresh_interval: Some(DNS_REFRESH_INTERVAL),
+            ip_preference: IpPreference::default(),
+            happy_eyeballs_timeout: Some(HAPPY_EYEBALLS_TIMEOUT),
+            http2: false,
+            proxy: None,
+            resolver: None,
+            refresh_dns_on_error: false,
+        }
+    }
+}
+
+/// The connection pool of the default HTTP client, shared by clones of the same [`Client`].
+///
+/// [`Client`]: crate::Client
+#[derive(Clone)]
+pub(crate) struct Pool {
+    pub(crate) options: PoolOptions,
+    pub(crate) metrics: Arc<PoolMetrics>,
+    /// `None` if the default TCP (and TLS) connector is used.
+    pub(crate) connector: Option<CustomConnector>,
+    /// `None` if proxies aren't used by the default connector.
+    pub(crate) proxy: Option<Arc<Matcher>>,
+}
+
+#[derive(Default)]
+pub(crate) struct PoolMetrics {
+    open: AtomicUsize,
+    in_flight: AtomicUsize,
+    /// Incremented by [`PoolMetrics::refresh_dns`], older connections aren't reused.
+    generation: AtomicU64,
+    next_connection_id: AtomicU64,
+    /// Connections which have served at least one request, to poison them on refresh.
+    connections: Mutex<HashMap<u64, CaptureConnection>>,
+    refresh_dns_on_error: bool,
+}
+
+/// Statistics about connections of the default HTTP client,
+/// see [`Client::pool_stats`].
+///
+/// The pool doesn't track the state of connections itself, so `idle` is
+/// calculated as `open - in_flight` and can be underestimated while new
+/// connections are being established.
+///
+/// [`Client::pool_stats`]: crate::Client::pool_stats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PoolStats {
+    /// The number of open connections, both idle and in use.
+    pub open: usize,
+    /// The number of idle connections that can be reused.
+    pub idle: usize,
+    /// The number of requests being sent or whose responses are being read.
+    pub in_flight: usize,
+}
+
+impl PoolMetrics {
+    pub(crate) fn stats(&self) -> PoolStats {
+        let open = self.open.load(Ordering::Relaxed);
+        let in_flight = self.in_flight.load(Ordering::Relaxed);
+
+        PoolStats {
+            open,
+            idle: open.saturating_sub(in_flight),
+            in_flight,
+        }
+    }
+}
+
+impl PoolMetrics {
+    pub(crate) fn new(options: &PoolOptions) -> Self {
+        Self {
+            refresh_dns_on_error: options.refresh_dns_on_error,
+            ..Self::default()
+        }
+    }
+
+    /// Called if a request fails to be sent, see [`Client::with_dns_refresh_on_error`].
+    ///
+    /// [`Client::with_dns_refresh_on_error`]: crate::Client::with_dns_refresh_on_error
+    pub(crate) fn on_request_error(&self, error: &Error) {
+        if self.refresh_dns_on_error && matches!(error, Error::Network(_)) {
+            tracing::debug!(%error, "refreshing DNS after a network error");
+            self.refresh_dns();
+        }
+    }
+
+    /// Poisons all open connections, so new ones are established
+    /// and hosts are resolved again.
+    pub(crate) fn refresh_dns(&self) {
+        let connections = self.connections.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::Relaxed);
+
+        for connection in connections.values() {
+            poison(connection);
+        }
+    }
+
+    /// Poisons the connection if it's outdated, otherwise remembers it
+    /// to poison on [`PoolMetrics::refresh_dns`].
+    pub(crate) fn track_connection(&self, info: &ConnectionInfo, connection: CaptureConnection) {
+        // Checked under the lock to not miss a concurrent refresh.
+        let mut connections = self.connections.lock().unwrap();
+
+        let is_outdated = info.generation != self.generation.load(Ordering::Relaxed)
+            || info.expires_at.is_some_and(|at| Instant::now() >= at);
+
+        if is_outdated {
+            tracing::debug!("recycling outdated connection");
+            poison(&connection);
+        } else {
+            connections.entry(info.id).or_insert(connection);
+        }
+    }
+}
+
+pub(crate) fn poison(connection: &CaptureConnection) {
+    if let Some(connected) = connection.connection_metadata().as_ref() {
+        connected.poison();
+    }
+}
+
+/// Counts a request as in-flight until the response is fully read or dropped.
+pub(crate) struct InFlight(Arc<PoolMetrics>);
+
+impl InFlight {
+    pub(crate) fn new(metrics: Arc<PoolMetrics>) -> Self {
+        metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+        Self(metrics)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// === CountingConnector ===
+
+/// Wraps a connector to count open connections and to tag them
+/// with [`ConnectionInfo`].
+#[derive(Clone)]
+pub(crate) struct CountingConnector<C> {
+    inner: C,
+    metrics: Arc<PoolMetrics>,
+    dns_refresh_interval: Option<Duration>,
+}
+
+impl<C> CountingConnector<C> {
+    pub(crate) fn new(inner: C, metrics: Arc<PoolMetrics>, options: &PoolOptions) -> Self {
+        Self {
+            inner,
+            metrics,
+            dns_refresh_interval: options.dns_refresh_interval,
+        }
+    }
+}
+
+/// Provided in extensions of responses to identify the connection.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionInfo {
+    id: u64,
+    generation: u64,
+    /// When the connection must not be reused anymore to resolve the host again.
+    expires_at: Option<Instant>,
+    writes: Arc<WriteWatch>,
+}
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+
+impl<C> tower_service::Service<Uri> for CountingConnector<C>
+where
+    C: tower_service::Service<Uri>,
+    C::Future: Send + 'static,
+    C::Error: Into<BoxError>,
+{
+    type Response = CountedConnection<C::Response>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        // Before resolving, so a concurrent refresh outdates the connection.
+        let info = ConnectionInfo {
+            id: self
+                .metrics
+                .next_connection_id
+                .fetch_add(1, Ordering::Relaxed),
+            generation: self.metrics.generation.load(Ordering::Relaxed),
+            expires_at: self
+                .dns_refresh_interval
+                .map(|interval| Instant::now() + interval),
+            writes: Arc::default(),
+        };
+
+        let connecting = self.inner.call(dst);
+        let metrics = self.metrics.clone();
+
+        Box::pin(async move {
+            let io = connecting.await.map_err(Into::into)?;
+            metrics.open.fetch_add(1, Ordering::Relaxed);
+            Ok(CountedConnection { io, metrics, info })
+        })
+    }
+}
+
+/// A connection counted as open until it's closed.
+pub(crate) struct CountedConnection<T> {
+    io: T,
+    metrics: Arc<PoolMetrics>,
+    info: ConnectionInfo,
+}
+
+impl<T> Drop for CountedConnection<T> {
+    fn drop(&mut self) {
+        self.metrics.open.fetch_sub(1, Ordering::Relaxed);
+        self.metrics
+            .connections
+            .lock()
+            .unwrap()
+            .remove(&self.info.id);
+    }
+}
+
+impl<T: Connection> Connection for CountedConnection<T> {
+    fn connected(&self) -> Connected {
+        self.io.connected().extra(self.info.clone())
+    }
+}
+
+impl<T: Read + Unpin> Read for CountedConnection<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<T: Write + Unpin> Write for CountedConnection<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        this.info.writes.check()?;
+        let res = Pin::new(&mut this.io).poll_write(cx, buf);
+        this.info.writes.on_poll(res.is_pending(), cx);
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        this.info.writes.check()?;
+        let res = Pin::new(&mut this.io).poll_flush(cx);
+        this.info.writes.on_poll(res.is_pending(), cx);
+        res
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        this.info.writes.check()?;
+        let res = Pin::new(&mut this.io).poll_write_vectored(cx, bufs);
+        this.info.writes.on_poll(res.is_pending(), cx);
+        res
+    }
+}
+
+// === WriteWatch ===
+
+/// Tracks how long writes to a connection have been blocked, e.g. because
+/// the server stopped reading, see [`Insert::with_stall_timeout`].
+///
+/// [`Insert::with_stall_timeout`]: crate::insert::Insert::with_stall_timeout
+#[derive(Debug, Default)]
+pub(crate) struct WriteWatch {
+    /// Set along with `blocked_since`, so unblocked writes don't lock.
+    is_blocked: AtomicBool,
+    is_stalled: AtomicBool,
+    state: Mutex<WriteState>,
+}
+
+#[derive(Debug, Default)]
+struct WriteState {
+    // `tokio::time::Instant` to be compared with deadlines of `Sleep`.
+    blocked_since: Option<tokio::time::Instant>,
+    /// Woken by [`WriteWatch::stall`] to fail the blocked write.
+    waker: Option<Waker>,
+}
+
+impl WriteWatch {
+    /// Returns `None` if the connection isn't established by `CountingConnector`,
+    /// e.g. if the client is created by [`Client::with_http_client`].
+    ///
+    /// [`Client::with_http_client`]: crate::Client::with_http_client
+    pub(crate) fn of(connection: &CaptureConnection) -> Option<Arc<Self>> {
+        let mut extensions = hyper::http::Extensions::new();
+        connection
+            .connection_metadata()
+            .as_ref()?
+            .get_extras(&mut extensions);
+        extensions
+            .get::<ConnectionInfo>()
+            .map(|info| info.writes.clone())
+    }
+
+    /// Returns when the pending write has been blocked since, if any.
+    pub(crate) fn blocked_since(&self) -> Option<tokio::time::Instant> {
+        if !self.is_blocked.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.state.lock().unwrap().blocked_since
+    }
+
+    /// Fails the pending and following writes, so the connection is closed.
+    pub(crate) fn stall(&self) {
+        self.is_stalled.store(true, Ordering::Relaxed);
+        if let Some(waker) = self.state.lock().unwrap().waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.is_stalled.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "write stalled"));
+        }
+        Ok(())
+    }
+
+    fn on_poll(&self, is_pending: bool, cx: &Context<'_>) {
+        if is_pending {
+            let mut state = self.state.lock().unwrap();
+            state
+                .blocked_since
+                .get_or_insert_with(tokio::time::Instant::now);
+            state.waker = Some(cx.waker().clone());
+            self.is_blocked.store(true, Ordering::Relaxed);
+        } else if self.is_blocked.swap(false, Ordering::Relaxed) {
+            let mut state = self.state.lock().unwrap();
+            state.blocked_since = None;
+            state.waker = None;
+        }
+    }
+}
+
+// === Resolver ===
+
+/// Which IP versions are used to connect to hosts, see [`Client::with_ip_preference`].
+///
+/// [`Client::with_ip_preference`]: crate::Client::with_ip_preference
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IpPreference {
+    /// Addresses are tried in the order provided by the system resolver.
+    #[default]
+    System,
+    /// IPv4 addresses are tried first, IPv6 ones are a fallback.
+    PreferIpv4,
+    /// IPv6 addresses are tried first, IPv4 ones are a fallback.
+    PreferIpv6,
+    /// Only IPv4 addresses are used.
+    Ipv4Only,
+    /// Only IPv6 addresses are used.
+    Ipv6Only,
+}
+
+impl IpPreference {
+    fn apply(self, addrs: impl Iterator<Item = SocketAddr>) -> Vec<SocketAddr> {
+        let mut addrs: Vec<_> = match self {
+            Self::Ipv4Only => addrs.filter(SocketAddr::is_ipv4).collect(),
+            Self::Ipv6Only => addrs.filter(SocketAddr::is_ipv6).collect(),
+            _ => addrs.collect(),
+        };
+
+        // The sort is stable, so the resolver's order is kept within families.
+        match self {
+            Self::PreferIpv4 => addrs.sort_by_key(SocketAddr::is_ipv6),
+            Self::PreferIpv6 => addrs.sort_by_key(SocketAddr::is_ipv4),
+            _ => {}
+        }
+
+        addrs
+    }
+}
+
+/// Resolves hosts to IP addresses for new connections,
+/// see [`Client::with_resolver`].
+///
+/// It's implemented for closures returning futures, so a separate type isn't
+/// required.
+///
+/// # Example
+/// ```
+/// use std::net::IpAddr;
+///
+/// // Overrides a single host, others are resolved by the system resolver.
+/// let client = clickhouse::Client::default()
+///     .with_url("http://clickhouse:8123")
+///     .with_resolver(|host: String| async move {
+///         if host == "clickhouse" {
+///             return Ok(vec![IpAddr::from([10, 0, 0, 1])]);
+///         }
+///         let addrs = tokio::net::lookup_host((host.as_str(), 0)).await?;
+///         Ok(addrs.map(|addr| addr.ip()).collect())
+///     });
+/// ```
+///
+/// [`Client::with_resolver`]: crate::Client::with_resolver
+pub trait Resolve: Send + Sync + 'static {
+    /// Returns addresses of the host, which are tried in order after
+    /// applying [`IpPreference`]. The port is taken from the URL.
+    ///
+    /// It's called for every new connection, so it should cache results
+    /// if resolving is expensive.
+    fn resolve(
+        &self,
+        host: String,
+    ) -> Pin<Box<dyn Future<Output = io::Result<Vec<IpAddr>>> + Send + '_>>;
+}
+
+impl<F, Fut> Resolve for F
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = io::Result<Vec<IpAddr>>> + Send + 'static,
+{
+    fn resolve(
+        &self,
+        host: String,
+    ) -> Pin<Box<dyn Future<Output = io::Result<Vec<IpAddr>>> + Send + '_>> {
+        Box::pin(self(host))
+    }
+}
+
+/// A shared resolver, compared by identity.
+#[derive(Clone)]
+pub(crate) struct SharedResolver(pub(crate) Arc<dyn Resolve>);
+
+impl fmt::Debug for SharedResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedResolver")
+    }
+}
+
+impl PartialEq for SharedResolver {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedResolver {}
+
+/// Resolves hosts by the system resolver (`getaddrinfo`) or [`Resolve`]
+/// on every new connection and orders addresses according to [`IpPreference`].
+///
+/// Happy Eyeballs of `HttpConnector` treats the family of the first address
+/// as preferred and the other one as a fallback.
+#[derive(Clone)]
+pub(crate) struct Resolver {
+    inner: GaiResolver,
+    custom: Option<SharedResolver>,
+    preference: IpPreference,
+}
+
+impl Resolver {
+    pub(crate) fn new(options: &PoolOptions) -> Self {
+        Self {
+            inner: GaiResolver::new(),
+            custom: options.resolver.clone(),
+            preference: options.ip_preference,
+        }
+    }
+}
+
+impl tower_service::Service<Name> for Resolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let preference = self.preference;
+
+        if let Some(SharedResolver(custom)) = self.custom.clone() {
+            let host = name.as_str().to_string();
+            return Box::pin(async move {
+                let ips = custom.resolve(host).await?;
+                // The port is set by `HttpConnector`.
+                let addrs = ips.into_iter().map(|ip| SocketAddr::new(ip, 0));
+                Ok(preference.apply(addrs).into_iter())
+            });
+        }
+
+        let resolving = self.inner.call(name);
+        Box::pin(async move {
+            let addrs = resolving.await?;
+            Ok(preference.apply(addrs).into_iter())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_orders_addresses_by_preference() {
+        let addrs: Vec<SocketAddr> = ["[::1]:1", "127.0.0.1:2", "[::2]:3", "127.0.0.2:4"]
+            .iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect();
+
+        let ports = |preference: IpPreference| {
+            preference
+                .apply(addrs.iter().copied())
+                .iter()
+                .map(SocketAddr::port)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ports(IpPreference::System), [1, 2, 3, 4]);
+        assert_eq!(ports(IpPreference::PreferIpv4), [2, 4, 1, 3]);
+        assert_eq!(ports(IpPreference::PreferIpv6), [1, 3, 2, 4]);
+        assert_eq!(ports(IpPreference::Ipv4Only), [2, 4]);
+        assert_eq!(ports(IpPreference::Ipv6Only), [1, 3]);
+    }
+
+    #[test]
+    fn it_refreshes_dns_on_network_errors() {
+        let generation = |metrics: &PoolMetrics| metrics.generation.load(Ordering::Relaxed);
+
+        let metrics = PoolMetrics::new(&PoolOptions::default());
+        metrics.on_request_error(&Error::Network("reset".into()));
+        assert_eq!(generation(&metrics), 0);
+
+        let metrics = PoolMetrics::new(&PoolOptions {
+            refresh_dns_on_error: true,
+            ..PoolOptions::default()
+        });
+        metrics.on_request_error(&Error::TimedOut);
+        assert_eq!(generation(&metrics), 0);
+        metrics.on_request_error(&Error::Network("reset".into()));
+        assert_eq!(generation(&metrics), 1);
+    }
+
+    #[tokio::test]
+    async fn it_uses_custom_resolver() {
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpListener,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                assert_ne!(n, 0, "unexpected EOF");
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\n42\n")
+                .await
+                .unwrap();
+        });
+
+        let resolved = Arc::new(Mutex::new(Vec::new()));
+        let client = crate::Client::default()
+            .with_url(format!("http://clickhouse.invalid:{port}"))
+            .with_resolver({
+                let resolved = resolved.clone();
+                move |host: String| {
+                    resolved.lock().unwrap().push(host);
+                    async { Ok(vec![IpAddr::from([127, 0, 0, 1])]) }
+                }
+            });
+
+        let bytes = client
+            .query("SELECT 42")
+            .fetch_bytes("TSV")
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"42\n");
+        assert_eq!(*resolved.lock().unwrap(), ["clickhouse.invalid"]);
+        server.await.unwrap();
+    }
+}
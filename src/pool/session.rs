@@ -0,0 +1,152 @@
+//! Support for pooling sessions with [`deadpool`].
+//!
+//! Every pooled object is a [`Client`] with a unique `session_id`, so
+//! temporary tables and `SET` settings are kept between queries sent by it.
+//! The pool guarantees that a session is used by one task at a time.
+
+use std::time::Duration;
+
+use deadpool::managed::{Manager, Metrics, Object, Pool, RecycleError, RecycleResult};
+
+use crate::{Client, audit::generate_query_id, error::Error, settings};
+
+// The same as ClickHouse uses by default.
+const DEFAULT_SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A pool of clients bound to sessions, see [`SessionManager`].
+pub type SessionPool = Pool<SessionManager>;
+
+/// A client bound to a session taken from [`SessionPool`].
+///
+/// Dereferences to [`Client`], and returns the session to the pool on drop.
+pub type PooledSession = Object<SessionManager>;
+
+/// A [`deadpool`] manager creating clients bound to new sessions.
+///
+/// Sessions are created lazily by the server on the first query, so creating
+/// a pooled session doesn't send any requests.
+///
+/// # State
+///
+/// The state of a session (temporary tables, `SET` settings) isn't reset
+/// when the session is returned to the pool, like in pools of connections
+/// of other databases. Don't rely on it in the next use of the session, or
+/// use [`Object::take`] to drop the session instead of returning it.
+///
+/// The server expires sessions idle for `session_timeout`, losing their state.
+/// Thus, sessions taken from the pool longer ago are replaced with new ones.
+///
+/// # Examples
+/// ```
+/// # async fn example() -> clickhouse::error::Result<()> {
+/// use clickhouse::{
+///     Client,
+///     pool::{SessionManager, SessionPool},
+/// };
+///
+/// let client = Client::default().with_url("http://localhost:8123");
+/// let pool = SessionPool::builder(SessionManager::new(client))
+///     .max_size(16)
+///     .build()
+///     .expect("the runtime isn't required");
+///
+/// let session = pool.get().await.expect("a session is created without requests");
+/// session
+///     .query("CREATE TEMPORARY TABLE ids (id UInt64)")
+///     .execute()
+///     .await?;
+/// session.query("INSERT INTO ids VALUES (1), (2)").execute().await?;
+/// let ids = session.query("SELECT id FROM ids").fetch_all::<u64>().await?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone)]
+pub struct SessionManager {
+    client: Client,
+    session_timeout: Duration,
+}
+
+impl SessionManager {
+    /// Creates a manager of sessions started by clones of the client,
+    /// inheriting its URL, credentials and settings.
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            session_timeout: DEFAULT_SESSION_TIMEOUT,
+        }
+    }
+
+    /// Sets the `session_timeout` setting of sessions, rounded down to seconds.
+    ///
+    /// By default, it's 60 seconds, like on the server.
+    ///
+    /// # Panics
+    /// If `timeout` is less than 1 second.
+    pub fn with_session_timeout(mut self, timeout: Duration) -> Self {
+        assert!(
+            timeout >= Duration::from_secs(1),
+            "session_timeout must be at least 1 second"
+        );
+        self.session_timeout = Duration::from_secs(timeout.as_secs());
+        self
+    }
+}
+
+impl Manager for SessionManager {
+    type Type = Client;
+    type Error = Error;
+
+    async fn create(&self) -> Result<Client, Error> {
+        Ok(self
+            .client
+            .clone()
+            .with_setting(settings::SESSION_ID, generate_query_id())
+            .with_setting(
+                settings::SESSION_TIMEOUT,
+                self.session_timeout.as_secs().to_string(),
+            ))
+    }
+
+    async fn recycle(&self, _client: &mut Client, metrics: &Metrics) -> RecycleResult<Error> {
+        // The time since the session was taken from the pool, which isn't less
+        // than the time since its last query, so expired sessions aren't reused.
+        if metrics.last_used() >= self.session_timeout {
+            return Err(RecycleError::message("session could have expired"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_id(client: &Client) -> String {
+        client.get_setting(settings::SESSION_ID).unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn it_pools_sessions() {
+        let manager = SessionManager::new(Client::default())
+            .with_session_timeout(Duration::from_millis(1500));
+        let pool = SessionPool::builder(manager).max_size(2).build().unwrap();
+
+        let first = pool.get().await.unwrap();
+        let first_id = session_id(&first);
+        assert_eq!(first.get_setting(settings::SESSION_TIMEOUT), Some("1"));
+        drop(first);
+
+        // Returned sessions are reused, taken ones aren't shared.
+        let first = pool.get().await.unwrap();
+        let second = pool.get().await.unwrap();
+        assert_eq!(session_id(&first), first_id);
+        assert_ne!(session_id(&second), first_id);
+        let second_id = session_id(&second);
+        drop((first, second));
+
+        // Sessions could have expired on the server.
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let third = pool.get().await.unwrap();
+        assert_ne!(session_id(&third), first_id);
+        assert_ne!(session_id(&third), second_id);
+    }
+}
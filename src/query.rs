@@ -1,29 +1,53 @@
+use clickhouse_types::{Column, error::TypesError, parse_rbwnat_columns_header};
 use hyper::{Method, Request, header::CONTENT_LENGTH};
+use hyper_util::client::legacy::connect::capture_connection;
 use serde::Serialize;
-use std::fmt::Display;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Debug, Display},
+    hash::{BuildHasher, Hash},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
 use tracing::Instrument;
 use url::Url;
 
 use crate::{
-    Client,
-    error::{Error, Result},
+    Client, ResponseHeaders,
+    error::{Error, ErrorContext, Result},
     formats,
     headers::with_request_headers,
     request_body::RequestBody,
-    response::Response,
-    row::{Row, RowOwned, RowRead},
+    response::{Response, ResponseOptions},
+    row::{Row, RowKind, RowOwned, RowRead, RowWrite},
+    spill::Spiller,
     sql::{Bind, SqlBuilder, ser},
 };
 
-pub use crate::cursors::{BytesCursor, RowCursor};
-use crate::headers::with_authentication;
-use crate::settings;
+use crate::cursors::TextFormat;
+pub use crate::cursors::{
+    BytesCursor, CursorStats, Extremes, JsonCursor, RowCursor, RowError, TextCursor, TotalsCursor,
+};
+use crate::headers::{send_request, with_authentication};
+pub use crate::keyset::{KeysetCheckpoint, KeysetStream, Subscription};
+use crate::observer::Observation;
+pub use crate::observer::{QueryEvent, QueryKind};
+pub use crate::partitioned::{PartitionedStream, Partitioning};
+pub use crate::read_hint::ReadHint;
+pub use crate::resumable::ResumableStream;
+use crate::settings::{self, Settings};
+pub use crate::spill::SpilledRows;
 
 #[must_use]
 #[derive(Clone)]
 pub struct Query {
     client: Client,
     sql: SqlBuilder,
+    read_hint: Option<ReadHint>,
+    read_buffer_size: usize,
+    strict_single_row: bool,
+    allow_extra_columns: bool,
 }
 
 impl Query {
@@ -31,6 +55,10 @@ impl Query {
         Self {
             client: client.clone(),
             sql: SqlBuilder::new(template),
+            read_hint: None,
+            read_buffer_size: 0,
+            strict_single_row: false,
+            allow_extra_columns: false,
         }
     }
 
@@ -44,6 +72,9 @@ impl Query {
     /// The `value`, which must either implement [`Serialize`] or be an
     /// [`Identifier`], will be appropriately escaped.
     ///
+    /// Sequences (e.g. `Vec`) are bound as arrays `[1,2]`, tuples as `(1,'a')`
+    /// and maps (e.g. `HashMap` or `BTreeMap`) as `{'a':1}`. They can be nested.
+    ///
     /// All possible errors will be returned as [`Error::InvalidParams`]
     /// during query execution (`execute()`, `fetch()`, etc.).
     ///
@@ -60,18 +91,37 @@ impl Query {
 
     /// Executes the query.
     pub async fn execute(self) -> Result<()> {
+        self.execute_with_headers().await.map(drop)
+    }
+
+    /// Similar to [`Query::execute`], but also returns the `X-ClickHouse-*`
+    /// headers of the response, e.g. the query ID or the server timezone.
+    pub async fn execute_with_headers(self) -> Result<ResponseHeaders> {
         // Enter the span for the `self.do_execute()` call
         let span = self.make_span(None);
 
         async {
-            let mut response = self
-                .do_execute(None)
+            let (mut response, observation, error_context) = self
+                .do_execute(None, QueryKind::Execute)
                 .inspect_err(|e| e.record_in_current_span("error executing query"))?;
 
-            response
+            let result = response
                 .finish()
                 .await
-                .inspect_err(|e| e.record_in_current_span("response error"))
+                .inspect_err(|e| e.record_in_current_span("response error"));
+
+            if let Some(mut observation) = observation {
+                if let Ok(Some(headers)) = &result
+                    && let Some(rows) = headers.summary().and_then(|s| s.written_rows())
+                {
+                    observation.set_rows(rows);
+                }
+                observation.finish(result.as_ref().err());
+            }
+
+            result
+                .map(|headers| headers.map(|headers| *headers).unwrap_or_default())
+                .map_err(|err| err.with_context(error_context.as_ref()))
         }
         .instrument(span)
         .await
@@ -100,6 +150,10 @@ impl Query {
     /// ```
     pub fn fetch<T: Row>(mut self) -> Result<RowCursor<T>> {
         let validation = self.client.get_validation();
+        let strict_utc = self.client.strict_utc;
+        let decode_limits = self.client.decode_limits;
+        let read_buffer_size = self.read_buffer_size;
+        let allow_extra_columns = self.allow_extra_columns;
         let format = if validation {
             formats::ROW_BINARY_WITH_NAMES_AND_TYPES
         } else {
@@ -110,21 +164,34 @@ impl Query {
 
         self.sql.bind_fields::<T>();
 
-        let response = self
-            .do_execute(Some(format))
+        let (response, observation, error_context) = self
+            .do_execute(Some(format), QueryKind::Fetch)
             .inspect_err(|e| e.record_in_current_span("error executing fetch"))?;
 
-        Ok(RowCursor::new(response, validation, span.exit()))
+        Ok(RowCursor::new(
+            response,
+            validation,
+            strict_utc,
+            span.exit(),
+            observation,
+            error_context,
+            read_buffer_size,
+        )
+        .allow_extra_columns(allow_extra_columns)
+        .strict_decode(decode_limits))
     }
 
     /// Executes the query and returns just a single row.
     ///
+    /// Returns [`Error::RowNotFound`] if no rows are returned. Other rows are
+    /// ignored unless [`Query::with_strict_single_row`] is enabled.
+    ///
     /// Note that `T` must be owned.
     pub async fn fetch_one<T>(self) -> Result<T>
     where
         T: RowOwned + RowRead,
     {
-        match self.fetch::<T>()?.next().await {
+        match self.fetch_optional::<T>().await {
             Ok(Some(row)) => Ok(row),
             Ok(None) => Err(Error::RowNotFound),
             Err(err) => Err(err),
@@ -133,12 +200,23 @@ impl Query {
 
     /// Executes the query and returns at most one row.
     ///
+    /// Other rows are ignored unless [`Query::with_strict_single_row`]
+    /// is enabled.
+    ///
     /// Note that `T` must be owned.
     pub async fn fetch_optional<T>(self) -> Result<Option<T>>
     where
         T: RowOwned + RowRead,
     {
-        self.fetch::<T>()?.next().await
+        let strict = self.strict_single_row;
+        let mut cursor = self.fetch::<T>()?;
+        let row = cursor.next().await?;
+
+        if strict && row.is_some() && cursor.next().await?.is_some() {
+            return Err(Error::TooManyRows);
+        }
+
+        Ok(row)
     }
 
     /// Executes the query and returns all the generated results,
@@ -159,6 +237,321 @@ impl Query {
         Ok(result)
     }
 
+    /// Executes the query and collects all rows like [`Query::fetch_all`],
+    /// but skips rows that cannot be deserialized into `T` instead of failing.
+    /// Errors of skipped rows are returned along with the collected rows.
+    ///
+    /// Useful for pipelines preferring losing a few bad rows over failing
+    /// the whole extract. See [`RowCursor::skip_invalid_rows`] for limitations.
+    ///
+    /// Note that `T` must be owned.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(clickhouse::Row, serde::Deserialize)]
+    /// struct Event {
+    ///     id: u64,
+    ///     payload: String,
+    /// }
+    ///
+    /// let client = clickhouse::Client::default();
+    /// let (events, errors) = client
+    ///     .query("SELECT ?fields FROM events")
+    ///     .fetch_all_lossy::<Event>()
+    ///     .await?;
+    ///
+    /// for error in &errors {
+    ///     eprintln!("skipped: {error}");
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub async fn fetch_all_lossy<T>(self) -> Result<(Vec<T>, Vec<RowError>)>
+    where
+        T: RowOwned + RowRead,
+    {
+        let mut result = Vec::new();
+        let mut cursor = self.fetch::<T>()?.skip_invalid_rows();
+
+        while let Some(row) = cursor.next().await? {
+            result.push(row);
+        }
+
+        Ok((result, cursor.take_row_errors()))
+    }
+
+    /// Executes the query and collects all rows like [`Query::fetch_all`],
+    /// but spills them to a file in `dir` once their size exceeds `budget_bytes`.
+    ///
+    /// The size of rows is estimated by their size in `RowBinary`,
+    /// which is close to the size of their heap allocations (e.g. strings),
+    /// plus `size_of::<T>()` per row. Thus, the budget is not exact.
+    ///
+    /// The returned iterator yields spilled rows first, then rows kept in memory,
+    /// preserving the order returned by the server.
+    ///
+    /// Note that `T` must be owned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(clickhouse::Row, serde::Serialize, serde::Deserialize)]
+    /// struct Event {
+    ///     id: u64,
+    ///     payload: String,
+    /// }
+    ///
+    /// let events = clickhouse::Client::default()
+    ///     .query("SELECT ?fields FROM events")
+    ///     .fetch_all_spilled::<Event>(256 * 1024 * 1024, std::env::temp_dir())
+    ///     .await?;
+    ///
+    /// for event in events {
+    ///     let event = event?;
+    ///     println!("{}: {}", event.id, event.payload);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub async fn fetch_all_spilled<T>(
+        self,
+        budget_bytes: usize,
+        dir: impl AsRef<Path>,
+    ) -> Result<SpilledRows<T>>
+    where
+        T: RowOwned + RowRead + RowWrite,
+    {
+        let mut spiller = Spiller::new(budget_bytes, dir.as_ref());
+        let mut cursor = self.fetch::<T>()?;
+        let mut decoded_bytes = cursor.decoded_bytes();
+
+        while let Some(row) = cursor.next().await? {
+            // The size of a whole chunk is attributed to the row that caused
+            // receiving it, but the total size of rows is exact.
+            let size = cursor.decoded_bytes() - decoded_bytes;
+            decoded_bytes = cursor.decoded_bytes();
+            spiller.push(row, size as usize).await?;
+        }
+
+        spiller.finish().await
+    }
+
+    /// Fetches rows ordered by `key` page by page using keyset pagination,
+    /// so arbitrarily long exports survive dropped connections and server
+    /// restarts.
+    ///
+    /// Every page is requested as
+    /// `SELECT * FROM (<query>) WHERE <key> > <last key> ORDER BY <key> LIMIT <page_size>`,
+    /// where `key` is a SQL expression, e.g. `id` or `(ts, id)`, and the last
+    /// key is calculated by `key_of` from the last returned row. Thus, the query
+    /// doesn't need `ORDER BY`, but `key` must be unique, otherwise rows with
+    /// the same key on the border of pages are lost.
+    ///
+    /// Failed pages are requested again, and [`KeysetStream::checkpoint`]
+    /// can be persisted to resume the export in another process.
+    ///
+    /// Note that `T` must be owned.
+    ///
+    /// # Panics
+    /// Panics if `page_size` is zero.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(clickhouse::Row, serde::Deserialize)]
+    /// struct Event {
+    ///     ts: u32,
+    ///     id: u64,
+    ///     payload: String,
+    /// }
+    ///
+    /// let client = clickhouse::Client::default();
+    /// let mut events = client
+    ///     .query("SELECT ?fields FROM events")
+    ///     .fetch_keyset("(ts, id)", 100_000, |event: &Event| (event.ts, event.id));
+    ///
+    /// while let Some(event) = events.next().await? {
+    ///     println!("{}: {}", event.id, event.payload);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn fetch_keyset<T, K>(
+        self,
+        key: &str,
+        page_size: u64,
+        key_of: impl Fn(&T) -> K + Send + Sync + 'static,
+    ) -> KeysetStream<T>
+    where
+        T: RowOwned + RowRead,
+        K: Serialize,
+    {
+        KeysetStream::new(self, key, page_size, key_of)
+    }
+
+    /// Fetches rows, requesting the rest of the result again if the connection
+    /// is lost, so long exports over flaky networks don't restart from scratch.
+    ///
+    /// Once a network error or a timeout happens, the query is requested as
+    /// `SELECT * FROM (<query>) ORDER BY <key> OFFSET <returned rows>`, see
+    /// [`ResumableStream::with_max_retries`]. Thus, the query must return rows
+    /// in a deterministic order, i.e. have `ORDER BY` by a unique key, which
+    /// must be passed to [`ResumableStream::with_order_by`]: ClickHouse doesn't
+    /// guarantee that the order of a subquery is preserved, so without the key
+    /// the order of the rest is undefined, and rows can be lost or duplicated.
+    /// Consider [`Query::fetch_keyset`], which doesn't skip rows on the server.
+    ///
+    /// Note that `T` must be owned.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(clickhouse::Row, serde::Deserialize)]
+    /// struct Event {
+    ///     id: u64,
+    ///     payload: String,
+    /// }
+    ///
+    /// let client = clickhouse::Client::default();
+    /// let mut events = client
+    ///     .query("SELECT ?fields FROM events ORDER BY id")
+    ///     .fetch_resumable::<Event>()
+    ///     .with_order_by("id");
+    ///
+    /// while let Some(event) = events.next().await? {
+    ///     println!("{}: {}", event.id, event.payload);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn fetch_resumable<T>(self) -> ResumableStream<T>
+    where
+        T: RowOwned + RowRead,
+    {
+        ResumableStream::new(self)
+    }
+
+    /// Fetches rows by `partitions` concurrent queries, each returning a part
+    /// of the result split according to `partitioning`, e.g. by ranges of
+    /// a numeric key or by a hash of any key, see [`Partitioning`].
+    ///
+    /// Every partition is requested as `SELECT * FROM (<query>) WHERE <filter>`,
+    /// so keys must be columns of the result. A single HTTP stream often cannot
+    /// saturate the network for multi-GB exports, but several ones can.
+    ///
+    /// Rows are emitted in the order they're received, so the order of the
+    /// query isn't preserved. Note that `T` must be owned.
+    ///
+    /// # Panics
+    /// Panics if `partitions` is zero.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// use clickhouse::query::Partitioning;
+    ///
+    /// #[derive(clickhouse::Row, serde::Deserialize)]
+    /// struct Event {
+    ///     id: u64,
+    ///     payload: String,
+    /// }
+    ///
+    /// let client = clickhouse::Client::default();
+    /// let mut events = client
+    ///     .query("SELECT ?fields FROM events")
+    ///     .fetch_partitioned::<Event>(4, Partitioning::range("id"));
+    ///
+    /// while let Some(event) = events.next().await? {
+    ///     println!("{}: {}", event.id, event.payload);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn fetch_partitioned<T>(
+        self,
+        partitions: u64,
+        partitioning: Partitioning,
+    ) -> PartitionedStream<T>
+    where
+        T: RowOwned + RowRead + Send,
+    {
+        PartitionedStream::new(self, partitions, partitioning)
+    }
+
+    /// Executes the query and collects a two-column result into a map,
+    /// using the first column as a key and the second one as a value.
+    ///
+    /// Any map implementing [`FetchMap`] can be used, e.g. [`HashMap`] or [`BTreeMap`].
+    /// Rows with the same key are handled according to `duplicates`.
+    ///
+    /// Note that both `K` and `V` must be owned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// use std::collections::HashMap;
+    /// use clickhouse::query::DuplicateKeys;
+    ///
+    /// let names: HashMap<u32, String> = clickhouse::Client::default()
+    ///     .query("SELECT id, name FROM users")
+    ///     .fetch_map(DuplicateKeys::Error)
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn fetch_map<M>(self, duplicates: DuplicateKeys) -> Result<M>
+    where
+        M: FetchMap<Key: Debug>,
+        (M::Key, M::Value): RowOwned + RowRead,
+    {
+        let mut result = M::default();
+        let mut cursor = self.fetch::<(M::Key, M::Value)>()?;
+
+        while let Some((key, value)) = cursor.next().await? {
+            duplicates.insert(&mut result, key, value)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Executes the query and collects all rows into a map,
+    /// using `key` to extract a key from every row.
+    ///
+    /// Rows with the same key are handled according to `duplicates`.
+    ///
+    /// Note that `T` must be owned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// use std::collections::BTreeMap;
+    /// use clickhouse::query::DuplicateKeys;
+    ///
+    /// #[derive(clickhouse::Row, serde::Deserialize)]
+    /// struct User {
+    ///     id: u32,
+    ///     name: String,
+    /// }
+    ///
+    /// let users: BTreeMap<u32, User> = clickhouse::Client::default()
+    ///     .query("SELECT ?fields FROM users")
+    ///     .fetch_map_by(DuplicateKeys::Last, |user: &User| user.id)
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn fetch_map_by<M, F>(self, duplicates: DuplicateKeys, mut key: F) -> Result<M>
+    where
+        M: FetchMap<Key: Debug, Value: RowOwned + RowRead>,
+        F: FnMut(&M::Value) -> M::Key,
+    {
+        let mut result = M::default();
+        let mut cursor = self.fetch::<M::Value>()?;
+
+        while let Some(row) = cursor.next().await? {
+            duplicates.insert(&mut result, key(&row), row)?;
+        }
+
+        Ok(result)
+    }
+
     /// Executes the query, returning a [`BytesCursor`] to obtain results as raw
     /// bytes containing data in the [provided format].
     ///
@@ -168,8 +561,245 @@ impl Query {
 
         let span = self.make_span(Some(format)).entered();
 
-        let response = self.do_execute(Some(format))?;
-        Ok(BytesCursor::new(response, span.exit()))
+        let (response, observation, error_context) =
+            self.do_execute(Some(format), QueryKind::Fetch)?;
+        Ok(BytesCursor::new(
+            response,
+            span.exit(),
+            observation,
+            error_context,
+        ))
+    }
+
+    /// Executes the query, returning a [`BytesCursor`] to obtain results
+    /// in the `Parquet` format, e.g. to export them to a file using
+    /// [`BytesCursor::write_to_file`].
+    ///
+    /// Parquet output can be tuned by settings, e.g.
+    /// `output_format_parquet_compression_method` or
+    /// `output_format_parquet_row_group_size`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// clickhouse::Client::default()
+    ///     .query("SELECT * FROM trips")
+    ///     .with_setting("output_format_parquet_compression_method", "zstd")
+    ///     .fetch_parquet()?
+    ///     .write_to_file("trips.parquet")
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub fn fetch_parquet(self) -> Result<BytesCursor> {
+        self.fetch_bytes(formats::PARQUET)
+    }
+
+    /// Executes an aggregation query, returning a [`TotalsCursor`] to obtain
+    /// its rows along with the `WITH TOTALS` row and extremes (if the
+    /// `extremes` setting is enabled).
+    ///
+    /// The `RowBinary` format used by [`Query::fetch`] doesn't carry these rows,
+    /// so the cursor reads `JSONCompactEachRowWithNamesAndTypes` instead and
+    /// deserializes rows from JSON, see [`TotalsCursor`] for details.
+    /// `?fields` is replaced with fields of `T` as usual.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(clickhouse::Row, serde::Deserialize)]
+    /// struct Stats {
+    ///     kind: String,
+    ///     count: u64,
+    /// }
+    ///
+    /// let mut cursor = clickhouse::Client::default()
+    ///     .query("SELECT kind, count() AS count FROM events GROUP BY kind WITH TOTALS")
+    ///     .with_setting("extremes", "1")
+    ///     .fetch_with_totals::<Stats>()?;
+    ///
+    /// while let Some(Stats { kind, count }) = cursor.next().await? {
+    ///     println!("{kind}: {count}");
+    /// }
+    ///
+    /// if let Some(totals) = cursor.totals() {
+    ///     println!("total: {}", totals.count);
+    /// }
+    /// if let Some(extremes) = cursor.extremes() {
+    ///     println!("min: {}, max: {}", extremes.min.count, extremes.max.count);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn fetch_with_totals<T>(mut self) -> Result<TotalsCursor<T>>
+    where
+        T: Row + serde::de::DeserializeOwned,
+    {
+        self.sql.bind_fields::<T>();
+        let bytes = self
+            .with_setting(settings::OUTPUT_FORMAT_JSON_QUOTE_64BIT_INTEGERS, "0")
+            .fetch_bytes(formats::JSON_COMPACT_EACH_ROW_WITH_NAMES_AND_TYPES)?;
+        Ok(TotalsCursor::new(bytes))
+    }
+
+    /// Executes the query, returning a [`JsonCursor`] to obtain rows,
+    /// which are deserialized from `JSONEachRow`, see [`JsonCursor`] for details.
+    ///
+    /// Unlike [`Query::fetch`], `T` isn't required to be [`Row`] and to match
+    /// the schema strictly: fields are matched by names of columns, and other
+    /// columns are ignored, e.g. to read some fields of `SELECT *`.
+    /// Thus, `?fields` isn't supported.
+    ///
+    /// 64-bit integers are written as numbers instead of strings, unless
+    /// `output_format_json_quote_64bit_integers` is set explicitly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(serde::Deserialize)]
+    /// struct Event {
+    ///     id: u64,
+    ///     tags: Vec<String>,
+    /// }
+    ///
+    /// let mut cursor = clickhouse::Client::default()
+    ///     .query("SELECT * FROM events")
+    ///     .fetch_json::<Event>()?;
+    ///
+    /// while let Some(Event { id, tags }) = cursor.next().await? {
+    ///     println!("{id}: {tags:?}");
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn fetch_json<T>(self) -> Result<JsonCursor<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let query = if self
+            .client
+            .settings
+            .contains_key(settings::OUTPUT_FORMAT_JSON_QUOTE_64BIT_INTEGERS)
+        {
+            self
+        } else {
+            self.with_setting(settings::OUTPUT_FORMAT_JSON_QUOTE_64BIT_INTEGERS, "0")
+        };
+
+        let bytes = query.fetch_bytes(formats::JSON_EACH_ROW)?;
+        Ok(JsonCursor::new(bytes))
+    }
+
+    /// Similar to [`Query::fetch_json`], but collects all rows into a `Vec`.
+    pub async fn fetch_json_all<T>(self) -> Result<Vec<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let mut result = Vec::new();
+        let mut cursor = self.fetch_json::<T>()?;
+
+        while let Some(row) = cursor.next().await? {
+            result.push(row);
+        }
+
+        Ok(result)
+    }
+
+    /// Executes the query, returning a [`TextCursor`] to obtain rows,
+    /// which are deserialized from `CSVWithNames`, see [`TextCursor`] for details.
+    /// `?fields` is replaced with fields of `T` as usual.
+    ///
+    /// Columns are matched to fields by serde names, so rows with fields marked
+    /// with `#[clickhouse(rename, nested, flatten)]` are rejected with
+    /// [`Error::Unsupported`].
+    ///
+    /// It's slower than [`Query::fetch`], but useful as a fallback for types
+    /// without RowBinary support and for debugging.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(clickhouse::Row, serde::Deserialize)]
+    /// struct Event {
+    ///     id: u64,
+    ///     // `DateTime` is deserialized as a string.
+    ///     created_at: String,
+    ///     comment: Option<String>,
+    /// }
+    ///
+    /// let mut cursor = clickhouse::Client::default()
+    ///     .query("SELECT ?fields FROM events")
+    ///     .fetch_csv::<Event>()?;
+    ///
+    /// while let Some(Event { id, created_at, .. }) = cursor.next().await? {
+    ///     println!("{id}: {created_at}");
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn fetch_csv<T>(mut self) -> Result<TextCursor<T>>
+    where
+        T: Row + serde::de::DeserializeOwned,
+    {
+        check_text_row::<T>()?;
+        self.sql.bind_fields::<T>();
+        let bytes = self.fetch_bytes(formats::CSV_WITH_NAMES)?;
+        Ok(TextCursor::new(bytes, TextFormat::Csv))
+    }
+
+    /// Similar to [`Query::fetch_csv`], but uses `TSVWithNames`.
+    pub fn fetch_tsv<T>(mut self) -> Result<TextCursor<T>>
+    where
+        T: Row + serde::de::DeserializeOwned,
+    {
+        check_text_row::<T>()?;
+        self.sql.bind_fields::<T>();
+        let bytes = self.fetch_bytes(formats::TSV_WITH_NAMES)?;
+        Ok(TextCursor::new(bytes, TextFormat::Tsv))
+    }
+
+    /// Returns names and types of columns of the query result without
+    /// fetching any rows, e.g. to build dynamic UIs or to check user-provided
+    /// SQL before executing it.
+    ///
+    /// The query is executed as `SELECT * FROM (<query>) LIMIT 0`,
+    /// so it must be a `SELECT` without `FORMAT` and `SETTINGS` clauses.
+    /// Use [`Query::with_setting`] to provide settings.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// # let client = clickhouse::Client::default();
+    /// let columns = client
+    ///     .query("SELECT id, name FROM users WHERE tenant = ?")
+    ///     .bind("tenant-42")
+    ///     .describe()
+    ///     .await?;
+    ///
+    /// for column in columns {
+    ///     println!("{}: {}", column.name, column.data_type);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub async fn describe(self) -> Result<Vec<Column>> {
+        let bytes = self
+            .wrap("SELECT * FROM (", ") LIMIT 0")
+            .fetch_bytes(formats::ROW_BINARY_WITH_NAMES_AND_TYPES)?
+            .collect()
+            .await?;
+
+        parse_columns(&bytes)
+    }
+
+    /// Replaces `?fields` with the fields of `T` in advance.
+    pub(crate) fn bind_fields<T: Row>(mut self) -> Self {
+        self.sql.bind_fields::<T>();
+        self
+    }
+
+    /// Surrounds the query with `prefix` and `suffix`, e.g. to use it as a subquery.
+    pub(crate) fn wrap(mut self, prefix: &str, suffix: &str) -> Self {
+        self.sql.wrap(prefix, suffix);
+        self
     }
 
     pub(crate) fn make_span(&self, response_format: Option<&str>) -> tracing::Span {
@@ -184,11 +814,9 @@ impl Query {
             otel.kind = cfg!(feature = "opentelemetry").then_some("client"),
             error.type = tracing::field::Empty,
             db.system.name = cfg!(feature = "opentelemetry").then_some("clickhouse"),
-            // Only log full query text at TRACE level
-            // Important that this is taken before client-side parameters are populated
-            // FIXME: we can't use `enabled!` due to https://github.com/tokio-rs/tracing/issues/2448
-            // but we don't want to log the full query at all verbosity levels.
-            // db.query.text = tracing::enabled!(tracing::Level::TRACE).then(|| self.sql.to_string()),
+            // Recorded with the `tracing` feature only,
+            // redacted by `Client::with_statement_redaction` if configured.
+            db.query.text = tracing::field::Empty,
             // TODO: generate summary
             db.query.summary = tracing::field::Empty,
             db.response.status_code = tracing::field::Empty,
@@ -199,11 +827,26 @@ impl Query {
             clickhouse.response.received_bytes = tracing::field::Empty,
             clickhouse.response.decoded_bytes = tracing::field::Empty,
             clickhouse.response.format = response_format,
+            clickhouse.response.written_rows = tracing::field::Empty,
+            clickhouse.query.duration = tracing::field::Empty,
         )
     }
 
-    pub(crate) fn do_execute(self, default_format: Option<&str>) -> Result<Response> {
+    pub(crate) fn do_execute(
+        self,
+        default_format: Option<&str>,
+        kind: QueryKind,
+    ) -> Result<(Response, Option<Observation>, Option<Arc<ErrorContext>>)> {
+        let bound_args = self.sql.bound_args();
+        let redacted = match &self.client.statement_redaction {
+            Some(redact) if Observation::reports_sql(&self.client) => {
+                Some(self.sql.redacted(&**redact))
+            }
+            _ => None,
+        };
+
         let query = self.sql.finish()?;
+        let observation = Observation::start(&self.client, kind, &query);
 
         let mut url =
             Url::parse(&self.client.url).map_err(|err| Error::InvalidParams(Box::new(err)))?;
@@ -234,12 +877,42 @@ impl Query {
             pairs.append_pair(name, value);
         }
 
+        if let Some(query_id) = observation.as_ref().and_then(|o| o.generated_query_id()) {
+            pairs.append_pair(settings::QUERY_ID, query_id);
+        }
+
+        // Settings configured explicitly take precedence over the hint.
+        if let Some(hint) = self.read_hint {
+            for (name, value) in hint.settings() {
+                if !self.client.settings.contains_key(*name) {
+                    pairs.append_pair(name, value);
+                }
+            }
+        }
+
+        // Stop the query on the server side too, unless it's configured explicitly.
+        if let Some(timeout) = self.client.timeouts.request
+            && !self
+                .client
+                .settings
+                .contains_key(settings::MAX_EXECUTION_TIME)
+        {
+            let seconds = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+            pairs.append_pair(settings::MAX_EXECUTION_TIME, &seconds.max(1).to_string());
+        }
+
         pairs.extend_pairs(self.client.roles.iter().map(|role| (settings::ROLE, role)));
 
         drop(pairs);
 
+        if let Some(observation) = &observation {
+            observation.sent(&url, redacted.as_deref(), bound_args);
+        }
+
+        let error_context = ErrorContext::new(&self.client, &url, &query);
+
         let mut builder = Request::builder().method(Method::POST).uri(url.as_str());
-        builder = with_request_headers(builder, &self.client.headers, &self.client.products_info);
+        builder = with_request_headers(builder, &self.client);
         builder = with_authentication(builder, &self.client.authentication);
 
         #[cfg(feature = "zstd")]
@@ -250,14 +923,25 @@ impl Query {
         let content_length = query.len();
         builder = builder.header(CONTENT_LENGTH, content_length.to_string());
 
-        let request = builder.body(RequestBody::full(query)).map_err(|err| {
+        let mut request = builder.body(RequestBody::full(query)).map_err(|err| {
             let err = Error::InvalidParams(Box::new(err));
             err.record_in_current_span("invalid params in query");
-            err
+            err.with_context(error_context.as_ref())
         })?;
 
-        let future = self.client.http.request(request);
-        Ok(Response::new(future, self.client.compression))
+        let pool_metrics = self.client.pool_metrics();
+        let options = ResponseOptions {
+            permit: self.client.pending_permit(),
+            timeouts: self.client.timeouts,
+            connection: (pool_metrics.is_some() || self.client.timeouts.connect.is_some())
+                .then(|| capture_connection(&mut request)),
+            pool_metrics,
+            drain_limit: self.client.cursor_drain_limit,
+        };
+
+        let future = send_request(&self.client, request);
+        let response = Response::new(future, self.client.compression, options);
+        Ok((response, observation, error_context))
     }
 
     /// Configure the [roles] to use when executing this query.
@@ -295,12 +979,243 @@ impl Query {
         self
     }
 
+    /// Sets a timeout for the whole query, including connecting,
+    /// executing the query and reading the response.
+    ///
+    /// Waiting for a free slot (if [`Client::with_max_concurrent_requests`] is used)
+    /// is bounded by [`Client::with_request_queue_timeout`] instead.
+    ///
+    /// If expired, the query fails with [`Error::TimedOut`].
+    /// For fetches, the error is returned by the cursor.
+    ///
+    /// Unless configured explicitly, the [`max_execution_time`] setting is set
+    /// to the same value (rounded up to seconds), so the server stops the query too.
+    ///
+    /// Overrides [`Client::with_request_timeout`] for this query.
+    ///
+    /// [`max_execution_time`]: https://clickhouse.com/docs/operations/settings/settings#max_execution_time
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client.timeouts.request = Some(timeout);
+        self
+    }
+
+    /// Tunes settings of this query for the given access pattern,
+    /// see [`ReadHint`] for the applied settings.
+    ///
+    /// Settings provided explicitly by [`Query::with_setting`] or
+    /// [`Client::with_setting`] take precedence over the hint.
+    /// Overrides the previously provided hint.
+    ///
+    /// # Examples
+    /// ```
+    /// # use clickhouse::{Client, query::ReadHint};
+    /// let query = Client::default()
+    ///     .query("SELECT * FROM some WHERE id = ?")
+    ///     .bind(42)
+    ///     .hint(ReadHint::PointLookup);
+    /// ```
+    pub fn hint(mut self, hint: ReadHint) -> Self {
+        self.read_hint = Some(hint);
+        self
+    }
+
+    /// Sets the minimal size of the buffer used by [`RowCursor`] to join
+    /// rows split between chunks of the response.
+    ///
+    /// Once allocated, the buffer is reused for subsequent chunks instead of
+    /// allocating a new one every time, which reduces allocations when many
+    /// small rows are fetched at a high rate. Setting it to the typical size
+    /// of decompressed chunks (up to 1 MiB) is a good start.
+    ///
+    /// By default, it's zero, i.e. the buffer grows only as needed.
+    pub fn with_read_buffer_size(mut self, bytes: usize) -> Self {
+        self.read_buffer_size = bytes;
+        self
+    }
+
+    /// Makes [`Query::fetch_one`] and [`Query::fetch_optional`] fail with
+    /// [`Error::TooManyRows`] if the query returns more than one row,
+    /// instead of silently taking the first one.
+    ///
+    /// It helps to catch bugs like a missing `GROUP BY` key producing
+    /// several rows in queries that are expected to return a single one.
+    /// Only the first two rows are read to detect it, the rest of the
+    /// response is discarded.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// let client = clickhouse::Client::default();
+    /// let count = client
+    ///     .query("SELECT count() FROM some WHERE id = ?")
+    ///     .bind(42)
+    ///     .with_strict_single_row()
+    ///     .fetch_one::<u64>()
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub fn with_strict_single_row(mut self) -> Self {
+        self.strict_single_row = true;
+        self
+    }
+
+    /// Allows the query to return columns not matching any field of the
+    /// fetched struct, which are skipped without decoding, e.g. to fetch
+    /// a few columns of a wide table with `SELECT *`.
+    ///
+    /// Fields are matched to columns by names, and every field must have
+    /// a column. Skipping a column requires its size to be known from
+    /// the type, so columns of `JSON`, `Dynamic` and `AggregateFunction`
+    /// types can't be skipped.
+    ///
+    /// It requires validation (see [`Client::with_validation`]), because
+    /// names of columns are unknown without it, and it affects only rows
+    /// defined as structs. Selecting only needed columns is still more
+    /// efficient, since skipped columns are read and sent by the server.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(clickhouse::Row, serde::Deserialize)]
+    /// struct User {
+    ///     id: u64,
+    ///     email: String,
+    /// }
+    ///
+    /// let client = clickhouse::Client::default();
+    /// let users = client
+    ///     .query("SELECT * FROM users")
+    ///     .allow_extra_columns()
+    ///     .fetch_all::<User>()
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub fn allow_extra_columns(mut self) -> Self {
+        self.allow_extra_columns = true;
+        self
+    }
+
+    /// Enables the server [query cache] for this query, setting
+    /// `use_query_cache`, `query_cache_ttl` and, if provided, `query_cache_tag`.
+    ///
+    /// The `ttl` is rounded down to seconds, but at least one second.
+    /// Results cached with different tags are stored separately.
+    ///
+    /// Use [`QuerySummary::query_cache_hit`] to check if the result came from
+    /// the cache.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// # use std::time::Duration;
+    /// # let client = clickhouse::Client::default();
+    /// let mut cursor = client
+    ///     .query("SELECT count() FROM events")
+    ///     .use_server_query_cache(Duration::from_secs(60), Some("dashboard"))
+    ///     .with_setting("wait_end_of_query", "1")
+    ///     .fetch::<u64>()?;
+    ///
+    /// let count = cursor.next().await?;
+    /// let cached = cursor.summary().and_then(|s| s.query_cache_hit());
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// [query cache]: https://clickhouse.com/docs/operations/query-cache
+    /// [`QuerySummary::query_cache_hit`]: crate::QuerySummary::query_cache_hit
+    pub fn use_server_query_cache(self, ttl: Duration, tag: Option<&str>) -> Self {
+        let mut settings = Settings::new()
+            .use_query_cache(true)
+            .query_cache_ttl(ttl.as_secs().max(1));
+        if let Some(tag) = tag {
+            settings = settings.query_cache_tag(tag);
+        }
+        self.with_settings(settings)
+    }
+
+    /// Similar to [`Client::with_quota_key`], but for this particular query
+    /// only. Overrides the key set on the [`Client`].
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// let client = clickhouse::Client::default().with_quota_key("service");
+    /// let count = client
+    ///     .query("SELECT count() FROM events WHERE tenant = ?")
+    ///     .bind("tenant-42")
+    ///     .with_quota_key("tenant-42")
+    ///     .fetch_one::<u64>()
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub fn with_quota_key(self, key: impl Into<String>) -> Self {
+        Self {
+            client: self.client.with_quota_key(key),
+            ..self
+        }
+    }
+
+    /// Similar to [`Client::with_header`], but for this particular query only.
+    /// Overrides the header with the same name set on the [`Client`].
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// # let client = clickhouse::Client::default();
+    /// # let token = "secret";
+    /// client
+    ///     .query("SELECT 1")
+    ///     .with_header("Authorization", format!("Bearer {token}"))
+    ///     .execute()
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.client.set_header(name.into(), value.into());
+        self
+    }
+
     /// Similar to [`Client::with_setting`], but for this particular query only.
     pub fn with_setting(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
         self.client.set_setting(name, value);
         self
     }
 
+    /// Similar to [`Client::with_settings`], but for this particular query only.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        for (name, value) in settings.into_pairs() {
+            self.client.set_setting(name, value);
+        }
+        self
+    }
+
+    /// Adds a tag to the `log_comment` setting, which is written to
+    /// `system.query_log`, so queries can be attributed to features or
+    /// endpoints of the application.
+    ///
+    /// Tags are accumulated into a JSON object, e.g. `{"endpoint":"/users","feature":"search"}`.
+    /// Setting the same key again replaces the value. A `log_comment` that
+    /// isn't a JSON object, e.g. set by [`Client::with_setting`], is kept
+    /// as the `comment` tag.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// # let client = clickhouse::Client::default();
+    /// client
+    ///     .query("SELECT count() FROM users")
+    ///     .with_tag("feature", "search")
+    ///     .with_tag("endpoint", "/users")
+    ///     .fetch_one::<u64>()
+    ///     .await?;
+    ///
+    /// // Later: SELECT JSONExtractString(log_comment, 'feature') FROM system.query_log
+    /// # Ok(()) }
+    /// ```
+    pub fn with_tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.client.add_tag(key.into(), value.into());
+        self
+    }
+
     // Used in `clickhouse-ext-arrow` to track Arrow adoption.
     /// Similar to [`Client::with_product_info()`], but for this query only.
     pub fn with_product_info(
@@ -326,4 +1241,176 @@ impl Query {
             self.with_setting(format!("param_{name}"), param)
         }
     }
+
+    /// Specifies a server side parameter for each field of `row`, named as
+    /// the corresponding column, see [`Query::param`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn example() -> clickhouse::error::Result<()> {
+    /// #[derive(clickhouse::Row, serde::Serialize)]
+    /// struct Event {
+    ///     id: u64,
+    ///     name: String,
+    ///     score: Option<f64>,
+    /// }
+    ///
+    /// let event = Event { id: 42, name: "foo".into(), score: None };
+    ///
+    /// clickhouse::Client::default()
+    ///     .query(
+    ///         "ALTER TABLE events
+    ///          UPDATE name = {name: String}, score = {score: Nullable(Float64)}
+    ///          WHERE id = {id: UInt64}",
+    ///     )
+    ///     .bind_fields_values(&event)
+    ///     .execute()
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub fn bind_fields_values<T: Row + Serialize>(mut self, row: &T) -> Self {
+        if !matches!(T::KIND, RowKind::Struct) {
+            self.sql = SqlBuilder::Failed("invalid param: only structs are supported".into());
+            return self;
+        }
+
+        match ser::struct_params(row) {
+            Ok(params) => {
+                for (name, param) in params {
+                    self.client.set_setting(format!("param_{name}"), param);
+                }
+            }
+            Err(err) => self.sql = SqlBuilder::Failed(format!("invalid param: {err}")),
+        }
+        self
+    }
+}
+
+/// Rejects rows that can't be matched to columns of text formats by serde names.
+fn check_text_row<T: Row>() -> Result<()> {
+    if T::REMAPPED_COLUMNS {
+        return Err(Error::Unsupported(format!(
+            "text formats match columns by serde names of fields, but {} has fields \
+             marked with `#[clickhouse(rename, nested, flatten)]`",
+            T::NAME
+        )));
+    }
+
+    Ok(())
+}
+
+/// Parses the `RowBinaryWithNamesAndTypes` header of an empty result.
+fn parse_columns(mut bytes: &[u8]) -> Result<Vec<Column>> {
+    match parse_rbwnat_columns_header(&mut bytes) {
+        Ok(columns) if !columns.is_empty() => Ok(columns),
+        Ok(_) => Err(Error::BadResponse(
+            "Expected at least one column in the header".to_string(),
+        )),
+        Err(TypesError::NotEnoughData(_)) => Err(Error::BadResponse(
+            "Could not read columns header".to_string(),
+        )),
+        Err(err) => Err(Error::InvalidColumnsHeader(err.into())),
+    }
+}
+
+/// Defines how [`Query::fetch_map`] and [`Query::fetch_map_by`]
+/// handle rows with the same key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DuplicateKeys {
+    /// Fail with [`Error::DuplicateKey`].
+    #[default]
+    Error,
+    /// Keep the first row with the key, ignoring the rest.
+    First,
+    /// Keep the last row with the key, replacing previous ones.
+    Last,
+}
+
+impl DuplicateKeys {
+    fn insert<M>(self, map: &mut M, key: M::Key, value: M::Value) -> Result<()>
+    where
+        M: FetchMap<Key: Debug>,
+    {
+        match self {
+            Self::Error if map.contains_key(&key) => {
+                return Err(Error::DuplicateKey(format!("{key:?}")));
+            }
+            Self::First if map.contains_key(&key) => {}
+            _ => map.insert(key, value),
+        }
+        Ok(())
+    }
+}
+
+/// A map that can be collected by [`Query::fetch_map`] and [`Query::fetch_map_by`].
+///
+/// Implemented for [`HashMap`] and [`BTreeMap`].
+pub trait FetchMap: Default {
+    /// The key type.
+    type Key;
+    /// The value type.
+    type Value;
+
+    #[doc(hidden)]
+    fn contains_key(&self, key: &Self::Key) -> bool;
+    #[doc(hidden)]
+    fn insert(&mut self, key: Self::Key, value: Self::Value);
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> FetchMap for HashMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    #[inline]
+    fn contains_key(&self, key: &K) -> bool {
+        HashMap::contains_key(self, key)
+    }
+
+    #[inline]
+    fn insert(&mut self, key: K, value: V) {
+        HashMap::insert(self, key, value);
+    }
+}
+
+impl<K: Ord, V> FetchMap for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    #[inline]
+    fn contains_key(&self, key: &K) -> bool {
+        BTreeMap::contains_key(self, key)
+    }
+
+    #[inline]
+    fn insert(&mut self, key: K, value: V) {
+        BTreeMap::insert(self, key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clickhouse_types::{DataTypeNode, put_rbwnat_columns_header};
+
+    #[test]
+    fn it_parses_columns() {
+        let columns = vec![
+            Column::new("id".into(), DataTypeNode::UInt64),
+            Column::new(
+                "name".into(),
+                DataTypeNode::new("LowCardinality(Nullable(String))").unwrap(),
+            ),
+        ];
+
+        let mut bytes = Vec::new();
+        put_rbwnat_columns_header(&columns, &mut bytes).unwrap();
+        assert_eq!(parse_columns(&bytes).unwrap(), columns);
+
+        let err = parse_columns(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(err, Error::BadResponse(_)), "{err}");
+        let err = parse_columns(&[]).unwrap_err();
+        assert!(matches!(err, Error::BadResponse(_)), "{err}");
+    }
 }
@@ -62,6 +62,22 @@ impl QuerySummary {
         self.get_u64("memory_usage")
     }
 
+    /// Returns whether the result is likely served from the query cache,
+    /// see [`Query::use_server_query_cache`].
+    ///
+    /// ClickHouse doesn't report cache hits explicitly, but a result served
+    /// from the cache doesn't read any rows from tables. Thus, `Some(true)` is
+    /// returned if rows were returned, but none were read. Results without rows
+    /// are never considered cached.
+    ///
+    /// It's reliable only with `wait_end_of_query=1`, otherwise the summary
+    /// can be sent before anything is read.
+    ///
+    /// [`Query::use_server_query_cache`]: crate::query::Query::use_server_query_cache
+    pub fn query_cache_hit(&self) -> Option<bool> {
+        Some(self.read_rows()? == 0 && self.result_rows()? > 0)
+    }
+
     fn get_u64(&self, key: &str) -> Option<u64> {
         self.fields.get(key)?.parse().ok()
     }
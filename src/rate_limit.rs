@@ -0,0 +1,96 @@
+use tokio::time::Duration;
+
+use crate::ticks::Instant;
+
+// === TokenBucket ===
+
+/// Limits the rate of rows or bytes written by [`crate::inserter::Inserter`].
+///
+/// Tokens are consumed after a row is written, because its size is unknown
+/// before serialization. Thus, the balance can become negative, e.g. if a row
+/// is bigger than the bucket, and the next write waits until it's repaid.
+pub(crate) struct TokenBucket {
+    /// Tokens per second.
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket allowing to consume up to `burst` worth of
+    /// tokens at once.
+    pub(crate) fn new(rate: u64, burst: Duration) -> Self {
+        assert!(rate > 0, "rate limit must be positive");
+
+        let rate = rate as f64;
+        let capacity = (rate * burst.as_secs_f64()).max(1.);
+
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            updated_at: Instant::now(),
+        }
+    }
+
+    pub(crate) fn consume(&mut self, amount: u64) {
+        self.refill();
+        self.tokens -= amount as f64;
+    }
+
+    /// Returns how long to wait until the debt is repaid.
+    pub(crate) fn delay(&mut self) -> Duration {
+        self.refill();
+
+        if self.tokens >= 0. {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+        self.updated_at = now;
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))] // only with `tokio::time::Instant`
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn smoke() {
+        let mut bucket = TokenBucket::new(100, Duration::from_secs(1));
+
+        // The bucket is full initially.
+        bucket.consume(100);
+        assert_eq!(bucket.delay(), Duration::ZERO);
+
+        bucket.consume(50);
+        assert_eq!(bucket.delay(), Duration::from_millis(500));
+        tokio::time::advance(Duration::from_millis(250)).await;
+        assert_eq!(bucket.delay(), Duration::from_millis(250));
+        tokio::time::advance(Duration::from_millis(250)).await;
+        assert_eq!(bucket.delay(), Duration::ZERO);
+
+        // Idle time isn't accumulated beyond the burst.
+        tokio::time::advance(Duration::from_secs(10)).await;
+        bucket.consume(150);
+        assert_eq!(bucket.delay(), Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn zero_burst() {
+        let mut bucket = TokenBucket::new(1000, Duration::ZERO);
+
+        // At least one token is allowed at once.
+        bucket.consume(1);
+        assert_eq!(bucket.delay(), Duration::ZERO);
+        bucket.consume(1);
+        assert_eq!(bucket.delay(), Duration::from_millis(1));
+    }
+}
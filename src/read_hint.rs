@@ -0,0 +1,48 @@
+/// An access pattern of a query, see [`Query::hint`].
+///
+/// Every hint translates into a set of settings tuned for the pattern.
+/// The exact values can be changed in future minor releases.
+///
+/// [`Query::hint`]: crate::query::Query::hint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReadHint {
+    /// Rows are processed one by one while the response is being read,
+    /// so smaller blocks are preferred to get the first rows sooner:
+    /// * `max_block_size = 8192`
+    /// * `preferred_block_size_bytes = 1048576`
+    Streaming,
+    /// A large part of a table is read at once, e.g. to export it,
+    /// so larger blocks are preferred and other queries take precedence:
+    /// * `max_block_size = 65536`
+    /// * `preferred_block_size_bytes = 8388608`
+    /// * `priority = 10`
+    FullScanExport,
+    /// A few rows are read by a key, so the query is executed in a single
+    /// thread and takes precedence over other queries:
+    /// * `max_threads = 1`
+    /// * `max_block_size = 1024`
+    /// * `priority = 1`
+    PointLookup,
+}
+
+impl ReadHint {
+    pub(crate) fn settings(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Streaming => &[
+                ("max_block_size", "8192"),
+                ("preferred_block_size_bytes", "1048576"),
+            ],
+            Self::FullScanExport => &[
+                ("max_block_size", "65536"),
+                ("preferred_block_size_bytes", "8388608"),
+                ("priority", "10"),
+            ],
+            Self::PointLookup => &[
+                ("max_threads", "1"),
+                ("max_block_size", "1024"),
+                ("priority", "1"),
+            ],
+        }
+    }
+}
@@ -6,12 +6,16 @@ use hyper::{
     StatusCode,
     body::{Body as _, Incoming},
 };
-use hyper_util::client::legacy::ResponseFuture as HyperResponseFuture;
+use hyper_util::client::legacy::connect::CaptureConnection;
 use std::{
+    collections::VecDeque,
     future::{self, Future},
     pin::{Pin, pin},
-    task::{Context, Poll},
+    sync::Arc,
+    task::{Context, Poll, ready},
+    time::Duration,
 };
+use tokio::time::{Instant, Sleep};
 
 #[cfg(feature = "lz4")]
 use crate::compression::lz4::Lz4Decoder;
@@ -20,10 +24,38 @@ use crate::compression::zstd::ZstdHttpDecoder;
 use crate::{
     compression::Compression,
     error::{Error, Result},
-    query_summary::QuerySummary,
+    limiter::{PendingPermit, RequestPermit},
+    pool::{ConnectionInfo, InFlight, PoolMetrics},
+    response_headers::ResponseHeaders,
 };
 use tracing::Instrument;
 
+// === ResponseOptions ===
+
+/// Per-request options applied while waiting for and reading the response.
+#[derive(Default)]
+pub(crate) struct ResponseOptions {
+    pub(crate) permit: Option<PendingPermit>,
+    /// Counts the request as in-flight once it's sent.
+    pub(crate) pool_metrics: Option<Arc<PoolMetrics>>,
+    pub(crate) timeouts: Timeouts,
+    /// Must be provided if the connect timeout is set or `pool_metrics` are,
+    /// in the latter case to recycle outdated connections.
+    pub(crate) connection: Option<CaptureConnection>,
+    /// See [`Chunks::drain_limit`].
+    pub(crate) drain_limit: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Timeouts {
+    /// Until a connection is established or taken from the pool.
+    pub(crate) connect: Option<Duration>,
+    /// Until the whole response is read.
+    pub(crate) request: Option<Duration>,
+    /// Until the response headers and between response chunks.
+    pub(crate) read: Option<Duration>,
+}
+
 // === Response ===
 
 pub(crate) enum Response {
@@ -35,10 +67,14 @@ pub(crate) enum Response {
 }
 
 pub(crate) type ResponseFuture =
-    Pin<Box<dyn Future<Output = Result<(Chunks, Option<Box<QuerySummary>>)>> + Send>>;
+    Pin<Box<dyn Future<Output = Result<(Chunks, Box<ResponseHeaders>)>> + Send>>;
 
 impl Response {
-    pub(crate) fn new(response: HyperResponseFuture, compression: Compression) -> Self {
+    pub(crate) fn new(
+        response: impl Future<Output = Result<hyper::Response<Incoming>>> + Send + 'static,
+        compression: Compression,
+        options: ResponseOptions,
+    ) -> Self {
         let span = tracing::info_span!(
             "response",
             otel.status_code = tracing::field::Empty,
@@ -48,7 +84,7 @@ impl Response {
         );
 
         Self::Waiting(Box::pin(
-            collect_response(response, compression).instrument(span),
+            collect_response(response, compression, options).instrument(span),
         ))
     }
 
@@ -59,11 +95,14 @@ impl Response {
         }
     }
 
-    pub(crate) async fn finish(&mut self) -> Result<()> {
+    /// Returns the headers if the response hasn't been started streaming yet.
+    pub(crate) async fn finish(&mut self) -> Result<Option<Box<ResponseHeaders>>> {
+        let mut headers = None;
         let chunks = loop {
             match self {
                 Self::Waiting(future) => {
-                    let (chunks, _summary) = future.await?;
+                    let (chunks, h) = future.await?;
+                    headers = Some(h);
                     *self = Self::Loading(chunks);
                 }
                 Self::Loading(chunks) => break chunks,
@@ -71,15 +110,48 @@ impl Response {
         };
 
         while chunks.try_next().await?.is_some() {}
-        Ok(())
+        Ok(headers)
     }
 }
 
 async fn collect_response(
-    response: HyperResponseFuture,
+    response: impl Future<Output = Result<hyper::Response<Incoming>>>,
     compression: Compression,
-) -> Result<(Chunks, Option<Box<QuerySummary>>)> {
-    let response = response.await?;
+    options: ResponseOptions,
+) -> Result<(Chunks, Box<ResponseHeaders>)> {
+    // The underlying request isn't sent until the response future is polled.
+    let permit = match options.permit {
+        Some(permit) => Some(permit.acquire().await?),
+        None => None,
+    };
+    let in_flight = options.pool_metrics.clone().map(InFlight::new);
+
+    let mut deadlines = Deadlines::new(options.timeouts, options.connection.clone());
+    let response = match &mut deadlines {
+        Some(deadlines) => {
+            let mut response = pin!(response);
+            future::poll_fn(|cx| match response.as_mut().poll(cx) {
+                Poll::Ready(response) => Poll::Ready(Ok(response)),
+                Poll::Pending => deadlines.poll_expired(cx).map(|_| Err(Error::TimedOut)),
+            })
+            .await?
+        }
+        None => response.await,
+    };
+
+    if let Err(err) = &response
+        && let Some(metrics) = &options.pool_metrics
+    {
+        metrics.on_request_error(err);
+    }
+    let response = response?;
+
+    if let Some(metrics) = &options.pool_metrics
+        && let Some(connection) = options.connection
+        && let Some(info) = response.extensions().get::<ConnectionInfo>()
+    {
+        metrics.track_connection(info, connection);
+    }
 
     let status = response.status();
     let exception_code = response.headers().get("X-ClickHouse-Exception-Code");
@@ -96,14 +168,25 @@ async fn collect_response(
             .get("X-ClickHouse-Exception-Tag")
             .map(|value| value.as_bytes().into());
 
-        let summary = response
-            .headers()
-            .get("X-ClickHouse-Summary")
-            .and_then(|v| v.to_str().ok())
-            .and_then(QuerySummary::from_header)
-            .map(Box::new); // More likely to be successful, start streaming.
+        let headers = Box::new(ResponseHeaders::from_header_map(response.headers()));
+
+        // More likely to be successful, start streaming.
         // It still can fail, but we'll handle it in `DetectDbException`.
-        Ok((Chunks::new(response.into_body(), compression, tag), summary))
+        if let Some(deadlines) = &mut deadlines {
+            deadlines.connected();
+            deadlines.reset_read();
+        }
+
+        let mut chunks = Chunks::new(
+            response.into_body(),
+            compression,
+            tag,
+            permit,
+            in_flight,
+            deadlines,
+        );
+        chunks.drain_limit = options.drain_limit;
+        Ok((chunks, headers))
     } else {
         // An instantly failed request.
         let error = collect_bad_response(
@@ -197,23 +280,45 @@ pub(crate) struct Chunk {
 // * Uses `Box<_>` in order to reduce the size of cursors.
 pub(crate) struct Chunks {
     inner: Option<Box<DetectDbException<Decompress<IncomingStream>>>>,
+    // Released once the stream is terminated.
+    permit: Option<RequestPermit>,
+    in_flight: Option<InFlight>,
+    deadlines: Option<Box<Deadlines>>,
+    /// If dropped before terminated, up to this number of bytes is read
+    /// in the background, so the connection can be reused if the response
+    /// ends in time. Otherwise, or if zero, the connection is closed.
+    drain_limit: u64,
 }
 
 impl Chunks {
-    fn new(stream: Incoming, compression: Compression, exception_tag: Option<Box<[u8]>>) -> Self {
+    fn new(
+        stream: Incoming,
+        compression: Compression,
+        exception_tag: Option<Box<[u8]>>,
+        permit: Option<RequestPermit>,
+        in_flight: Option<InFlight>,
+        deadlines: Option<Box<Deadlines>>,
+    ) -> Self {
         let stream = IncomingStream(stream);
         let stream = Decompress::new(stream, compression);
-        let stream = DetectDbException {
-            stream,
-            exception_tag,
-        };
+        let stream = DetectDbException::new(stream, exception_tag);
         Self {
             inner: Some(Box::new(stream)),
+            permit,
+            in_flight,
+            deadlines,
+            drain_limit: 0,
         }
     }
 
     pub(crate) fn empty() -> Self {
-        Self { inner: None }
+        Self {
+            inner: None,
+            permit: None,
+            in_flight: None,
+            deadlines: None,
+            drain_limit: 0,
+        }
     }
 
     #[cfg(feature = "futures03")]
@@ -228,10 +333,24 @@ impl Stream for Chunks {
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         // We use `take()` to make the stream fused, including the case of panics.
         if let Some(mut stream) = self.inner.take() {
-            let res = Pin::new(&mut stream).poll_next(cx);
+            let mut res = Pin::new(&mut stream).poll_next(cx);
+
+            if let Some(deadlines) = &mut self.deadlines {
+                match res {
+                    Poll::Ready(Some(Ok(_))) => deadlines.reset_read(),
+                    Poll::Pending if deadlines.poll_expired(cx).is_ready() => {
+                        res = Poll::Ready(Some(Err(Error::TimedOut)));
+                    }
+                    _ => {}
+                }
+            }
 
             if matches!(res, Poll::Pending | Poll::Ready(Some(Ok(_)))) {
                 self.inner = Some(stream);
+            } else {
+                self.permit = None;
+                self.in_flight = None;
+                self.deadlines = None;
             }
 
             res
@@ -243,6 +362,115 @@ impl Stream for Chunks {
     // `size_hint()` is unimplemented because unused.
 }
 
+impl Drop for Chunks {
+    fn drop(&mut self) {
+        if self.drain_limit == 0 || self.inner.is_none() {
+            return;
+        }
+
+        // Dropped outside of the runtime, e.g. at shutdown.
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let chunks = Self {
+            inner: self.inner.take(),
+            permit: self.permit.take(),
+            in_flight: self.in_flight.take(),
+            deadlines: self.deadlines.take(),
+            drain_limit: 0,
+        };
+
+        runtime.spawn(drain(chunks, self.drain_limit));
+    }
+}
+
+/// Reads the rest of the response to return the connection to the pool,
+/// unless the response is too long or too slow, see [`Chunks::drain_limit`].
+async fn drain(mut chunks: Chunks, limit: u64) {
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    let mut drained = 0;
+    let result = tokio::time::timeout(TIMEOUT, async {
+        while let Some(chunk) = chunks.try_next().await? {
+            drained += chunk.net_size as u64;
+            if drained > limit {
+                return Ok(false);
+            }
+        }
+        Ok::<_, Error>(true)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(true)) => tracing::debug!(drained, "drained dropped response"),
+        Ok(Ok(false)) => tracing::debug!(drained, "dropped response is too long, aborting"),
+        Ok(Err(err)) => tracing::debug!(error=?err, "failed to drain dropped response"),
+        Err(_) => tracing::debug!(drained, "dropped response is too slow, aborting"),
+    }
+}
+
+// === Deadlines ===
+
+struct Deadlines {
+    connect: Option<(Pin<Box<Sleep>>, CaptureConnection)>,
+    request: Option<Pin<Box<Sleep>>>,
+    read: Option<(Duration, Pin<Box<Sleep>>)>,
+}
+
+impl Deadlines {
+    fn new(timeouts: Timeouts, connection: Option<CaptureConnection>) -> Option<Box<Self>> {
+        if timeouts == Timeouts::default() {
+            return None;
+        }
+
+        let sleep = |timeout| Box::pin(tokio::time::sleep(timeout));
+        Some(Box::new(Self {
+            connect: timeouts.connect.zip(connection).map(|(t, c)| (sleep(t), c)),
+            request: timeouts.request.map(sleep),
+            read: timeouts.read.map(|t| (t, sleep(t))),
+        }))
+    }
+
+    /// Disarms the connect timeout.
+    fn connected(&mut self) {
+        self.connect = None;
+    }
+
+    fn reset_read(&mut self) {
+        if let Some((timeout, sleep)) = &mut self.read {
+            sleep.as_mut().reset(Instant::now() + *timeout);
+        }
+    }
+
+    /// Returns `Ready` if any timeout is expired.
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some((sleep, connection)) = &mut self.connect
+            && sleep.as_mut().poll(cx).is_ready()
+        {
+            // The connection is captured once it's established or taken from the pool.
+            if connection.connection_metadata().is_none() {
+                return Poll::Ready(());
+            }
+            self.connect = None;
+        }
+
+        if let Some(sleep) = &mut self.request
+            && sleep.as_mut().poll(cx).is_ready()
+        {
+            return Poll::Ready(());
+        }
+
+        if let Some((_, sleep)) = &mut self.read
+            && sleep.as_mut().poll(cx).is_ready()
+        {
+            return Poll::Ready(());
+        }
+
+        Poll::Pending
+    }
+}
+
 // === IncomingStream ===
 
 // * Produces bytes from incoming data frames.
@@ -318,9 +546,94 @@ where
 
 // === DetectDbException ===
 
+/// The maximum size of an exception sent in the middle of a response that can be
+/// detected if it's split across several chunks. Messages are usually much shorter.
+pub(crate) const MAX_EXCEPTION_SIZE: usize = 16 * 1024;
+
+const OLD_EXCEPTION_SUFFIX: &[u8] = b"))\n";
+const NEW_EXCEPTION_SUFFIX: &[u8] = b"__exception__\r\n";
+
 struct DetectDbException<S> {
     stream: S,
     exception_tag: Option<Box<[u8]>>,
+    /// The last chunks of at least `MAX_EXCEPTION_SIZE` bytes in total,
+    /// because the exception can be split by chunk boundaries.
+    recent: VecDeque<Bytes>,
+    recent_size: usize,
+}
+
+impl<S> DetectDbException<S> {
+    fn new(stream: S, exception_tag: Option<Box<[u8]>>) -> Self {
+        Self {
+            stream,
+            exception_tag,
+            recent: VecDeque::new(),
+            recent_size: 0,
+        }
+    }
+
+    fn detect(&self, chunk: &[u8]) -> Option<Error> {
+        let may_be_exception = self.ends_with(chunk, OLD_EXCEPTION_SUFFIX)
+            || (self.exception_tag.is_some() && self.ends_with(chunk, NEW_EXCEPTION_SUFFIX));
+
+        if !may_be_exception {
+            None
+        } else if self.recent.is_empty() {
+            extract_exception(chunk, self.exception_tag.as_deref())
+        } else {
+            self.detect_split(chunk)
+        }
+    }
+
+    /// Checks if the recent chunks followed by `chunk` end with `suffix`.
+    fn ends_with(&self, chunk: &[u8], suffix: &[u8]) -> bool {
+        let Some(rest) = suffix.len().checked_sub(chunk.len()) else {
+            return chunk.ends_with(suffix);
+        };
+
+        if !suffix.ends_with(chunk) {
+            return false;
+        }
+
+        let mut head = &suffix[..rest];
+        for recent in self.recent.iter().rev() {
+            if head.is_empty() {
+                break;
+            }
+
+            let (left, right) = head.split_at(head.len().saturating_sub(recent.len()));
+            if !recent.ends_with(right) {
+                return false;
+            }
+            head = left;
+        }
+
+        head.is_empty()
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn detect_split(&self, chunk: &[u8]) -> Option<Error> {
+        let mut joined = Vec::with_capacity(self.recent_size + chunk.len());
+        for recent in &self.recent {
+            joined.extend_from_slice(recent);
+        }
+        joined.extend_from_slice(chunk);
+
+        extract_exception(&joined, self.exception_tag.as_deref())
+    }
+
+    fn remember(&mut self, chunk: &Bytes) {
+        self.recent_size += chunk.len();
+        self.recent.push_back(chunk.clone());
+
+        while let Some(front) = self.recent.front()
+            && self.recent_size - front.len() >= MAX_EXCEPTION_SIZE
+        {
+            self.recent_size -= front.len();
+            self.recent.pop_front();
+        }
+    }
 }
 
 impl<S> Stream for DetectDbException<S>
@@ -330,16 +643,18 @@ where
     type Item = Result<Chunk>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let res = Pin::new(&mut self.stream).poll_next(cx);
+        let res = ready!(Pin::new(&mut self.stream).poll_next(cx));
 
-        if let Poll::Ready(Some(Ok(chunk))) = &res
-            && let Some(err) = extract_exception(&chunk.data, self.exception_tag.as_deref())
-        {
-            err.record_in_current_span("response error");
-            return Poll::Ready(Some(Err(err)));
+        if let Some(Ok(chunk)) = &res {
+            if let Some(err) = self.detect(&chunk.data) {
+                err.record_in_current_span("response error");
+                return Poll::Ready(Some(Err(err)));
+            }
+
+            self.remember(&chunk.data);
         }
 
-        res
+        Poll::Ready(res)
     }
 }
 
@@ -347,10 +662,10 @@ fn extract_exception(chunk: &[u8], tag: Option<&[u8]>) -> Option<Error> {
     // 25.11 introduced a new exception tagging format that's incompatible with the previous
     // https://github.com/ClickHouse/clickhouse-rs/issues/359
     if let Some(tag) = tag
-        && chunk.ends_with(b"__exception__\r\n")
+        && chunk.ends_with(NEW_EXCEPTION_SUFFIX)
     {
         extract_exception_new(chunk, tag)
-    } else if chunk.ends_with(b"))\n") {
+    } else if chunk.ends_with(OLD_EXCEPTION_SUFFIX) {
         // `))\n` is very rare in real data, so it's fast dirty check.
         // In random data, it occurs with a probability of ~6*10^-8 only.
         extract_exception_old(chunk)
@@ -359,6 +674,16 @@ fn extract_exception(chunk: &[u8], tag: Option<&[u8]>) -> Option<Error> {
     }
 }
 
+/// Parses `Code: <code>. DB::Exception: ...`.
+fn server_exception(message: String) -> Error {
+    let code = message
+        .strip_prefix("Code: ")
+        .and_then(|rest| rest.split_once('.'))
+        .and_then(|(code, _)| code.parse().ok());
+
+    Error::ServerException { code, message }
+}
+
 // Format:
 // ```
 //   <data>Code: <code>. DB::Exception: <desc> (version <version> (official build))\n
@@ -373,7 +698,7 @@ fn extract_exception_old(chunk: &[u8]) -> Option<Error> {
     }
 
     let exception = String::from_utf8_lossy(&chunk[index..chunk.len() - 1]);
-    Some(Error::BadResponse(exception.into()))
+    Some(server_exception(exception.into()))
 }
 
 // https://github.com/ClickHouse/ClickHouse/blob/4eaa92852bac117e95f28abe61237b0257d939d6/src/Server/HTTP/WriteBufferFromHTTPServerResponse.cpp#L347-L357
@@ -413,9 +738,7 @@ fn extract_exception_new(chunk: &[u8], tag: &[u8]) -> Option<Error> {
     };
 
     // We shouldn't discard the exception message if it fails to validate as UTF-8
-    Some(Error::BadResponse(
-        String::from_utf8_lossy(msg).trim().into(),
-    ))
+    Some(server_exception(String::from_utf8_lossy(msg).trim().into()))
 }
 
 // FIXME: this can be replaced with `usize::from_ascii()` when stable
@@ -446,7 +769,7 @@ fn it_extracts_exception_old() {
     for error in errors {
         let chunk = format!("{error}\n");
         let err = extract_exception(chunk.as_bytes(), None).expect("failed to extract exception");
-        assert_eq!(err.to_string(), format!("bad response: {error}"));
+        assert_eq!(err.to_string(), format!("server exception: {error}"));
     }
 }
 
@@ -457,5 +780,58 @@ fn it_extracts_exception_new() {
     let error = "Code: 159. DB::Exception: Timeout exceeded: elapsed 126.147987 ms, maximum: 100 ms. (TIMEOUT_EXCEEDED) (version 25.12.1.649 (official build))";
 
     let err = extract_exception(chunk, Some(tag)).expect("failed to extract exception");
-    assert_eq!(err.to_string(), format!("bad response: {error}"));
+    assert_eq!(err.to_string(), format!("server exception: {error}"));
+    assert!(matches!(
+        err,
+        Error::ServerException {
+            code: Some(159),
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn it_detects_split_exceptions() {
+    let tag = b"rnywyenlaeqynhmu";
+    let old = b"some data\nCode: 159. DB::Exception: Timeout exceeded. (TIMEOUT_EXCEEDED) (version 24.10.1.2812 (official build))\n";
+    let new = b"some data\r\n__exception__\r\nrnywyenlaeqynhmu\r\nCode: 159. DB::Exception: Timeout exceeded. (TIMEOUT_EXCEEDED) (version 25.12.1.649 (official build))\n102 rnywyenlaeqynhmu\r\n__exception__\r\n";
+
+    let cases: [(&[u8], Option<&[u8]>); 2] = [(old, None), (new, Some(tag))];
+    for (body, tag) in cases {
+        // Split the body into three chunks at all possible positions.
+        for i in 0..body.len() {
+            for j in i..body.len() {
+                let chunks = [&body[..i], &body[i..j], &body[j..]].map(|data| {
+                    Ok(Chunk {
+                        data: Bytes::copy_from_slice(data),
+                        net_size: data.len(),
+                    })
+                });
+
+                let stream = DetectDbException::new(stream::iter(chunks), tag.map(Into::into));
+                let err = stream.try_collect::<Vec<_>>().await.err();
+                assert!(
+                    matches!(
+                        err,
+                        Some(Error::ServerException {
+                            code: Some(159),
+                            ..
+                        })
+                    ),
+                    "split at {i} and {j}: {err:?}"
+                );
+            }
+        }
+    }
+
+    // Chunks without an exception are passed through.
+    let chunks = [&b"foo))"[..], b"\n", b"bar"].map(|data| {
+        Ok(Chunk {
+            data: Bytes::from_static(data),
+            net_size: data.len(),
+        })
+    });
+    let stream = DetectDbException::new(stream::iter(chunks), None);
+    let chunks = stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(chunks.len(), 3);
 }
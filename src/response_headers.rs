@@ -0,0 +1,119 @@
+use hyper::HeaderMap;
+
+use crate::query_summary::QuerySummary;
+
+const PREFIX: &str = "x-clickhouse-";
+
+/// `X-ClickHouse-*` HTTP headers of a query response.
+///
+/// Provides typed getters for commonly used headers and a generic
+/// [`get`](Self::get) fallback for others. Headers with non-UTF-8 values
+/// are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseHeaders {
+    /// Lowercase names with the `X-ClickHouse-` prefix.
+    fields: Vec<(String, String)>,
+    summary: Option<QuerySummary>,
+}
+
+impl ResponseHeaders {
+    /// Returns the value of the given header, if present.
+    ///
+    /// The name is case-insensitive and can be specified with or without
+    /// the `X-ClickHouse-` prefix, e.g. `"X-ClickHouse-Timezone"` and
+    /// `"timezone"` are equivalent.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let name = match name.get(..PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(PREFIX) => &name[PREFIX.len()..],
+            _ => name,
+        };
+
+        self.fields
+            .iter()
+            .find(|(n, _)| n[PREFIX.len()..].eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns an iterator over all `X-ClickHouse-*` headers as pairs of
+    /// lowercase names and values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// The `X-ClickHouse-Query-Id` header: the ID assigned to the query,
+    /// either provided by the client or generated by the server.
+    pub fn query_id(&self) -> Option<&str> {
+        self.get("query-id")
+    }
+
+    /// The `X-ClickHouse-Timezone` header: the timezone of the server,
+    /// or the one set by the `session_timezone` setting.
+    pub fn timezone(&self) -> Option<&str> {
+        self.get("timezone")
+    }
+
+    /// The `X-ClickHouse-Format` header: the output format of the response.
+    pub fn format(&self) -> Option<&str> {
+        self.get("format")
+    }
+
+    /// The `X-ClickHouse-Server-Display-Name` header.
+    pub fn server_display_name(&self) -> Option<&str> {
+        self.get("server-display-name")
+    }
+
+    /// The parsed `X-ClickHouse-Summary` header, see [`QuerySummary`].
+    pub fn summary(&self) -> Option<&QuerySummary> {
+        self.summary.as_ref()
+    }
+
+    pub(crate) fn from_header_map(headers: &HeaderMap) -> Self {
+        let fields = headers
+            .iter()
+            .filter(|(name, _)| name.as_str().starts_with(PREFIX))
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.as_str().to_string(), value.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        let summary = fields
+            .iter()
+            .find(|(name, _)| name == "x-clickhouse-summary")
+            .and_then(|(_, value)| QuerySummary::from_header(value));
+
+        Self { fields, summary }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_headers() {
+        let mut map = HeaderMap::new();
+        map.insert("X-ClickHouse-Query-Id", "abc".parse().unwrap());
+        map.insert("X-ClickHouse-Timezone", "Europe/Berlin".parse().unwrap());
+        map.insert(
+            "X-ClickHouse-Summary",
+            r#"{"read_rows":"3"}"#.parse().unwrap(),
+        );
+        map.insert("Content-Type", "text/plain".parse().unwrap());
+        map.insert(
+            "X-ClickHouse-Invalid",
+            hyper::header::HeaderValue::from_bytes(b"\xff").unwrap(),
+        );
+
+        let headers = ResponseHeaders::from_header_map(&map);
+        assert_eq!(headers.query_id(), Some("abc"));
+        assert_eq!(headers.timezone(), Some("Europe/Berlin"));
+        assert_eq!(headers.get("X-CLICKHOUSE-TIMEZONE"), Some("Europe/Berlin"));
+        assert_eq!(headers.get("x-clickhouse-query-id"), Some("abc"));
+        assert_eq!(headers.format(), None);
+        assert_eq!(headers.get("Content-Type"), None);
+        assert_eq!(headers.get("invalid"), None);
+        assert_eq!(headers.summary().unwrap().read_rows(), Some(3));
+        assert_eq!(headers.iter().count(), 3);
+    }
+}
@@ -0,0 +1,175 @@
+//! Support for [`Query::fetch_resumable`].
+//!
+//! The query is fetched as usual, but if the connection is lost, it's
+//! requested again as `SELECT * FROM (<query>) ORDER BY <key> OFFSET <returned rows>`,
+//! so the stream continues after the last returned row.
+//!
+//! The ordering key is repeated in the outer query, because ClickHouse
+//! doesn't guarantee that the order of a subquery is preserved.
+//!
+//! [`Query::fetch_resumable`]: crate::query::Query::fetch_resumable
+
+use std::time::Duration;
+
+use crate::{
+    cursors::RowCursor,
+    error::Result,
+    keyset::{DEFAULT_MAX_RETRIES, DEFAULT_RETRY_DELAY, is_retryable},
+    query::Query,
+    row::{RowOwned, RowRead},
+};
+
+/// Rows of a query, which is requested again after the last returned row
+/// if the connection is lost, see [`Query::fetch_resumable`].
+///
+/// [`Query::fetch_resumable`]: crate::query::Query::fetch_resumable
+#[must_use]
+pub struct ResumableStream<T> {
+    query: Query,
+    order_by: Option<String>,
+    max_retries: u32,
+    retry_delay: Duration,
+    cursor: Option<RowCursor<T>>,
+    rows: u64,
+    retries: u32,
+    resumptions: u32,
+    is_finished: bool,
+}
+
+impl<T> ResumableStream<T> {
+    pub(crate) fn new(query: Query) -> Self {
+        Self {
+            query,
+            order_by: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            cursor: None,
+            rows: 0,
+            retries: 0,
+            resumptions: 0,
+            is_finished: false,
+        }
+    }
+
+    /// Sets the ordering key of the query (e.g. `"id"` or `"ts, id"`), which is
+    /// repeated as `ORDER BY` when the rest of the result is requested.
+    ///
+    /// Columns must be in the result of the query and identify rows uniquely.
+    /// Without the key, the rest is requested without `ORDER BY`, and since
+    /// ClickHouse doesn't guarantee that the order of the subquery is kept,
+    /// rows after resuming can be lost or duplicated.
+    pub fn with_order_by(mut self, key: impl Into<String>) -> Self {
+        self.order_by = Some(key.into());
+        self
+    }
+
+    /// Sets how many times the query is requested again in a row after
+    /// failing because of a network error or a timeout.
+    ///
+    /// Successfully returned rows reset the counter.
+    /// By default, it's `3`.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before requesting the query again.
+    ///
+    /// By default, it's 1 second.
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Returns the number of rows returned so far.
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// Returns how many times the query has been requested again.
+    pub fn resumptions(&self) -> u32 {
+        self.resumptions
+    }
+
+    /// Emits the next row, requesting the rest of the result if needed.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancellation safe. If cancelled, the rest of the result
+    /// is requested again starting after the last returned row.
+    pub async fn next(&mut self) -> Result<Option<T>>
+    where
+        T: RowOwned + RowRead,
+    {
+        loop {
+            if self.is_finished {
+                return Ok(None);
+            }
+
+            // `None` at the start or once the request failed.
+            let mut cursor = match self.cursor.take() {
+                Some(cursor) => cursor,
+                None => self.rest_query().fetch::<T>()?,
+            };
+
+            match cursor.next().await {
+                Ok(Some(row)) => {
+                    self.rows += 1;
+                    self.retries = 0;
+                    self.cursor = Some(cursor);
+                    return Ok(Some(row));
+                }
+                Ok(None) => self.is_finished = true,
+                Err(err) if is_retryable(&err) && self.retries < self.max_retries => {
+                    tracing::debug!(error=?err, rows = self.rows, "resuming fetch");
+                    self.retries += 1;
+                    self.resumptions += 1;
+                    drop(cursor);
+                    tokio::time::sleep(self.retry_delay).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn rest_query(&self) -> Query {
+        if self.rows == 0 {
+            return self.query.clone();
+        }
+
+        let suffix = match &self.order_by {
+            Some(key) => format!(") ORDER BY {key} OFFSET {}", self.rows),
+            None => format!(") OFFSET {}", self.rows),
+        };
+
+        self.query.clone().wrap("SELECT * FROM (", &suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+
+    #[test]
+    fn it_requests_rest_after_returned_rows() {
+        let query = Client::default().query("SELECT ?fields FROM t ORDER BY id");
+        let mut stream = ResumableStream::<(u64, String)>::new(query);
+        assert_eq!(
+            stream.rest_query().sql_display().to_string(),
+            "SELECT ?fields FROM t ORDER BY id"
+        );
+
+        stream.rows = 42;
+        assert_eq!(
+            stream.rest_query().sql_display().to_string(),
+            "SELECT * FROM (SELECT ?fields FROM t ORDER BY id) OFFSET 42"
+        );
+
+        stream = stream.with_order_by("id");
+        assert_eq!(
+            stream.rest_query().sql_display().to_string(),
+            "SELECT * FROM (SELECT ?fields FROM t ORDER BY id) ORDER BY id OFFSET 42"
+        );
+    }
+}
@@ -29,13 +29,27 @@ pub trait Row {
 
     #[doc(hidden)]
     const NAME: &'static str;
-    // TODO: different list for SELECT/INSERT (de/ser)
     #[doc(hidden)]
     const COLUMN_NAMES: &'static [&'static str];
     #[doc(hidden)]
     const COLUMN_COUNT: usize;
     #[doc(hidden)]
     const KIND: RowKind;
+    /// Columns used for inserts, differs from `COLUMN_NAMES` only if some
+    /// fields are marked with `#[clickhouse(skip_insert)]`.
+    #[doc(hidden)]
+    const INSERT_COLUMN_NAMES: &'static [&'static str] = Self::COLUMN_NAMES;
+    /// Serde names of fields that are skipped while serializing for inserts.
+    #[doc(hidden)]
+    const INSERT_SKIPPED_FIELDS: &'static [&'static str] = &[];
+    /// Columns of fields marked with `#[serde(default)]`, which can be absent
+    /// in fetched rows and are filled with defaults then.
+    #[doc(hidden)]
+    const DEFAULTED_COLUMNS: &'static [&'static str] = &[];
+    /// Whether names of some columns differ from serde names of fields,
+    /// i.e. fields are marked with `#[clickhouse(rename, nested, flatten)]`.
+    #[doc(hidden)]
+    const REMAPPED_COLUMNS: bool = false;
     #[doc(hidden)]
     type Value<'a>: Row;
 }
@@ -135,7 +149,8 @@ impl<R> RowRead for R where R: for<'a> Row<Value<'a>: Deserialize<'a>> {}
 ///     for row in data {
 ///         insert.write(row).await?;
 ///     }
-///     insert.end().await
+///     insert.end().await?;
+///     Ok(())
 /// }
 ///
 /// // Usage
@@ -162,7 +177,8 @@ impl<R> RowRead for R where R: for<'a> Row<Value<'a>: Deserialize<'a>> {}
 ///     /* same code */
 /// #   let mut insert = client.insert::<R>(table).await?;
 /// #   for row in data { insert.write(row).await?; }
-/// #   insert.end().await
+/// #   insert.end().await?;
+/// #   Ok(())
 /// }
 ///
 /// // Usage
@@ -244,6 +260,8 @@ macro_rules! impl_row_for_tuple {
             const COLUMN_NAMES: &'static [&'static str] = $i::COLUMN_NAMES;
             const COLUMN_COUNT: usize = $i::COLUMN_COUNT + count_tokens!($($other)*);
             const KIND: RowKind = RowKind::Tuple;
+            const INSERT_COLUMN_NAMES: &'static [&'static str] = $i::INSERT_COLUMN_NAMES;
+            const INSERT_SKIPPED_FIELDS: &'static [&'static str] = $i::INSERT_SKIPPED_FIELDS;
 
             type Value<'a> = Self;
         }
@@ -278,11 +296,20 @@ impl<T> Row for Vec<T> {
 
 /// Collects all field names in depth and joins them with comma.
 pub(crate) fn join_column_names<R: Row>() -> Option<String> {
-    if R::COLUMN_NAMES.is_empty() {
+    join(R::COLUMN_NAMES)
+}
+
+/// Same as [`join_column_names`], but excludes fields skipped for inserts.
+pub(crate) fn join_insert_column_names<R: Row>() -> Option<String> {
+    join(R::INSERT_COLUMN_NAMES)
+}
+
+fn join(column_names: &[&str]) -> Option<String> {
+    if column_names.is_empty() {
         return None;
     }
 
-    let out = R::COLUMN_NAMES
+    let out = column_names
         .iter()
         .enumerate()
         .fold(String::new(), |mut res, (idx, name)| {
@@ -381,6 +408,114 @@ mod tests {
         assert_eq!(join_column_names::<TopLevel>().unwrap(), "`one`");
     }
 
+    #[test]
+    fn it_renames_columns() {
+        use serde::Serialize;
+
+        #[derive(Row, Serialize)]
+        #[clickhouse(crate = "crate")]
+        #[allow(dead_code)]
+        struct TopLevel {
+            #[clickhouse(rename = "one.a")]
+            #[serde(rename = "x")]
+            one: u32,
+            #[clickhouse(rename = "two.b")]
+            two: u32,
+        }
+
+        assert_eq!(join_column_names::<TopLevel>().unwrap(), "`one.a`,`two.b`");
+        assert_eq!(
+            join_insert_column_names::<TopLevel>().unwrap(),
+            "`one.a`,`two.b`"
+        );
+    }
+
+    #[test]
+    fn it_skips_insert() {
+        use serde::Serialize;
+
+        #[derive(Row, Serialize)]
+        #[clickhouse(crate = "crate")]
+        #[allow(dead_code)]
+        struct TopLevel {
+            one: u32,
+            #[clickhouse(skip_insert)]
+            two: u32,
+            #[clickhouse(skip_insert, rename = "three.c")]
+            #[serde(rename = "3")]
+            three: u32,
+        }
+
+        assert_eq!(
+            join_column_names::<TopLevel>().unwrap(),
+            "`one`,`two`,`three.c`"
+        );
+        assert_eq!(join_insert_column_names::<TopLevel>().unwrap(), "`one`");
+        assert_eq!(TopLevel::INSERT_SKIPPED_FIELDS, &["two", "3"]);
+    }
+
+    #[test]
+    fn it_collects_defaulted_columns() {
+        use serde::Deserialize;
+
+        #[derive(Row, Deserialize)]
+        #[clickhouse(crate = "crate")]
+        #[allow(dead_code)]
+        struct Sample {
+            one: u32,
+            #[serde(default)]
+            two: u32,
+            #[serde(default, rename = "3")]
+            three: u32,
+        }
+
+        #[derive(Row, Deserialize)]
+        #[clickhouse(crate = "crate")]
+        #[allow(dead_code)]
+        struct Wrapper(Sample);
+
+        assert_eq!(Sample::DEFAULTED_COLUMNS, &["two", "3"]);
+        assert_eq!(Wrapper::DEFAULTED_COLUMNS, &["two", "3"]);
+        assert!(<(Sample, u64)>::DEFAULTED_COLUMNS.is_empty());
+    }
+
+    #[test]
+    fn it_expands_nested() {
+        use serde::Serialize;
+
+        #[derive(Row)]
+        #[clickhouse(crate = "crate", nested)]
+        #[allow(dead_code)]
+        struct Goal {
+            #[clickhouse(rename = "ID")]
+            id: u32,
+            #[clickhouse(rename = "Serial")]
+            serial: u32,
+        }
+
+        #[derive(Row, Serialize)]
+        #[clickhouse(crate = "crate")]
+        #[allow(dead_code)]
+        struct TopLevel {
+            one: u32,
+            #[clickhouse(nested, rename = "Goals")]
+            #[serde(with = "crate::serde::nested")]
+            goals: Vec<Goal>,
+            #[clickhouse(skip_insert)]
+            two: u32,
+        }
+
+        assert_eq!(
+            join_column_names::<TopLevel>().unwrap(),
+            "`one`,`Goals.ID`,`Goals.Serial`,`two`"
+        );
+        assert_eq!(
+            join_insert_column_names::<TopLevel>().unwrap(),
+            "`one`,`Goals.ID`,`Goals.Serial`"
+        );
+        assert_eq!(TopLevel::COLUMN_COUNT, 4);
+    }
+
     #[test]
     fn it_rejects_other() {
         #[allow(dead_code)]
@@ -3,9 +3,13 @@ use crate::error::Error;
 use crate::error::Result;
 use crate::row::RowKind;
 use clickhouse_types::Column;
-use std::collections::HashMap;
-use std::fmt::{Display, Formatter};
+use clickhouse_types::data_types::{DataTypeNode, DecimalType};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 #[derive(Debug, PartialEq)]
 pub(crate) enum AccessType {
@@ -33,12 +37,56 @@ pub(crate) struct RowMetadata {
     /// on the shape of the data. In some cases, there is no noticeable difference,
     /// in others, it could be up to 2-3x slower.
     pub(crate) access_type: AccessType,
+    /// Whether `DateTime` columns with non-UTC timezones are rejected when read
+    /// by serde helpers assuming UTC, see [`crate::Client::with_strict_utc`].
+    pub(crate) strict_utc: bool,
+    /// The size of each row in bytes if all columns are fixed-size scalars
+    /// or tuples of them, and the struct's fields are in the same order
+    /// as in the schema.
+    ///
+    /// Once the first row is validated, such rows are deserialized without
+    /// validation, because they're read the same way regardless of values,
+    /// see [`crate::rowbinary::deserialize_fixed_row`].
+    pub(crate) fixed_row_size: Option<usize>,
+    /// Strings read by [`crate::serde::lc_interned`], shared by all rows of the cursor.
+    pub(crate) strings: StringTable,
+    /// Whether each column isn't mapped to any struct field and must be skipped.
+    /// Empty if all columns are mapped, see [`crate::query::Query::allow_extra_columns`].
+    pub(crate) extra_columns: Vec<bool>,
+}
+
+/// A set of interned strings, which allows `LowCardinality(String)` values
+/// to be deserialized without allocating the same string for every row.
+///
+/// The number of interned strings is limited by [`StringTable::MAX_LEN`];
+/// once it's reached, new values are allocated as usual.
+#[derive(Default)]
+pub(crate) struct StringTable(Mutex<HashSet<Arc<str>>>);
+
+impl StringTable {
+    const MAX_LEN: usize = 1 << 16;
+
+    pub(crate) fn intern(&self, value: &str) -> Arc<str> {
+        // The lock is never contended, because rows are read sequentially.
+        let mut set = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(interned) = set.get(value) {
+            return interned.clone();
+        }
+
+        let interned = Arc::<str>::from(value);
+        if set.len() < Self::MAX_LEN {
+            set.insert(interned.clone());
+        }
+        interned
+    }
 }
 
 pub(crate) struct InsertMetadata {
     pub(crate) row_metadata: RowMetadata,
     pub(crate) column_default_kinds: Vec<ColumnDefaultKind>,
     pub(crate) column_lookup: HashMap<String, usize>,
+    /// When the schema was fetched (or preloaded), see [`crate::Client::with_metadata_ttl`].
+    pub(crate) fetched_at: Instant,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -51,7 +99,19 @@ pub(crate) enum ColumnDefaultKind {
 }
 
 impl RowMetadata {
+    #[cfg(any(test, feature = "test-util"))]
     pub(crate) fn new_for_cursor<T: Row>(columns: Vec<Column>) -> Result<Self> {
+        Self::new_for_cursor_with::<T>(columns, false)
+    }
+
+    /// Same as [`RowMetadata::new_for_cursor`], but columns not matching
+    /// any field of a struct are skipped if `allow_extra_columns` is set.
+    pub(crate) fn new_for_cursor_with<T: Row>(
+        columns: Vec<Column>,
+        allow_extra_columns: bool,
+    ) -> Result<Self> {
+        let allow_extra_columns = allow_extra_columns && T::KIND == RowKind::Struct;
+        let mut extra_columns = Vec::new();
         let access_type = match T::KIND {
             RowKind::Primitive => {
                 if columns.len() != 1 {
@@ -91,7 +151,13 @@ impl RowMetadata {
                 AccessType::WithSeqAccess // ignored
             }
             RowKind::Struct => {
-                if columns.len() != T::COLUMN_NAMES.len() {
+                let has_extra_columns =
+                    allow_extra_columns && columns.len() > T::COLUMN_NAMES.len();
+                // Columns of `#[serde(default)]` fields can be absent.
+                let min_columns = T::COLUMN_NAMES.len() - T::DEFAULTED_COLUMNS.len();
+                if !(min_columns..=T::COLUMN_NAMES.len()).contains(&columns.len())
+                    && !has_extra_columns
+                {
                     return Err(Error::SchemaMismatch(format!(
                         "While processing struct {}: database schema has {} columns, \
                         but the struct definition has {} fields.\
@@ -106,7 +172,10 @@ impl RowMetadata {
                 let mut mapping = Vec::with_capacity(T::COLUMN_NAMES.len());
                 let mut expected_index = 0;
                 let mut should_use_map = false;
-                for col in &columns {
+                if has_extra_columns {
+                    extra_columns = vec![false; columns.len()];
+                }
+                for (col_index, col) in columns.iter().enumerate() {
                     if let Some(index) = T::COLUMN_NAMES.iter().position(|field| col.name == *field)
                     {
                         if index != expected_index {
@@ -114,6 +183,8 @@ impl RowMetadata {
                         }
                         expected_index += 1;
                         mapping.push(index);
+                    } else if has_extra_columns {
+                        extra_columns[col_index] = true;
                     } else {
                         return Err(Error::SchemaMismatch(format!(
                             "While processing struct {}: database schema has a column {col} \
@@ -125,6 +196,26 @@ impl RowMetadata {
                         )));
                     }
                 }
+                if mapping.len() != T::COLUMN_NAMES.len() {
+                    let missing_fields = T::COLUMN_NAMES
+                        .iter()
+                        .filter(|field| !columns.iter().any(|col| col.name == **field))
+                        .filter(|field| !T::DEFAULTED_COLUMNS.contains(field))
+                        .collect::<Vec<_>>();
+                    if missing_fields.is_empty() {
+                        // Absent fields are filled by serde only for maps.
+                        should_use_map = true;
+                    } else {
+                        return Err(Error::SchemaMismatch(format!(
+                            "While processing struct {}: database schema has no columns \
+                        for the following struct fields:\n{}\
+                        \n#### All schema columns:\n{}",
+                            T::NAME,
+                            join_panic_schema_hint(missing_fields),
+                            join_panic_schema_hint(&columns),
+                        )));
+                    }
+                }
                 if should_use_map {
                     AccessType::WithMapAccess(mapping)
                 } else {
@@ -132,9 +223,24 @@ impl RowMetadata {
                 }
             }
         };
+        let fixed_row_size = match (&access_type, T::KIND) {
+            // Extra columns are skipped, so rows are read partially.
+            _ if !extra_columns.is_empty() => None,
+            (AccessType::WithSeqAccess, RowKind::Primitive | RowKind::Tuple | RowKind::Struct) => {
+                columns
+                    .iter()
+                    .map(|column| fixed_size_of(&column.data_type))
+                    .sum()
+            }
+            _ => None,
+        };
         Ok(Self {
             columns,
             access_type,
+            strict_utc: false,
+            fixed_row_size,
+            strings: StringTable::default(),
+            extra_columns,
         })
     }
 
@@ -161,6 +267,16 @@ impl RowMetadata {
         }
     }
 
+    /// Returns the number of struct fields having columns in the database schema,
+    /// which is less than `fields` if some `#[serde(default)]` fields are absent.
+    #[inline]
+    pub(crate) fn mapped_field_count(&self, fields: usize) -> usize {
+        match &self.access_type {
+            AccessType::WithMapAccess(mapping) => mapping.len(),
+            AccessType::WithSeqAccess => fields,
+        }
+    }
+
     /// Returns `true` if the field order in the struct is different from the database schema.
     ///
     /// Only makes sense for selects; for inserts, it is always `false`.
@@ -214,6 +330,30 @@ impl Display for ColumnDefaultKind {
 }
 
 impl InsertMetadata {
+    pub(crate) fn new(columns: Vec<Column>, column_default_kinds: Vec<ColumnDefaultKind>) -> Self {
+        debug_assert_eq!(columns.len(), column_default_kinds.len());
+
+        let column_lookup = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| (column.name.clone(), index))
+            .collect();
+
+        Self {
+            row_metadata: RowMetadata {
+                columns,
+                access_type: AccessType::WithSeqAccess, // ignored on insert
+                strict_utc: false,
+                fixed_row_size: None,
+                strings: Default::default(),
+                extra_columns: Vec::new(),
+            },
+            column_default_kinds,
+            column_lookup,
+            fetched_at: Instant::now(),
+        }
+    }
+
     pub(crate) fn to_row<T: Row>(&self) -> Result<RowMetadata> {
         if T::KIND != RowKind::Struct {
             return Err(Error::SchemaMismatch(format!(
@@ -227,7 +367,7 @@ impl InsertMetadata {
         let mut result_columns: Vec<Column> = Vec::with_capacity(T::COLUMN_COUNT);
         let mut set_columns: Vec<bool> = vec![false; self.row_metadata.columns.len()];
 
-        for struct_column_name in T::COLUMN_NAMES {
+        for struct_column_name in T::INSERT_COLUMN_NAMES {
             match self.column_lookup.get(*struct_column_name) {
                 Some(&col) => {
                     if self.column_default_kinds[col].is_immutable() {
@@ -248,7 +388,7 @@ impl InsertMetadata {
                         "While processing struct {}: database schema has no column named {struct_column_name}.\
                         \n#### All struct fields:\n{}\n#### All schema columns:\n{}",
                         T::NAME,
-                        join_panic_schema_hint(T::COLUMN_NAMES),
+                        join_panic_schema_hint(T::INSERT_COLUMN_NAMES),
                         join_panic_schema_hint(&self.row_metadata.columns),
                     )));
                 }
@@ -270,7 +410,7 @@ impl InsertMetadata {
                 "While processing struct {}: the following non-default columns are missing:\n{missing_columns_hint}\
                  \n#### All struct fields:\n{}\n#### All schema columns:\n{}",
                 T::NAME,
-                join_panic_schema_hint(T::COLUMN_NAMES),
+                join_panic_schema_hint(T::INSERT_COLUMN_NAMES),
                 join_panic_schema_hint(&self.row_metadata.columns),
             )));
         }
@@ -278,13 +418,200 @@ impl InsertMetadata {
         Ok(RowMetadata {
             columns: result_columns,
             access_type: AccessType::WithSeqAccess, // ignored
+            strict_utc: false,
+            fixed_row_size: None,
+            strings: StringTable::default(),
+            extra_columns: Vec::new(),
         })
     }
 }
 
+/// Returns the size of a value in `RowBinary` if it's a fixed-size scalar
+/// or a tuple of them, which is deserialized the same way regardless
+/// of its value.
+///
+/// Enums are excluded, because their values are validated,
+/// as well as `FixedString`, which can be deserialized as a string.
+fn fixed_size_of(data_type: &DataTypeNode) -> Option<usize> {
+    use DataTypeNode::*;
+
+    Some(match data_type {
+        Bool | UInt8 | Int8 => 1,
+        UInt16 | Int16 | BFloat16 | Date => 2,
+        // Explicit timezones are provided by the validator to serde helpers,
+        // so such columns cannot be read without validation.
+        UInt32 | Int32 | Float32 | Date32 | DateTime(None) | Time | IPv4 => 4,
+        UInt64 | Int64 | Float64 | DateTime64(_, None) | Time64(_) => 8,
+        UInt128 | Int128 | UUID | IPv6 => 16,
+        UInt256 | Int256 => 32,
+        Decimal(_, _, decimal_type) => match decimal_type {
+            DecimalType::Decimal32 => 4,
+            DecimalType::Decimal64 => 8,
+            DecimalType::Decimal128 => 16,
+            DecimalType::Decimal256 => 32,
+        },
+        Tuple(elements) => return elements.iter().map(fixed_size_of).sum(),
+        _ => return None,
+    })
+}
+
 fn join_panic_schema_hint<T: Display>(col: impl IntoIterator<Item = T>) -> String {
     col.into_iter()
         .map(|c| format!("- {c}"))
         .collect::<Vec<String>>()
         .join("\n")
 }
+
+/// A snapshot of table schemas cached by [`Client`] for validated inserts,
+/// see [`Client::export_metadata_cache`] and [`Client::with_preloaded_metadata`].
+///
+/// It implements [`Serialize`] and [`Deserialize`], so it can be stored
+/// between runs of short-lived processes, which avoids fetching the schema
+/// of each table on the first insert into it. Column types are validated
+/// on deserialization.
+///
+/// [`Client`]: crate::Client
+/// [`Client::export_metadata_cache`]: crate::Client::export_metadata_cache
+/// [`Client::with_preloaded_metadata`]: crate::Client::with_preloaded_metadata
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(into = "CachedTables", try_from = "CachedTables")]
+pub struct MetadataCache {
+    pub(crate) tables: HashMap<String, Arc<InsertMetadata>>,
+}
+
+impl MetadataCache {
+    /// Returns the number of cached tables.
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Returns `true` if no tables are cached.
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Returns names of cached tables, as passed to [`Client::insert`].
+    ///
+    /// [`Client::insert`]: crate::Client::insert
+    pub fn tables(&self) -> impl Iterator<Item = &str> {
+        self.tables.keys().map(String::as_str)
+    }
+}
+
+impl fmt::Debug for MetadataCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetadataCache")
+            .field("tables", &self.tables.keys())
+            .finish()
+    }
+}
+
+/// The serialized representation of [`MetadataCache`], sorted by tables
+/// to make it deterministic.
+#[derive(Serialize, Deserialize)]
+struct CachedTables(BTreeMap<String, Vec<CachedColumn>>);
+
+#[derive(Serialize, Deserialize)]
+struct CachedColumn {
+    name: String,
+    r#type: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    default_kind: String,
+}
+
+impl From<MetadataCache> for CachedTables {
+    fn from(cache: MetadataCache) -> Self {
+        let tables = cache.tables.iter().map(|(table, metadata)| {
+            let columns = metadata
+                .row_metadata
+                .columns
+                .iter()
+                .zip(&metadata.column_default_kinds)
+                .map(|(column, default_kind)| CachedColumn {
+                    name: column.name.clone(),
+                    r#type: column.data_type.to_string(),
+                    default_kind: default_kind.to_string(),
+                })
+                .collect();
+
+            (table.clone(), columns)
+        });
+
+        Self(tables.collect())
+    }
+}
+
+impl TryFrom<CachedTables> for MetadataCache {
+    type Error = Error;
+
+    fn try_from(cached: CachedTables) -> Result<Self> {
+        let mut tables = HashMap::with_capacity(cached.0.len());
+
+        for (table, cached_columns) in cached.0 {
+            let mut columns = Vec::with_capacity(cached_columns.len());
+            let mut column_default_kinds = Vec::with_capacity(cached_columns.len());
+
+            for column in cached_columns {
+                columns.push(Column {
+                    data_type: DataTypeNode::new(&column.r#type)?,
+                    name: column.name,
+                });
+                column_default_kinds.push(column.default_kind.parse()?);
+            }
+
+            let metadata = InsertMetadata::new(columns, column_default_kinds);
+            tables.insert(table, Arc::new(metadata));
+        }
+
+        Ok(Self { tables })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_roundtrips_metadata_cache() {
+        let columns = [
+            ("id", "UInt64", ""),
+            ("name", "LowCardinality(Nullable(String))", "DEFAULT"),
+            ("at", "DateTime64(3, 'Europe/Amsterdam')", ""),
+            (
+                "tags",
+                "Map(String, Array(Tuple(Int8, Decimal(18, 4))))",
+                "",
+            ),
+            ("kind", "Enum8('a' = 1, 'b' = 2)", "MATERIALIZED"),
+        ];
+
+        let metadata = InsertMetadata::new(
+            columns
+                .iter()
+                .map(|(name, ty, _)| Column::new(name.to_string(), DataTypeNode::new(ty).unwrap()))
+                .collect(),
+            columns
+                .iter()
+                .map(|(.., kind)| kind.parse().unwrap())
+                .collect(),
+        );
+
+        let cache = MetadataCache {
+            tables: HashMap::from([("db.t".to_string(), Arc::new(metadata))]),
+        };
+
+        let json = serde_json::to_string(&cache).unwrap();
+        let restored_cache: MetadataCache = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored_cache.tables().collect::<Vec<_>>(), ["db.t"]);
+        assert_eq!(serde_json::to_string(&restored_cache).unwrap(), json);
+
+        let (original, restored) = (&cache.tables["db.t"], &restored_cache.tables["db.t"]);
+        assert_eq!(restored.row_metadata.columns, original.row_metadata.columns);
+        assert_eq!(restored.column_default_kinds, original.column_default_kinds);
+        assert_eq!(restored.column_lookup, original.column_lookup);
+
+        let err = serde_json::from_str::<MetadataCache>(r#"{"t":[{"name":"id","type":"UInt42"}]}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("UInt42"), "{err}");
+    }
+}
@@ -0,0 +1,92 @@
+use bytes::{Bytes, BytesMut};
+use clickhouse_types::{
+    Column, error::TypesError, parse_rbwnat_columns_header, put_rbwnat_columns_header,
+};
+use serde::Serialize;
+
+use crate::{
+    Row, RowOwned, RowRead,
+    error::{Error, Result},
+    row_metadata::{ColumnDefaultKind, InsertMetadata, RowMetadata},
+    rowbinary::{deserialize_row, serialize_with_validation},
+};
+
+/// Encodes `rows` in the `RowBinaryWithNamesAndTypes` format, the same way
+/// [`Insert`] does, without sending them anywhere.
+///
+/// `columns` are columns of the table, e.g. returned by [`Query::describe`].
+/// Rows are validated against them like on inserts, and the header lists
+/// only columns of fields of `T`, so other columns are filled with defaults
+/// by the server. The result can be inserted by [`Client::insert_raw`]:
+///
+/// ```no_run
+/// # async fn example() -> clickhouse::error::Result<()> {
+/// #[derive(clickhouse::Row, serde::Serialize)]
+/// struct Event {
+///     id: u64,
+///     name: String,
+/// }
+///
+/// let client = clickhouse::Client::default();
+/// let columns = client.query("SELECT * FROM events").describe().await?;
+///
+/// // Could be done in another thread or process.
+/// let rows = [Event { id: 1, name: "foo".into() }];
+/// let buffer = clickhouse::rowbinary::encode_rows(&rows, &columns)?;
+///
+/// client
+///     .insert_raw("events", "RowBinaryWithNamesAndTypes", &buffer[..])
+///     .await?;
+/// # Ok(()) }
+/// ```
+///
+/// Unlike [`Insert`], it cannot check which columns are `MATERIALIZED` or
+/// `ALIAS`, so inserting into such columns is reported by the server.
+///
+/// [`Insert`]: crate::insert::Insert
+/// [`Query::describe`]: crate::query::Query::describe
+/// [`Client::insert_raw`]: crate::Client::insert_raw
+pub fn encode_rows<'a, T>(
+    rows: impl IntoIterator<Item = &'a T>,
+    columns: &[Column],
+) -> Result<Bytes>
+where
+    T: Row + Serialize + 'a,
+{
+    // Kinds of defaults are unknown, so any column is allowed to be omitted.
+    let column_default_kinds = vec![ColumnDefaultKind::Default; columns.len()];
+    let metadata = InsertMetadata::new(columns.to_vec(), column_default_kinds).to_row::<T>()?;
+
+    let mut buffer = BytesMut::new();
+    put_rbwnat_columns_header(&metadata.columns, &mut buffer)?;
+    for row in rows {
+        serialize_with_validation(&mut buffer, row, &metadata)?;
+    }
+
+    Ok(buffer.freeze())
+}
+
+/// Decodes rows encoded in the `RowBinaryWithNamesAndTypes` format,
+/// e.g. by [`encode_rows`] or fetched by [`Query::fetch_bytes`].
+///
+/// Rows are validated against columns of the header like by [`Query::fetch`].
+///
+/// [`Query::fetch_bytes`]: crate::query::Query::fetch_bytes
+/// [`Query::fetch`]: crate::query::Query::fetch
+pub fn decode_rows<T>(mut data: &[u8]) -> Result<Vec<T>>
+where
+    T: RowOwned + RowRead,
+{
+    let columns = parse_rbwnat_columns_header(&mut data).map_err(|err| match err {
+        TypesError::NotEnoughData(_) => Error::NotEnoughData,
+        err => Error::InvalidColumnsHeader(err.into()),
+    })?;
+    let metadata = RowMetadata::new_for_cursor_with::<T>(columns, false)?;
+
+    let mut rows = Vec::new();
+    while !data.is_empty() {
+        rows.push(deserialize_row(&mut data, Some(&metadata))?);
+    }
+
+    Ok(rows)
+}
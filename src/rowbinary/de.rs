@@ -1,14 +1,25 @@
 use crate::Row;
 use crate::error::{Error, Result};
+use crate::nested;
 use crate::row_metadata::RowMetadata;
+use crate::rowbinary::DecodeLimits;
 use crate::rowbinary::utils::{ensure_size, get_unsigned_leb128};
-use crate::rowbinary::validation::{DataTypeValidator, NullEncoding, SchemaValidator, SerdeType};
+use crate::rowbinary::validation::{
+    DataTypeValidator, NullEncoding, SchemaValidator, SerdeType, enum_as_string_without_validation,
+};
+use crate::serde::{
+    BYTES_ZERO_COPY, ENUM_AS_STRING, LC_INTERNED, Passed, TZ_DATETIME, TZ_DATETIME64, UTC_DATETIME,
+    UTC_DATETIME64,
+};
 use crate::types::bf16;
+use crate::types::geo;
 use crate::types::int256;
-use bytes::Buf;
+use bytes::{Buf, Bytes};
+use clickhouse_types::data_types::EnumType;
 use core::mem::size_of;
+use serde::de::IntoDeserializer;
 use serde::de::MapAccess;
-use serde::de::value::BytesDeserializer;
+use serde::de::value::{BytesDeserializer, StrDeserializer};
 use serde::{
     Deserialize,
     de::{DeserializeSeed, Deserializer, EnumAccess, SeqAccess, VariantAccess, Visitor},
@@ -16,6 +27,9 @@ use serde::{
 use std::marker::PhantomData;
 use std::{convert::TryFrom, str};
 
+/// Lengths are encoded by at most 9 bytes, see [`get_unsigned_leb128`].
+const MAX_LEB128_LEN: usize = 9;
+
 /// Deserializes a row from `input` with a row encoded in `RowBinary`.
 ///
 /// If the optional metadata ([`RowMetadata`]) parsed from `RowBinaryWithNamesAndTypes` header
@@ -27,19 +41,64 @@ use std::{convert::TryFrom, str};
 pub(crate) fn deserialize_row<'data, 'cursor, T: Deserialize<'data> + Row>(
     input: &mut &'data [u8],
     metadata: Option<&'cursor RowMetadata>,
+) -> Result<T> {
+    deserialize_row_from(input, metadata, None, None)
+}
+
+/// Same as [`deserialize_row`], but `input` must be a part of `source`, which
+/// allows [`crate::serde::bytes_zero_copy`] to slice it instead of copying.
+///
+/// Lengths are checked against `limits` if the strict decoding mode is enabled,
+/// see [`crate::Client::with_strict_decode`].
+pub(crate) fn deserialize_row_from<'data, 'cursor, T: Deserialize<'data> + Row>(
+    input: &mut &'data [u8],
+    metadata: Option<&'cursor RowMetadata>,
+    source: Option<&'data Bytes>,
+    limits: Option<DecodeLimits>,
 ) -> Result<T> {
     match metadata {
-        Some(metadata) => deserialize_row_with_validation(input, metadata),
-        None => deserialize_row_without_validation(input),
+        Some(metadata) => deserialize_row_with_validation(input, metadata, source, limits),
+        None => deserialize_row_without_validation(input, source, limits),
+    }
+}
+
+/// Deserializes a row of `row_size` bytes from `input` without validation.
+///
+/// It's a fast path for rows consisting only of fixed-size scalar columns
+/// (see [`RowMetadata::fixed_row_size`]) once the first row is validated:
+/// such rows are deserialized the same way regardless of values, so
+/// validating every row is redundant. Fails if the row is read differently,
+/// e.g. by a custom `Deserialize` implementation.
+pub(crate) fn deserialize_fixed_row<'data, T: Deserialize<'data> + Row>(
+    input: &mut &'data [u8],
+    row_size: usize,
+) -> Result<T> {
+    let Some((mut row, rest)) = input.split_at_checked(row_size) else {
+        return Err(Error::NotEnoughData);
+    };
+
+    // Fixed-size rows contain no strings, so there is nothing to slice.
+    let value = deserialize_row_without_validation(&mut row, None, None)?;
+    if !row.is_empty() {
+        return Err(Error::SchemaMismatch(format!(
+            "{} read {} bytes of a fixed-size row of {row_size} bytes",
+            T::NAME,
+            row_size - row.len(),
+        )));
     }
+
+    *input = rest;
+    Ok(value)
 }
 
 /// Deserializes a value from `input` with a row encoded in `RowBinary`,
 /// i.e. only when validation is disabled in the client.
 fn deserialize_row_without_validation<'data, 'cursor, T: Deserialize<'data> + Row>(
     input: &mut &'data [u8],
+    source: Option<&'data Bytes>,
+    limits: Option<DecodeLimits>,
 ) -> Result<T> {
-    let mut deserializer = RowBinaryDeserializer::<T, _>::new(input, ());
+    let mut deserializer = RowBinaryDeserializer::<T, _>::new(input, (), source).strict(limits);
     T::deserialize(&mut deserializer)
 }
 
@@ -49,9 +108,12 @@ fn deserialize_row_without_validation<'data, 'cursor, T: Deserialize<'data> + Ro
 fn deserialize_row_with_validation<'data, 'cursor, T: Deserialize<'data> + Row>(
     input: &mut &'data [u8],
     metadata: &'cursor RowMetadata,
+    source: Option<&'data Bytes>,
+    limits: Option<DecodeLimits>,
 ) -> Result<T> {
     let validator = DataTypeValidator::new(metadata);
-    let mut deserializer = RowBinaryDeserializer::<T, _>::new(input, validator);
+    let mut deserializer =
+        RowBinaryDeserializer::<T, _>::new(input, validator, source).strict(limits);
     T::deserialize(&mut deserializer)
 }
 
@@ -64,21 +126,42 @@ where
 {
     input: &'cursor mut &'data [u8],
     validator: V,
+    /// The buffer containing `input`, if known, see [`deserialize_row_from`].
+    source: Option<&'data Bytes>,
+    /// Set in the strict decoding mode, see [`deserialize_row_from`].
+    strict: Option<Strict>,
     _marker: PhantomData<R>,
 }
 
+#[derive(Clone, Copy)]
+struct Strict {
+    limits: DecodeLimits,
+    /// The length of the input at the start of the row to report offsets.
+    row_len: usize,
+}
+
 impl<'cursor, 'data, R: Row, V> RowBinaryDeserializer<'cursor, 'data, R, V>
 where
     V: SchemaValidator<R>,
 {
-    fn new(input: &'cursor mut &'data [u8], validator: V) -> Self {
+    fn new(input: &'cursor mut &'data [u8], validator: V, source: Option<&'data Bytes>) -> Self {
         Self {
             input,
             validator,
+            source,
+            strict: None,
             _marker: PhantomData,
         }
     }
 
+    fn strict(mut self, limits: Option<DecodeLimits>) -> Self {
+        self.strict = limits.map(|limits| Strict {
+            limits,
+            row_len: self.input.len(),
+        });
+        self
+    }
+
     fn inner(
         &mut self,
         serde_type: SerdeType,
@@ -87,6 +170,8 @@ where
         Ok(RowBinaryDeserializer {
             validator,
             input: self.input,
+            source: self.source,
+            strict: self.strict,
             _marker: PhantomData,
         })
     }
@@ -103,9 +188,45 @@ where
     }
 
     fn read_size(&mut self) -> Result<usize> {
-        let size = get_unsigned_leb128(&mut self.input)?;
-        // TODO: what about another error?
-        usize::try_from(size).map_err(|_| Error::NotEnoughData)
+        self.read_size_limited(|limits| limits.max_string_size, "string")
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        self.read_size_limited(|limits| limits.max_array_size, "array")
+    }
+
+    #[inline(always)]
+    fn read_size_limited(
+        &mut self,
+        limit: fn(&DecodeLimits) -> usize,
+        what: &str,
+    ) -> Result<usize> {
+        let Some(strict) = self.strict else {
+            let size = get_unsigned_leb128(&mut self.input)?;
+            // TODO: what about another error?
+            return usize::try_from(size).map_err(|_| Error::NotEnoughData);
+        };
+
+        let offset = strict.row_len - self.input.len();
+        let available = self.input.len();
+        let corrupt = |reason| Error::CorruptData { offset, reason };
+
+        let size = match get_unsigned_leb128(&mut self.input) {
+            Ok(size) => size,
+            // A truncated length is shorter, so more bytes mean an overflow.
+            Err(Error::NotEnoughData) if available >= MAX_LEB128_LEN => {
+                return Err(corrupt("the length overflows u64".into()));
+            }
+            Err(err) => return Err(err),
+        };
+
+        let limit = limit(&strict.limits);
+        match usize::try_from(size) {
+            Ok(size) if size <= limit => Ok(size),
+            _ => Err(corrupt(format!(
+                "the {what} length {size} exceeds the limit of {limit}"
+            ))),
+        }
     }
 }
 
@@ -276,14 +397,14 @@ where
 
     #[inline(always)]
     fn deserialize_seq<V: Visitor<'data>>(self, visitor: V) -> Result<V::Value> {
-        let len = self.read_size()?;
+        let len = self.read_len()?;
         let deserializer = &mut self.inner(SerdeType::Seq(len))?;
         visitor.visit_seq(RowBinarySeqAccess { deserializer, len })
     }
 
     #[inline(always)]
     fn deserialize_map<V: Visitor<'data>>(self, visitor: V) -> Result<V::Value> {
-        let len = self.read_size()?;
+        let len = self.read_len()?;
         let deserializer = &mut self.inner(SerdeType::Map(len))?;
         visitor.visit_map(RowBinaryMapAccess {
             deserializer,
@@ -298,18 +419,33 @@ where
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        if !self.validator.is_field_order_wrong() {
+        let value = if !self.validator.is_field_order_wrong() {
             visitor.visit_seq(RowBinarySeqAccess {
-                deserializer: self,
+                deserializer: &mut *self,
                 len: fields.len(),
-            })
+            })?
         } else {
+            // Nested and flattened fields span several columns,
+            // so struct fields cannot be matched with columns one-to-one.
+            if fields.len() < R::COLUMN_COUNT {
+                return Err(Error::SchemaMismatch(format!(
+                    "While processing struct {}: nested and flattened fields require \
+                     columns to be in the same order as struct fields, use `?fields`",
+                    R::NAME
+                )));
+            }
+            let len = self.validator.mapped_field_count(fields.len());
             visitor.visit_map(RowBinaryStructAsMapAccess {
-                deserializer: self,
+                deserializer: &mut *self,
                 current_field_idx: 0,
+                len,
                 fields,
-            })
-        }
+            })?
+        };
+
+        // Columns after the last field, see `Query::allow_extra_columns`.
+        self.validator.skip_extra_columns(self.input)?;
+        Ok(value)
     }
 
     #[inline(always)]
@@ -323,6 +459,91 @@ where
             (bf16::MODULE_PATH, bf16::BYTE_LEN),
         ];
 
+        if name == UTC_DATETIME {
+            self.validator.validate(SerdeType::UtcDateTime)?;
+            ensure_size(&mut self.input, size_of::<u32>())?;
+            return visitor.visit_u32(self.input.get_u32_le());
+        }
+
+        if name == UTC_DATETIME64 {
+            self.validator.validate(SerdeType::UtcDateTime64)?;
+            ensure_size(&mut self.input, size_of::<i64>())?;
+            return visitor.visit_i64(self.input.get_i64_le());
+        }
+
+        if name == TZ_DATETIME {
+            let validator = self.validator.validate(SerdeType::TzDateTime)?;
+            ensure_size(&mut self.input, size_of::<u32>())?;
+            return visitor.visit_seq(TimestampWithTimezone {
+                timestamp: Some(self.input.get_u32_le()),
+                timezone: validator.timezone(),
+            });
+        }
+
+        if name == TZ_DATETIME64 {
+            let validator = self.validator.validate(SerdeType::TzDateTime64)?;
+            ensure_size(&mut self.input, size_of::<i64>())?;
+            return visitor.visit_seq(TimestampWithTimezone {
+                timestamp: Some(self.input.get_i64_le()),
+                timezone: validator.timezone(),
+            });
+        }
+
+        if name == LC_INTERNED {
+            self.validator.validate(SerdeType::Str)?;
+            let size = self.read_size()?;
+            let slice = self.read_slice(size)?;
+            let str = str::from_utf8(slice).map_err(Error::from)?;
+            let Some(strings) = self.validator.string_table() else {
+                return visitor.visit_borrowed_str(str);
+            };
+            crate::serde::pass_to_visitor(Passed::Str(strings.intern(str)));
+            return visitor.visit_unit();
+        }
+
+        if name == BYTES_ZERO_COPY {
+            let size = self.read_size()?;
+            self.validator.validate(SerdeType::ByteBuf(size))?;
+            let slice = self.read_slice(size)?;
+            let Some(source) = self.source else {
+                return visitor.visit_borrowed_bytes(slice);
+            };
+            crate::serde::pass_to_visitor(Passed::Bytes(source.slice_ref(slice)));
+            return visitor.visit_unit();
+        }
+
+        if name == ENUM_AS_STRING {
+            let validator = self.validator.validate(SerdeType::EnumAsString)?;
+            let Some((enum_type, values_map)) = validator.enum_values() else {
+                return Err(enum_as_string_without_validation());
+            };
+
+            let value = match enum_type {
+                EnumType::Enum8 => {
+                    ensure_size(&mut self.input, size_of::<i8>())?;
+                    i16::from(self.input.get_i8())
+                }
+                EnumType::Enum16 => {
+                    ensure_size(&mut self.input, size_of::<i16>())?;
+                    self.input.get_i16_le()
+                }
+            };
+
+            let Some(variant) = values_map.get(&value) else {
+                return Err(Error::SchemaMismatch(format!(
+                    "enum value {value} is not present in the database schema"
+                )));
+            };
+            return visitor.visit_newtype_struct(StrDeserializer::<Error>::new(variant));
+        }
+
+        if let Some(data_type) = geo::data_type_of(name) {
+            self.validator.validate(SerdeType::Geo(data_type))?;
+            let mut inner = RowBinaryDeserializer::<R, ()>::new(self.input, (), self.source);
+            inner.strict = self.strict;
+            return visitor.visit_newtype_struct(&mut inner);
+        }
+
         match FIXED_BYTES
             .iter()
             .find(|(prefix, _)| name.starts_with(prefix))
@@ -354,10 +575,15 @@ where
     fn deserialize_tuple_struct<V: Visitor<'data>>(
         self,
         name: &'static str,
-        _len: usize,
-        _visitor: V,
+        len: usize,
+        visitor: V,
     ) -> Result<V::Value> {
-        panic!("tuple struct types are unsupported: `{name}`");
+        // Only `#[clickhouse(nested)]` fields, read as consecutive `Array` columns.
+        if name != nested::SERDE_NAME {
+            panic!("tuple struct types are unsupported: `{name}`");
+        }
+        let deserializer = &mut self.inner(SerdeType::Nested(len))?;
+        visitor.visit_seq(RowBinaryTupleSeqAccess { deserializer, len })
     }
 
     #[inline(always)]
@@ -432,7 +658,11 @@ where
     {
         if self.len > 0 {
             self.len -= 1;
-            let value = DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
+            let deserializer = &mut *self.deserializer;
+            deserializer
+                .validator
+                .skip_extra_columns(deserializer.input)?;
+            let value = DeserializeSeed::deserialize(seed, deserializer)?;
             Ok(Some(value))
         } else {
             Ok(None)
@@ -490,6 +720,8 @@ where
 {
     deserializer: &'de mut RowBinaryDeserializer<'cursor, 'data, R, Validator>,
     current_field_idx: usize,
+    /// The number of fields having columns, see [`SchemaValidator::mapped_field_count`].
+    len: usize,
     fields: &'static [&'static str],
 }
 
@@ -548,7 +780,11 @@ where
     where
         K: DeserializeSeed<'data>,
     {
-        if self.current_field_idx >= self.fields.len() {
+        let deserializer = &mut *self.deserializer;
+        deserializer
+            .validator
+            .skip_extra_columns(deserializer.input)?;
+        if self.current_field_idx >= self.len {
             return Ok(None);
         }
         let schema_index = self
@@ -568,7 +804,7 @@ where
     }
 
     fn size_hint(&self) -> Option<usize> {
-        Some(self.fields.len())
+        Some(self.len)
     }
 }
 
@@ -639,3 +875,30 @@ where
         Ok((value, deserializer))
     }
 }
+
+/// Provides a timestamp followed by the column's timezone, if it's explicit,
+/// to timezone-aware helpers in [`crate::serde`].
+struct TimestampWithTimezone<'a, T> {
+    timestamp: Option<T>,
+    timezone: Option<&'a str>,
+}
+
+impl<'data, T> SeqAccess<'data> for TimestampWithTimezone<'_, T>
+where
+    T: IntoDeserializer<'data, Error>,
+{
+    type Error = Error;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>>
+    where
+        S: DeserializeSeed<'data>,
+    {
+        if let Some(timestamp) = self.timestamp.take() {
+            seed.deserialize(timestamp.into_deserializer()).map(Some)
+        } else if let Some(timezone) = self.timezone.take() {
+            seed.deserialize(timezone.into_deserializer()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,46 @@
+/// Limits of lengths read in the strict decoding mode,
+/// see [`Client::with_strict_decode`].
+///
+/// Lengths of strings and arrays are encoded before their values, so a
+/// malformed response (e.g. garbage from a misconfigured proxy) can announce
+/// a huge value, and the cursor would buffer the response waiting for it.
+/// In the strict mode, such lengths fail with [`Error::CorruptData`] instead.
+///
+/// By default, both limits are 1 GiB, like the `format_binary_max_string_size`
+/// and `format_binary_max_array_size` settings of the server.
+///
+/// [`Client::with_strict_decode`]: crate::Client::with_strict_decode
+/// [`Error::CorruptData`]: crate::error::Error::CorruptData
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    pub(crate) max_string_size: usize,
+    pub(crate) max_array_size: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_string_size: 1 << 30,
+            max_array_size: 1 << 30,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Creates the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum size of `String` values in bytes.
+    pub fn with_max_string_size(mut self, size: usize) -> Self {
+        self.max_string_size = size;
+        self
+    }
+
+    /// Sets the maximum number of elements of `Array` and `Map` values.
+    pub fn with_max_array_size(mut self, size: usize) -> Self {
+        self.max_array_size = size;
+        self
+    }
+}
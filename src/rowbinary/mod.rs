@@ -1,11 +1,24 @@
-pub(crate) use de::deserialize_row;
+//! Encoding and decoding rows in the `RowBinaryWithNamesAndTypes` format
+//! without a client, e.g. to prepare buffers for [`Client::insert_raw`] in advance.
+//!
+//! [`Client::insert_raw`]: crate::Client::insert_raw
+
+pub use buffers::{decode_rows, encode_rows};
+pub use limits::DecodeLimits;
+
+pub(crate) use de::{deserialize_fixed_row, deserialize_row, deserialize_row_from};
 pub(crate) use ser::serialize_row_binary;
+pub(crate) use ser::serialize_row_binary_as_output;
 pub(crate) use ser::serialize_with_validation;
+pub(crate) use skip::row_len;
 
 pub(crate) mod validation;
 
+mod buffers;
 mod de;
+mod limits;
 mod ser;
+mod skip;
 #[cfg(test)]
 mod tests;
 mod utils;
@@ -1,23 +1,43 @@
 use crate::Row;
 use crate::error::Error::SequenceMustHaveLength;
 use crate::error::{Error, Result};
+use crate::nested;
 use crate::row_metadata::RowMetadata;
-use crate::rowbinary::validation::{DataTypeValidator, SchemaValidator, SerdeType};
+use crate::rowbinary::validation::{
+    DataTypeValidator, SchemaValidator, SerdeType, enum_as_string_without_validation,
+};
+use crate::serde::ENUM_AS_STRING;
 use crate::types::bf16;
+use crate::types::geo;
 use crate::types::int256;
 use bytes::BufMut;
-use clickhouse_types::put_leb128;
+use clickhouse_types::{data_types::EnumType, put_leb128};
 use serde::ser::SerializeMap;
 use serde::{
     Serialize,
-    ser::{Impossible, SerializeSeq, SerializeStruct, SerializeTuple, Serializer},
+    ser::{
+        Impossible, SerializeSeq, SerializeStruct, SerializeTuple, SerializeTupleStruct, Serializer,
+    },
 };
-use std::marker::PhantomData;
+use std::{marker::PhantomData, mem};
 
 /// Serializes `row` using the `RowBinary` format and writes to `buffer`.
+/// Fields marked with `#[clickhouse(skip_insert)]` are not written.
 pub(crate) fn serialize_row_binary<B: BufMut, R: Row + Serialize>(
     buffer: B,
     row: &R,
+) -> Result<()> {
+    let mut serializer = RowBinarySerializer::<B, R>::new(buffer, ());
+    serializer.skipped_fields = R::INSERT_SKIPPED_FIELDS;
+    row.serialize(&mut serializer)?;
+    Ok(())
+}
+
+/// Same as [`serialize_row_binary`], but writes all fields,
+/// i.e. produces the same data as the server responds on selects.
+pub(crate) fn serialize_row_binary_as_output<B: BufMut, R: Row + Serialize>(
+    buffer: B,
+    row: &R,
 ) -> Result<()> {
     let mut serializer = RowBinarySerializer::<B, R>::new(buffer, ());
     row.serialize(&mut serializer)?;
@@ -35,6 +55,7 @@ pub(crate) fn serialize_with_validation<B: BufMut, R: Row + Serialize>(
 ) -> Result<()> {
     let validator = DataTypeValidator::<R>::new(metadata);
     let mut serializer = RowBinarySerializer::new(buffer, validator);
+    serializer.skipped_fields = R::INSERT_SKIPPED_FIELDS;
     value.serialize(&mut serializer)?;
     Ok(())
 }
@@ -45,6 +66,8 @@ pub(crate) fn serialize_with_validation<B: BufMut, R: Row + Serialize>(
 struct RowBinarySerializer<B: BufMut, R: Row, V: SchemaValidator<R> = ()> {
     buffer: B,
     validator: V,
+    /// Serde names of top-level fields that must not be written.
+    skipped_fields: &'static [&'static str],
     _marker: PhantomData<R>,
 }
 
@@ -55,6 +78,7 @@ impl<B: BufMut, R: Row, V: SchemaValidator<R>> RowBinarySerializer<B, R, V> {
         Self {
             buffer,
             validator,
+            skipped_fields: &[],
             _marker: PhantomData,
         }
     }
@@ -96,8 +120,9 @@ impl<'ser, B: BufMut, R: Row, V: SchemaValidator<R>> Serializer
     type SerializeTuple = Self::SerializeSeq;
     type SerializeMap = Self::SerializeSeq;
 
+    type SerializeTupleStruct = Self::SerializeSeq;
+
     type SerializeStructVariant = Impossible<(), Error>;
-    type SerializeTupleStruct = Impossible<(), Error>;
     type SerializeTupleVariant = Impossible<(), Error>;
 
     impl_num_or_enum!(i8, serialize_i8, put_i8, SerdeType::I8);
@@ -175,7 +200,25 @@ impl<'ser, B: BufMut, R: Row, V: SchemaValidator<R>> Serializer
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
-        panic!("unit variant types are unsupported: `{name}::{variant}`");
+        // Only enums written by `serde::enum_as_string`.
+        let Some((enum_type, values_map)) = self.validator.enum_values() else {
+            panic!("unit variant types are unsupported: `{name}::{variant}`");
+        };
+
+        let Some(value) = values_map
+            .iter()
+            .find_map(|(value, v)| (v == variant).then_some(*value))
+        else {
+            return Err(Error::SchemaMismatch(format!(
+                "enum variant {name}::{variant} is not present in the database schema"
+            )));
+        };
+
+        match enum_type {
+            EnumType::Enum8 => self.buffer.put_i8(value as i8),
+            EnumType::Enum16 => self.buffer.put_i16_le(value),
+        }
+        Ok(())
     }
 
     #[inline]
@@ -189,6 +232,20 @@ impl<'ser, B: BufMut, R: Row, V: SchemaValidator<R>> Serializer
             (bf16::MODULE_PATH, bf16::BYTE_LEN),
         ];
 
+        if name == ENUM_AS_STRING {
+            let inner = self.validator.validate(SerdeType::EnumAsString)?;
+            if inner.enum_values().is_none() {
+                return Err(enum_as_string_without_validation());
+            }
+            return value.serialize(&mut RowBinarySerializer::new(&mut self.buffer, inner));
+        }
+
+        if let Some(data_type) = geo::data_type_of(name) {
+            self.validator.validate(SerdeType::Geo(data_type))?;
+            let mut inner = RowBinarySerializer::<_, R>::new(&mut self.buffer, ());
+            return value.serialize(&mut inner);
+        }
+
         match FIXED_BYTES
             .iter()
             .find(|(prefix, _)| name.starts_with(prefix))
@@ -244,9 +301,14 @@ impl<'ser, B: BufMut, R: Row, V: SchemaValidator<R>> Serializer
     fn serialize_tuple_struct(
         self,
         name: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        panic!("tuple struct types are unsupported: `{name}`");
+        // Only `#[clickhouse(nested)]` fields, written as consecutive `Array` columns.
+        if name != nested::SERDE_NAME {
+            panic!("tuple struct types are unsupported: `{name}`");
+        }
+        let inner = self.validator.validate(SerdeType::Nested(len))?;
+        Ok(RowBinarySerializer::new(&mut self.buffer, inner))
     }
 
     #[inline]
@@ -302,8 +364,24 @@ impl<B: BufMut, R: Row, V: SchemaValidator<R>> SerializeStruct
     type Ok = ();
 
     #[inline]
-    fn serialize_field<T: Serialize + ?Sized>(&mut self, _: &'static str, value: &T) -> Result<()> {
-        value.serialize(&mut **self)
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        if self.skipped_fields.is_empty() {
+            return value.serialize(&mut **self);
+        }
+        if self.skipped_fields.contains(&key) {
+            return Ok(());
+        }
+
+        // Inner structs are serialized by the same serializer, but only
+        // fields of the top-level row are skipped.
+        let skipped_fields = mem::take(&mut self.skipped_fields);
+        let result = value.serialize(&mut **self);
+        self.skipped_fields = skipped_fields;
+        result
     }
 
     #[inline]
@@ -352,6 +430,26 @@ impl<B: BufMut, R: Row, V: SchemaValidator<R>> SerializeTuple
     }
 }
 
+impl<B: BufMut, R: Row, V: SchemaValidator<R>> SerializeTupleStruct
+    for RowBinaryInnerTypeSerializer<'_, B, R, V>
+{
+    type Error = Error;
+    type Ok = ();
+
+    #[inline]
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
 /// TODO: usage of [`SerializeMap::serialize_entry`] implies some borrow checker issues.
 impl<'ser, B: BufMut, R: Row, V: SchemaValidator<R>> SerializeMap
     for RowBinaryInnerTypeSerializer<'ser, B, R, V>
@@ -0,0 +1,108 @@
+use crate::error::{Error, Result};
+use crate::rowbinary::utils::{ensure_size, get_unsigned_leb128};
+use bytes::Buf;
+use clickhouse_types::data_types::{Column, DataTypeNode, DecimalType, EnumType};
+
+/// Returns the size of the row at the beginning of `data` encoded in `RowBinary`
+/// according to `columns`, without deserializing it.
+///
+/// It's used to skip rows that cannot be deserialized into the requested type.
+/// Returns [`Error::NotEnoughData`] if the row is incomplete and
+/// [`Error::Unsupported`] if the size of some column cannot be determined.
+pub(crate) fn row_len(data: &[u8], columns: &[Column]) -> Result<usize> {
+    let mut input = data;
+    for column in columns {
+        skip_value(&mut input, &column.data_type)?;
+    }
+    Ok(data.len() - input.len())
+}
+
+pub(super) fn skip_value(input: &mut &[u8], data_type: &DataTypeNode) -> Result<()> {
+    use DataTypeNode::*;
+
+    let size = match data_type {
+        Bool | UInt8 | Int8 => 1,
+        UInt16 | Int16 | BFloat16 | Date => 2,
+        UInt32 | Int32 | Float32 | Date32 | DateTime(_) | Time | IPv4 => 4,
+        UInt64 | Int64 | Float64 | DateTime64(..) | Time64(_) | Interval(_) => 8,
+        UInt128 | Int128 | UUID | IPv6 | Point => 16,
+        UInt256 | Int256 => 32,
+        Decimal(_, _, decimal_type) => match decimal_type {
+            DecimalType::Decimal32 => 4,
+            DecimalType::Decimal64 => 8,
+            DecimalType::Decimal128 => 16,
+            DecimalType::Decimal256 => 32,
+        },
+        Enum(EnumType::Enum8, _) => 1,
+        Enum(EnumType::Enum16, _) => 2,
+        FixedString(size) => *size,
+        String => get_size(input)?,
+        Nullable(inner) => {
+            ensure_size(&mut *input, 1)?;
+            if input.get_u8() == 0 {
+                skip_value(input, inner)?;
+            }
+            0
+        }
+        LowCardinality(inner) | SimpleAggregateFunction(_, inner) => {
+            return skip_value(input, inner);
+        }
+        Array(inner) => {
+            for _ in 0..get_size(input)? {
+                skip_value(input, inner)?;
+            }
+            0
+        }
+        Map([key, value]) => {
+            for _ in 0..get_size(input)? {
+                skip_value(input, key)?;
+                skip_value(input, value)?;
+            }
+            0
+        }
+        Tuple(elements) => {
+            for element in elements {
+                skip_value(input, element)?;
+            }
+            0
+        }
+        Variant(types) => {
+            ensure_size(&mut *input, 1)?;
+            let discriminator = input.get_u8();
+            // 0xFF is NULL.
+            if discriminator != 0xFF {
+                let inner = types.get(usize::from(discriminator)).ok_or(
+                    Error::VariantDiscriminatorIsOutOfBound(discriminator.into()),
+                )?;
+                skip_value(input, inner)?;
+            }
+            0
+        }
+        Ring | LineString => return skip_array_of(input, &Point),
+        MultiLineString => return skip_array_of(input, &LineString),
+        Polygon => return skip_array_of(input, &Ring),
+        MultiPolygon => return skip_array_of(input, &Polygon),
+        // `Dynamic`, `JSON`, `AggregateFunction` and unknown types.
+        _ => {
+            return Err(Error::Unsupported(format!(
+                "cannot determine the size of a {data_type} value"
+            )));
+        }
+    };
+
+    ensure_size(&mut *input, size)?;
+    input.advance(size);
+    Ok(())
+}
+
+fn skip_array_of(input: &mut &[u8], inner: &DataTypeNode) -> Result<()> {
+    for _ in 0..get_size(input)? {
+        skip_value(input, inner)?;
+    }
+    Ok(())
+}
+
+fn get_size(input: &mut &[u8]) -> Result<usize> {
+    let size = get_unsigned_leb128(&mut *input)?;
+    usize::try_from(size).map_err(|_| Error::NotEnoughData)
+}
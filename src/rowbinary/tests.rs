@@ -1,5 +1,7 @@
 use crate::Row;
 use crate::row::Primitive;
+use crate::row_metadata::RowMetadata;
+use clickhouse_types::{Column, DataTypeNode};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -164,6 +166,198 @@ fn it_serializes() {
     assert_eq!(actual, sample_serialized());
 }
 
+#[test]
+fn it_skips_insert_fields() {
+    #[derive(Debug, PartialEq, crate::Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Skipped {
+        a: u8,
+        #[clickhouse(skip_insert)]
+        b: u8,
+        c: u8,
+    }
+
+    let row = Skipped { a: 1, b: 2, c: 3 };
+
+    let mut actual = Vec::new();
+    super::serialize_row_binary(&mut actual, &row).unwrap();
+    assert_eq!(actual, [1, 3]);
+
+    // Fetched rows still contain the field.
+    let actual: Skipped = super::deserialize_row(&mut &[1, 2, 3][..], None).unwrap();
+    assert_eq!(actual, row);
+}
+
+#[test]
+fn it_skips_insert_fields_only_at_top_level() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Point {
+        x: u8,
+        b: u8,
+    }
+
+    #[derive(Debug, PartialEq, crate::Row)]
+    #[clickhouse(crate = "crate", nested)]
+    struct Goal {
+        id: u8,
+        b: u8,
+    }
+
+    #[derive(Debug, PartialEq, crate::Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Sample {
+        a: u8,
+        #[clickhouse(skip_insert)]
+        b: u8,
+        point: Point,
+        #[clickhouse(nested)]
+        #[serde(with = "crate::serde::nested")]
+        goals: Vec<Goal>,
+    }
+
+    let row = Sample {
+        a: 1,
+        b: 2,
+        point: Point { x: 3, b: 4 },
+        goals: vec![Goal { id: 5, b: 6 }],
+    };
+
+    // Only `Sample::b` is skipped, `b` fields of inner structs are written.
+    let mut actual = Vec::new();
+    super::serialize_row_binary(&mut actual, &row).unwrap();
+    assert_eq!(actual, [1, 3, 4, 1, 5, 1, 6]);
+}
+
+#[test]
+fn it_transposes_nested_fields() {
+    #[derive(Debug, PartialEq, crate::Row)]
+    #[clickhouse(crate = "crate", nested)]
+    struct Goal {
+        id: u8,
+        serial: u8,
+    }
+
+    #[derive(Debug, PartialEq, crate::Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Sample {
+        no: u8,
+        #[clickhouse(nested)]
+        #[serde(with = "crate::serde::nested")]
+        goals: Vec<Goal>,
+        last: u8,
+    }
+
+    let row = Sample {
+        no: 1,
+        goals: vec![Goal { id: 2, serial: 3 }, Goal { id: 4, serial: 5 }],
+        last: 6,
+    };
+    // `goals.id` and `goals.serial` are separate arrays.
+    let serialized = [1, 2, 2, 4, 2, 3, 5, 6];
+
+    let mut actual = Vec::new();
+    super::serialize_row_binary(&mut actual, &row).unwrap();
+    assert_eq!(actual, serialized);
+
+    let actual: Sample = super::deserialize_row(&mut &serialized[..], None).unwrap();
+    assert_eq!(actual, row);
+
+    // With validation against the schema.
+    let column = |name: &str, data_type| Column::new(name.to_string(), data_type);
+    let array = |t| DataTypeNode::Array(Box::new(t));
+    let mut columns = vec![
+        column("no", DataTypeNode::UInt8),
+        column("goals.id", array(DataTypeNode::UInt8)),
+        column("goals.serial", array(DataTypeNode::UInt8)),
+        column("last", DataTypeNode::UInt8),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Sample>(columns.clone()).unwrap();
+
+    let mut actual = Vec::new();
+    super::serialize_with_validation(&mut actual, &row, &metadata).unwrap();
+    assert_eq!(actual, serialized);
+
+    let actual: Sample = super::deserialize_row(&mut &serialized[..], Some(&metadata)).unwrap();
+    assert_eq!(actual, row);
+
+    columns[2] = column("goals.serial", array(DataTypeNode::String));
+    let metadata = RowMetadata::new_for_cursor::<Sample>(columns).unwrap();
+    let err = super::deserialize_row::<Sample>(&mut &serialized[..], Some(&metadata)).unwrap_err();
+    assert!(matches!(err, crate::error::Error::SchemaMismatch(_)));
+
+    // Arrays of different lengths.
+    let err = super::deserialize_row::<Sample>(&mut &[1, 2, 2, 4, 1, 3, 6][..], None).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("nested column serial has 1 elements, expected 2")
+    );
+}
+
+#[test]
+fn it_measures_rows() {
+    use DataTypeNode::*;
+    use clickhouse_types::data_types::{DateTimePrecision, DecimalType};
+
+    let column = |data_type| Column::new("c".into(), data_type);
+    let columns = [
+        Int8,
+        Int32,
+        Int64,
+        UInt8,
+        UInt32,
+        UInt64,
+        Float32,
+        Float64,
+        DateTime(None),
+        DateTime64(DateTimePrecision::Precision3, None),
+        Time,
+        Time64(DateTimePrecision::Precision9),
+        Decimal(18, 9, DecimalType::Decimal64),
+        Decimal(38, 9, DecimalType::Decimal128),
+        String,
+        String,
+        Nullable(Box::new(Decimal(18, 9, DecimalType::Decimal64))),
+        Nullable(Box::new(DateTime(None))),
+        FixedString(4),
+        Array(Box::new(Int8)),
+        Bool,
+    ]
+    .map(column);
+
+    let mut input = sample_serialized();
+    let len = input.len();
+    input.extend_from_slice(&[1, 2, 3]); // the next row
+    assert_eq!(super::row_len(&input, &columns).unwrap(), len);
+
+    for i in 0..len {
+        let err = super::row_len(&input[..i], &columns).unwrap_err();
+        assert!(matches!(err, crate::error::Error::NotEnoughData), "{err}");
+    }
+
+    let columns = [
+        LowCardinality(Box::new(String)),
+        Map([Box::new(String), Box::new(UInt16)]),
+        Variant(vec![String, UInt32]),
+        Variant(vec![String, UInt32]),
+        Ring,
+    ]
+    .map(column);
+    let input = [
+        // LowCardinality(String) "a"
+        1, b'a', //
+        // Map(String, UInt16) {"b": 1}
+        1, 1, b'b', 1, 0, //
+        // Variant(String, UInt32) 2, NULL
+        1, 2, 0, 0, 0, 0xff, //
+        // Ring [(0, 0)]
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    assert_eq!(super::row_len(&input, &columns).unwrap(), input.len());
+
+    let err = super::row_len(&input, &[column(Dynamic)]).unwrap_err();
+    assert!(matches!(err, crate::error::Error::Unsupported(_)), "{err}");
+}
+
 #[test]
 fn it_deserializes() {
     let input = sample_serialized();
@@ -320,3 +514,1196 @@ fn it_time_serializes_time64_nanos_overflow_fails() {
         "Unexpected error message: {err}"
     );
 }
+
+#[cfg(feature = "chrono")]
+#[test]
+fn it_checks_timezones_in_strict_utc_mode() {
+    use chrono::{DateTime, Utc};
+
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Event {
+        #[serde(with = "crate::serde::chrono::datetime")]
+        dt: DateTime<Utc>,
+        #[serde(with = "crate::serde::chrono::datetime64::millis::option")]
+        dt64: Option<DateTime<Utc>>,
+    }
+
+    let expected = Event {
+        dt: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        dt64: DateTime::from_timestamp_millis(1_700_000_000_123),
+    };
+
+    let mut serialized = 1_700_000_000_u32.to_le_bytes().to_vec();
+    serialized.push(0); // not NULL
+    serialized.extend(1_700_000_000_123_i64.to_le_bytes());
+
+    let check = |tz: Option<&str>, tz64: Option<&str>, strict_utc| {
+        let columns = vec![
+            Column::new("dt".into(), DataTypeNode::DateTime(tz.map(Into::into))),
+            Column::new(
+                "dt64".into(),
+                DataTypeNode::Nullable(Box::new(DataTypeNode::DateTime64(
+                    clickhouse_types::data_types::DateTimePrecision::Precision3,
+                    tz64.map(Into::into),
+                ))),
+            ),
+        ];
+        let mut metadata = RowMetadata::new_for_cursor::<Event>(columns).unwrap();
+        metadata.strict_utc = strict_utc;
+        super::deserialize_row::<Event>(&mut &serialized[..], Some(&metadata))
+    };
+
+    // Without strict UTC mode, any timezone is accepted.
+    assert_eq!(check(Some("Asia/Tokyo"), None, false).unwrap(), expected);
+
+    // UTC and columns without a timezone are accepted in strict UTC mode.
+    assert_eq!(check(None, None, true).unwrap(), expected);
+    assert_eq!(check(Some("UTC"), Some("Etc/UTC"), true).unwrap(), expected);
+
+    let err = check(Some("Asia/Tokyo"), None, true).unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "ClickHouse type DateTime('Asia/Tokyo') as a UTC timestamp (strict UTC mode)"
+        ),
+        "{err}"
+    );
+
+    let err = check(None, Some("Europe/Berlin"), true).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("DateTime64(3, 'Europe/Berlin') as a UTC timestamp"),
+        "{err}"
+    );
+
+    // Plain integers aren't checked.
+    let columns = vec![Column::new(
+        "ts".into(),
+        DataTypeNode::DateTime(Some("Asia/Tokyo".into())),
+    )];
+    let mut metadata = RowMetadata::new_for_cursor::<u32>(columns).unwrap();
+    metadata.strict_utc = true;
+    let actual: u32 = super::deserialize_row(&mut &serialized[..4], Some(&metadata)).unwrap();
+    assert_eq!(actual, 1_700_000_000);
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn it_checks_timezones_in_strict_utc_mode_for_time() {
+    use time::OffsetDateTime;
+
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Event {
+        #[serde(with = "crate::serde::time::datetime64::nanos")]
+        dt64: OffsetDateTime,
+    }
+
+    let serialized = 1_700_000_000_123_456_789_i64.to_le_bytes();
+    let columns = vec![Column::new(
+        "dt64".into(),
+        DataTypeNode::DateTime64(
+            clickhouse_types::data_types::DateTimePrecision::Precision9,
+            Some("America/New_York".into()),
+        ),
+    )];
+    let mut metadata = RowMetadata::new_for_cursor::<Event>(columns).unwrap();
+
+    let actual: Event = super::deserialize_row(&mut &serialized[..], Some(&metadata)).unwrap();
+    assert_eq!(
+        actual.dt64,
+        OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_123_456_789).unwrap()
+    );
+
+    metadata.strict_utc = true;
+    let err = super::deserialize_row::<Event>(&mut &serialized[..], Some(&metadata)).unwrap_err();
+    assert!(
+        matches!(err, crate::error::Error::SchemaMismatch(_)),
+        "{err}"
+    );
+}
+
+#[cfg(all(feature = "chrono", feature = "time", feature = "tz"))]
+#[test]
+fn it_deserializes_datetimes_with_timezones() {
+    use chrono::{DateTime, Utc};
+    use chrono_tz::Tz;
+    use clickhouse_types::data_types::DateTimePrecision;
+    use time::{OffsetDateTime, UtcOffset};
+
+    #[derive(Debug, PartialEq, crate::Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Event {
+        #[serde(with = "crate::serde::chrono::datetime_tz")]
+        dt: DateTime<Tz>,
+        #[serde(with = "crate::serde::chrono::datetime64_tz::millis::option")]
+        dt64: Option<DateTime<Tz>>,
+        #[serde(with = "crate::serde::time::datetime_tz")]
+        dt_time: OffsetDateTime,
+        #[serde(with = "crate::serde::time::datetime64_tz::nanos")]
+        dt64_time: OffsetDateTime,
+    }
+
+    let event = Event {
+        dt: DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .with_timezone(&Tz::UTC),
+        dt64: DateTime::from_timestamp_millis(1_720_000_000_123)
+            .map(|dt| dt.with_timezone(&Tz::UTC)),
+        dt_time: OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap(),
+        dt64_time: OffsetDateTime::from_unix_timestamp_nanos(1_720_000_000_123_456_789).unwrap(),
+    };
+    let mut serialized = Vec::new();
+    super::serialize_row_binary(&mut serialized, &event).unwrap();
+
+    let columns = |tz: Option<&str>| {
+        let tz = tz.map(String::from);
+        vec![
+            Column::new("dt".into(), DataTypeNode::DateTime(tz.clone())),
+            Column::new(
+                "dt64".into(),
+                DataTypeNode::Nullable(Box::new(DataTypeNode::DateTime64(
+                    DateTimePrecision::Precision3,
+                    tz.clone(),
+                ))),
+            ),
+            Column::new("dt_time".into(), DataTypeNode::DateTime(tz.clone())),
+            Column::new(
+                "dt64_time".into(),
+                DataTypeNode::DateTime64(DateTimePrecision::Precision9, tz),
+            ),
+        ]
+    };
+    let deserialize = |columns| {
+        let metadata = RowMetadata::new_for_cursor::<Event>(columns).unwrap();
+        super::deserialize_row::<Event>(&mut &serialized[..], Some(&metadata))
+    };
+
+    // Without an explicit timezone, UTC is used.
+    assert_eq!(deserialize(columns(None)).unwrap(), event);
+    let actual: Event = super::deserialize_row(&mut &serialized[..], None).unwrap();
+    assert_eq!(actual, event);
+
+    // Values carry the timezone of the column, including DST.
+    let actual = deserialize(columns(Some("Europe/Berlin"))).unwrap();
+    assert_eq!(actual.dt.timezone(), Tz::Europe__Berlin);
+    assert_eq!(actual.dt.to_rfc3339(), "2023-11-14T23:13:20+01:00");
+    assert_eq!(
+        actual.dt64.unwrap().to_rfc3339(),
+        "2024-07-03T11:46:40.123+02:00"
+    );
+    assert_eq!(
+        actual.dt_time.offset(),
+        UtcOffset::from_hms(1, 0, 0).unwrap()
+    );
+    assert_eq!(
+        actual.dt64_time.offset(),
+        UtcOffset::from_hms(2, 0, 0).unwrap()
+    );
+    assert_eq!(actual.dt.with_timezone(&Utc), event.dt);
+    assert_eq!(actual.dt_time, event.dt_time);
+    assert_eq!(actual.dt64_time, event.dt64_time);
+
+    let err = deserialize(columns(Some("Mars/Olympus_Mons"))).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("unknown timezone: Mars/Olympus_Mons"),
+        "{err}"
+    );
+}
+
+#[test]
+fn it_ser_des_json_maps() {
+    use serde_json::{Map, Value, json};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, crate::Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Event {
+        #[serde(with = "crate::serde::json_map")]
+        attrs: BTreeMap<String, Value>,
+        #[serde(with = "crate::serde::json_map::option")]
+        attrs_opt: Option<Map<String, Value>>,
+        #[serde(with = "crate::serde::json_map::vec")]
+        attrs_vec: Vec<BTreeMap<String, Value>>,
+    }
+
+    let map = |value: Value| match value {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    };
+
+    let event = Event {
+        attrs: map(json!({"a": 1, "b": "x", "c": [true, null]}))
+            .into_iter()
+            .collect(),
+        attrs_opt: None,
+        attrs_vec: vec![
+            BTreeMap::new(),
+            map(json!({"d": {"e": 1.5}})).into_iter().collect(),
+        ],
+    };
+
+    let string_map = || {
+        DataTypeNode::Map([
+            Box::new(DataTypeNode::String),
+            Box::new(DataTypeNode::String),
+        ])
+    };
+    let columns = vec![
+        Column::new("attrs".into(), string_map()),
+        Column::new("attrs_opt".into(), string_map()),
+        Column::new(
+            "attrs_vec".into(),
+            DataTypeNode::Array(Box::new(string_map())),
+        ),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Event>(columns).unwrap();
+
+    let mut serialized = Vec::new();
+    super::serialize_with_validation(&mut serialized, &event, &metadata).unwrap();
+
+    // Values are stored as JSON text.
+    let mut expected = vec![3];
+    for (key, value) in [("a", "1"), ("b", "\"x\""), ("c", "[true,null]")] {
+        expected.push(key.len() as u8);
+        expected.extend(key.as_bytes());
+        expected.push(value.len() as u8);
+        expected.extend(value.as_bytes());
+    }
+    expected.extend([0, 2, 0, 1, 1, b'd', 9]);
+    expected.extend(br#"{"e":1.5}"#);
+    assert_eq!(serialized, expected);
+
+    let actual: Event = super::deserialize_row(&mut &serialized[..], Some(&metadata)).unwrap();
+    assert_eq!(actual, event);
+
+    // Non-empty maps are read as `Some`.
+    let event = Event {
+        attrs_opt: Some(map(json!({"f": null}))),
+        ..event
+    };
+    let mut serialized = Vec::new();
+    super::serialize_with_validation(&mut serialized, &event, &metadata).unwrap();
+    let actual: Event = super::deserialize_row(&mut &serialized[..], Some(&metadata)).unwrap();
+    assert_eq!(actual, event);
+
+    // Values must be valid JSON.
+    let serialized = [1, 1, b'a', 3, b'f', b'o', b'o', 0, 0];
+    let err = super::deserialize_row::<Event>(&mut &serialized[..], Some(&metadata)).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("invalid JSON in the value of \"a\""),
+        "{err}"
+    );
+}
+
+#[test]
+fn it_deserializes_fixed_rows() {
+    use DataTypeNode::*;
+
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Metric {
+        ts: u64,
+        min: f64,
+        max: f64,
+    }
+
+    let column = |name: &str, data_type| Column::new(name.to_string(), data_type);
+    let columns = vec![
+        column("ts", UInt64),
+        column("min", Float64),
+        column("max", Float64),
+    ];
+
+    let metadata = RowMetadata::new_for_cursor::<Metric>(columns.clone()).unwrap();
+    assert_eq!(metadata.fixed_row_size, Some(24));
+    let metadata = RowMetadata::new_for_cursor::<(u64, f64, f64)>(columns.clone()).unwrap();
+    assert_eq!(metadata.fixed_row_size, Some(24));
+
+    // Variable-size columns, enums and reordered fields are not fixed.
+    let mut other = columns.clone();
+    other[1] = column("min", String);
+    let metadata = RowMetadata::new_for_cursor::<Metric>(other).unwrap();
+    assert_eq!(metadata.fixed_row_size, None);
+    let mut other = columns.clone();
+    other[1] = column("min", Nullable(Box::new(Float64)));
+    let metadata = RowMetadata::new_for_cursor::<Metric>(other).unwrap();
+    assert_eq!(metadata.fixed_row_size, None);
+    let mut other = columns.clone();
+    other.swap(1, 2);
+    let metadata = RowMetadata::new_for_cursor::<Metric>(other).unwrap();
+    assert_eq!(metadata.fixed_row_size, None);
+
+    let mut serialized = Vec::new();
+    for (ts, min, max) in [(1u64, 0.5f64, 1.5f64), (2, -1., 1.)] {
+        serialized.extend(ts.to_le_bytes());
+        serialized.extend(min.to_le_bytes());
+        serialized.extend(max.to_le_bytes());
+    }
+
+    let mut input = &serialized[..];
+    let first: Metric = super::deserialize_fixed_row(&mut input, 24).unwrap();
+    assert_eq!(
+        first,
+        Metric {
+            ts: 1,
+            min: 0.5,
+            max: 1.5
+        }
+    );
+    assert_eq!(input.len(), 24);
+
+    let err = super::deserialize_fixed_row::<Metric>(&mut &input[..23], 24).unwrap_err();
+    assert!(matches!(err, crate::error::Error::NotEnoughData), "{err}");
+
+    let second: Metric = super::deserialize_fixed_row(&mut input, 24).unwrap();
+    assert_eq!(second.ts, 2);
+    assert!(input.is_empty());
+
+    // The row is read differently than expected.
+    let err = super::deserialize_fixed_row::<Metric>(&mut &serialized[..], 32).unwrap_err();
+    assert!(
+        matches!(err, crate::error::Error::SchemaMismatch(_)),
+        "{err}"
+    );
+}
+
+#[test]
+fn it_ser_des_geo_types() {
+    use crate::types::{LineString, MultiLineString, MultiPolygon, Point, Polygon, Ring};
+
+    #[derive(Debug, PartialEq, crate::Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Shapes {
+        point: Point,
+        ring: Ring,
+        polygon: Polygon,
+        multi_polygon: MultiPolygon,
+        line: LineString,
+        multi_line: MultiLineString,
+        points: Vec<Point>,
+    }
+
+    let ring = Ring(vec![
+        Point::new(0., 0.),
+        Point::new(1., 0.),
+        Point::new(0., 1.),
+    ]);
+    let line = LineString(ring.0.clone());
+    let row = Shapes {
+        point: Point::new(1.5, -2.),
+        ring: ring.clone(),
+        polygon: Polygon(vec![ring.clone(), ring.clone()]),
+        multi_polygon: MultiPolygon(vec![Polygon(vec![ring]), Polygon::default()]),
+        line: line.clone(),
+        multi_line: MultiLineString(vec![line]),
+        points: vec![Point::new(3., 4.)],
+    };
+
+    // Geo types are encoded the same way as tuples and vectors.
+    #[derive(crate::Row, Serialize)]
+    #[clickhouse(crate = "crate")]
+    struct RawShapes {
+        point: (f64, f64),
+        ring: Vec<(f64, f64)>,
+        polygon: Vec<Vec<(f64, f64)>>,
+        multi_polygon: Vec<Vec<Vec<(f64, f64)>>>,
+        line: Vec<(f64, f64)>,
+        multi_line: Vec<Vec<(f64, f64)>>,
+        points: Vec<(f64, f64)>,
+    }
+
+    let raw_ring = vec![(0., 0.), (1., 0.), (0., 1.)];
+    let raw = RawShapes {
+        point: (1.5, -2.),
+        ring: raw_ring.clone(),
+        polygon: vec![raw_ring.clone(); 2],
+        multi_polygon: vec![vec![raw_ring.clone()], vec![]],
+        line: raw_ring.clone(),
+        multi_line: vec![raw_ring],
+        points: vec![(3., 4.)],
+    };
+    let mut expected = Vec::new();
+    super::serialize_row_binary(&mut expected, &raw).unwrap();
+
+    let column = |name: &str, data_type| Column::new(name.to_string(), data_type);
+    let mut columns = vec![
+        column("point", DataTypeNode::Point),
+        column("ring", DataTypeNode::Ring),
+        column("polygon", DataTypeNode::Polygon),
+        column("multi_polygon", DataTypeNode::MultiPolygon),
+        column("line", DataTypeNode::LineString),
+        column("multi_line", DataTypeNode::MultiLineString),
+        column("points", DataTypeNode::Array(Box::new(DataTypeNode::Point))),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Shapes>(columns.clone()).unwrap();
+
+    let mut actual = Vec::new();
+    super::serialize_with_validation(&mut actual, &row, &metadata).unwrap();
+    assert_eq!(actual, expected);
+
+    let actual: Shapes = super::deserialize_row(&mut &expected[..], Some(&metadata)).unwrap();
+    assert_eq!(actual, row);
+    let actual: Shapes = super::deserialize_row(&mut &expected[..], None).unwrap();
+    assert_eq!(actual, row);
+
+    // A mismatch is reported for the whole value.
+    columns[1] = column("ring", DataTypeNode::LineString);
+    let metadata = RowMetadata::new_for_cursor::<Shapes>(columns.clone()).unwrap();
+    let err = super::deserialize_row::<Shapes>(&mut &expected[..], Some(&metadata)).unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "column Shapes.ring: attempting to (de)serialize ClickHouse type LineString \
+             as clickhouse::types::Ring"
+        ),
+        "{err}"
+    );
+
+    columns[1] = column("ring", DataTypeNode::Ring);
+    columns[6] = column("points", DataTypeNode::Array(Box::new(DataTypeNode::Ring)));
+    let metadata = RowMetadata::new_for_cursor::<Shapes>(columns).unwrap();
+    let err = super::serialize_with_validation(&mut Vec::new(), &row, &metadata).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("nested ClickHouse type Ring as clickhouse::types::Point"),
+        "{err}"
+    );
+}
+
+#[test]
+fn it_ser_des_map_entries() {
+    #[derive(Debug, PartialEq, crate::Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Event<'a> {
+        #[serde(borrow, with = "crate::serde::map::entries")]
+        labels: Vec<(&'a str, &'a str)>,
+        #[serde(with = "crate::serde::map::entries")]
+        counters: Vec<(u8, u32)>,
+    }
+
+    let event = Event {
+        labels: vec![("b", "1"), ("a", "2"), ("b", "3")],
+        counters: vec![(2, 20), (1, 10), (2, 30)],
+    };
+
+    let columns = vec![
+        Column::new(
+            "labels".into(),
+            DataTypeNode::Map([
+                Box::new(DataTypeNode::String),
+                Box::new(DataTypeNode::String),
+            ]),
+        ),
+        Column::new(
+            "counters".into(),
+            DataTypeNode::Map([
+                Box::new(DataTypeNode::UInt8),
+                Box::new(DataTypeNode::UInt32),
+            ]),
+        ),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Event<'_>>(columns).unwrap();
+
+    let mut serialized = Vec::new();
+    super::serialize_with_validation(&mut serialized, &event, &metadata).unwrap();
+
+    let mut expected = vec![3];
+    for (key, value) in [("b", "1"), ("a", "2"), ("b", "3")] {
+        expected.extend([1, key.as_bytes()[0], 1, value.as_bytes()[0]]);
+    }
+    expected.push(3);
+    for (key, value) in [(2u8, 20u32), (1, 10), (2, 30)] {
+        expected.push(key);
+        expected.extend(value.to_le_bytes());
+    }
+    assert_eq!(serialized, expected);
+
+    // The order and duplicate keys are preserved.
+    let actual: Event<'_> = super::deserialize_row(&mut &serialized[..], Some(&metadata)).unwrap();
+    assert_eq!(actual, event);
+    let actual: Event<'_> = super::deserialize_row(&mut &serialized[..], None).unwrap();
+    assert_eq!(actual, event);
+
+    // Other formats see a map.
+    assert_eq!(
+        serde_json::to_string(&event).unwrap(),
+        r#"{"labels":{"b":"1","a":"2","b":"3"},"counters":{"2":20,"1":10,"2":30}}"#
+    );
+
+    // A mismatched value type is reported.
+    let columns = vec![
+        Column::new(
+            "labels".into(),
+            DataTypeNode::Map([
+                Box::new(DataTypeNode::String),
+                Box::new(DataTypeNode::UInt8),
+            ]),
+        ),
+        Column::new(
+            "counters".into(),
+            DataTypeNode::Map([
+                Box::new(DataTypeNode::UInt8),
+                Box::new(DataTypeNode::UInt32),
+            ]),
+        ),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Event<'_>>(columns).unwrap();
+    let err =
+        super::deserialize_row::<Event<'_>>(&mut &serialized[..], Some(&metadata)).unwrap_err();
+    assert!(
+        matches!(err, crate::error::Error::SchemaMismatch(_)),
+        "{err}"
+    );
+}
+
+#[test]
+fn it_validates_nested_nullable() {
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Nullables {
+        array: Vec<Option<u32>>,
+        map: HashMap<String, Option<u32>>,
+    }
+
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Plain {
+        array: Vec<u32>,
+        map: HashMap<String, u32>,
+    }
+
+    let nullable = || Box::new(DataTypeNode::Nullable(Box::new(DataTypeNode::UInt32)));
+    let columns = vec![
+        Column::new("array".into(), DataTypeNode::Array(nullable())),
+        Column::new(
+            "map".into(),
+            DataTypeNode::Map([Box::new(DataTypeNode::String), nullable()]),
+        ),
+    ];
+
+    let row = Nullables {
+        array: vec![Some(1), None],
+        map: HashMap::from([("a".into(), None)]),
+    };
+    let metadata = RowMetadata::new_for_cursor::<Nullables>(columns.clone()).unwrap();
+    let mut serialized = Vec::new();
+    super::serialize_with_validation(&mut serialized, &row, &metadata).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        2, 0, 1, 0, 0, 0, 1, // [1, NULL]
+        1, 1, b'a', 1,       // {'a': NULL}
+    ];
+    assert_eq!(serialized, expected);
+
+    let actual: Nullables = super::deserialize_row(&mut &serialized[..], Some(&metadata)).unwrap();
+    assert_eq!(actual, row);
+
+    // The missing `Option` is reported instead of writing corrupted data.
+    let metadata = RowMetadata::new_for_cursor::<Plain>(columns).unwrap();
+    for row in [
+        Plain {
+            array: vec![1],
+            map: HashMap::new(),
+        },
+        Plain {
+            array: vec![],
+            map: HashMap::from([("a".into(), 1)]),
+        },
+    ] {
+        let err = super::serialize_with_validation(&mut Vec::new(), &row, &metadata).unwrap_err();
+        let crate::error::Error::SchemaMismatch(msg) = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert!(msg.contains("Nullable(UInt32)"), "{msg}");
+        assert!(msg.contains("use Option<T>"), "{msg}");
+    }
+}
+
+#[test]
+fn it_maps_enums_by_names() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Debug,
+        Info,
+        Error,
+    }
+
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Event {
+        #[serde(with = "crate::serde::enum_as_string")]
+        level8: Level,
+        #[serde(with = "crate::serde::enum_as_string")]
+        level16: Level,
+        #[serde(with = "crate::serde::enum_as_string::option")]
+        prev_level: Option<Level>,
+    }
+
+    let columns = vec![
+        Column::new(
+            "level8".into(),
+            DataTypeNode::new("Enum8('debug' = -1, 'info' = 5, 'error' = 7)").unwrap(),
+        ),
+        Column::new(
+            "level16".into(),
+            DataTypeNode::new("Enum16('error' = 1000, 'info' = 1)").unwrap(),
+        ),
+        Column::new(
+            "prev_level".into(),
+            DataTypeNode::new("Nullable(Enum8('debug' = 1, 'info' = 2))").unwrap(),
+        ),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Event>(columns).unwrap();
+
+    for (row, expected) in [
+        (
+            Event {
+                level8: Level::Debug,
+                level16: Level::Error,
+                prev_level: Some(Level::Info),
+            },
+            &[0xff, 0xe8, 0x03, 0, 2][..],
+        ),
+        (
+            Event {
+                level8: Level::Error,
+                level16: Level::Info,
+                prev_level: None,
+            },
+            &[7, 1, 0, 1][..],
+        ),
+    ] {
+        let mut serialized = Vec::new();
+        super::serialize_with_validation(&mut serialized, &row, &metadata).unwrap();
+        assert_eq!(serialized, expected);
+
+        let actual: Event = super::deserialize_row(&mut &serialized[..], Some(&metadata)).unwrap();
+        assert_eq!(actual, row);
+    }
+
+    // Other serializers still use names of variants.
+    let json = serde_json::to_value(Event {
+        level8: Level::Info,
+        level16: Level::Info,
+        prev_level: None,
+    })
+    .unwrap();
+    assert_eq!(json["level8"], "info");
+    let from_json: Event = serde_json::from_value(json).unwrap();
+    assert_eq!(from_json.level8, Level::Info);
+
+    // A variant that is missing in the schema.
+    let row = Event {
+        level8: Level::Info,
+        level16: Level::Debug,
+        prev_level: None,
+    };
+    let err = super::serialize_with_validation(&mut Vec::new(), &row, &metadata).unwrap_err();
+    assert!(
+        matches!(&err, crate::error::Error::SchemaMismatch(msg) if msg.contains("Level::debug")),
+        "{err}"
+    );
+
+    // A value that is missing in the schema.
+    let err = super::deserialize_row::<Event>(&mut &[3, 1, 0, 1][..], Some(&metadata)).unwrap_err();
+    assert!(
+        matches!(&err, crate::error::Error::SchemaMismatch(msg) if msg.contains("value 3")),
+        "{err}"
+    );
+
+    // Values are unknown without validation.
+    let err = super::deserialize_row::<Event>(&mut &[7, 1, 0, 1][..], None).unwrap_err();
+    assert!(matches!(err, crate::error::Error::Unsupported(_)), "{err}");
+}
+
+#[test]
+fn it_interns_low_cardinality_strings() {
+    use std::sync::Arc;
+
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Hit {
+        #[serde(with = "crate::serde::lc_interned")]
+        country: Arc<str>,
+        #[serde(with = "crate::serde::lc_interned::option")]
+        city: Option<Arc<str>>,
+    }
+
+    let columns = vec![
+        Column::new(
+            "country".into(),
+            DataTypeNode::new("LowCardinality(String)").unwrap(),
+        ),
+        Column::new(
+            "city".into(),
+            DataTypeNode::new("LowCardinality(Nullable(String))").unwrap(),
+        ),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Hit>(columns).unwrap();
+
+    let rows = [
+        Hit {
+            country: "NL".into(),
+            city: Some("Amsterdam".into()),
+        },
+        Hit {
+            country: "NL".into(),
+            city: None,
+        },
+        Hit {
+            country: "NL".into(),
+            city: Some("Amsterdam".into()),
+        },
+    ];
+
+    let mut serialized = Vec::new();
+    for row in &rows {
+        super::serialize_with_validation(&mut serialized, row, &metadata).unwrap();
+    }
+    assert_eq!(&serialized[..14], b"\x02NL\x00\x09Amsterdam");
+
+    let mut input = &serialized[..];
+    let actual: Vec<Hit> = (0..rows.len())
+        .map(|_| super::deserialize_row(&mut input, Some(&metadata)).unwrap())
+        .collect();
+    assert!(input.is_empty());
+    assert_eq!(actual, rows);
+
+    // Equal strings are shared by rows of the same cursor.
+    assert!(Arc::ptr_eq(&actual[0].country, &actual[1].country));
+    assert!(Arc::ptr_eq(
+        actual[0].city.as_ref().unwrap(),
+        actual[2].city.as_ref().unwrap()
+    ));
+
+    // Without validation, strings are read, but not interned.
+    let mut input = &serialized[..];
+    let first: Hit = super::deserialize_row(&mut input, None).unwrap();
+    let second: Hit = super::deserialize_row(&mut input, None).unwrap();
+    assert_eq!(first.country, second.country);
+    assert!(!Arc::ptr_eq(&first.country, &second.country));
+
+    // Other formats are supported as well.
+    let json = serde_json::to_string(&rows[1]).unwrap();
+    assert_eq!(json, r#"{"country":"NL","city":null}"#);
+    assert_eq!(serde_json::from_str::<Hit>(&json).unwrap(), rows[1]);
+}
+
+#[test]
+fn it_deserializes_bytes_without_copying() {
+    use bytes::Bytes;
+
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Log {
+        #[serde(with = "crate::serde::bytes_zero_copy")]
+        message: Bytes,
+        #[serde(with = "crate::serde::bytes_zero_copy::option")]
+        trace: Option<Bytes>,
+    }
+
+    let columns = vec![
+        Column::new("message".into(), DataTypeNode::String),
+        Column::new(
+            "trace".into(),
+            DataTypeNode::new("Nullable(String)").unwrap(),
+        ),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Log>(columns).unwrap();
+
+    let row = Log {
+        message: Bytes::from_static(b"hello"),
+        trace: Some(Bytes::from_static(b"world")),
+    };
+    let mut serialized = Vec::new();
+    super::serialize_with_validation(&mut serialized, &row, &metadata).unwrap();
+    assert_eq!(serialized, b"\x05hello\x00\x05world");
+
+    let source = Bytes::from(serialized);
+    let source_range = source.as_ptr_range();
+    for metadata in [Some(&metadata), None] {
+        let mut input = &source[..];
+        let actual: Log =
+            super::deserialize_row_from(&mut input, metadata, Some(&source), None).unwrap();
+        assert!(input.is_empty());
+        assert_eq!(actual, row);
+
+        // Values point into the source buffer.
+        assert!(source_range.contains(&actual.message.as_ptr()));
+        assert!(source_range.contains(&actual.trace.as_ref().unwrap().as_ptr()));
+    }
+
+    // Values are copied if the source is unknown.
+    let actual: Log = super::deserialize_row(&mut &source[..], Some(&metadata)).unwrap();
+    assert_eq!(actual, row);
+    assert!(!source_range.contains(&actual.message.as_ptr()));
+
+    // Only strings are allowed.
+    let columns = vec![
+        Column::new("message".into(), DataTypeNode::UInt64),
+        Column::new(
+            "trace".into(),
+            DataTypeNode::new("Nullable(String)").unwrap(),
+        ),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Log>(columns).unwrap();
+    let err = super::deserialize_row::<Log>(&mut &source[..], Some(&metadata)).unwrap_err();
+    assert!(
+        matches!(err, crate::error::Error::SchemaMismatch(_)),
+        "{err}"
+    );
+}
+
+#[test]
+fn it_skips_extra_columns() {
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct User {
+        id: u64,
+        email: String,
+    }
+
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Reordered {
+        email: String,
+        id: u64,
+    }
+
+    let column = |name: &str, data_type| Column::new(name.to_string(), data_type);
+    let columns = vec![
+        column("created", DataTypeNode::DateTime(None)),
+        column("id", DataTypeNode::UInt64),
+        column(
+            "tags",
+            DataTypeNode::Array(Box::new(DataTypeNode::Nullable(Box::new(
+                DataTypeNode::String,
+            )))),
+        ),
+        column("email", DataTypeNode::String),
+        column("score", DataTypeNode::Float64),
+    ];
+
+    let mut serialized = Vec::new();
+    for (id, email) in [(1u64, "a@example.com"), (2, "b@example.com")] {
+        serialized.extend(1_700_000_000u32.to_le_bytes());
+        serialized.extend(id.to_le_bytes());
+        serialized.extend([2, 0, 1, b'x', 1]); // ['x', NULL]
+        serialized.push(email.len() as u8);
+        serialized.extend(email.as_bytes());
+        serialized.extend(0.5f64.to_le_bytes());
+    }
+
+    let Err(err) = RowMetadata::new_for_cursor::<User>(columns.clone()) else {
+        panic!("extra columns must be rejected by default");
+    };
+    assert!(err.to_string().contains("has 5 columns"), "{err}");
+
+    let metadata = RowMetadata::new_for_cursor_with::<User>(columns.clone(), true).unwrap();
+    assert_eq!(metadata.fixed_row_size, None);
+    let mut input = &serialized[..];
+    let first: User = super::deserialize_row(&mut input, Some(&metadata)).unwrap();
+    let second: User = super::deserialize_row(&mut input, Some(&metadata)).unwrap();
+    assert!(input.is_empty());
+    assert_eq!(
+        [first, second],
+        [
+            User {
+                id: 1,
+                email: "a@example.com".into()
+            },
+            User {
+                id: 2,
+                email: "b@example.com".into()
+            },
+        ]
+    );
+
+    let metadata = RowMetadata::new_for_cursor_with::<Reordered>(columns.clone(), true).unwrap();
+    let mut input = &serialized[..];
+    let first: Reordered = super::deserialize_row(&mut input, Some(&metadata)).unwrap();
+    assert_eq!(first.id, 1);
+    assert_eq!(first.email, "a@example.com");
+    assert_eq!(input.len(), serialized.len() / 2);
+
+    // Fields still must have columns.
+    let mut other = columns.clone();
+    other[3] = column("mail", DataTypeNode::String);
+    let Err(err) = RowMetadata::new_for_cursor_with::<User>(other, true) else {
+        panic!("fields without columns must be rejected");
+    };
+    assert!(err.to_string().contains("- email"), "{err}");
+
+    // Columns of unknown size cannot be skipped.
+    let mut other = columns;
+    other[0] = column("created", DataTypeNode::Dynamic);
+    let metadata = RowMetadata::new_for_cursor_with::<User>(other, true).unwrap();
+    let err = super::deserialize_row::<User>(&mut &serialized[..], Some(&metadata)).unwrap_err();
+    assert!(matches!(err, crate::error::Error::Unsupported(_)), "{err}");
+}
+
+#[test]
+fn it_fills_defaults_for_absent_columns() {
+    fn default_score() -> f64 {
+        1.5
+    }
+
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct User {
+        id: u64,
+        #[serde(default)]
+        tags: Vec<String>,
+        email: String,
+        #[serde(default = "default_score")]
+        score: f64,
+    }
+
+    let column = |name: &str, data_type| Column::new(name.to_string(), data_type);
+    let columns = vec![
+        column("id", DataTypeNode::UInt64),
+        column("email", DataTypeNode::String),
+    ];
+
+    let mut serialized = Vec::new();
+    serialized.extend(42u64.to_le_bytes());
+    serialized.extend([3, b'a', b'@', b'b']);
+
+    let metadata = RowMetadata::new_for_cursor::<User>(columns.clone()).unwrap();
+    assert_eq!(metadata.fixed_row_size, None);
+    let mut input = &serialized[..];
+    let user: User = super::deserialize_row(&mut input, Some(&metadata)).unwrap();
+    assert!(input.is_empty());
+    assert_eq!(
+        user,
+        User {
+            id: 42,
+            tags: vec![],
+            email: "a@b".into(),
+            score: 1.5,
+        }
+    );
+
+    // Fields without defaults still must have columns.
+    let Err(err) = RowMetadata::new_for_cursor::<User>(columns[..1].to_vec()) else {
+        panic!("fields without defaults must be rejected");
+    };
+    assert!(err.to_string().contains("- email"), "{err}");
+}
+
+#[test]
+fn it_rejects_corrupt_lengths_in_strict_mode() {
+    use crate::error::Error;
+    use crate::rowbinary::DecodeLimits;
+
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Sample {
+        id: u32,
+        name: String,
+        tags: Vec<u8>,
+    }
+
+    let columns = vec![
+        Column::new("id".to_string(), DataTypeNode::UInt32),
+        Column::new("name".to_string(), DataTypeNode::String),
+        Column::new(
+            "tags".to_string(),
+            DataTypeNode::Array(Box::new(DataTypeNode::UInt8)),
+        ),
+    ];
+    let metadata = RowMetadata::new_for_cursor::<Sample>(columns).unwrap();
+    let limits = DecodeLimits::new()
+        .with_max_string_size(4)
+        .with_max_array_size(2);
+
+    // The same result with and without validation.
+    let decode = |input: &[u8], limits| {
+        let validated =
+            super::deserialize_row_from::<Sample>(&mut &input[..], Some(&metadata), None, limits);
+        let plain = super::deserialize_row_from::<Sample>(&mut &input[..], None, None, limits);
+        assert_eq!(format!("{validated:?}"), format!("{plain:?}"));
+        validated
+    };
+
+    let valid = b"\x01\x00\x00\x00\x03foo\x02\x07\x08";
+    let row = decode(valid, Some(limits)).unwrap();
+    assert_eq!(
+        row,
+        Sample {
+            id: 1,
+            name: "foo".into(),
+            tags: vec![7, 8],
+        }
+    );
+
+    // Too long values.
+    let err = decode(b"\x01\x00\x00\x00\x05hello\x00", Some(limits)).unwrap_err();
+    assert!(
+        matches!(err, Error::CorruptData { offset: 4, ref reason } if reason.contains("string length 5")),
+        "{err}"
+    );
+    let err = decode(b"\x01\x00\x00\x00\x00\x03\x01\x02\x03", Some(limits)).unwrap_err();
+    assert!(
+        matches!(err, Error::CorruptData { offset: 5, ref reason } if reason.contains("array length 3")),
+        "{err}"
+    );
+
+    // A huge length, which is only waited for without the strict mode.
+    let garbage = b"\x01\x00\x00\x00\xff\xff\xff\xff\x0f";
+    let err = decode(garbage, None).unwrap_err();
+    assert!(matches!(err, Error::NotEnoughData), "{err}");
+    let err = decode(garbage, Some(DecodeLimits::new())).unwrap_err();
+    assert!(matches!(err, Error::CorruptData { offset: 4, .. }), "{err}");
+
+    // An overflowing length.
+    let err = decode(
+        b"\x01\x00\x00\x00\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01",
+        Some(limits),
+    )
+    .unwrap_err();
+    assert!(
+        matches!(err, Error::CorruptData { offset: 4, ref reason } if reason.contains("overflows")),
+        "{err}"
+    );
+
+    // A truncated row is still incomplete.
+    let err = decode(&valid[..6], Some(limits)).unwrap_err();
+    assert!(matches!(err, Error::NotEnoughData), "{err}");
+    let err = decode(&valid[..4], Some(limits)).unwrap_err();
+    assert!(matches!(err, Error::NotEnoughData), "{err}");
+}
+
+#[test]
+fn it_encodes_and_decodes_buffers() {
+    use crate::error::Error;
+    use crate::rowbinary::{decode_rows, encode_rows};
+
+    #[derive(Debug, PartialEq, crate::Row, Serialize, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Event {
+        id: u64,
+        name: String,
+    }
+
+    let column = |name: &str, data_type| Column::new(name.to_string(), data_type);
+    let columns = vec![
+        column("name", DataTypeNode::String),
+        column("created", DataTypeNode::DateTime(None)),
+        column("id", DataTypeNode::UInt64),
+    ];
+
+    let events = vec![
+        Event {
+            id: 1,
+            name: "foo".into(),
+        },
+        Event {
+            id: 2,
+            name: "bar".into(),
+        },
+    ];
+
+    let buffer = encode_rows(&events, &columns).unwrap();
+
+    // The header lists columns of fields in order of fields.
+    let mut header = Vec::new();
+    clickhouse_types::put_rbwnat_columns_header(
+        &[columns[2].clone(), columns[0].clone()],
+        &mut header,
+    )
+    .unwrap();
+    assert!(buffer.starts_with(&header));
+    assert_eq!(&buffer[header.len()..][..9], &[1, 0, 0, 0, 0, 0, 0, 0, 3]);
+
+    assert_eq!(decode_rows::<Event>(&buffer).unwrap(), events);
+    assert_eq!(decode_rows::<Event>(&header).unwrap(), vec![]);
+
+    let err = decode_rows::<Event>(&buffer[..buffer.len() - 1]).unwrap_err();
+    assert!(matches!(err, Error::NotEnoughData), "{err}");
+
+    // Fields still must have columns of the same types.
+    let err = encode_rows(&events, &columns[..2]).unwrap_err();
+    assert!(err.to_string().contains("no column named id"), "{err}");
+
+    let mut other = columns;
+    other[2] = column("id", DataTypeNode::UInt32);
+    let err = encode_rows(&events, &other).unwrap_err();
+    assert!(matches!(err, Error::SchemaMismatch(_)), "{err}");
+}
+
+#[test]
+fn it_deserializes_fixed_rows_with_flattened_structs() {
+    use DataTypeNode::*;
+
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Range {
+        min: f64,
+        max: f64,
+    }
+
+    #[derive(Debug, PartialEq, crate::Row, Deserialize)]
+    #[clickhouse(crate = "crate")]
+    struct Metric {
+        ts: u64,
+        #[clickhouse(flatten)]
+        range: Range,
+        point: (i32, i32),
+    }
+
+    let column = |name: &str, data_type| Column::new(name.to_string(), data_type);
+    let columns = vec![
+        column("ts", UInt64),
+        column("min", Float64),
+        column("max", Float64),
+        column("point", Tuple(vec![Int32, Int32])),
+    ];
+
+    let metadata = RowMetadata::new_for_cursor::<Metric>(columns.clone()).unwrap();
+    assert_eq!(metadata.fixed_row_size, Some(32));
+
+    // Tuples with variable-size elements are not fixed.
+    let mut other = columns.clone();
+    other[3] = column("point", Tuple(vec![Int32, String]));
+    let metadata = RowMetadata::new_for_cursor::<Metric>(other).unwrap();
+    assert_eq!(metadata.fixed_row_size, None);
+
+    let mut serialized = Vec::new();
+    for (ts, min, max, x, y) in [(1u64, 0.5f64, 1.5f64, 3i32, -4i32), (2, -1., 1., 5, 6)] {
+        serialized.extend(ts.to_le_bytes());
+        serialized.extend(min.to_le_bytes());
+        serialized.extend(max.to_le_bytes());
+        serialized.extend(x.to_le_bytes());
+        serialized.extend(y.to_le_bytes());
+    }
+
+    // The first row is validated, the rest are read without validation.
+    let metadata = RowMetadata::new_for_cursor::<Metric>(columns).unwrap();
+    let mut input = &serialized[..];
+    let first: Metric = super::deserialize_row(&mut input, Some(&metadata)).unwrap();
+    let second: Metric = super::deserialize_fixed_row(&mut input, 32).unwrap();
+    assert!(input.is_empty());
+
+    assert_eq!(
+        [first, second],
+        [
+            Metric {
+                ts: 1,
+                range: Range { min: 0.5, max: 1.5 },
+                point: (3, -4),
+            },
+            Metric {
+                ts: 2,
+                range: Range { min: -1., max: 1. },
+                point: (5, 6),
+            },
+        ]
+    );
+}
@@ -1,7 +1,12 @@
 use crate::error::{Error, Result};
+use crate::rowbinary::skip::skip_value;
 use crate::types::bf16;
 use crate::types::int256;
-use crate::{Row, row::RowKind, row_metadata::RowMetadata};
+use crate::{
+    Row,
+    row::RowKind,
+    row_metadata::{RowMetadata, StringTable},
+};
 use clickhouse_types::data_types::{Column, DataTypeNode, DecimalType, EnumType};
 use std::collections::HashMap;
 use std::fmt::Display;
@@ -52,6 +57,40 @@ pub(crate) trait SchemaValidator<R: Row>: Sized {
     fn null_encoding(&self) -> Option<NullEncoding> {
         None
     }
+    /// Returns the timezone of the just validated `DateTime` or `DateTime64`
+    /// column requested by a timezone-aware serde helper, if it's explicit.
+    fn timezone(&self) -> Option<&str> {
+        None
+    }
+    /// Returns the type and values of the just validated `Enum8` or `Enum16`
+    /// column requested by [`crate::serde::enum_as_string`].
+    fn enum_values(&self) -> Option<(&EnumType, &HashMap<i16, String>)> {
+        None
+    }
+    /// Returns the cursor-level table of strings requested by [`crate::serde::lc_interned`].
+    fn string_table(&self) -> Option<&StringTable> {
+        None
+    }
+    /// Returns the number of struct fields read by `MapAccess`, which is less than
+    /// `fields` if columns of some `#[serde(default)]` fields are absent.
+    #[inline(always)]
+    fn mapped_field_count(&self, fields: usize) -> usize {
+        fields
+    }
+    /// Skips values of the following columns not mapped to any struct field,
+    /// see [`crate::query::Query::allow_extra_columns`].
+    #[inline(always)]
+    fn skip_extra_columns(&mut self, _input: &mut &[u8]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Returned if [`crate::serde::enum_as_string`] is used without validation,
+/// because enum values are unknown then.
+pub(crate) fn enum_as_string_without_validation() -> Error {
+    Error::Unsupported(
+        "`serde::enum_as_string` requires validation, see `Client::with_validation`".into(),
+    )
 }
 
 pub(crate) struct DataTypeValidator<'caller, R: Row> {
@@ -72,8 +111,7 @@ impl<'caller, R: Row> DataTypeValidator<'caller, R> {
     fn get_current_column(&self) -> Result<Option<&Column>> {
         if self.current_column_idx > 0 && self.current_column_idx <= self.metadata.columns.len() {
             // index is immediately moved to the next column after the root validator is called
-            let schema_index = self.get_schema_index(self.current_column_idx - 1)?;
-            Ok(Some(&self.metadata.columns[schema_index]))
+            Ok(Some(&self.metadata.columns[self.current_column_idx - 1]))
         } else {
             Ok(None)
         }
@@ -93,33 +131,42 @@ impl<'caller, R: Row> DataTypeValidator<'caller, R> {
         serde_type: &SerdeType,
         is_inner: bool,
     ) -> Result<Option<InnerDataTypeValidator<'serde, 'caller, R>>> {
+        // Nullability mismatches are easy to miss in nested types,
+        // e.g. `Vec<u32>` instead of `Vec<Option<u32>>` for `Array(Nullable(UInt32))`.
+        let hint = if matches!(data_type, DataTypeNode::Nullable(_)) {
+            "; use Option<T> for Nullable types"
+        } else {
+            ""
+        };
+
         match R::KIND {
             RowKind::Primitive => Err(Error::SchemaMismatch(format!(
                 "While processing row as a primitive: attempting to (de)serialize \
-                 ClickHouse type {data_type} as {serde_type} which is not compatible"
+                 ClickHouse type {data_type} as {serde_type} which is not compatible{hint}"
             ))),
             RowKind::Vec => Err(Error::SchemaMismatch(format!(
                 "While processing row as a vector: attempting to (de)serialize \
-                 ClickHouse type {data_type} as {serde_type} which is not compatible"
+                 ClickHouse type {data_type} as {serde_type} which is not compatible{hint}"
             ))),
             RowKind::Tuple => Err(Error::SchemaMismatch(format!(
                 "While processing row as a tuple: attempting to (de)serialize \
-                 ClickHouse type {data_type} as {serde_type} which is not compatible"
+                 ClickHouse type {data_type} as {serde_type} which is not compatible{hint}"
             ))),
             RowKind::Struct => {
                 if is_inner {
                     let (full_name, full_data_type) = self.get_current_column_name_and_type()?;
                     Err(Error::SchemaMismatch(format!(
                         "While processing column {full_name} defined as {full_data_type}: attempting to (de)serialize \
-                        nested ClickHouse type {data_type} as {serde_type} which is not compatible"
+                        nested ClickHouse type {data_type} as {serde_type} which is not compatible{hint}"
                     )))
                 } else {
                     Err(Error::SchemaMismatch(format!(
                         "While processing column {}: attempting to (de)serialize \
-                        ClickHouse type {} as {} which is not compatible",
+                        ClickHouse type {} as {} which is not compatible{}",
                         self.get_current_column_name_and_type()?.0,
                         data_type,
-                        serde_type
+                        serde_type,
+                        hint,
                     )))
                 }
             }
@@ -179,6 +226,22 @@ impl<'caller, R: Row> SchemaValidator<R> for DataTypeValidator<'caller, R> {
             }
             // `fetch::<T>` for a "struct row" type, which is supposed to be the default flow
             RowKind::Struct => {
+                // `#[clickhouse(nested)]` fields span several consecutive columns,
+                // each of them is validated as an element of a "root tuple".
+                if let SerdeType::Nested(len) = serde_type {
+                    let start = self.current_column_idx;
+                    let Some(columns) = self.metadata.columns.get(start..start + len) else {
+                        return Err(Error::SchemaMismatch(format!(
+                            "Struct {} has more fields than columns in the database schema",
+                            R::NAME
+                        )));
+                    };
+                    self.current_column_idx += len;
+                    return Ok(Some(InnerDataTypeValidator {
+                        root: self,
+                        kind: InnerDataTypeValidatorKind::RootTuple(columns, 0),
+                    }));
+                }
                 if self.current_column_idx < self.metadata.columns.len() {
                     let current_column = &self.metadata.columns[self.current_column_idx];
                     self.current_column_idx += 1;
@@ -213,6 +276,26 @@ impl<'caller, R: Row> SchemaValidator<R> for DataTypeValidator<'caller, R> {
         unreachable!()
     }
 
+    fn string_table(&self) -> Option<&StringTable> {
+        Some(&self.metadata.strings)
+    }
+
+    #[inline]
+    fn mapped_field_count(&self, fields: usize) -> usize {
+        self.metadata.mapped_field_count(fields)
+    }
+
+    #[inline]
+    fn skip_extra_columns(&mut self, input: &mut &[u8]) -> Result<()> {
+        let metadata = self.metadata;
+        while metadata.extra_columns.get(self.current_column_idx) == Some(&true) {
+            let data_type = &metadata.columns[self.current_column_idx].data_type;
+            skip_value(input, data_type)?;
+            self.current_column_idx += 1;
+        }
+        Ok(())
+    }
+
     fn null_encoding(&self) -> Option<NullEncoding> {
         if self.current_column_idx >= self.metadata.columns.len() {
             return None;
@@ -221,18 +304,29 @@ impl<'caller, R: Row> SchemaValidator<R> for DataTypeValidator<'caller, R> {
     }
 }
 
+/// Strips `LowCardinality(T)` and `SimpleAggregateFunction(_, T)` wrappers,
+/// since those are encoded identically to the inner `T`.
+///
+/// Wrappers can be combined in any order, e.g.
+/// `SimpleAggregateFunction(any, LowCardinality(Nullable(String)))`.
+fn remove_transparent_wrappers(mut node: &DataTypeNode) -> &DataTypeNode {
+    loop {
+        match node {
+            DataTypeNode::LowCardinality(inner)
+            | DataTypeNode::SimpleAggregateFunction(_, inner) => node = inner,
+            _ => return node,
+        }
+    }
+}
+
 /// Returns the wire-level null encoding of the given type, transparently
-/// stripping `LowCardinality(T)` and `SimpleAggregateFunction(_, T)` since
-/// those are encoded identically to the inner `T`.
+/// stripping `LowCardinality(T)` and `SimpleAggregateFunction(_, T)`.
 ///
 /// This must stay aligned with the same wrapper stripping in `validate_impl`.
 /// If the two drift, `deserialize_option` and `validate(SerdeType::Option)`
 /// will disagree on the NULL marker length and the input stream goes out of sync.
 fn null_encoding_for(node: &DataTypeNode) -> Option<NullEncoding> {
-    let node = node
-        .remove_low_cardinality()
-        .remove_simple_aggregate_function();
-    match node {
+    match remove_transparent_wrappers(node) {
         DataTypeNode::Nullable(_) => Some(NullEncoding::Nullable),
         DataTypeNode::Variant(_) => Some(NullEncoding::Discriminator),
         _ => None,
@@ -281,6 +375,11 @@ pub(crate) enum InnerDataTypeValidatorKind<'caller> {
     Enum(&'caller HashMap<i16, String>),
     Variant(&'caller [DataTypeNode], VariantValidationState),
     Nullable(&'caller DataTypeNode),
+    /// The explicit timezone of a `DateTime` or `DateTime64` column,
+    /// see [`SchemaValidator::timezone`].
+    Timezone(&'caller str),
+    /// An enum (de)serialized by variant names, see [`SchemaValidator::enum_values`].
+    EnumAsString(&'caller EnumType, &'caller HashMap<i16, String>),
 }
 
 #[derive(Debug)]
@@ -412,6 +511,34 @@ impl<'caller, R: Row> SchemaValidator<R> for Option<InnerDataTypeValidator<'_, '
             InnerDataTypeValidatorKind::Enum(_values_map) => {
                 unreachable!()
             }
+            InnerDataTypeValidatorKind::Timezone(_)
+            | InnerDataTypeValidatorKind::EnumAsString(..) => {
+                unreachable!()
+            }
+        }
+    }
+
+    fn enum_values(&self) -> Option<(&EnumType, &HashMap<i16, String>)> {
+        match self {
+            Some(InnerDataTypeValidator {
+                kind: InnerDataTypeValidatorKind::EnumAsString(enum_type, values_map),
+                ..
+            }) => Some((enum_type, values_map)),
+            _ => None,
+        }
+    }
+
+    fn string_table(&self) -> Option<&StringTable> {
+        self.as_ref().map(|inner| &inner.root.metadata.strings)
+    }
+
+    fn timezone(&self) -> Option<&str> {
+        match self {
+            Some(InnerDataTypeValidator {
+                kind: InnerDataTypeValidatorKind::Timezone(tz),
+                ..
+            }) => Some(tz),
+            _ => None,
         }
     }
 
@@ -548,15 +675,48 @@ fn validate_impl<'serde, 'caller, R: Row>(
     serde_type: &SerdeType,
     is_inner: bool,
 ) -> Result<Option<InnerDataTypeValidator<'serde, 'caller, R>>> {
-    let data_type = column_data_type
-        .remove_low_cardinality()
-        .remove_simple_aggregate_function();
+    let data_type = remove_transparent_wrappers(column_data_type);
     match serde_type {
+        SerdeType::UtcDateTime | SerdeType::UtcDateTime64 => {
+            if root.metadata.strict_utc && !has_utc_timezone(data_type) {
+                return root.err_on_schema_mismatch(
+                    data_type,
+                    serde_type,
+                    is_inner || !std::ptr::eq(data_type, column_data_type),
+                );
+            }
+
+            let serde_type = match serde_type {
+                SerdeType::UtcDateTime => SerdeType::U32,
+                _ => SerdeType::I64,
+            };
+            validate_impl(root, column_data_type, &serde_type, is_inner)
+        }
+        SerdeType::TzDateTime | SerdeType::TzDateTime64 => {
+            let timestamp_type = match serde_type {
+                SerdeType::TzDateTime => SerdeType::U32,
+                _ => SerdeType::I64,
+            };
+            validate_impl(root, column_data_type, &timestamp_type, is_inner)?;
+
+            match data_type {
+                DataTypeNode::DateTime(Some(tz)) | DataTypeNode::DateTime64(_, Some(tz)) => {
+                    Ok(Some(InnerDataTypeValidator {
+                        root,
+                        kind: InnerDataTypeValidatorKind::Timezone(tz),
+                    }))
+                }
+                _ => Ok(None),
+            }
+        }
         SerdeType::Bool
             if data_type == &DataTypeNode::Bool || data_type == &DataTypeNode::UInt8 =>
         {
             Ok(None)
         }
+        // Elements of geo types are not validated, because they're fully
+        // defined by the type itself.
+        SerdeType::Geo(geo_type) if data_type == *geo_type => Ok(None),
         SerdeType::I8 => match data_type {
             DataTypeNode::Int8 => Ok(None),
             DataTypeNode::Enum(EnumType::Enum8, values_map) => Ok(Some(InnerDataTypeValidator {
@@ -565,6 +725,13 @@ fn validate_impl<'serde, 'caller, R: Row>(
             })),
             _ => root.err_on_schema_mismatch(data_type, serde_type, is_inner),
         },
+        SerdeType::EnumAsString => match data_type {
+            DataTypeNode::Enum(enum_type, values_map) => Ok(Some(InnerDataTypeValidator {
+                root,
+                kind: InnerDataTypeValidatorKind::EnumAsString(enum_type, values_map),
+            })),
+            _ => root.err_on_schema_mismatch(data_type, serde_type, is_inner),
+        },
         SerdeType::I16 => match data_type {
             DataTypeNode::Int16 => Ok(None),
             DataTypeNode::Enum(EnumType::Enum16, values_map) => Ok(Some(InnerDataTypeValidator {
@@ -758,16 +925,34 @@ fn validate_impl<'serde, 'caller, R: Row>(
         _ => root.err_on_schema_mismatch(
             data_type,
             serde_type,
-            is_inner
-                || matches!(column_data_type, DataTypeNode::LowCardinality { .. })
-                || matches!(
-                    column_data_type,
-                    DataTypeNode::SimpleAggregateFunction { .. }
-                ),
+            is_inner || !std::ptr::eq(data_type, column_data_type),
         ),
     }
 }
 
+/// Returns `false` for `DateTime` and `DateTime64` columns with a timezone
+/// other than UTC or its aliases. Columns without an explicit timezone use
+/// the server's one, which is unknown to the client, so they aren't rejected.
+fn has_utc_timezone(data_type: &DataTypeNode) -> bool {
+    const UTC_ALIASES: &[&str] = &[
+        "UTC",
+        "UCT",
+        "Universal",
+        "Zulu",
+        "Etc/UTC",
+        "Etc/UCT",
+        "Etc/Universal",
+        "Etc/Zulu",
+    ];
+
+    match data_type {
+        DataTypeNode::DateTime(Some(tz)) | DataTypeNode::DateTime64(_, Some(tz)) => {
+            UTC_ALIASES.contains(&tz.as_str())
+        }
+        _ => true,
+    }
+}
+
 impl<R: Row> SchemaValidator<R> for () {
     type Inner<'serde> = ();
 
@@ -825,6 +1010,19 @@ pub(crate) enum SerdeType {
     Tuple(usize),
     Seq(usize),
     Map(usize),
+    Nested(usize),
+    /// `DateTime` read by a serde helper assuming UTC, see [`crate::serde`].
+    UtcDateTime,
+    /// `DateTime64` read by a serde helper assuming UTC, see [`crate::serde`].
+    UtcDateTime64,
+    /// `DateTime` read by a timezone-aware serde helper, see [`crate::serde`].
+    TzDateTime,
+    /// `DateTime64` read by a timezone-aware serde helper, see [`crate::serde`].
+    TzDateTime64,
+    /// One of geo types defined in [`crate::types`], validated as a whole.
+    Geo(&'static DataTypeNode),
+    /// An enum (de)serialized by variant names, see [`crate::serde::enum_as_string`].
+    EnumAsString,
     // Identifier,
     // Char,
     // Unit,
@@ -860,6 +1058,14 @@ impl Display for SerdeType {
             SerdeType::Seq(_len) => write!(f, "Vec<T>"),
             SerdeType::Tuple(len) => write!(f, "a tuple or sequence with length {len}"),
             SerdeType::Map(_len) => write!(f, "Map<K, V>"),
+            SerdeType::Nested(len) => write!(f, "a nested structure with {len} columns"),
+            SerdeType::UtcDateTime | SerdeType::UtcDateTime64 => {
+                write!(f, "a UTC timestamp (strict UTC mode)")
+            }
+            SerdeType::TzDateTime => write!(f, "u32"),
+            SerdeType::TzDateTime64 => write!(f, "i64"),
+            SerdeType::EnumAsString => write!(f, "an enum (by variant names)"),
+            SerdeType::Geo(data_type) => write!(f, "clickhouse::types::{data_type}"),
             // SerdeType::Identifier => "identifier",
             // SerdeType::Char => "char",
             // SerdeType::Unit => "()",
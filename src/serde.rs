@@ -5,6 +5,147 @@ use serde::{
     ser::{Serialize, Serializer},
 };
 
+/// Names of newtype structs requested by helpers assuming UTC, which allows
+/// the `RowBinary` deserializer to check timezones of columns.
+/// See `Client::with_strict_utc` for details.
+pub(crate) const UTC_DATETIME: &str = "clickhouse::serde::UtcDateTime";
+pub(crate) const UTC_DATETIME64: &str = "clickhouse::serde::UtcDateTime64";
+
+/// Names of newtype structs requested by timezone-aware helpers, which makes
+/// the `RowBinary` deserializer provide the timezone of the column along with
+/// the timestamp.
+pub(crate) const TZ_DATETIME: &str = "clickhouse::serde::TzDateTime";
+pub(crate) const TZ_DATETIME64: &str = "clickhouse::serde::TzDateTime64";
+
+/// The name of a newtype struct requested by [`enum_as_string`], which makes
+/// the `RowBinary` (de)serializer map enum variants by names.
+pub(crate) const ENUM_AS_STRING: &str = "clickhouse::serde::EnumAsString";
+
+/// The name of a newtype struct requested by [`lc_interned`], which makes
+/// the `RowBinary` deserializer look up strings in the cursor's string table.
+pub(crate) const LC_INTERNED: &str = "clickhouse::serde::LcInterned";
+
+/// The name of a newtype struct requested by [`bytes_zero_copy`], which makes
+/// the `RowBinary` deserializer slice the response buffer instead of copying.
+pub(crate) const BYTES_ZERO_COPY: &str = "clickhouse::serde::BytesZeroCopy";
+
+/// A value passed from the `RowBinary` deserializer to helpers, because the
+/// serde data model cannot carry `Arc<str>` or `Bytes` to a visitor.
+pub(crate) enum Passed {
+    Str(std::sync::Arc<str>),
+    Bytes(bytes::Bytes),
+}
+
+thread_local! {
+    static PASSED: std::cell::Cell<Option<Passed>> = const { std::cell::Cell::new(None) };
+}
+
+/// Provides `value` to the visitor of [`lc_interned`] or [`bytes_zero_copy`],
+/// which must be called with [`Visitor::visit_unit`] right after.
+///
+/// [`Visitor::visit_unit`]: serde::de::Visitor::visit_unit
+pub(crate) fn pass_to_visitor(value: Passed) {
+    PASSED.set(Some(value));
+}
+
+fn take_passed<E: serde::de::Error>() -> Result<Passed, E> {
+    PASSED
+        .take()
+        .ok_or_else(|| E::custom("no value is passed by the deserializer"))
+}
+
+/// Deserializes a UNIX timestamp, either as is or wrapped into
+/// a newtype struct with the provided name.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn deserialize_utc_timestamp<'de, D, T>(deserializer: D, name: &'static str) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    use serde::de::{Error, IntoDeserializer, Visitor};
+    use std::{fmt, marker::PhantomData};
+
+    struct TimestampVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for TimestampVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a UNIX timestamp")
+        }
+
+        fn visit_newtype_struct<D: Deserializer<'de>>(self, d: D) -> Result<T, D::Error> {
+            T::deserialize(d)
+        }
+
+        fn visit_u32<E: Error>(self, v: u32) -> Result<T, E> {
+            T::deserialize(v.into_deserializer())
+        }
+
+        fn visit_i64<E: Error>(self, v: i64) -> Result<T, E> {
+            T::deserialize(v.into_deserializer())
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(name, TimestampVisitor(PhantomData))
+}
+
+/// Deserializes a UNIX timestamp along with the timezone of the column.
+///
+/// UTC is used if the timezone is unknown, i.e. the column's type has no
+/// explicit timezone (the server's one is unknown to the client), validation
+/// is disabled or the value comes from another format.
+#[cfg(all(feature = "tz", any(feature = "chrono", feature = "time")))]
+fn deserialize_tz_timestamp<'de, D, T>(
+    deserializer: D,
+    name: &'static str,
+) -> Result<(T, chrono_tz::Tz), D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    use chrono_tz::Tz;
+    use serde::de::{Error, IntoDeserializer, SeqAccess, Visitor};
+    use std::{fmt, marker::PhantomData};
+
+    struct TimestampVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for TimestampVisitor<T> {
+        type Value = (T, Tz);
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a UNIX timestamp with an optional timezone")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let ts = seq
+                .next_element()?
+                .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+            let tz = match seq.next_element::<String>()? {
+                Some(tz) => tz
+                    .parse()
+                    .map_err(|_| A::Error::custom(format!("unknown timezone: {tz}")))?,
+                None => Tz::UTC,
+            };
+            Ok((ts, tz))
+        }
+
+        fn visit_newtype_struct<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+            Ok((T::deserialize(d)?, Tz::UTC))
+        }
+
+        fn visit_u32<E: Error>(self, v: u32) -> Result<Self::Value, E> {
+            Ok((T::deserialize(v.into_deserializer())?, Tz::UTC))
+        }
+
+        fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+            Ok((T::deserialize(v.into_deserializer())?, Tz::UTC))
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(name, TimestampVisitor(PhantomData))
+}
+
 macro_rules! option {
     ($name:ident, $doc:literal) => {
         #[doc = $doc]
@@ -61,12 +202,644 @@ pub mod ipv4 {
         u32::from(*ipv4).serialize(serializer)
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Ipv4Addr, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let ip: u32 = Deserialize::deserialize(deserializer)?;
-        Ok(Ipv4Addr::from(ip))
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Ipv4Addr, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ip: u32 = Deserialize::deserialize(deserializer)?;
+        Ok(Ipv4Addr::from(ip))
+    }
+}
+
+/// Ser/de Rust enums to/from `Enum8` and `Enum16` by variant names.
+///
+/// Unlike [serde_repr](https://docs.rs/serde_repr), discriminants don't have
+/// to be kept in sync with the database schema: unit variants are matched
+/// with enum values by names, which are serde's names of variants, so
+/// `#[serde(rename)]` and `#[serde(rename_all)]` are respected.
+///
+/// It requires validation (enabled by default, see [`Client::with_validation`])
+/// to know the values of the column. Unknown variants are reported as
+/// [`Error::SchemaMismatch`].
+///
+/// # Example
+/// ```
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// #[serde(rename_all = "lowercase")]
+/// enum Level {
+///     Debug,
+///     Info,
+///     Error,
+/// }
+///
+/// #[derive(clickhouse::Row, Serialize, Deserialize)]
+/// struct Event {
+///     // Enum8('debug' = 1, 'info' = 2, 'error' = 3)
+///     #[serde(with = "clickhouse::serde::enum_as_string")]
+///     level: Level,
+///     // Nullable(Enum8('debug' = 1, 'info' = 2, 'error' = 3))
+///     #[serde(with = "clickhouse::serde::enum_as_string::option")]
+///     prev_level: Option<Level>,
+/// }
+/// ```
+///
+/// [`Client::with_validation`]: crate::Client::with_validation
+/// [`Error::SchemaMismatch`]: crate::error::Error::SchemaMismatch
+pub mod enum_as_string {
+    use super::*;
+    use serde::de::{Error, IntoDeserializer, Visitor};
+    use std::{fmt, marker::PhantomData};
+
+    /// Ser/de `Option<T>` to/from `Nullable(Enum8)` and `Nullable(Enum16)`.
+    pub mod option {
+        use super::*;
+
+        struct Wrapper<T>(T);
+
+        impl<T: Serialize> Serialize for Wrapper<&T> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                super::serialize(self.0, serializer)
+            }
+        }
+
+        impl<'de, T: Deserialize<'de>> Deserialize<'de> for Wrapper<T> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                super::deserialize(deserializer).map(Wrapper)
+            }
+        }
+
+        pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: Serialize,
+            S: Serializer,
+        {
+            value.as_ref().map(Wrapper).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            T: Deserialize<'de>,
+            D: Deserializer<'de>,
+        {
+            let opt: Option<Wrapper<T>> = Deserialize::deserialize(deserializer)?;
+            Ok(opt.map(|v| v.0))
+        }
+    }
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(ENUM_AS_STRING, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        struct VariantVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for VariantVisitor<T> {
+            type Value = T;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a name of an enum variant")
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(self, d: D) -> Result<T, D::Error> {
+                T::deserialize(d)
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<T, E> {
+                T::deserialize(v.into_deserializer())
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(ENUM_AS_STRING, VariantVisitor(PhantomData))
+    }
+}
+
+/// Ser/de `Arc<str>` to/from `LowCardinality(String)`, interning strings.
+///
+/// Values of `LowCardinality(String)` columns are sent as plain strings in
+/// `RowBinary`, so deserializing them into `String` allocates the same values
+/// for every row. With this helper, equal strings are shared by all rows of
+/// the same cursor, which saves memory and allocations in large fetches of
+/// dimension-heavy tables.
+///
+/// Strings are interned only if validation is enabled (default, see
+/// [`Client::with_validation`]), because the table of strings is attached to
+/// the schema of the cursor. Otherwise, every value is allocated separately.
+/// Also, at most 65536 distinct values are interned per cursor; others are
+/// allocated as usual, so plain `String` columns can also be used, but with
+/// less benefit.
+///
+/// # Example
+/// ```
+/// # use std::sync::Arc;
+/// # use serde::Deserialize;
+/// #[derive(clickhouse::Row, Deserialize)]
+/// struct Hit {
+///     // LowCardinality(String)
+///     #[serde(with = "clickhouse::serde::lc_interned")]
+///     country: Arc<str>,
+///     // LowCardinality(Nullable(String))
+///     #[serde(with = "clickhouse::serde::lc_interned::option")]
+///     city: Option<Arc<str>>,
+/// }
+/// ```
+///
+/// [`Client::with_validation`]: crate::Client::with_validation
+pub mod lc_interned {
+    use super::*;
+    use serde::de::{Error, Visitor};
+    use std::{fmt, sync::Arc};
+
+    /// Ser/de `Option<Arc<str>>` to/from `LowCardinality(Nullable(String))`.
+    pub mod option {
+        use super::*;
+
+        struct Wrapper(Arc<str>);
+
+        impl<'de> Deserialize<'de> for Wrapper {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                super::deserialize(deserializer).map(Wrapper)
+            }
+        }
+
+        pub fn serialize<S>(value: &Option<Arc<str>>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            value.as_deref().serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Arc<str>>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let opt: Option<Wrapper> = Deserialize::deserialize(deserializer)?;
+            Ok(opt.map(|v| v.0))
+        }
+    }
+
+    pub fn serialize<S>(value: &Arc<str>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(value)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Arc<str>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct InternedVisitor;
+
+        impl<'de> Visitor<'de> for InternedVisitor {
+            type Value = Arc<str>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            // Called by the `RowBinary` deserializer, see `pass_to_visitor()`.
+            fn visit_unit<E: Error>(self) -> Result<Arc<str>, E> {
+                match take_passed()? {
+                    Passed::Str(value) => Ok(value),
+                    Passed::Bytes(_) => Err(E::custom("unexpected bytes instead of a string")),
+                }
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(
+                self,
+                d: D,
+            ) -> Result<Arc<str>, D::Error> {
+                d.deserialize_str(self)
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Arc<str>, E> {
+                Ok(Arc::from(v))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(LC_INTERNED, InternedVisitor)
+    }
+}
+
+/// Ser/de [`Bytes`](bytes::Bytes) to/from `String` without copying.
+///
+/// Rows with `&str` and `&[u8]` fields borrow the cursor, so they cannot be
+/// kept after the next call to [`RowCursor::next`]. With this helper, fields
+/// are declared as `Bytes`, which are refcounted slices of the response
+/// buffer: rows remain owned ([`RowOwned`]) and can be collected or sent to
+/// other tasks, but strings are neither copied nor allocated.
+///
+/// Note that every such value keeps the whole chunk of the response alive,
+/// so prefer `String` if only a few rows of a large response are retained.
+/// If the source buffer is unknown (e.g. in mocks), values are copied.
+///
+/// Use [`String::from_utf8`] or [`std::str::from_utf8`] to get strings back.
+///
+/// # Example
+/// ```
+/// # use bytes::Bytes;
+/// # use serde::Deserialize;
+/// #[derive(clickhouse::Row, Deserialize)]
+/// struct Log {
+///     // String
+///     #[serde(with = "clickhouse::serde::bytes_zero_copy")]
+///     message: Bytes,
+///     // Nullable(String)
+///     #[serde(with = "clickhouse::serde::bytes_zero_copy::option")]
+///     trace: Option<Bytes>,
+/// }
+/// ```
+///
+/// [`RowCursor::next`]: crate::query::RowCursor::next
+/// [`RowOwned`]: crate::RowOwned
+pub mod bytes_zero_copy {
+    use super::*;
+    use bytes::Bytes;
+    use serde::de::{Error, SeqAccess, Visitor};
+    use std::fmt;
+
+    /// Ser/de `Option<Bytes>` to/from `Nullable(String)` without copying.
+    pub mod option {
+        use super::*;
+
+        struct Wrapper(Bytes);
+
+        impl<'de> Deserialize<'de> for Wrapper {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                super::deserialize(deserializer).map(Wrapper)
+            }
+        }
+
+        struct SerializeWrapper<'a>(&'a Bytes);
+
+        impl Serialize for SerializeWrapper<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                super::serialize(self.0, serializer)
+            }
+        }
+
+        pub fn serialize<S>(value: &Option<Bytes>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            value.as_ref().map(SerializeWrapper).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Bytes>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let opt: Option<Wrapper> = Deserialize::deserialize(deserializer)?;
+            Ok(opt.map(|v| v.0))
+        }
+    }
+
+    pub fn serialize<S>(value: &Bytes, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(value)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Bytes, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("bytes")
+            }
+
+            // Called by the `RowBinary` deserializer, see `pass_to_visitor()`.
+            fn visit_unit<E: Error>(self) -> Result<Bytes, E> {
+                match take_passed()? {
+                    Passed::Bytes(value) => Ok(value),
+                    Passed::Str(_) => Err(E::custom("unexpected string instead of bytes")),
+                }
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(self, d: D) -> Result<Bytes, D::Error> {
+                d.deserialize_bytes(self)
+            }
+
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Bytes, E> {
+                Ok(Bytes::copy_from_slice(v))
+            }
+
+            fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Bytes, E> {
+                Ok(Bytes::from(v))
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Bytes, E> {
+                Ok(Bytes::copy_from_slice(v.as_bytes()))
+            }
+
+            fn visit_string<E: Error>(self, v: String) -> Result<Bytes, E> {
+                Ok(Bytes::from(v))
+            }
+
+            // Bytes are represented as sequences by some formats, e.g. JSON.
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Bytes, A::Error> {
+                let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    vec.push(byte);
+                }
+                Ok(Bytes::from(vec))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(BYTES_ZERO_COPY, BytesVisitor)
+    }
+}
+
+/// Ser/de `Vec<T>` to/from `Nested(..)` columns, where `T` is a struct
+/// marked with `#[clickhouse(nested)]`.
+///
+/// The field must also be marked with `#[clickhouse(nested)]`
+/// to expand its column names, see [`Row`](derive@crate::Row) for details.
+pub mod nested {
+    use crate::nested::Nested;
+
+    use super::*;
+
+    pub fn serialize<T, S>(items: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Nested,
+        S: Serializer,
+    {
+        T::serialize_nested(items, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: Nested,
+        D: Deserializer<'de>,
+    {
+        T::deserialize_nested(deserializer)
+    }
+}
+
+/// Ser/de maps of [`serde_json::Value`]s to/from `Map(String, String)`,
+/// where every value is stored as JSON text.
+///
+/// It's a common way to store semi-structured attributes without using
+/// the `JSON` type. Any map implementing `FromIterator<(String, Value)>`
+/// can be used, e.g. `HashMap<String, Value>`, `BTreeMap<String, Value>`
+/// or [`serde_json::Map`].
+///
+/// Values are parsed strictly, so reading a value that isn't valid JSON
+/// (e.g. `foo` instead of `"foo"`) fails.
+///
+/// # Example
+/// ```
+/// # use std::collections::HashMap;
+/// # use clickhouse::Row;
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_json::Value;
+/// #[derive(Row, Serialize, Deserialize)]
+/// struct Event {
+///     id: u64,
+///     // attributes Map(String, String)
+///     #[serde(with = "clickhouse::serde::json_map")]
+///     attributes: HashMap<String, Value>,
+/// }
+/// ```
+pub mod json_map {
+    use std::{fmt, marker::PhantomData};
+
+    use serde::{
+        de::{Error as _, MapAccess, SeqAccess, Visitor},
+        ser::{Error as _, SerializeMap, SerializeSeq},
+    };
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Ser/de `Option<M>` to/from `Map(String, String)`.
+    ///
+    /// `Map` columns cannot be `Nullable`, so `None` is written as an empty
+    /// map and an empty map is read as `None`.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<M, S>(map: &Option<M>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            for<'a> &'a M: IntoIterator<Item = (&'a String, &'a Value)>,
+            S: Serializer,
+        {
+            match map {
+                Some(map) => super::serialize(map, serializer),
+                None => serializer.serialize_map(Some(0))?.end(),
+            }
+        }
+
+        pub fn deserialize<'de, M, D>(deserializer: D) -> Result<Option<M>, D::Error>
+        where
+            M: FromIterator<(String, Value)>,
+            D: Deserializer<'de>,
+        {
+            let entries = deserializer.deserialize_map(EntriesVisitor)?;
+            Ok((!entries.is_empty()).then(|| entries.into_iter().collect()))
+        }
+    }
+
+    /// Ser/de `Vec<M>` to/from `Array(Map(String, String))`.
+    pub mod vec {
+        use super::*;
+
+        pub fn serialize<M, S>(maps: &[M], serializer: S) -> Result<S::Ok, S::Error>
+        where
+            for<'a> &'a M: IntoIterator<Item = (&'a String, &'a Value)>,
+            S: Serializer,
+        {
+            let mut seq = serializer.serialize_seq(Some(maps.len()))?;
+            for map in maps {
+                seq.serialize_element(&JsonMap(map))?;
+            }
+            seq.end()
+        }
+
+        pub fn deserialize<'de, M, D>(deserializer: D) -> Result<Vec<M>, D::Error>
+        where
+            M: FromIterator<(String, Value)>,
+            D: Deserializer<'de>,
+        {
+            struct MapsVisitor<M>(PhantomData<M>);
+
+            impl<'de, M: FromIterator<(String, Value)>> Visitor<'de> for MapsVisitor<M> {
+                type Value = Vec<M>;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, "a sequence of maps with JSON values")
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<M>, A::Error> {
+                    let mut maps = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                    while let Some(OwnedJsonMap(map)) = seq.next_element()? {
+                        maps.push(map);
+                    }
+                    Ok(maps)
+                }
+            }
+
+            deserializer.deserialize_seq(MapsVisitor(PhantomData))
+        }
+    }
+
+    pub fn serialize<M, S>(map: &M, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a M: IntoIterator<Item = (&'a String, &'a Value)>,
+        S: Serializer,
+    {
+        // `RowBinary` requires the length to be known upfront.
+        let entries = map.into_iter().collect::<Vec<_>>();
+
+        let mut ser = serializer.serialize_map(Some(entries.len()))?;
+        for (key, value) in entries {
+            let value = serde_json::to_string(value).map_err(S::Error::custom)?;
+            ser.serialize_entry(key, &value)?;
+        }
+        ser.end()
+    }
+
+    pub fn deserialize<'de, M, D>(deserializer: D) -> Result<M, D::Error>
+    where
+        M: FromIterator<(String, Value)>,
+        D: Deserializer<'de>,
+    {
+        let entries = deserializer.deserialize_map(EntriesVisitor)?;
+        Ok(entries.into_iter().collect())
+    }
+
+    struct EntriesVisitor;
+
+    impl<'de> Visitor<'de> for EntriesVisitor {
+        type Value = Vec<(String, Value)>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a map with JSON values")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+            while let Some((key, value)) = map.next_entry::<String, String>()? {
+                let value = serde_json::from_str(&value).map_err(|err| {
+                    A::Error::custom(format!("invalid JSON in the value of {key:?}: {err}"))
+                })?;
+                entries.push((key, value));
+            }
+            Ok(entries)
+        }
+    }
+
+    struct JsonMap<'a, M>(&'a M);
+
+    impl<M> Serialize for JsonMap<'_, M>
+    where
+        for<'a> &'a M: IntoIterator<Item = (&'a String, &'a Value)>,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self.0, serializer)
+        }
+    }
+
+    struct OwnedJsonMap<M>(M);
+
+    impl<'de, M: FromIterator<(String, Value)>> Deserialize<'de> for OwnedJsonMap<M> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize(deserializer).map(Self)
+        }
+    }
+}
+
+/// Ser/de `Map(K, V)` columns without building maps.
+pub mod map {
+    /// Ser/de `Vec<(K, V)>` to/from `Map(K, V)`, keeping entries
+    /// in the order stored by the server, including duplicate keys.
+    ///
+    /// Unlike `HashMap<K, V>`, no hashing is involved and duplicates are not lost,
+    /// and keys and values can borrow the response, e.g. `Vec<(&str, &str)>`.
+    /// Unlike a plain `Vec<(K, V)>` field, which is also allowed for `Map` columns,
+    /// it's (de)serialized as a map, so other formats (e.g. JSON) see an object.
+    ///
+    /// # Example
+    /// ```
+    /// # use clickhouse::Row;
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Row, Serialize, Deserialize)]
+    /// struct Event<'a> {
+    ///     id: u64,
+    ///     // labels Map(String, String)
+    ///     #[serde(borrow, with = "clickhouse::serde::map::entries")]
+    ///     labels: Vec<(&'a str, &'a str)>,
+    /// }
+    /// ```
+    pub mod entries {
+        use std::{fmt, marker::PhantomData};
+
+        use serde::{
+            de::{MapAccess, Visitor},
+            ser::SerializeMap,
+        };
+
+        use super::super::*;
+
+        pub fn serialize<K, V, S>(entries: &[(K, V)], serializer: S) -> Result<S::Ok, S::Error>
+        where
+            K: Serialize,
+            V: Serialize,
+            S: Serializer,
+        {
+            let mut ser = serializer.serialize_map(Some(entries.len()))?;
+            for (key, value) in entries {
+                ser.serialize_entry(key, value)?;
+            }
+            ser.end()
+        }
+
+        pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<Vec<(K, V)>, D::Error>
+        where
+            K: Deserialize<'de>,
+            V: Deserialize<'de>,
+            D: Deserializer<'de>,
+        {
+            struct EntriesVisitor<K, V>(PhantomData<(K, V)>);
+
+            impl<'de, K, V> Visitor<'de> for EntriesVisitor<K, V>
+            where
+                K: Deserialize<'de>,
+                V: Deserialize<'de>,
+            {
+                type Value = Vec<(K, V)>;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, "a map")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                    let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                    while let Some(entry) = map.next_entry()? {
+                        entries.push(entry);
+                    }
+                    Ok(entries)
+                }
+            }
+
+            deserializer.deserialize_map(EntriesVisitor(PhantomData))
+        }
     }
 }
 
@@ -104,7 +877,130 @@ pub mod uuid {
             Ok(Uuid::from_u64_pair(bytes.0, bytes.1))
         }
     }
+
+    /// Ser/de [`::uuid::Uuid`] to/from `FixedString(16)`.
+    ///
+    /// The UUID is stored as 16 bytes in the big-endian (RFC 4122) order,
+    /// i.e. the same way as `UUIDStringToNum` produces it.
+    pub mod fixed_string {
+        use ::uuid::Uuid;
+        use serde::de::Error;
+
+        use super::*;
+
+        option!(
+            Uuid,
+            "Ser/de `Option<Uuid>` to/from `Nullable(FixedString(16))`."
+        );
+
+        pub fn serialize<S>(uuid: &Uuid, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            if serializer.is_human_readable() {
+                uuid.to_string().serialize(serializer)
+            } else {
+                uuid.as_bytes().serialize(serializer)
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Uuid, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                let uuid_str: &str = Deserialize::deserialize(deserializer)?;
+                Uuid::parse_str(uuid_str).map_err(D::Error::custom)
+            } else {
+                let bytes: [u8; 16] = Deserialize::deserialize(deserializer)?;
+                Ok(Uuid::from_bytes(bytes))
+            }
+        }
+    }
+}
+
+/// Ser/de [`::ulid::Ulid`] to/from `FixedString(26)` in the canonical
+/// Crockford's base32 representation.
+#[cfg(feature = "ulid")]
+pub mod ulid {
+    use ::ulid::Ulid;
+    use serde::de::Error;
+
+    use super::*;
+
+    option!(
+        Ulid,
+        "Ser/de `Option<Ulid>` to/from `Nullable(FixedString(26))`."
+    );
+
+    pub fn serialize<S>(ulid: &Ulid, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut buffer = [0; ::ulid::ULID_LEN];
+        let encoded = ulid.array_to_str(&mut buffer);
+
+        if serializer.is_human_readable() {
+            encoded.serialize(serializer)
+        } else {
+            buffer.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Ulid, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let ulid_str: &str = Deserialize::deserialize(deserializer)?;
+            Ulid::from_string(ulid_str).map_err(D::Error::custom)
+        } else {
+            let bytes: [u8; ::ulid::ULID_LEN] = Deserialize::deserialize(deserializer)?;
+            let ulid_str = std::str::from_utf8(&bytes).map_err(D::Error::custom)?;
+            Ulid::from_string(ulid_str).map_err(D::Error::custom)
+        }
+    }
+
+    /// Ser/de [`::ulid::Ulid`] to/from `FixedString(16)`.
+    ///
+    /// The ULID is stored as 16 bytes in the big-endian order.
+    pub mod fixed_string {
+        use ::ulid::Ulid;
+        use serde::de::Error;
+
+        use super::*;
+
+        option!(
+            Ulid,
+            "Ser/de `Option<Ulid>` to/from `Nullable(FixedString(16))`."
+        );
+
+        pub fn serialize<S>(ulid: &Ulid, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            if serializer.is_human_readable() {
+                ulid.to_string().serialize(serializer)
+            } else {
+                ulid.to_bytes().serialize(serializer)
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Ulid, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                let ulid_str: &str = Deserialize::deserialize(deserializer)?;
+                Ulid::from_string(ulid_str).map_err(D::Error::custom)
+            } else {
+                let bytes: [u8; 16] = Deserialize::deserialize(deserializer)?;
+                Ok(Ulid::from_bytes(bytes))
+            }
+        }
+    }
 }
+
 /// Ser/de `Vec<`[`::uuid::Uuid`]`>` to/from `Array(UUID)`.
 #[cfg(feature = "uuid")]
 pub mod uuid_vec {
@@ -218,7 +1114,7 @@ pub mod chrono {
         where
             D: Deserializer<'de>,
         {
-            let ts: u32 = Deserialize::deserialize(deserializer)?;
+            let ts: u32 = deserialize_utc_timestamp(deserializer, UTC_DATETIME)?;
             DateTime::<Utc>::from_timestamp(i64::from(ts), 0).ok_or_else(|| {
                 D::Error::custom(format!("{ts} cannot be converted to DateTime<Utc>"))
             })
@@ -251,7 +1147,7 @@ pub mod chrono {
             where
                 D: Deserializer<'de>,
             {
-                let ts: i64 = Deserialize::deserialize(deserializer)?;
+                let ts: i64 = deserialize_utc_timestamp(deserializer, UTC_DATETIME64)?;
                 DateTime::<Utc>::from_timestamp(ts, 0).ok_or_else(|| {
                     D::Error::custom(format!("Can't create DateTime<Utc> from {ts}"))
                 })
@@ -279,7 +1175,7 @@ pub mod chrono {
             where
                 D: Deserializer<'de>,
             {
-                let ts: i64 = Deserialize::deserialize(deserializer)?;
+                let ts: i64 = deserialize_utc_timestamp(deserializer, UTC_DATETIME64)?;
                 DateTime::<Utc>::from_timestamp_millis(ts).ok_or_else(|| {
                     D::Error::custom(format!("Can't create DateTime<Utc> from {ts}"))
                 })
@@ -307,7 +1203,7 @@ pub mod chrono {
             where
                 D: Deserializer<'de>,
             {
-                let ts: i64 = Deserialize::deserialize(deserializer)?;
+                let ts: i64 = deserialize_utc_timestamp(deserializer, UTC_DATETIME64)?;
                 DateTime::<Utc>::from_timestamp_micros(ts).ok_or_else(|| {
                     D::Error::custom(format!("Can't create DateTime<Utc> from {ts}"))
                 })
@@ -337,12 +1233,176 @@ pub mod chrono {
             where
                 D: Deserializer<'de>,
             {
-                let ts: i64 = Deserialize::deserialize(deserializer)?;
+                let ts: i64 = deserialize_utc_timestamp(deserializer, UTC_DATETIME64)?;
                 Ok(DateTime::<Utc>::from_timestamp_nanos(ts))
             }
         }
     }
 
+    /// Ser/de `DateTime<Tz>` to/from `DateTime` using the timezone of the column.
+    ///
+    /// Deserialized values carry the timezone of the column's type,
+    /// e.g. `DateTime('Europe/Berlin')`. UTC is used if the type has no explicit
+    /// timezone or validation is disabled. Serialization writes the UNIX
+    /// timestamp regardless of the value's timezone.
+    ///
+    /// Requires the `tz` feature.
+    ///
+    /// # Example
+    /// ```
+    /// # #[derive(clickhouse::Row, serde::Deserialize)]
+    /// # struct Event {
+    /// #[serde(with = "clickhouse::serde::chrono::datetime_tz")]
+    /// created_at: chrono::DateTime<chrono_tz::Tz>,
+    /// # }
+    /// ```
+    #[cfg(feature = "tz")]
+    pub mod datetime_tz {
+        use super::*;
+        use chrono_tz::Tz;
+        use serde::de::value::U32Deserializer;
+
+        type DateTimeTz = DateTime<Tz>;
+
+        option!(
+            DateTimeTz,
+            "Ser/de `Option<DateTime<Tz>>` to/from `Nullable(DateTime)`."
+        );
+
+        pub fn serialize<S>(dt: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            super::datetime::serialize(&dt.with_timezone(&Utc), serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Tz>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let (ts, tz): (u32, Tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME)?;
+            let dt = super::datetime::deserialize(U32Deserializer::<D::Error>::new(ts))?;
+            Ok(dt.with_timezone(&tz))
+        }
+    }
+
+    /// Contains modules to ser/de `DateTime<Tz>` to/from `DateTime64(_)`
+    /// using the timezone of the column, see [`datetime_tz`] for details.
+    ///
+    /// Requires the `tz` feature.
+    #[cfg(feature = "tz")]
+    pub mod datetime64_tz {
+        use super::*;
+        use chrono_tz::Tz;
+        use serde::de::value::I64Deserializer;
+
+        type DateTimeTz = DateTime<Tz>;
+
+        /// Ser/de `DateTime<Tz>` to/from `DateTime64(0)` (seconds).
+        pub mod secs {
+            use super::*;
+
+            option!(
+                DateTimeTz,
+                "Ser/de `Option<DateTime<Tz>>` to/from `Nullable(DateTime64(0))`."
+            );
+
+            pub fn serialize<S>(dt: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                super::super::datetime64::secs::serialize(&dt.with_timezone(&Utc), serializer)
+            }
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Tz>, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (ts, tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME64)?;
+                let dt = super::super::datetime64::secs::deserialize(I64Deserializer::new(ts))?;
+                Ok(dt.with_timezone(&tz))
+            }
+        }
+
+        /// Ser/de `DateTime<Tz>` to/from `DateTime64(3)` (milliseconds).
+        pub mod millis {
+            use super::*;
+
+            option!(
+                DateTimeTz,
+                "Ser/de `Option<DateTime<Tz>>` to/from `Nullable(DateTime64(3))`."
+            );
+
+            pub fn serialize<S>(dt: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                super::super::datetime64::millis::serialize(&dt.with_timezone(&Utc), serializer)
+            }
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Tz>, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (ts, tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME64)?;
+                let dt = super::super::datetime64::millis::deserialize(I64Deserializer::new(ts))?;
+                Ok(dt.with_timezone(&tz))
+            }
+        }
+
+        /// Ser/de `DateTime<Tz>` to/from `DateTime64(6)` (microseconds).
+        pub mod micros {
+            use super::*;
+
+            option!(
+                DateTimeTz,
+                "Ser/de `Option<DateTime<Tz>>` to/from `Nullable(DateTime64(6))`."
+            );
+
+            pub fn serialize<S>(dt: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                super::super::datetime64::micros::serialize(&dt.with_timezone(&Utc), serializer)
+            }
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Tz>, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (ts, tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME64)?;
+                let dt = super::super::datetime64::micros::deserialize(I64Deserializer::new(ts))?;
+                Ok(dt.with_timezone(&tz))
+            }
+        }
+
+        /// Ser/de `DateTime<Tz>` to/from `DateTime64(9)` (nanoseconds).
+        pub mod nanos {
+            use super::*;
+
+            option!(
+                DateTimeTz,
+                "Ser/de `Option<DateTime<Tz>>` to/from `Nullable(DateTime64(9))`."
+            );
+
+            pub fn serialize<S>(dt: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                super::super::datetime64::nanos::serialize(&dt.with_timezone(&Utc), serializer)
+            }
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Tz>, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (ts, tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME64)?;
+                let dt = super::super::datetime64::nanos::deserialize(I64Deserializer::new(ts))?;
+                Ok(dt.with_timezone(&tz))
+            }
+        }
+    }
+
     /// Ser/de `serde::NaiveDate` to/from `Date`.
     pub mod date {
         use super::*;
@@ -606,7 +1666,7 @@ pub mod time {
         where
             D: Deserializer<'de>,
         {
-            let ts: u32 = Deserialize::deserialize(deserializer)?;
+            let ts: u32 = deserialize_utc_timestamp(deserializer, UTC_DATETIME)?;
             OffsetDateTime::from_unix_timestamp(i64::from(ts)).map_err(D::Error::custom)
         }
     }
@@ -726,12 +1786,150 @@ pub mod time {
         where
             D: Deserializer<'de>,
         {
-            let ts: i64 = Deserialize::deserialize(deserializer)?;
+            let ts: i64 = deserialize_utc_timestamp(deserializer, UTC_DATETIME64)?;
             let ts = i128::from(ts) * mul; // cannot overflow: `mul` fits in `i64`
             OffsetDateTime::from_unix_timestamp_nanos(ts).map_err(D::Error::custom)
         }
     }
 
+    /// Ser/de `OffsetDateTime` to/from `DateTime` using the timezone of the column.
+    ///
+    /// Deserialized values have the offset of the column's timezone at that
+    /// moment, e.g. `+02:00` for `DateTime('Europe/Berlin')` in summer. UTC is
+    /// used if the type has no explicit timezone or validation is disabled.
+    /// Serialization writes the UNIX timestamp regardless of the offset.
+    ///
+    /// Requires the `tz` feature.
+    #[cfg(feature = "tz")]
+    pub mod datetime_tz {
+        use super::*;
+        use serde::de::value::U32Deserializer;
+
+        option!(
+            OffsetDateTime,
+            "Ser/de `Option<OffsetDateTime>` to/from `Nullable(DateTime)`."
+        );
+
+        pub use super::datetime::serialize;
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let (ts, tz) = deserialize_tz_timestamp::<_, u32>(deserializer, TZ_DATETIME)?;
+            let dt = super::datetime::deserialize(U32Deserializer::<D::Error>::new(ts))?;
+            Ok(to_timezone(dt, tz))
+        }
+    }
+
+    /// Contains modules to ser/de `OffsetDateTime` to/from `DateTime64(_)`
+    /// using the timezone of the column, see [`datetime_tz`] for details.
+    ///
+    /// Requires the `tz` feature.
+    #[cfg(feature = "tz")]
+    pub mod datetime64_tz {
+        use super::*;
+        use serde::de::value::I64Deserializer;
+
+        /// Ser/de `OffsetDateTime` to/from `DateTime64(0)`.
+        pub mod secs {
+            use super::*;
+
+            option!(
+                OffsetDateTime,
+                "Ser/de `Option<OffsetDateTime>` to/from `Nullable(DateTime64(0))`."
+            );
+
+            pub use super::super::datetime64::secs::serialize;
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (ts, tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME64)?;
+                let dt = super::super::datetime64::secs::deserialize(I64Deserializer::new(ts))?;
+                Ok(to_timezone(dt, tz))
+            }
+        }
+
+        /// Ser/de `OffsetDateTime` to/from `DateTime64(3)`.
+        pub mod millis {
+            use super::*;
+
+            option!(
+                OffsetDateTime,
+                "Ser/de `Option<OffsetDateTime>` to/from `Nullable(DateTime64(3))`."
+            );
+
+            pub use super::super::datetime64::millis::serialize;
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (ts, tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME64)?;
+                let dt = super::super::datetime64::millis::deserialize(I64Deserializer::new(ts))?;
+                Ok(to_timezone(dt, tz))
+            }
+        }
+
+        /// Ser/de `OffsetDateTime` to/from `DateTime64(6)`.
+        pub mod micros {
+            use super::*;
+
+            option!(
+                OffsetDateTime,
+                "Ser/de `Option<OffsetDateTime>` to/from `Nullable(DateTime64(6))`."
+            );
+
+            pub use super::super::datetime64::micros::serialize;
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (ts, tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME64)?;
+                let dt = super::super::datetime64::micros::deserialize(I64Deserializer::new(ts))?;
+                Ok(to_timezone(dt, tz))
+            }
+        }
+
+        /// Ser/de `OffsetDateTime` to/from `DateTime64(9)`.
+        pub mod nanos {
+            use super::*;
+
+            option!(
+                OffsetDateTime,
+                "Ser/de `Option<OffsetDateTime>` to/from `Nullable(DateTime64(9))`."
+            );
+
+            pub use super::super::datetime64::nanos::serialize;
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (ts, tz) = deserialize_tz_timestamp(deserializer, TZ_DATETIME64)?;
+                let dt = super::super::datetime64::nanos::deserialize(I64Deserializer::new(ts))?;
+                Ok(to_timezone(dt, tz))
+            }
+        }
+    }
+
+    /// Converts `dt` to the offset of `tz` at that moment.
+    #[cfg(feature = "tz")]
+    fn to_timezone(dt: OffsetDateTime, tz: chrono_tz::Tz) -> OffsetDateTime {
+        use ::chrono::{Offset, TimeZone};
+        use ::time::UtcOffset;
+
+        let offset = ::chrono::DateTime::from_timestamp(dt.unix_timestamp(), 0)
+            .map(|utc| tz.offset_from_utc_datetime(&utc.naive_utc()).fix())
+            .and_then(|offset| UtcOffset::from_whole_seconds(offset.local_minus_utc()).ok())
+            .unwrap_or(UtcOffset::UTC);
+
+        dt.to_offset(offset)
+    }
+
     /// Ser/de `time::Date` to/from `Date`.
     pub mod date {
         use super::*;
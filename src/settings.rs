@@ -0,0 +1,300 @@
+pub(crate) const DATABASE: &str = "database";
+pub(crate) const DEFAULT_FORMAT: &str = "default_format";
+pub(crate) const COMPRESS: &str = "compress";
+pub(crate) const DECOMPRESS: &str = "decompress";
+#[cfg(feature = "zstd")]
+pub(crate) const ENABLE_HTTP_COMPRESSION: &str = "enable_http_compression";
+pub(crate) const ROLE: &str = "role";
+pub(crate) const QUERY: &str = "query";
+pub(crate) const QUERY_ID: &str = "query_id";
+pub(crate) const MAX_EXECUTION_TIME: &str = "max_execution_time";
+pub(crate) const SESSION_ID: &str = "session_id";
+#[cfg(feature = "deadpool")]
+pub(crate) const SESSION_TIMEOUT: &str = "session_timeout";
+pub(crate) const WAIT_END_OF_QUERY: &str = "wait_end_of_query";
+pub(crate) const OUTPUT_FORMAT_JSON_QUOTE_64BIT_INTEGERS: &str =
+    "output_format_json_quote_64bit_integers";
+pub(crate) const LOG_COMMENT: &str = "log_comment";
+pub(crate) const MIN_INSERT_BLOCK_SIZE_ROWS: &str = "min_insert_block_size_rows";
+pub(crate) const MIN_INSERT_BLOCK_SIZE_BYTES: &str = "min_insert_block_size_bytes";
+pub(crate) const MAX_INSERT_BLOCK_SIZE: &str = "max_insert_block_size";
+
+/// Adds the tag to `log_comment` stored as a JSON object, e.g. `{"feature":"export"}`.
+///
+/// A comment that isn't a JSON object is kept as the `comment` tag.
+pub(crate) fn add_tag(log_comment: Option<&str>, key: String, value: String) -> String {
+    use serde_json::{Map, Value};
+
+    let mut tags = match log_comment {
+        None | Some("") => Map::new(),
+        Some(comment) => match serde_json::from_str(comment) {
+            Ok(Value::Object(tags)) => tags,
+            _ => Map::from_iter([("comment".into(), Value::String(comment.into()))]),
+        },
+    };
+
+    tags.insert(key, Value::String(value));
+    Value::Object(tags).to_string()
+}
+
+/// A typed set of ClickHouse settings, accepted by [`Client::with_settings`],
+/// [`Query::with_settings`] and similar methods.
+///
+/// Unlike [`Client::with_setting`], names of settings are checked at compile
+/// time. Settings not covered by typed methods can be added by [`Settings::set`].
+///
+/// See [the list of settings](https://clickhouse.com/docs/operations/settings/settings)
+/// for details.
+///
+/// # Example
+/// ```
+/// # use clickhouse::{Client, Settings};
+/// let settings = Settings::new()
+///     .max_execution_time(30)
+///     .max_block_size(65536)
+///     .log_comment("nightly report");
+///
+/// let client = Client::default().with_settings(settings);
+/// ```
+///
+/// [`Client::with_settings`]: crate::Client::with_settings
+/// [`Client::with_setting`]: crate::Client::with_setting
+/// [`Query::with_settings`]: crate::query::Query::with_settings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[must_use]
+pub struct Settings {
+    pairs: Vec<(String, String)>,
+}
+
+impl Settings {
+    /// Creates an empty set of settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an arbitrary setting, e.g. one not covered by typed methods.
+    ///
+    /// Setting the same name again replaces the previous value.
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        let value = value.into();
+
+        match self.pairs.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.pairs.push((name, value)),
+        }
+        self
+    }
+
+    /// Returns the value of the setting, if it's set.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns `true` if no settings are set.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Iterates over `(name, value)` pairs in the order they are set.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub(crate) fn into_pairs(self) -> impl Iterator<Item = (String, String)> {
+        self.pairs.into_iter()
+    }
+}
+
+trait SettingValue {
+    fn into_value(self) -> String;
+}
+
+impl SettingValue for u64 {
+    fn into_value(self) -> String {
+        self.to_string()
+    }
+}
+
+impl SettingValue for bool {
+    fn into_value(self) -> String {
+        if self { "1" } else { "0" }.into()
+    }
+}
+
+impl SettingValue for &str {
+    fn into_value(self) -> String {
+        self.into()
+    }
+}
+
+macro_rules! typed_settings {
+    ($($(#[$attr:meta])* $name:ident: $ty:ty;)*) => {
+        impl Settings {
+            $(
+                $(#[$attr])*
+                pub fn $name(self, value: $ty) -> Self {
+                    // Raw identifiers are used for keywords, e.g. `r#final`.
+                    let name = stringify!($name).trim_start_matches("r#");
+                    self.set(name, SettingValue::into_value(value))
+                }
+            )*
+        }
+    };
+}
+
+// Commonly used settings, names match the server's ones.
+typed_settings! {
+    // Limits.
+    /// Sets `max_execution_time`, the maximum query execution time in seconds.
+    max_execution_time: u64;
+    /// Sets `max_memory_usage`, the maximum amount of RAM in bytes to use
+    /// for running a query on a single server.
+    max_memory_usage: u64;
+    /// Sets `max_rows_to_read`, the maximum number of rows read from a table.
+    max_rows_to_read: u64;
+    /// Sets `max_bytes_to_read`, the maximum number of uncompressed bytes
+    /// read from a table.
+    max_bytes_to_read: u64;
+    /// Sets `read_overflow_mode`, `throw` or `break`.
+    read_overflow_mode: &str;
+    /// Sets `max_result_rows`, the maximum number of rows in the result.
+    max_result_rows: u64;
+    /// Sets `max_result_bytes`, the maximum number of uncompressed bytes
+    /// in the result.
+    max_result_bytes: u64;
+    /// Sets `result_overflow_mode`, `throw` or `break`.
+    result_overflow_mode: &str;
+    /// Sets `timeout_overflow_mode`, `throw` or `break`.
+    timeout_overflow_mode: &str;
+    /// Sets `max_bytes_before_external_group_by`, enables spilling
+    /// of `GROUP BY` to disk once the threshold is exceeded.
+    max_bytes_before_external_group_by: u64;
+    /// Sets `max_bytes_before_external_sort`, enables spilling
+    /// of `ORDER BY` to disk once the threshold is exceeded.
+    max_bytes_before_external_sort: u64;
+    /// Sets `readonly`: `0` allows all queries, `1` only reads,
+    /// `2` reads and changing settings.
+    readonly: u64;
+
+    // Reading.
+    /// Sets `max_threads`, the maximum number of query processing threads.
+    max_threads: u64;
+    /// Sets `max_block_size`, the maximum size of blocks read from tables in rows.
+    max_block_size: u64;
+    /// Sets `preferred_block_size_bytes`, the preferred size of blocks
+    /// read from tables in bytes.
+    preferred_block_size_bytes: u64;
+    /// Sets `priority`, lower values mean higher priority, `0` disables priorities.
+    priority: u64;
+    /// Sets `final`, applies `FINAL` to all tables in the query.
+    r#final: bool;
+    /// Sets `optimize_read_in_order`, reads data in the order of the sorting key
+    /// for `ORDER BY` if possible.
+    optimize_read_in_order: bool;
+    /// Sets `select_sequential_consistency`, enables sequential consistency
+    /// for `SELECT` queries on replicated tables.
+    select_sequential_consistency: bool;
+    /// Sets `join_algorithm`, e.g. `hash`, `parallel_hash` or `grace_hash`.
+    join_algorithm: &str;
+    /// Sets `join_use_nulls`, fills non-joined rows with `NULL`s instead of defaults.
+    join_use_nulls: bool;
+    /// Sets `use_query_cache`, enables the query cache.
+    use_query_cache: bool;
+    /// Sets `query_cache_ttl`, the time in seconds entries of the query cache
+    /// are considered fresh.
+    query_cache_ttl: u64;
+    /// Sets `query_cache_tag`, a label used to store several results
+    /// of the same query in the query cache.
+    query_cache_tag: &str;
+
+    // Inserting.
+    /// Sets `async_insert`, enables asynchronous inserts.
+    async_insert: bool;
+    /// Sets `wait_for_async_insert`, waits until asynchronous inserts are processed.
+    wait_for_async_insert: bool;
+    /// Sets `async_insert_busy_timeout_ms`, the maximum time in milliseconds
+    /// to wait before flushing collected asynchronous inserts.
+    async_insert_busy_timeout_ms: u64;
+    /// Sets `insert_deduplicate`, enables deduplication of inserted blocks
+    /// for replicated tables.
+    insert_deduplicate: bool;
+    /// Sets `insert_deduplication_token`, a token used for deduplication
+    /// of inserted blocks.
+    insert_deduplication_token: &str;
+    /// Sets `max_insert_block_size`, the size of blocks formed for inserting
+    /// into a table in rows.
+    max_insert_block_size: u64;
+    /// Sets `max_partitions_per_insert_block`, the maximum number of partitions
+    /// in a single inserted block.
+    max_partitions_per_insert_block: u64;
+
+    // Mutations.
+    /// Sets `mutations_sync`: `0` executes mutations asynchronously,
+    /// `1` waits for the current server, `2` waits for all replicas.
+    mutations_sync: u64;
+    /// Sets `alter_sync`: `0` doesn't wait for `ALTER`s on replicas,
+    /// `1` waits for the current server, `2` waits for all replicas.
+    alter_sync: u64;
+    /// Sets `lightweight_deletes_sync`, similar to `mutations_sync`,
+    /// but for lightweight `DELETE`s.
+    lightweight_deletes_sync: u64;
+
+    // Misc.
+    /// Sets `log_comment`, a comment written to `system.query_log`.
+    log_comment: &str;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_collects_settings() {
+        let settings = Settings::new()
+            .max_execution_time(30)
+            .async_insert(true)
+            .r#final(false)
+            .join_algorithm("grace_hash")
+            .set("custom_setting", "foo")
+            .max_execution_time(60);
+
+        assert_eq!(
+            settings.iter().collect::<Vec<_>>(),
+            [
+                ("max_execution_time", "60"),
+                ("async_insert", "1"),
+                ("final", "0"),
+                ("join_algorithm", "grace_hash"),
+                ("custom_setting", "foo"),
+            ]
+        );
+        assert_eq!(settings.get("async_insert"), Some("1"));
+        assert_eq!(settings.get("max_threads"), None);
+        assert!(!settings.is_empty());
+        assert!(Settings::new().is_empty());
+    }
+
+    #[test]
+    fn it_adds_tags() {
+        let comment = add_tag(None, "feature".into(), "export".into());
+        assert_eq!(comment, r#"{"feature":"export"}"#);
+
+        let comment = add_tag(Some(&comment), "endpoint".into(), "/v1/\"q\"".into());
+        assert_eq!(comment, r#"{"endpoint":"/v1/\"q\"","feature":"export"}"#);
+
+        // Overrides the same tag.
+        let comment = add_tag(Some(&comment), "feature".into(), "import".into());
+        assert_eq!(comment, r#"{"endpoint":"/v1/\"q\"","feature":"import"}"#);
+
+        // Keeps a plain comment.
+        let comment = add_tag(Some("nightly report"), "feature".into(), "export".into());
+        assert_eq!(
+            comment,
+            r#"{"comment":"nightly report","feature":"export"}"#
+        );
+    }
+}
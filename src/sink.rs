@@ -0,0 +1,301 @@
+//! Support for [`Client::insert_sink`].
+//!
+//! Rows are sent to a bounded channel, which is read by a background task.
+//! The task collects rows into batches and inserts them one by one, starting
+//! a batch again if it fails with a network error. Since the channel is
+//! bounded, senders wait while the task is busy with previous batches.
+//!
+//! [`Client::insert_sink`]: crate::Client::insert_sink
+
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Client,
+    error::{Error, Result},
+    insert::WriteStats,
+    inserter::Quantities,
+    keyset::{DEFAULT_MAX_RETRIES, DEFAULT_RETRY_DELAY, is_retryable},
+    row::{RowOwned, RowWrite},
+};
+
+const DEFAULT_MAX_ROWS: usize = 100_000;
+const DEFAULT_PERIOD: Duration = Duration::from_secs(1);
+
+/// A handle to insert rows in batches by a background task,
+/// see [`Client::insert_sink`].
+///
+/// Rows are sent by [`InsertSink::send`] or by [`SinkSender`]s created by
+/// [`InsertSink::sender`], e.g. one for each actor. The task starts a new
+/// `INSERT` once the first row of a batch is received, and ends it once
+/// the batch has [`InsertSink::with_max_rows`] rows or it's been started
+/// [`InsertSink::with_period`] ago.
+///
+/// If a batch fails with a network error or a timeout, the whole batch is
+/// inserted again, see [`InsertSink::with_max_retries`]. Note that the server
+/// could have inserted the batch before the error, so rows can be inserted
+/// twice unless the table deduplicates inserted blocks (e.g. replicated
+/// tables, see the `insert_deduplicate` setting).
+///
+/// Other errors stop the task, and the following sends fail.
+/// The error is returned by [`InsertSink::end`].
+///
+/// # Dropping
+///
+/// If [`InsertSink::end`] isn't called, the task is aborted along with
+/// the batch being inserted and rows in the channel.
+///
+/// [`Client::insert_sink`]: crate::Client::insert_sink
+#[must_use]
+pub struct InsertSink<T> {
+    sender: SinkSender<T>,
+    task: Option<JoinHandle<Result<Quantities>>>,
+}
+
+/// Sends rows to [`InsertSink`], created by [`InsertSink::sender`].
+pub struct SinkSender<T> {
+    sender: mpsc::Sender<T>,
+    shared: Arc<Shared>,
+}
+
+// Manual impl, because `T` doesn't have to be `Clone`.
+impl<T> Clone for SinkSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+struct Shared {
+    config: Mutex<Config>,
+    /// The error stopped the task, if any.
+    failure: Mutex<Option<String>>,
+}
+
+#[derive(Clone, Copy)]
+struct Config {
+    max_rows: usize,
+    period: Duration,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl<T> InsertSink<T>
+where
+    T: RowOwned + RowWrite + Send + Sync,
+{
+    pub(crate) fn new(client: &Client, table: &str, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let shared = Arc::new(Shared {
+            config: Mutex::new(Config {
+                max_rows: DEFAULT_MAX_ROWS,
+                period: DEFAULT_PERIOD,
+                max_retries: DEFAULT_MAX_RETRIES,
+                retry_delay: DEFAULT_RETRY_DELAY,
+            }),
+            failure: Mutex::new(None),
+        });
+
+        let task = tokio::spawn(run(
+            client.clone(),
+            table.to_string(),
+            receiver,
+            shared.clone(),
+        ));
+
+        Self {
+            sender: SinkSender { sender, shared },
+            task: Some(task),
+        }
+    }
+}
+
+impl<T> InsertSink<T> {
+    /// Sets the maximum number of rows in a batch.
+    ///
+    /// By default, it's `100_000`.
+    ///
+    /// # Panics
+    /// If `max_rows` is `0`.
+    pub fn with_max_rows(self, max_rows: usize) -> Self {
+        assert!(max_rows > 0, "max_rows must be positive");
+        self.update_config(|config| config.max_rows = max_rows);
+        self
+    }
+
+    /// Sets how long rows are collected into a batch after its first row.
+    ///
+    /// By default, it's 1 second.
+    pub fn with_period(self, period: Duration) -> Self {
+        self.update_config(|config| config.period = period);
+        self
+    }
+
+    /// Sets how many times a batch is inserted again in a row after
+    /// failing because of a network error or a timeout.
+    ///
+    /// By default, it's `3`.
+    pub fn with_max_retries(self, max_retries: u32) -> Self {
+        self.update_config(|config| config.max_retries = max_retries);
+        self
+    }
+
+    /// Sets the delay before inserting a failed batch again.
+    ///
+    /// By default, it's 1 second.
+    pub fn with_retry_delay(self, delay: Duration) -> Self {
+        self.update_config(|config| config.retry_delay = delay);
+        self
+    }
+
+    fn update_config(&self, f: impl FnOnce(&mut Config)) {
+        f(&mut self.sender.shared.config.lock().unwrap());
+    }
+
+    /// Sends a row to be inserted, waiting for free capacity of the channel.
+    ///
+    /// Returns an error if the task has been stopped by an error.
+    pub async fn send(&self, row: T) -> Result<()> {
+        self.sender.send(row).await
+    }
+
+    /// Creates a new sender, which can be moved to another task.
+    pub fn sender(&self) -> SinkSender<T> {
+        self.sender.clone()
+    }
+
+    /// Waits until all rows are inserted and stops the task.
+    ///
+    /// The task stops once all [`SinkSender`]s are dropped, so make sure
+    /// they are dropped before, otherwise it waits for them.
+    ///
+    /// Returns totals of inserted rows, where `transactions` is the number of
+    /// batches and `bytes` are reported by the server, or the error stopped
+    /// the task.
+    pub async fn end(mut self) -> Result<Quantities> {
+        let task = self.task.take().expect("the task is taken only here");
+        drop(self);
+
+        match task.await {
+            Ok(result) => result,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+}
+
+impl<T> Drop for InsertSink<T> {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+impl<T> SinkSender<T> {
+    /// Sends a row to be inserted, see [`InsertSink::send`].
+    pub async fn send(&self, row: T) -> Result<()> {
+        if self.sender.send(row).await.is_ok() {
+            return Ok(());
+        }
+
+        let failure = self.shared.failure.lock().unwrap();
+        let reason = failure.as_deref().unwrap_or("aborted");
+        Err(Error::Other(
+            format!("insert sink is stopped: {reason}").into(),
+        ))
+    }
+}
+
+async fn run<T>(
+    client: Client,
+    table: String,
+    mut receiver: mpsc::Receiver<T>,
+    shared: Arc<Shared>,
+) -> Result<Quantities>
+where
+    T: RowOwned + RowWrite + Send + Sync,
+{
+    let mut totals = Quantities::ZERO;
+    let mut batch = Vec::new();
+
+    // Waits for the first row of a batch.
+    while let Some(row) = receiver.recv().await {
+        let config = *shared.config.lock().unwrap();
+        let deadline = Instant::now() + config.period;
+        batch.push(row);
+
+        let mut closed = false;
+        while batch.len() < config.max_rows {
+            tokio::select! {
+                row = receiver.recv() => match row {
+                    Some(row) => batch.push(row),
+                    None => {
+                        closed = true;
+                        break;
+                    }
+                },
+                _ = tokio::time::sleep_until(deadline) => break,
+            }
+        }
+
+        match insert_batch(&client, &table, &batch, &config).await {
+            Ok(stats) => {
+                totals.rows += batch.len() as u64;
+                totals.bytes += stats.bytes.unwrap_or(0);
+                totals.transactions += 1;
+                totals.stats = Some(stats);
+                batch.clear();
+            }
+            Err(err) => {
+                *shared.failure.lock().unwrap() = Some(err.to_string());
+                return Err(err);
+            }
+        }
+
+        if closed {
+            break;
+        }
+    }
+
+    Ok(totals)
+}
+
+async fn insert_batch<T>(
+    client: &Client,
+    table: &str,
+    batch: &[T],
+    config: &Config,
+) -> Result<WriteStats>
+where
+    T: RowOwned + RowWrite,
+{
+    let mut retries = 0;
+    loop {
+        match try_insert_batch(client, table, batch).await {
+            Err(err) if is_retryable(&err) && retries < config.max_retries => {
+                tracing::debug!(error=?err, rows = batch.len(), "inserting batch again");
+                retries += 1;
+                tokio::time::sleep(config.retry_delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn try_insert_batch<T>(client: &Client, table: &str, batch: &[T]) -> Result<WriteStats>
+where
+    T: RowOwned + RowWrite,
+{
+    let mut insert = client.insert::<T>(table).await?;
+    for row in batch {
+        insert.write(row).await?;
+    }
+    insert.end().await
+}
@@ -0,0 +1,223 @@
+//! Support for [`Query::fetch_all_spilled`].
+//!
+//! Rows are spilled in batches, each of which is written as its length
+//! (`u64`, little endian) followed by rows encoded in `RowBinary`.
+//!
+//! [`Query::fetch_all_spilled`]: crate::query::Query::fetch_all_spilled
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, Write},
+    marker::PhantomData,
+    mem,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    vec,
+};
+
+use crate::{
+    error::Result,
+    row::{RowOwned, RowRead, RowWrite},
+    rowbinary,
+};
+
+/// Collects rows in memory, spilling them to a file once the budget is exceeded.
+pub(crate) struct Spiller<T> {
+    budget: usize,
+    dir: PathBuf,
+    rows: Vec<T>,
+    // Estimated size of `rows`.
+    size: usize,
+    file: Option<SpillFile>,
+}
+
+impl<T: RowOwned + RowRead + RowWrite> Spiller<T> {
+    pub(crate) fn new(budget: usize, dir: &Path) -> Self {
+        Self {
+            budget,
+            dir: dir.to_path_buf(),
+            rows: Vec::new(),
+            size: 0,
+            file: None,
+        }
+    }
+
+    /// `size` is the size of the row in `RowBinary`,
+    /// used to estimate the size of its heap allocations.
+    pub(crate) async fn push(&mut self, row: T, size: usize) -> Result<()> {
+        self.size += mem::size_of::<T>() + size;
+        self.rows.push(row);
+
+        if self.size > self.budget {
+            self.spill().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn spill(&mut self) -> Result<()> {
+        let mut batch = Vec::with_capacity(self.size);
+        batch.extend_from_slice(&[0; 8]);
+        for row in self.rows.drain(..) {
+            rowbinary::serialize_row_binary_as_output(&mut batch, &row)?;
+        }
+        let len = (batch.len() - 8) as u64;
+        batch[..8].copy_from_slice(&len.to_le_bytes());
+        self.size = 0;
+
+        let file = self.file.take();
+        let dir = self.dir.clone();
+        let file = run_blocking(move || {
+            let mut file = match file {
+                Some(file) => file,
+                None => SpillFile::create(&dir)?,
+            };
+            file.file.write_all(&batch)?;
+            Ok(file)
+        })
+        .await?;
+
+        self.file = Some(file);
+        Ok(())
+    }
+
+    pub(crate) async fn finish(self) -> Result<SpilledRows<T>> {
+        let reader = match self.file {
+            Some(mut file) => Some(
+                run_blocking(move || {
+                    file.file.rewind()?;
+                    Ok(SpillReader {
+                        file,
+                        batch: Vec::new(),
+                        pos: 0,
+                    })
+                })
+                .await?,
+            ),
+            None => None,
+        };
+
+        Ok(SpilledRows {
+            reader,
+            in_memory: self.rows.into_iter(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+async fn run_blocking<R: Send + 'static>(
+    f: impl FnOnce() -> io::Result<R> + Send + 'static,
+) -> Result<R> {
+    let result = tokio::task::spawn_blocking(f)
+        .await
+        .expect("spilling task panicked");
+    Ok(result?)
+}
+
+/// Rows collected by [`Query::fetch_all_spilled`], in the order returned
+/// by the server.
+///
+/// Spilled rows are read from the file lazily, batch by batch,
+/// so iterating doesn't exceed the memory budget either.
+/// The file is removed once this iterator is dropped.
+///
+/// Note: reading the file is blocking, so prefer iterating outside of
+/// async tasks, e.g. using [`tokio::task::spawn_blocking`].
+///
+/// [`Query::fetch_all_spilled`]: crate::query::Query::fetch_all_spilled
+pub struct SpilledRows<T> {
+    reader: Option<SpillReader>,
+    in_memory: vec::IntoIter<T>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> SpilledRows<T> {
+    /// Returns `true` if any rows have been spilled to a file.
+    pub fn is_spilled(&self) -> bool {
+        self.reader.is_some()
+    }
+}
+
+impl<T: RowOwned + RowRead> Iterator for SpilledRows<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(reader) = &mut self.reader {
+            match reader.next::<T>() {
+                Ok(Some(row)) => return Some(Ok(row)),
+                Ok(None) => self.reader = None,
+                Err(err) => {
+                    // The rest of rows cannot be read in the right order.
+                    self.reader = None;
+                    self.in_memory = Vec::new().into_iter();
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        self.in_memory.next().map(Ok)
+    }
+}
+
+struct SpillReader {
+    file: SpillFile,
+    batch: Vec<u8>,
+    pos: usize,
+}
+
+impl SpillReader {
+    fn next<T: RowOwned + RowRead>(&mut self) -> Result<Option<T>> {
+        if self.pos == self.batch.len() {
+            let mut len = [0; 8];
+            match self.file.file.read_exact(&mut len) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(err.into()),
+            }
+
+            self.batch.resize(u64::from_le_bytes(len) as usize, 0);
+            self.file.file.read_exact(&mut self.batch)?;
+            self.pos = 0;
+        }
+
+        let mut slice = &self.batch[self.pos..];
+        let row = rowbinary::deserialize_row::<T::Value<'_>>(&mut slice, None)?;
+        self.pos = self.batch.len() - slice.len();
+        Ok(Some(row))
+    }
+}
+
+/// A file removed on drop.
+struct SpillFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl SpillFile {
+    fn create(dir: &Path) -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        loop {
+            let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+            let path = dir.join(format!("clickhouse-spill-{}-{n}", std::process::id()));
+
+            match OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(file) => return Ok(Self { path, file }),
+                // Left by another process with the same PID.
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
@@ -19,9 +19,21 @@ impl<S: Serialize> Bind for S {
 
 /// Bound the provided string as an identifier.
 /// It can be used for table names, for instance.
+///
+/// The whole string is a single identifier, embedded backticks are escaped,
+/// so `"db.table"` is bound as `` `db.table` ``, not as a table in `db`.
+/// Use [`Identifier::qualified`] for that.
 #[derive(Copy, Clone)]
 pub struct Identifier<'a>(pub &'a str);
 
+impl<'a> Identifier<'a> {
+    /// Creates a table name qualified by the database,
+    /// bound as `` `database`.`table` ``.
+    pub fn qualified(database: &'a str, table: &'a str) -> QualifiedTable<'a> {
+        QualifiedTable::new(database, table)
+    }
+}
+
 impl sealed::Sealed for Identifier<'_> {}
 
 impl Bind for Identifier<'_> {
@@ -31,6 +43,249 @@ impl Bind for Identifier<'_> {
     }
 }
 
+/// A table name optionally qualified by the database.
+///
+/// Bound as `` `database`.`table` `` or `` `table` ``, each part is escaped
+/// separately. Also accepted by [`Client::insert`](crate::Client::insert).
+///
+/// `&str` is converted into an unqualified name as is, without splitting
+/// on dots, so the table is looked up in the database of the client.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct QualifiedTable<'a> {
+    pub database: Option<&'a str>,
+    pub table: &'a str,
+}
+
+impl<'a> QualifiedTable<'a> {
+    /// Creates a table name qualified by the database.
+    pub fn new(database: &'a str, table: &'a str) -> Self {
+        Self {
+            database: Some(database),
+            table,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for QualifiedTable<'a> {
+    fn from(table: &'a str) -> Self {
+        Self {
+            database: None,
+            table,
+        }
+    }
+}
+
+impl<'a> From<&'a String> for QualifiedTable<'a> {
+    fn from(table: &'a String) -> Self {
+        Self::from(table.as_str())
+    }
+}
+
+impl<'a> From<(&'a str, &'a str)> for QualifiedTable<'a> {
+    fn from((database, table): (&'a str, &'a str)) -> Self {
+        Self::new(database, table)
+    }
+}
+
+impl sealed::Sealed for QualifiedTable<'_> {}
+
+impl Bind for QualifiedTable<'_> {
+    fn write(&self, dst: &mut impl fmt::Write) -> Result<(), String> {
+        if let Some(database) = self.database {
+            Identifier(database).write(dst)?;
+            dst.write_char('.').map_err(|err| err.to_string())?;
+        }
+        Identifier(self.table).write(dst)
+    }
+}
+
+/// Bound the wrapped value as a typed ClickHouse literal.
+///
+/// [`Bind`] is implemented for all [`Serialize`] types, so types from other
+/// crates can't get dedicated implementations, and their `Serialize` output
+/// isn't always a valid literal (or requires features, e.g. `uuid/serde`).
+/// This wrapper renders them properly instead:
+///
+/// | Type                                                  | Literal                                           |
+/// |-------------------------------------------------------|---------------------------------------------------|
+/// | `std::net::{IpAddr, Ipv4Addr, Ipv6Addr}`              | `toIPv4('127.0.0.1')`, `toIPv6('::1')`            |
+/// | `uuid::Uuid` (`uuid` feature)                         | `toUUID('67e55044-10b1-426f-9247-bb680e5fe0c8')`  |
+/// | `chrono::NaiveDate`, `time::Date`                     | `toDate32('2024-01-02')`                          |
+/// | `chrono::DateTime<Tz>`, `time::OffsetDateTime`        | `toDateTime64('2024-01-02 03:04:05.000000006', 9, 'UTC')` |
+///
+/// Timestamps are converted to UTC, so they're compared correctly to
+/// `DateTime` and `DateTime64` columns regardless of the server timezone.
+///
+/// # Example
+/// ```
+/// # use std::net::Ipv4Addr;
+/// use clickhouse::sql::Literal;
+///
+/// # let client = clickhouse::Client::default();
+/// let query = client
+///     .query("SELECT count() FROM logs WHERE ip = ?")
+///     .bind(Literal(Ipv4Addr::LOCALHOST));
+/// assert_eq!(
+///     query.sql_display().to_string(),
+///     "SELECT count() FROM logs WHERE ip = toIPv4('127.0.0.1')",
+/// );
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Literal<T>(pub T);
+
+macro_rules! impl_literal {
+    ($(#[$attr:meta])* $ty:ty, |$value:ident, $dst:ident| $body:expr) => {
+        $(#[$attr])*
+        impl sealed::Sealed for Literal<$ty> {}
+
+        $(#[$attr])*
+        impl Bind for Literal<$ty> {
+            fn write(&self, $dst: &mut impl fmt::Write) -> Result<(), String> {
+                let $value = &self.0;
+                $body.map_err(|err: fmt::Error| err.to_string())
+            }
+        }
+    };
+}
+
+impl_literal!(std::net::Ipv4Addr, |ip, dst| write!(dst, "toIPv4('{ip}')"));
+impl_literal!(std::net::Ipv6Addr, |ip, dst| write!(dst, "toIPv6('{ip}')"));
+impl_literal!(std::net::IpAddr, |ip, dst| match ip {
+    std::net::IpAddr::V4(ip) => write!(dst, "toIPv4('{ip}')"),
+    std::net::IpAddr::V6(ip) => write!(dst, "toIPv6('{ip}')"),
+});
+
+impl_literal!(
+    #[cfg(feature = "uuid")]
+    uuid::Uuid,
+    |uuid, dst| write!(dst, "toUUID('{}')", uuid.hyphenated())
+);
+
+impl_literal!(
+    #[cfg(feature = "chrono")]
+    chrono::NaiveDate,
+    |date, dst| write!(dst, "toDate32('{}')", date.format("%Y-%m-%d"))
+);
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> sealed::Sealed for Literal<chrono::DateTime<Tz>> {}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> Bind for Literal<chrono::DateTime<Tz>> {
+    fn write(&self, dst: &mut impl fmt::Write) -> Result<(), String> {
+        let utc = self.0.naive_utc();
+        write!(
+            dst,
+            "toDateTime64('{}', 9, 'UTC')",
+            utc.format("%Y-%m-%d %H:%M:%S%.9f")
+        )
+        .map_err(|err| err.to_string())
+    }
+}
+
+impl_literal!(
+    #[cfg(feature = "time")]
+    time::Date,
+    |date, dst| write!(
+        dst,
+        "toDate32('{:04}-{:02}-{:02}')",
+        date.year(),
+        date.month() as u8,
+        date.day()
+    )
+);
+
+impl_literal!(
+    #[cfg(feature = "time")]
+    time::OffsetDateTime,
+    |dt, dst| {
+        let utc = dt.to_offset(time::UtcOffset::UTC);
+        write!(
+            dst,
+            "toDateTime64('{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:09}', 9, 'UTC')",
+            utc.year(),
+            utc.month() as u8,
+            utc.day(),
+            utc.hour(),
+            utc.minute(),
+            utc.second(),
+            utc.nanosecond()
+        )
+    }
+);
+
 mod sealed {
     pub trait Sealed {}
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    fn check(value: impl Bind) -> String {
+        let mut out = String::new();
+        value.write(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn ip_literals() {
+        assert_eq!(check(Literal(Ipv4Addr::LOCALHOST)), "toIPv4('127.0.0.1')");
+        assert_eq!(check(Literal(Ipv6Addr::LOCALHOST)), "toIPv6('::1')");
+        assert_eq!(
+            check(Literal(IpAddr::from([10, 0, 0, 1]))),
+            "toIPv4('10.0.0.1')"
+        );
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn uuid_literal() {
+        let uuid = uuid::Uuid::from_u128(0x67e5504410b1426f9247bb680e5fe0c8);
+        assert_eq!(
+            check(Literal(uuid)),
+            "toUUID('67e55044-10b1-426f-9247-bb680e5fe0c8')"
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_literals() {
+        use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        assert_eq!(check(Literal(date)), "toDate32('2024-01-02')");
+
+        let dt = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        assert_eq!(
+            check(Literal(dt)),
+            "toDateTime64('2024-01-02 03:04:05.000000000', 9, 'UTC')"
+        );
+
+        // Converted to UTC.
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        let dt = offset.with_ymd_and_hms(2024, 1, 2, 0, 30, 0).unwrap()
+            + chrono::Duration::nanoseconds(6);
+        assert_eq!(
+            check(Literal(dt)),
+            "toDateTime64('2024-01-01 23:30:00.000000006', 9, 'UTC')"
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_literals() {
+        use time::macros::{date, datetime};
+
+        assert_eq!(
+            check(Literal(date!(2024 - 01 - 02))),
+            "toDate32('2024-01-02')"
+        );
+        assert_eq!(
+            check(Literal(datetime!(2024-01-02 0:30:00.000000006 +1))),
+            "toDateTime64('2024-01-01 23:30:00.000000006', 9, 'UTC')"
+        );
+    }
+}
@@ -5,11 +5,13 @@ use crate::{
     row::{self, Row},
 };
 
-pub use bind::{Bind, Identifier};
+pub use bind::{Bind, Identifier, Literal, QualifiedTable};
 
 mod bind;
 pub(crate) mod escape;
 pub(crate) mod ser;
+pub(crate) mod split;
+pub(crate) mod update;
 
 #[derive(Debug, Clone)]
 pub(crate) enum SqlBuilder {
@@ -22,6 +24,8 @@ pub(crate) enum Part {
     Arg,
     Fields,
     Text(String),
+    /// A bound argument, kept separately to be redacted, see [`SqlBuilder::redacted`].
+    Bound(String),
 }
 
 /// Display SQL query as string.
@@ -33,7 +37,7 @@ impl fmt::Display for SqlBuilder {
                     match part {
                         Part::Arg => f.write_char('?')?,
                         Part::Fields => f.write_str("?fields")?,
-                        Part::Text(text) => f.write_str(text)?,
+                        Part::Text(text) | Part::Bound(text) => f.write_str(text)?,
                     }
                 }
             }
@@ -84,7 +88,7 @@ impl SqlBuilder {
                 return self.error(format_args!("invalid argument: {err}"));
             }
 
-            *part = Part::Text(s);
+            *part = Part::Bound(s);
         } else {
             self.error("unexpected bind(), all arguments are already bound");
         }
@@ -104,6 +108,16 @@ impl SqlBuilder {
         }
     }
 
+    /// Surrounds the query with `prefix` and `suffix`, e.g. to use it as a subquery.
+    pub(crate) fn wrap(&mut self, prefix: &str, suffix: &str) {
+        let Self::InProgress(parts) = self else {
+            return;
+        };
+
+        parts.insert(0, Part::Text(prefix.into()));
+        parts.push(Part::Text(suffix.into()));
+    }
+
     pub(crate) fn finish(self) -> Result<String> {
         let mut sql = String::new();
 
@@ -111,7 +125,7 @@ impl SqlBuilder {
             Self::InProgress(parts) => {
                 for part in parts {
                     match part {
-                        Part::Text(text) => sql.push_str(&text),
+                        Part::Text(text) | Part::Bound(text) => sql.push_str(&text),
                         Part::Arg => {
                             return Err(Error::InvalidParams(
                                 error_msg("unbound query argument").into(),
@@ -131,6 +145,37 @@ impl SqlBuilder {
         }
     }
 
+    /// Returns the number of bound arguments.
+    pub(crate) fn bound_args(&self) -> usize {
+        match self {
+            Self::InProgress(parts) => parts
+                .iter()
+                .filter(|part| matches!(part, Part::Bound(_)))
+                .count(),
+            Self::Failed(_) => 0,
+        }
+    }
+
+    /// Returns the query with bound arguments replaced by `redact`,
+    /// i.e. the same as [`SqlBuilder::finish`] returns if `redact` returns
+    /// arguments as is.
+    pub(crate) fn redacted(&self, redact: &dyn Fn(&str) -> String) -> String {
+        let mut sql = String::new();
+
+        if let Self::InProgress(parts) = self {
+            for part in parts {
+                match part {
+                    Part::Text(text) => sql.push_str(text),
+                    Part::Bound(arg) => sql.push_str(&redact(arg)),
+                    Part::Arg => sql.push('?'),
+                    Part::Fields => sql.push_str("?fields"),
+                }
+            }
+        }
+
+        sql
+    }
+
     fn error(&mut self, err: impl Display) {
         *self = Self::Failed(error_msg(err));
     }
@@ -192,6 +237,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn redacted() {
+        let mut sql = SqlBuilder::new("SELECT ?fields FROM ? WHERE token = ? AND b < ?");
+        sql.bind_arg(Identifier("t"));
+        sql.bind_arg("secret");
+        sql.bind_fields::<Row>();
+        assert_eq!(sql.bound_args(), 2);
+
+        let redact = |arg: &str| {
+            if arg.starts_with('\'') {
+                "'***'".to_string()
+            } else {
+                arg.to_string()
+            }
+        };
+        assert_eq!(
+            sql.redacted(&redact),
+            "SELECT `a`,`b` FROM `t` WHERE token = '***' AND b < ?"
+        );
+
+        sql.bind_arg(42);
+        assert_eq!(sql.bound_args(), 3);
+        assert_eq!(
+            sql.finish().unwrap(),
+            "SELECT `a`,`b` FROM `t` WHERE token = 'secret' AND b < 42"
+        );
+    }
+
     #[test]
     fn in_clause() {
         fn t(arg: &[&str], expected: &str) {
@@ -235,6 +308,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn qualified_table() {
+        let mut sql = SqlBuilder::new("INSERT INTO ? SELECT * FROM ?");
+        sql.bind_arg(Identifier::qualified("my`db", "t.1"));
+        sql.bind_arg(QualifiedTable::from("db.t"));
+        assert_eq!(
+            sql.finish().unwrap(),
+            r"INSERT INTO `my\`db`.`t.1` SELECT * FROM `db.t`"
+        );
+    }
+
     #[test]
     fn option_as_null() {
         let mut sql = SqlBuilder::new("SELECT 1 FROM test WHERE a = ?");
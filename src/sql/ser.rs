@@ -4,7 +4,7 @@ use super::escape;
 use crate::types::{Int256, UInt256};
 use serde::{
     Serialize,
-    ser::{self, SerializeSeq, SerializeTuple, Serializer},
+    ser::{self, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple, Serializer},
 };
 use thiserror::Error;
 
@@ -77,7 +77,7 @@ macro_rules! forward_to_display {
 impl<'a, W: Write> Serializer for SqlSerializer<'a, W> {
     type Error = SerializerError;
     type Ok = ();
-    type SerializeMap = Impossible;
+    type SerializeMap = SqlListSerializer<'a, W>;
     type SerializeSeq = SqlListSerializer<'a, W>;
     type SerializeStruct = Impossible;
     type SerializeStructVariant = Impossible;
@@ -85,11 +85,7 @@ impl<'a, W: Write> Serializer for SqlSerializer<'a, W> {
     type SerializeTupleStruct = Impossible;
     type SerializeTupleVariant = Impossible;
 
-    unsupported!(
-        serialize_map(Option<usize>) -> Result<Impossible>,
-        serialize_unit,
-        serialize_unit_struct(&'static str),
-    );
+    unsupported!(serialize_unit, serialize_unit_struct(&'static str),);
 
     #[inline]
     fn serialize_bytes(self, value: &[u8]) -> Result {
@@ -174,6 +170,17 @@ impl<'a, W: Write> Serializer for SqlSerializer<'a, W> {
         })
     }
 
+    #[inline]
+    fn serialize_map(self, _len: Option<usize>) -> Result<SqlListSerializer<'a, W>> {
+        self.writer.write_char('{')?;
+        Ok(SqlListSerializer {
+            writer: self.writer,
+            in_param: self.in_param,
+            has_items: false,
+            closing_char: '}',
+        })
+    }
+
     #[inline]
     fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result {
         _value.serialize(self)
@@ -339,6 +346,38 @@ impl<W: Write> SerializeTuple for SqlListSerializer<'_, W> {
     }
 }
 
+/// Writes `{key:value,...}`, the literal of `Map`.
+impl<W: Write> SerializeMap for SqlListSerializer<'_, W> {
+    type Error = SerializerError;
+    type Ok = ();
+
+    #[inline]
+    fn serialize_key<T>(&mut self, key: &T) -> Result
+    where
+        T: Serialize + ?Sized,
+    {
+        SerializeSeq::serialize_element(self, key)
+    }
+
+    #[inline]
+    fn serialize_value<T>(&mut self, value: &T) -> Result
+    where
+        T: Serialize + ?Sized,
+    {
+        self.writer.write_char(':')?;
+        value.serialize(SqlSerializer {
+            writer: self.writer,
+            in_param: self.in_param,
+            skip_next_string_escape: false,
+        })
+    }
+
+    #[inline]
+    fn end(self) -> Result {
+        SerializeSeq::end(self)
+    }
+}
+
 // === ParamSerializer ===
 
 struct ParamSerializer<'a, W> {
@@ -348,7 +387,7 @@ struct ParamSerializer<'a, W> {
 impl<'a, W: Write> Serializer for ParamSerializer<'a, W> {
     type Error = SerializerError;
     type Ok = ();
-    type SerializeMap = Impossible;
+    type SerializeMap = SqlListSerializer<'a, W>;
     type SerializeSeq = SqlListSerializer<'a, W>;
     type SerializeStruct = Impossible;
     type SerializeStructVariant = Impossible;
@@ -356,11 +395,7 @@ impl<'a, W: Write> Serializer for ParamSerializer<'a, W> {
     type SerializeTupleStruct = Impossible;
     type SerializeTupleVariant = Impossible;
 
-    unsupported!(
-        serialize_map(Option<usize>) -> Result<Impossible>,
-        serialize_unit,
-        serialize_unit_struct(&'static str),
-    );
+    unsupported!(serialize_unit, serialize_unit_struct(&'static str),);
 
     forward_to_display!(
         serialize_i8(i8),
@@ -418,6 +453,17 @@ impl<'a, W: Write> Serializer for ParamSerializer<'a, W> {
         })
     }
 
+    #[inline]
+    fn serialize_map(self, _len: Option<usize>) -> Result<SqlListSerializer<'a, W>> {
+        self.writer.write_char('{')?;
+        Ok(SqlListSerializer {
+            writer: self.writer,
+            in_param: true,
+            has_items: false,
+            closing_char: '}',
+        })
+    }
+
     #[inline]
     fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result {
         _value.serialize(self)
@@ -506,6 +552,154 @@ impl<'a, W: Write> Serializer for ParamSerializer<'a, W> {
     }
 }
 
+// === FieldsSerializer ===
+
+/// Serializes top-level fields of a struct into SQL literals or values of parameters.
+struct FieldsSerializer<'a> {
+    fields: &'a mut Vec<(&'static str, String)>,
+    as_params: bool,
+}
+
+impl<'a> Serializer for FieldsSerializer<'a> {
+    type Error = SerializerError;
+    type Ok = ();
+    type SerializeMap = Impossible;
+    type SerializeSeq = Impossible;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible;
+    type SerializeTuple = Impossible;
+    type SerializeTupleStruct = Impossible;
+    type SerializeTupleVariant = Impossible;
+
+    unsupported!(
+        serialize_map(Option<usize>) -> Result<Impossible>,
+        serialize_seq(Option<usize>) -> Result<Impossible>,
+        serialize_tuple(usize) -> Result<Impossible>,
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none,
+        serialize_unit,
+        serialize_unit_struct(&'static str),
+    );
+
+    #[inline]
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result {
+        Err(SerializerError::Unsupported("serialize_some"))
+    }
+
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result {
+        Err(SerializerError::Unsupported("serialize_unit_variant"))
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result {
+        Err(SerializerError::Unsupported("serialize_newtype_variant"))
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Impossible> {
+        Err(SerializerError::Unsupported("serialize_tuple_struct"))
+    }
+
+    #[inline]
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Impossible> {
+        Err(SerializerError::Unsupported("serialize_tuple_variant"))
+    }
+
+    #[inline]
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self> {
+        self.fields.reserve(len);
+        Ok(self)
+    }
+
+    #[inline]
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(SerializerError::Unsupported("serialize_struct_variant"))
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+}
+
+impl SerializeStruct for FieldsSerializer<'_> {
+    type Error = SerializerError;
+    type Ok = ();
+
+    #[inline]
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result {
+        let mut literal = String::new();
+        let result = if self.as_params {
+            value.serialize(ParamSerializer {
+                writer: &mut literal,
+            })
+        } else {
+            value.serialize(SqlSerializer {
+                writer: &mut literal,
+                in_param: false,
+                skip_next_string_escape: false,
+            })
+        };
+        result.map_err(|err| SerializerError::Custom(format!("field `{key}`: {err}")))?;
+
+        self.fields.push((key, literal));
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result {
+        Ok(())
+    }
+}
+
 // === Public API ===
 
 pub(crate) fn write_arg(writer: &mut impl Write, value: &impl Serialize) -> Result<(), String> {
@@ -518,6 +712,30 @@ pub(crate) fn write_arg(writer: &mut impl Write, value: &impl Serialize) -> Resu
         .map_err(|err| err.to_string())
 }
 
+/// Returns serde names of top-level fields of a struct with their values as SQL literals.
+pub(crate) fn struct_fields(value: &impl Serialize) -> Result<Vec<(&'static str, String)>, String> {
+    let mut fields = Vec::new();
+    value
+        .serialize(FieldsSerializer {
+            fields: &mut fields,
+            as_params: false,
+        })
+        .map_err(|err| err.to_string())?;
+    Ok(fields)
+}
+
+/// Returns serde names of top-level fields of a struct with their values as parameters.
+pub(crate) fn struct_params(value: &impl Serialize) -> Result<Vec<(&'static str, String)>, String> {
+    let mut fields = Vec::new();
+    value
+        .serialize(FieldsSerializer {
+            fields: &mut fields,
+            as_params: true,
+        })
+        .map_err(|err| err.to_string())?;
+    Ok(fields)
+}
+
 pub(crate) fn write_param(writer: &mut impl Write, value: &impl Serialize) -> Result<(), String> {
     value
         .serialize(ParamSerializer { writer })
@@ -534,6 +752,36 @@ mod tests {
         out
     }
 
+    #[test]
+    fn it_writes_struct_params() {
+        #[derive(Serialize)]
+        struct Event<'a> {
+            id: u64,
+            #[serde(rename = "title")]
+            name: &'a str,
+            tags: Vec<&'a str>,
+            score: Option<f64>,
+        }
+
+        let event = Event {
+            id: 42,
+            name: "it's",
+            tags: vec!["a", "b"],
+            score: None,
+        };
+
+        assert_eq!(
+            struct_params(&event).unwrap(),
+            [
+                ("id", "42".into()),
+                ("title", r"it\'s".into()),
+                ("tags", "['a','b']".into()),
+                ("score", r"\N".into()),
+            ]
+        );
+        assert!(struct_params(&42).is_err());
+    }
+
     #[test]
     fn it_writes_param_options() {
         assert_eq!(check_param(None::<i32>), r"\N");
@@ -622,9 +870,42 @@ mod tests {
         assert_eq!(check(vec![42, 43]), "[42,43]");
     }
 
+    #[test]
+    fn it_writes_nested_arrays() {
+        assert_eq!(check(vec![vec![1, 2], vec![]]), "[[1,2],[]]");
+        assert_eq!(check(vec![vec![vec!["a'b"]]]), "[[['a\\'b']]]");
+    }
+
     #[test]
     fn it_writes_tuples() {
         assert_eq!(check((42, 43)), "(42,43)");
+        assert_eq!(check((42, "foo", vec![(1, 2.5)])), "(42,'foo',[(1,2.5)])");
+        assert_eq!(
+            check(vec![("a", Some(1)), ("b", None)]),
+            "[('a',1),('b',NULL)]"
+        );
+    }
+
+    #[test]
+    fn it_writes_maps() {
+        use std::collections::{BTreeMap, HashMap};
+
+        assert_eq!(check(BTreeMap::<u32, u32>::new()), "{}");
+        assert_eq!(check(HashMap::from([("a'b", 1)])), "{'a\\'b':1}");
+        assert_eq!(
+            check(BTreeMap::from([("a", vec![1, 2]), ("b", vec![])])),
+            "{'a':[1,2],'b':[]}"
+        );
+        assert_eq!(
+            check(vec![BTreeMap::from([(1, (2, "x"))])]),
+            "[{1:(2,'x')}]"
+        );
+
+        assert_eq!(
+            check_param(BTreeMap::from([("a", "b"), ("c", "d")])),
+            "{'a':'b','c':'d'}"
+        );
+        assert_eq!(check_param(HashMap::from([(1, None::<u32>)])), "{1:NULL}");
     }
 
     #[test]
@@ -637,7 +918,6 @@ mod tests {
     #[test]
     fn it_fails_on_unsupported() {
         let mut out = String::new();
-        assert!(write_arg(&mut out, &std::collections::HashMap::<u32, u32>::new()).is_err());
         assert!(write_arg(&mut out, &()).is_err());
 
         #[derive(Serialize)]
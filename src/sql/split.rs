@@ -0,0 +1,228 @@
+/// Splits a multi-statement SQL script on top-level semicolons.
+///
+/// Semicolons inside string literals, quoted identifiers and comments are
+/// ignored. Statements are trimmed, empty ones and ones consisting of comments
+/// only are skipped.
+pub(crate) fn split_statements(script: &str) -> Vec<&str> {
+    let bytes = script.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    // Set once a non-comment token is found in the current statement.
+    let mut has_code = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                has_code = true;
+                i = skip_quoted(bytes, i + 1, quote);
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(bytes, i + 2),
+            b'#' if matches!(bytes.get(i + 1), Some(b' ' | b'!')) => i = skip_line(bytes, i + 2),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i + 2),
+            b';' => {
+                if has_code {
+                    statements.push(script[start..i].trim());
+                }
+                has_code = false;
+                i += 1;
+                start = i;
+            }
+            c => {
+                has_code |= !c.is_ascii_whitespace();
+                i += 1;
+            }
+        }
+    }
+
+    if has_code {
+        statements.push(script[start..].trim());
+    }
+    statements
+}
+
+/// Returns words of the statement up to the first parenthesis, comma or
+/// semicolon, skipping comments. Quoted identifiers are kept as is, e.g.
+/// `` `db`.`t` `` is a single word.
+pub(crate) fn leading_words(sql: &str) -> impl Iterator<Item = &str> {
+    let bytes = sql.as_bytes();
+    let mut i = 0;
+
+    std::iter::from_fn(move || {
+        // Skip whitespaces and comments.
+        loop {
+            match bytes.get(i)? {
+                c if c.is_ascii_whitespace() => i += 1,
+                b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(bytes, i + 2),
+                b'#' if matches!(bytes.get(i + 1), Some(b' ' | b'!')) => {
+                    i = skip_line(bytes, i + 2)
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i + 2),
+                b'(' | b')' | b',' | b';' => return None,
+                _ => break,
+            }
+        }
+
+        let start = i;
+        while i < bytes.len() {
+            match bytes[i] {
+                quote @ (b'`' | b'"') => i = skip_quoted(bytes, i + 1, quote),
+                b'(' | b')' | b',' | b';' => break,
+                c if c.is_ascii_whitespace() => break,
+                _ => i += 1,
+            }
+        }
+
+        Some(&sql[start..i])
+    })
+}
+
+/// Replaces string literals with `'?'`, because bound arguments can be sensitive.
+/// Quoted identifiers and comments are kept as is.
+pub(crate) fn redact_literals(sql: &str) -> String {
+    let bytes = sql.as_bytes();
+    let mut redacted = String::with_capacity(sql.len());
+    // The start of the part not yet copied.
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => {
+                redacted.push_str(&sql[start..i]);
+                redacted.push_str("'?'");
+                i = skip_quoted(bytes, i + 1, b'\'');
+                start = i;
+            }
+            quote @ (b'"' | b'`') => i = skip_quoted(bytes, i + 1, quote),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(bytes, i + 2),
+            b'#' if matches!(bytes.get(i + 1), Some(b' ' | b'!')) => i = skip_line(bytes, i + 2),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i + 2),
+            _ => i += 1,
+        }
+    }
+
+    redacted.push_str(&sql[start..]);
+    redacted
+}
+
+/// Returns the position after the closing quote or the end of the script.
+/// Both backslash escapes and doubled quotes are supported.
+fn skip_quoted(bytes: &[u8], mut i: usize, quote: u8) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            c if c == quote && bytes.get(i + 1) == Some(&quote) => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn skip_line(bytes: &[u8], i: usize) -> usize {
+    bytes[i.min(bytes.len())..]
+        .iter()
+        .position(|&c| c == b'\n')
+        .map_or(bytes.len(), |pos| i + pos + 1)
+}
+
+/// ClickHouse supports nested block comments.
+fn skip_block_comment(bytes: &[u8], mut i: usize) -> usize {
+    let mut depth = 1;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'/', Some(b'*')) => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', Some(b'/')) => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_statements() {
+        assert_eq!(split_statements(""), Vec::<&str>::new());
+        assert_eq!(split_statements(" ;\n; "), Vec::<&str>::new());
+        assert_eq!(split_statements("SELECT 1"), ["SELECT 1"]);
+        assert_eq!(
+            split_statements("SELECT 1;\nSELECT 2;\n"),
+            ["SELECT 1", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn it_ignores_quoted_semicolons() {
+        assert_eq!(
+            split_statements(r"SELECT ';', 'a\';b', 'c'';d'; SELECT 2"),
+            [r"SELECT ';', 'a\';b', 'c'';d'", "SELECT 2"]
+        );
+        assert_eq!(
+            split_statements(r#"SELECT 1 AS "a;b", 2 AS `c;d`; SELECT 2"#),
+            [r#"SELECT 1 AS "a;b", 2 AS `c;d`"#, "SELECT 2"]
+        );
+        // Unterminated literals are left to the server.
+        assert_eq!(split_statements("SELECT 'a;b"), ["SELECT 'a;b"]);
+    }
+
+    #[test]
+    fn it_ignores_commented_semicolons() {
+        assert_eq!(
+            split_statements("-- a; b\nSELECT 1; # c; d\nSELECT 2 /* e; /* f; */ g; */"),
+            ["-- a; b\nSELECT 1", "# c; d\nSELECT 2 /* e; /* f; */ g; */"]
+        );
+        // Comments only.
+        assert_eq!(
+            split_statements("SELECT 1; -- the end;\n/* ; */"),
+            ["SELECT 1"]
+        );
+        // Not a comment.
+        assert_eq!(
+            split_statements("SELECT 2 - -1; SELECT 3"),
+            ["SELECT 2 - -1", "SELECT 3"]
+        );
+    }
+
+    #[test]
+    fn it_returns_leading_words() {
+        let words = |sql| leading_words(sql).collect::<Vec<_>>();
+
+        assert_eq!(words(""), Vec::<&str>::new());
+        assert_eq!(
+            words(" -- comment\n/* c */ INSERT INTO db.t(a, b) FORMAT RowBinary"),
+            ["INSERT", "INTO", "db.t"]
+        );
+        assert_eq!(
+            words("alter table `my db`.\"my t\" DELETE WHERE 1"),
+            ["alter", "table", "`my db`.\"my t\"", "DELETE", "WHERE", "1"]
+        );
+        assert_eq!(words("DROP TABLE t; SELECT 1"), ["DROP", "TABLE", "t"]);
+    }
+
+    #[test]
+    fn it_redacts_literals() {
+        assert_eq!(redact_literals("SELECT 1"), "SELECT 1");
+        assert_eq!(
+            redact_literals("SELECT * FROM t WHERE a = 'sec''ret' AND b = 'x\\'y' -- 'c'"),
+            "SELECT * FROM t WHERE a = '?' AND b = '?' -- 'c'"
+        );
+        assert_eq!(
+            redact_literals("SELECT `it's` FROM \"t's\" WHERE s = 'ünï' /* 'c' */"),
+            "SELECT `it's` FROM \"t's\" WHERE s = '?' /* 'c' */"
+        );
+        assert_eq!(redact_literals("SELECT 'unclosed"), "SELECT '?'");
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use super::{escape, ser};
+use crate::{
+    error::{Error, Result},
+    row::{Row, RowKind},
+};
+
+/// Builds `ALTER TABLE <table> UPDATE ... WHERE ...` setting columns whose
+/// values differ between `old` and `new`, or returns `None` if there are none.
+///
+/// Values are written as SQL literals, so `?` is escaped in the result.
+pub(crate) fn update_diff<T: Row + Serialize>(
+    table: &str,
+    key: &[&str],
+    old: &T,
+    new: &T,
+) -> Result<Option<String>> {
+    if !matches!(T::KIND, RowKind::Struct) {
+        return Err(invalid("only structs are supported"));
+    }
+
+    if key.is_empty() {
+        return Err(invalid("at least one key column is required"));
+    }
+
+    let old = fields::<T>(old)?;
+    let new = fields::<T>(new)?;
+
+    let find = |column: &str| {
+        T::INSERT_COLUMN_NAMES
+            .iter()
+            .position(|name| *name == column)
+            .ok_or_else(|| invalid(format!("unknown key column `{column}`")))
+    };
+
+    let mut filter = String::new();
+    for (i, column) in key.iter().enumerate() {
+        let idx = find(column)?;
+        if old[idx] != new[idx] {
+            return Err(invalid(format!("key column `{column}` is changed")));
+        }
+
+        if i > 0 {
+            filter.push_str(" AND ");
+        }
+
+        escape::identifier(column, &mut filter).map_err(invalid)?;
+        if old[idx] == "NULL" {
+            filter.push_str(" IS NULL");
+        } else {
+            write!(filter, " = {}", old[idx]).map_err(invalid)?;
+        }
+    }
+
+    let mut assignments = String::new();
+    for (idx, column) in T::INSERT_COLUMN_NAMES.iter().enumerate() {
+        if old[idx] == new[idx] || key.contains(column) {
+            continue;
+        }
+
+        if !assignments.is_empty() {
+            assignments.push_str(", ");
+        }
+
+        escape::identifier(column, &mut assignments).map_err(invalid)?;
+        write!(assignments, " = {}", new[idx]).map_err(invalid)?;
+    }
+
+    if assignments.is_empty() {
+        return Ok(None);
+    }
+
+    let sql = format!("ALTER TABLE {table} UPDATE {assignments} WHERE {filter}");
+    Ok(Some(sql.replace('?', "??")))
+}
+
+/// Returns values of columns as SQL literals in the order of `T::INSERT_COLUMN_NAMES`.
+fn fields<T: Row + Serialize>(row: &T) -> Result<Vec<String>> {
+    let fields = ser::struct_fields(row).map_err(invalid)?;
+
+    let values = fields
+        .into_iter()
+        .filter(|(name, _)| !T::INSERT_SKIPPED_FIELDS.contains(name))
+        .map(|(_, value)| value)
+        .collect::<Vec<_>>();
+
+    if values.len() != T::INSERT_COLUMN_NAMES.len() {
+        // E.g. `#[clickhouse(nested)]` or `#[serde(skip_serializing_if)]` fields.
+        return Err(invalid(format!(
+            "{} has {} columns, but {} fields are serialized",
+            T::NAME,
+            T::INSERT_COLUMN_NAMES.len(),
+            values.len(),
+        )));
+    }
+
+    Ok(values)
+}
+
+fn invalid(reason: impl std::fmt::Display) -> Error {
+    Error::InvalidParams(format!("failed to build an update: {reason}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, crate::Row, Serialize)]
+    #[clickhouse(crate = "crate")]
+    struct Event {
+        id: u64,
+        name: String,
+        tags: Vec<String>,
+        score: Option<f64>,
+    }
+
+    fn sample() -> Event {
+        Event {
+            id: 42,
+            name: "foo".into(),
+            tags: vec!["a".into()],
+            score: Some(1.5),
+        }
+    }
+
+    #[test]
+    fn it_updates_changed_columns() {
+        let old = sample();
+        assert_eq!(
+            update_diff("events", &["id"], &old, &sample()).unwrap(),
+            None
+        );
+
+        let new = Event {
+            name: "it's?".into(),
+            score: None,
+            ..sample()
+        };
+        assert_eq!(
+            update_diff("events", &["id"], &old, &new).unwrap().unwrap(),
+            "ALTER TABLE events UPDATE `name` = 'it\\'s??', `score` = NULL WHERE `id` = 42"
+        );
+
+        let new = Event {
+            tags: vec![],
+            ..sample()
+        };
+        assert_eq!(
+            update_diff("events", &["id", "score"], &old, &new)
+                .unwrap()
+                .unwrap(),
+            "ALTER TABLE events UPDATE `tags` = [] WHERE `id` = 42 AND `score` = 1.5"
+        );
+
+        let old = Event {
+            score: None,
+            ..sample()
+        };
+        let new = Event {
+            score: None,
+            id: 1,
+            ..sample()
+        };
+        assert_eq!(
+            update_diff("events", &["score"], &old, &new)
+                .unwrap()
+                .unwrap(),
+            "ALTER TABLE events UPDATE `id` = 1 WHERE `score` IS NULL"
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_keys() {
+        let old = sample();
+        let new = Event { id: 1, ..sample() };
+
+        for (key, expected) in [
+            (&[][..], "at least one key column is required"),
+            (&["id"], "key column `id` is changed"),
+            (&["unknown"], "unknown key column `unknown`"),
+        ] {
+            let err = update_diff("events", key, &old, &new).unwrap_err();
+            assert!(err.to_string().contains(expected), "{err}");
+        }
+    }
+}
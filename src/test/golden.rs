@@ -0,0 +1,278 @@
+//! Golden `RowBinary` fixtures and helpers to verify custom types against them.
+//!
+//! Every [`Fixture`] is a single value of some ClickHouse data type encoded
+//! exactly as the server sends it in `RowBinary` (and as it expects it on insert).
+//! The corpus is stable across versions of this crate: any change in the encoding
+//! of the existing fixtures is considered a breaking change.
+//!
+//! It's useful for users writing custom `serde` implementations (e.g. wrappers
+//! around decimals, enums, timestamps) to prove that their types are compatible
+//! with ClickHouse without running a server:
+//! ```
+//! use clickhouse::{Row, test::golden};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Cents(i64);
+//!
+//! #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+//! struct Price {
+//!     amount: Cents,
+//! }
+//!
+//! let fixture = golden::fixture("decimal64").unwrap();
+//! golden::assert_compatible(fixture, &Price { amount: Cents(-1) });
+//! ```
+
+use std::fmt::{self, Write};
+
+use clickhouse_types::{Column, DataTypeNode};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    Row,
+    error::{Error, Result},
+    row::RowKind,
+    row_metadata::RowMetadata,
+    rowbinary,
+};
+
+/// A single value of the specific data type encoded in `RowBinary`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    /// A unique name of the fixture.
+    pub name: &'static str,
+    /// A ClickHouse data type, e.g. `Nullable(UInt8)`.
+    pub data_type: &'static str,
+    /// The value encoded in `RowBinary`.
+    pub bytes: &'static [u8],
+}
+
+impl Fixture {
+    /// Returns a column with the provided name and the fixture's data type.
+    ///
+    /// # Panics
+    /// Panics if the data type cannot be parsed, that never happens for
+    /// fixtures provided by this module.
+    pub fn column(&self, name: &str) -> Column {
+        let data_type = DataTypeNode::new(self.data_type)
+            .unwrap_or_else(|err| panic!("invalid data type {}: {err}", self.data_type));
+        Column::new(name.to_string(), data_type)
+    }
+
+    /// Returns the fixture encoded in `RowBinaryWithNamesAndTypes`
+    /// as a single column with the provided name.
+    pub fn to_rbwnat(&self, name: &str) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        clickhouse_types::put_rbwnat_columns_header(&[self.column(name)], &mut buffer)
+            .expect("failed to write RBWNAT header");
+        buffer.extend_from_slice(self.bytes);
+        buffer
+    }
+}
+
+/// Returns the fixture with the provided name, if any.
+pub fn fixture(name: &str) -> Option<&'static Fixture> {
+    FIXTURES.iter().find(|f| f.name == name)
+}
+
+/// Serializes `row` in `RowBinary`.
+///
+/// If `columns` are provided, the row is validated against them,
+/// similarly to how [`Insert`] does it when validation is enabled.
+///
+/// [`Insert`]: crate::insert::Insert
+pub fn encode<T: Row + Serialize>(row: &T, columns: Option<&[Column]>) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    match columns {
+        Some(columns) => {
+            let metadata = RowMetadata::new_for_cursor::<T>(columns.to_vec())?;
+            rowbinary::serialize_with_validation(&mut buffer, row, &metadata)?;
+        }
+        None => rowbinary::serialize_row_binary(&mut buffer, row)?,
+    }
+    Ok(buffer)
+}
+
+/// Deserializes a single row from `RowBinary`.
+///
+/// If `columns` are provided, the row is validated against them,
+/// similarly to how [`RowCursor`] does it when validation is enabled.
+///
+/// Fails if `bytes` contain anything except the row.
+///
+/// [`RowCursor`]: crate::query::RowCursor
+pub fn decode<'a, T>(bytes: &'a [u8], columns: Option<&[Column]>) -> Result<T>
+where
+    T: Row + Deserialize<'a>,
+{
+    let metadata = columns
+        .map(|columns| RowMetadata::new_for_cursor::<T>(columns.to_vec()))
+        .transpose()?;
+
+    let mut input = bytes;
+    let row = rowbinary::deserialize_row(&mut input, metadata.as_ref())?;
+
+    if !input.is_empty() {
+        return Err(Error::BadResponse(format!(
+            "{} trailing bytes left after deserializing {}",
+            input.len(),
+            T::NAME,
+        )));
+    }
+
+    Ok(row)
+}
+
+/// Checks that `value` is encoded exactly as `fixture` in both directions,
+/// validating the row against the fixture's data type.
+///
+/// For structs, the only field is used as a column name.
+///
+/// # Panics
+/// Panics with a detailed message if the encoding doesn't match.
+#[track_caller]
+pub fn assert_compatible<T>(fixture: &Fixture, value: &T)
+where
+    T: Row + Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+{
+    let name = match T::KIND {
+        RowKind::Struct => T::COLUMN_NAMES.first().copied().unwrap_or("value"),
+        _ => "value",
+    };
+    let columns = [fixture.column(name)];
+
+    let encoded = encode(value, Some(&columns))
+        .unwrap_or_else(|err| panic!("failed to encode {value:?} as {}: {err}", fixture.name));
+    assert!(
+        encoded == fixture.bytes,
+        "encoding mismatch for fixture {} ({})\n expected: {}\n   actual: {}",
+        fixture.name,
+        fixture.data_type,
+        hex(fixture.bytes),
+        hex(&encoded),
+    );
+
+    let decoded: T = decode(fixture.bytes, Some(&columns))
+        .unwrap_or_else(|err| panic!("failed to decode fixture {}: {err}", fixture.name));
+    assert!(
+        decoded == *value,
+        "decoding mismatch for fixture {} ({})\n expected: {value:?}\n   actual: {decoded:?}",
+        fixture.name,
+        fixture.data_type,
+    );
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        write!(out, "{byte:02X}").unwrap();
+    }
+    out
+}
+
+macro_rules! fixtures {
+    ($($name:literal: $data_type:literal => $bytes:expr,)*) => {
+        /// All provided fixtures.
+        pub static FIXTURES: &[Fixture] = &[
+            $(Fixture { name: $name, data_type: $data_type, bytes: $bytes },)*
+        ];
+    };
+}
+
+fixtures! {
+    // Integers, little-endian.
+    "uint8": "UInt8" => &[0xC8],
+    "uint16": "UInt16" => &[0x34, 0x12],
+    "uint32": "UInt32" => &[0xEF, 0xBE, 0xAD, 0xDE],
+    "uint64": "UInt64" => &[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01],
+    "uint128": "UInt128" => &[
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    "int8": "Int8" => &[0xFF],
+    "int16": "Int16" => &[0xFE, 0xFF],
+    "int32": "Int32" => &[0xFD, 0xFF, 0xFF, 0xFF],
+    "int64": "Int64" => &[0xFC, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+    "int128": "Int128" => &[
+        0xFB, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    ],
+    "bool": "Bool" => &[0x01],
+
+    // IEEE 754, little-endian.
+    "float32": "Float32" => &[0x00, 0x00, 0xC0, 0x3F],
+    "float64": "Float64" => &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0xC0],
+
+    // Strings are prefixed with LEB128 length.
+    "string": "String" => b"\x05hello",
+    "string_empty": "String" => b"\x00",
+    "string_utf8": "String" => b"\x06\xE6\x97\xA5\xE6\x9C\xAC",
+    "fixed_string": "FixedString(4)" => b"ab\x00\x00",
+    "low_cardinality": "LowCardinality(String)" => b"\x01x",
+
+    // Nullable is prefixed with 1 for NULL and 0 for a value.
+    "nullable_null": "Nullable(UInt8)" => &[0x01],
+    "nullable_value": "Nullable(UInt8)" => &[0x00, 0x07],
+    "low_cardinality_nullable": "LowCardinality(Nullable(String))" => &[0x01],
+    "low_cardinality_fixed_string": "LowCardinality(FixedString(2))" => b"ab",
+    "low_cardinality_uint64": "LowCardinality(UInt64)" => &[0x2A, 0, 0, 0, 0, 0, 0, 0],
+    // `SimpleAggregateFunction` is encoded as its inner type too.
+    "simple_aggregate_function_low_cardinality":
+        "SimpleAggregateFunction(any, LowCardinality(Nullable(String)))" => b"\x00\x01a",
+
+    // Compound types.
+    "array": "Array(UInt16)" => &[0x02, 0x01, 0x00, 0x02, 0x00],
+    "array_empty": "Array(String)" => &[0x00],
+    "array_nested": "Array(Array(UInt8))" => &[0x02, 0x01, 0x01, 0x02, 0x02, 0x03],
+    "array_low_cardinality": "Array(LowCardinality(String))" => b"\x02\x01a\x02bc",
+    "array_low_cardinality_nullable": "Array(LowCardinality(Nullable(String)))" => b"\x02\x01\x00\x01a",
+    "array_low_cardinality_fixed_string": "Array(LowCardinality(FixedString(2)))" => b"\x01ab",
+    "map": "Map(String, UInt8)" => b"\x01\x01a\x01",
+    "map_low_cardinality": "Map(LowCardinality(String), UInt8)" => b"\x01\x01a\x01",
+    "tuple": "Tuple(UInt8, String)" => b"\x01\x01x",
+    "tuple_low_cardinality": "Tuple(LowCardinality(String), LowCardinality(Nullable(UInt8)))" => b"\x01x\x00\x07",
+    "point": "Point" => &[
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF0, 0x3F,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40,
+    ],
+    // The discriminator is an index in the alphabetically sorted list of types.
+    "variant": "Variant(String, UInt8)" => &[0x01, 0x05],
+    "variant_null": "Variant(String, UInt8)" => &[0xFF],
+
+    // Enums are encoded as their numeric values.
+    "enum8": "Enum8('a' = 1, 'b' = 2)" => &[0x02],
+    "enum16": "Enum16('x' = -1000)" => &[0x18, 0xFC],
+
+    // Dates and times.
+    "date": "Date" => &[0x38, 0x4A],
+    "date32": "Date32" => &[0xFF, 0xFF, 0xFF, 0xFF],
+    "datetime": "DateTime('UTC')" => &[0x00, 0xF1, 0x53, 0x65],
+    "datetime64": "DateTime64(3, 'UTC')" => &[0x7B, 0x68, 0xE5, 0xCF, 0x8B, 0x01, 0x00, 0x00],
+    "time": "Time" => &[0xF0, 0xF1, 0xFF, 0xFF],
+    "time64": "Time64(3)" => &[0xC0, 0xD0, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00],
+
+    // Decimals are encoded as scaled integers of the corresponding size.
+    "decimal32": "Decimal(9, 2)" => &[0x39, 0x30, 0x00, 0x00],
+    "decimal64": "Decimal(18, 4)" => &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+    "decimal128": "Decimal(38, 6)" => &[
+        0x00, 0x10, 0xA5, 0xD4, 0xE8, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+
+    // Special types.
+    // UUID is encoded as two little-endian u64 halves.
+    "uuid": "UUID" => &[
+        0xE7, 0x11, 0xB3, 0x5C, 0x04, 0xC4, 0xF0, 0x61,
+        0xA0, 0xDB, 0xD3, 0x6A, 0x00, 0xA6, 0x7B, 0x90,
+    ],
+    // IPv4 is a little-endian u32, while IPv6 is in network byte order.
+    "ipv4": "IPv4" => &[0x04, 0x03, 0x02, 0x01],
+    "ipv6": "IPv6" => &[
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    ],
+}
@@ -55,7 +55,7 @@ where
 {
     let mut buffer = Vec::with_capacity(BUFFER_INITIAL_CAPACITY);
     for row in rows {
-        rowbinary::serialize_row_binary(&mut buffer, &row).expect("failed to serialize");
+        rowbinary::serialize_row_binary_as_output(&mut buffer, &row).expect("failed to serialize");
     }
     Thunk(Response::new(buffer.into()))
 }
@@ -70,7 +70,7 @@ where
 {
     let mut buffer = Vec::with_capacity(BUFFER_INITIAL_CAPACITY);
     for row in rows {
-        rowbinary::serialize_row_binary(&mut buffer, &row).expect("failed to serialize");
+        rowbinary::serialize_row_binary_as_output(&mut buffer, &row).expect("failed to serialize");
     }
     Thunk(
         Response::builder()
@@ -3,6 +3,7 @@ use hyper::{Request, Response, StatusCode};
 
 pub use self::mock::Mock;
 
+pub mod golden;
 pub mod handlers;
 mod mock;
 
@@ -13,7 +14,7 @@ pub trait Handler: sealed::Sealed {
     fn make(self) -> (HandlerFn, Self::Control);
 }
 
-type HandlerFn = Box<dyn FnOnce(Request<Bytes>) -> Response<Bytes> + Send>;
+pub(crate) type HandlerFn = Box<dyn FnOnce(Request<Bytes>) -> Response<Bytes> + Send>;
 
 // List: https://github.com/ClickHouse/ClickHouse/blob/495c6e03aa9437dac3cd7a44ab3923390bef9982/src/Server/HTTPHandler.cpp#L132
 pub mod status {
@@ -30,6 +31,6 @@ pub mod status {
     pub const INTERNAL_SERVER_ERROR: StatusCode = StatusCode::INTERNAL_SERVER_ERROR;
 }
 
-mod sealed {
+pub(crate) mod sealed {
     pub trait Sealed {}
 }
@@ -0,0 +1,311 @@
+use clickhouse_types::data_types::DataTypeNode;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+pub(crate) const MODULE_PATH: &str = module_path!();
+
+/// A point on a plane, corresponds to the `Point` type.
+///
+/// Unlike `(f64, f64)`, it's validated against the column type as a whole,
+/// so a mismatch is reported as e.g. "`Ring` as `clickhouse::types::Point`".
+///
+/// See [Geo types](https://clickhouse.com/docs/en/sql-reference/data-types/geo).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub(crate) const SERDE_NAME: &'static str = concat!(module_path!(), "::Point");
+
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<(f64, f64)> for Point {
+    fn from((x, y): (f64, f64)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<Point> for (f64, f64) {
+    fn from(point: Point) -> Self {
+        (point.x, point.y)
+    }
+}
+
+impl Serialize for Point {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(Self::SERDE_NAME, &(self.x, self.y))
+    }
+}
+
+impl<'de> Deserialize<'de> for Point {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let visitor = VisitNewtype::<(f64, f64)>(PhantomData);
+        let point = deserializer.deserialize_newtype_struct(Self::SERDE_NAME, visitor)?;
+        Ok(point.into())
+    }
+}
+
+macro_rules! impl_geo_collection {
+    ($(#[$meta:meta])* $name:ident($item:ty)) => {
+        $(#[$meta])*
+        ///
+        /// See [Geo types](https://clickhouse.com/docs/en/sql-reference/data-types/geo).
+        #[derive(Debug, Clone, Default, PartialEq)]
+        pub struct $name(pub Vec<$item>);
+
+        impl $name {
+            pub(crate) const SERDE_NAME: &'static str = concat!(module_path!(), "::", stringify!($name));
+        }
+
+        impl From<Vec<$item>> for $name {
+            fn from(items: Vec<$item>) -> Self {
+                Self(items)
+            }
+        }
+
+        impl From<$name> for Vec<$item> {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl FromIterator<$item> for $name {
+            fn from_iter<I: IntoIterator<Item = $item>>(iter: I) -> Self {
+                Self(iter.into_iter().collect())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_newtype_struct(Self::SERDE_NAME, &self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let visitor = VisitNewtype::<Vec<$item>>(PhantomData);
+                deserializer
+                    .deserialize_newtype_struct(Self::SERDE_NAME, visitor)
+                    .map(Self)
+            }
+        }
+    };
+}
+
+impl_geo_collection!(
+    /// A closed polygon without holes, corresponds to the `Ring` type.
+    Ring(Point)
+);
+impl_geo_collection!(
+    /// A line of points, corresponds to the `LineString` type.
+    LineString(Point)
+);
+impl_geo_collection!(
+    /// Several lines, corresponds to the `MultiLineString` type.
+    MultiLineString(LineString)
+);
+impl_geo_collection!(
+    /// A polygon with holes, corresponds to the `Polygon` type.
+    /// The first ring is the outer one, others are holes.
+    Polygon(Ring)
+);
+impl_geo_collection!(
+    /// Several polygons, corresponds to the `MultiPolygon` type.
+    MultiPolygon(Polygon)
+);
+
+struct VisitNewtype<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> serde::de::Visitor<'de> for VisitNewtype<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("a geo value")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Returns the ClickHouse type corresponding to the geo type's serde name.
+pub(crate) fn data_type_of(serde_name: &str) -> Option<&'static DataTypeNode> {
+    if !serde_name.starts_with(MODULE_PATH) {
+        return None;
+    }
+
+    Some(match serde_name {
+        Point::SERDE_NAME => &DataTypeNode::Point,
+        Ring::SERDE_NAME => &DataTypeNode::Ring,
+        LineString::SERDE_NAME => &DataTypeNode::LineString,
+        MultiLineString::SERDE_NAME => &DataTypeNode::MultiLineString,
+        Polygon::SERDE_NAME => &DataTypeNode::Polygon,
+        MultiPolygon::SERDE_NAME => &DataTypeNode::MultiPolygon,
+        _ => return None,
+    })
+}
+
+#[cfg(feature = "geo-types")]
+mod geo_types_interop {
+    use super::*;
+
+    impl From<Point> for geo_types::Coord<f64> {
+        fn from(point: Point) -> Self {
+            geo_types::coord! { x: point.x, y: point.y }
+        }
+    }
+
+    impl From<geo_types::Coord<f64>> for Point {
+        fn from(coord: geo_types::Coord<f64>) -> Self {
+            Self::new(coord.x, coord.y)
+        }
+    }
+
+    impl From<Point> for geo_types::Point<f64> {
+        fn from(point: Point) -> Self {
+            geo_types::Point::new(point.x, point.y)
+        }
+    }
+
+    impl From<geo_types::Point<f64>> for Point {
+        fn from(point: geo_types::Point<f64>) -> Self {
+            Self::new(point.x(), point.y())
+        }
+    }
+
+    macro_rules! impl_line_interop {
+        ($name:ident) => {
+            impl From<$name> for geo_types::LineString<f64> {
+                fn from(line: $name) -> Self {
+                    line.0.into_iter().map(geo_types::Coord::from).collect()
+                }
+            }
+
+            impl From<geo_types::LineString<f64>> for $name {
+                fn from(line: geo_types::LineString<f64>) -> Self {
+                    line.0.into_iter().map(Point::from).collect()
+                }
+            }
+        };
+    }
+
+    impl_line_interop!(Ring);
+    impl_line_interop!(LineString);
+
+    impl From<MultiLineString> for geo_types::MultiLineString<f64> {
+        fn from(lines: MultiLineString) -> Self {
+            lines
+                .0
+                .into_iter()
+                .map(geo_types::LineString::from)
+                .collect()
+        }
+    }
+
+    impl From<geo_types::MultiLineString<f64>> for MultiLineString {
+        fn from(lines: geo_types::MultiLineString<f64>) -> Self {
+            lines.0.into_iter().map(LineString::from).collect()
+        }
+    }
+
+    impl From<Polygon> for geo_types::Polygon<f64> {
+        fn from(polygon: Polygon) -> Self {
+            let mut rings = polygon.0.into_iter().map(geo_types::LineString::from);
+            let exterior = rings
+                .next()
+                .unwrap_or_else(|| geo_types::LineString(Vec::new()));
+            geo_types::Polygon::new(exterior, rings.collect())
+        }
+    }
+
+    impl From<geo_types::Polygon<f64>> for Polygon {
+        fn from(polygon: geo_types::Polygon<f64>) -> Self {
+            let (exterior, interiors) = polygon.into_inner();
+            std::iter::once(exterior)
+                .chain(interiors)
+                .map(Ring::from)
+                .collect()
+        }
+    }
+
+    impl From<MultiPolygon> for geo_types::MultiPolygon<f64> {
+        fn from(polygons: MultiPolygon) -> Self {
+            polygons
+                .0
+                .into_iter()
+                .map(geo_types::Polygon::from)
+                .collect()
+        }
+    }
+
+    impl From<geo_types::MultiPolygon<f64>> for MultiPolygon {
+        fn from(polygons: geo_types::MultiPolygon<f64>) -> Self {
+            polygons.0.into_iter().map(Polygon::from).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_names_have_module_path_prefix() {
+        for name in [
+            Point::SERDE_NAME,
+            Ring::SERDE_NAME,
+            LineString::SERDE_NAME,
+            MultiLineString::SERDE_NAME,
+            Polygon::SERDE_NAME,
+            MultiPolygon::SERDE_NAME,
+        ] {
+            assert!(name.starts_with(MODULE_PATH), "{name}");
+            assert!(data_type_of(name).is_some(), "{name}");
+        }
+        assert_eq!(data_type_of(crate::types::bf16::MODULE_PATH), None);
+    }
+
+    #[cfg(feature = "geo-types")]
+    #[test]
+    fn converts_to_geo_types() {
+        let ring = Ring(vec![
+            Point::new(0., 0.),
+            Point::new(1., 0.),
+            Point::new(0., 1.),
+            Point::new(0., 0.),
+        ]);
+        let polygon = Polygon(vec![ring.clone(), ring]);
+
+        let converted = geo_types::Polygon::from(polygon.clone());
+        assert_eq!(converted.interiors().len(), 1);
+        assert_eq!(
+            converted.exterior().0[1],
+            geo_types::coord! { x: 1., y: 0. }
+        );
+        assert_eq!(Polygon::from(converted), polygon);
+
+        let empty = geo_types::Polygon::from(Polygon::default());
+        assert!(empty.exterior().0.is_empty());
+    }
+}
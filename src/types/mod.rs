@@ -1,7 +1,12 @@
 //! Bespoke data types for use with ClickHouse.
 
 pub use bf16::BFloat16;
+pub use geo::{LineString, MultiLineString, MultiPolygon, Point, Polygon, Ring};
 pub use int256::{Int256, TryFromInt256Error, TryFromUInt256Error, UInt256};
 
+// Returned by `Query::describe`.
+pub use clickhouse_types::{Column, DataTypeNode};
+
 pub(crate) mod bf16;
+pub(crate) mod geo;
 pub(crate) mod int256;
@@ -0,0 +1,191 @@
+#![cfg(feature = "test-util")]
+
+use std::sync::{Arc, Mutex};
+
+use clickhouse::{
+    Client,
+    audit::{AuditRecord, StatementInfo, StatementKind},
+    query::QueryKind,
+    test,
+};
+use hyper::StatusCode;
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn audit_sink() {
+    #[derive(Debug, PartialEq)]
+    struct Audited {
+        kind: StatementKind,
+        table: Option<String>,
+        user: Option<String>,
+        query_id: String,
+        rows: Option<u64>,
+        failed: bool,
+    }
+
+    let audited = Arc::new(Mutex::new(Vec::new()));
+    let mock = test::Mock::new();
+    let client = Client::default()
+        .with_mock(&mock)
+        .with_user("alice")
+        .with_audit_sink({
+            let audited = audited.clone();
+            move |record: &AuditRecord<'_>| {
+                audited.lock().unwrap().push(Audited {
+                    kind: record.kind,
+                    table: record.table.map(Into::into),
+                    user: record.user.map(Into::into),
+                    query_id: record.query_id.into(),
+                    rows: record.rows,
+                    failed: record.error.is_some(),
+                });
+            }
+        });
+
+    // Not audited.
+    mock.add(test::handlers::provide(vec![SimpleRow::new(1, "one")]));
+    client
+        .query("SELECT ?fields FROM t")
+        .fetch_all::<SimpleRow>()
+        .await
+        .unwrap();
+
+    mock.add(test::handlers::provide_with_summary(
+        Vec::<SimpleRow>::new(),
+        r#"{"written_rows":"3"}"#,
+    ));
+    client
+        .query("ALTER TABLE t UPDATE data = '' WHERE 1")
+        .with_setting("query_id", "q1")
+        .execute()
+        .await
+        .unwrap();
+
+    mock.add(test::handlers::failure(StatusCode::BAD_REQUEST));
+    client.query("DROP TABLE t").execute().await.unwrap_err();
+
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client.insert::<SimpleRow>("t").await.unwrap();
+    insert.write(&SimpleRow::new(1, "one")).await.unwrap();
+    insert.end().await.unwrap();
+    let _: Vec<SimpleRow> = recording.collect().await;
+
+    let mut audited = audited.lock().unwrap();
+    assert_eq!(audited.len(), 3);
+
+    // Generated query IDs are unique.
+    let (drop_id, insert_id) = (audited[1].query_id.clone(), audited[2].query_id.clone());
+    assert_eq!(drop_id.len(), 36);
+    assert_ne!(drop_id, insert_id);
+    audited[1].query_id.clear();
+    audited[2].query_id.clear();
+
+    let user = Some("alice".to_string());
+    assert_eq!(
+        *audited,
+        [
+            Audited {
+                kind: StatementKind::Alter,
+                table: Some("t".into()),
+                user: user.clone(),
+                query_id: "q1".into(),
+                rows: Some(3),
+                failed: false,
+            },
+            Audited {
+                kind: StatementKind::Drop,
+                table: Some("t".into()),
+                user: user.clone(),
+                query_id: String::new(),
+                rows: None,
+                failed: true,
+            },
+            Audited {
+                kind: StatementKind::Insert,
+                table: Some("`t`".into()),
+                user,
+                query_id: String::new(),
+                rows: Some(1),
+                failed: false,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn statement_hook() {
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let mock = test::Mock::new();
+    let client = Client::default()
+        .with_mock(&mock)
+        .with_setting("max_threads", "1")
+        .with_statement_hook({
+            let reported = reported.clone();
+            move |info: &StatementInfo<'_>| {
+                let mut settings = info
+                    .settings
+                    .iter()
+                    .filter(|(name, _)| name != "database")
+                    .cloned()
+                    .collect::<Vec<_>>();
+                settings.sort();
+                reported.lock().unwrap().push((
+                    info.kind,
+                    info.sql.to_string(),
+                    info.bound_args,
+                    settings,
+                ));
+            }
+        })
+        // Keeps identifiers, but hides all values.
+        .with_statement_redaction(|arg| {
+            if arg.starts_with('`') {
+                arg.into()
+            } else {
+                "***".into()
+            }
+        });
+
+    mock.add(test::handlers::provide(vec![SimpleRow::new(1, "one")]));
+    client
+        .query("SELECT ?fields FROM ? WHERE token = ? AND id = {id:UInt32}")
+        .bind(clickhouse::sql::Identifier("t"))
+        .bind("secret")
+        .param("id", 42)
+        .fetch_all::<SimpleRow>()
+        .await
+        .unwrap();
+
+    // Unbound arguments fail before sending, so aren't reported.
+    client.query("SELECT ?").execute().await.unwrap_err();
+
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client.insert::<SimpleRow>("t").await.unwrap();
+    insert.write(&SimpleRow::new(1, "one")).await.unwrap();
+    insert.end().await.unwrap();
+    let _: Vec<SimpleRow> = recording.collect().await;
+
+    let setting = |name: &str, value: &str| (name.to_string(), value.to_string());
+    assert_eq!(
+        *reported.lock().unwrap(),
+        [
+            (
+                QueryKind::Fetch,
+                "SELECT `id`,`data` FROM `t` WHERE token = *** AND id = {id:UInt32}".to_string(),
+                2,
+                vec![
+                    setting("default_format", "RowBinary"),
+                    setting("max_threads", "1"),
+                    setting("param_id", "***"),
+                ],
+            ),
+            (
+                QueryKind::Insert,
+                "INSERT INTO `t`(`id`,`data`) FORMAT RowBinary".to_string(),
+                0,
+                vec![setting("max_threads", "1")],
+            ),
+        ]
+    );
+}
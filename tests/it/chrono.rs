@@ -121,6 +121,45 @@ async fn datetime() {
     assert_eq!(row_str.dt64ns, &original_row.dt64ns.to_string()[..29]);
 }
 
+#[tokio::test]
+async fn strict_utc() {
+    let client = prepare_database!().with_strict_utc(true);
+
+    #[derive(Debug, Deserialize, Row)]
+    struct MyRow {
+        #[serde(with = "clickhouse::serde::chrono::datetime")]
+        dt: DateTime<Utc>,
+        #[serde(with = "clickhouse::serde::chrono::datetime64::millis")]
+        dt64ms: DateTime<Utc>,
+    }
+
+    let row = client
+        .query(
+            "SELECT toDateTime(1700000000, 'UTC') AS dt,
+                    toDateTime64(1700000000.123, 3, 'Etc/UTC') AS dt64ms",
+        )
+        .fetch_one::<MyRow>()
+        .await
+        .unwrap();
+
+    assert_eq!(row.dt.timestamp(), 1_700_000_000);
+    assert_eq!(row.dt64ms.timestamp_millis(), 1_700_000_000_123);
+
+    let err = client
+        .query(
+            "SELECT toDateTime(1700000000, 'UTC') AS dt,
+                    toDateTime64(1700000000.123, 3, 'Europe/Berlin') AS dt64ms",
+        )
+        .fetch_one::<MyRow>()
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(&err, clickhouse::error::Error::SchemaMismatch(msg) if msg.contains("Europe/Berlin")),
+        "{err}"
+    );
+}
+
 #[tokio::test]
 async fn date() {
     let client = prepare_database!();
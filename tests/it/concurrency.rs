@@ -0,0 +1,62 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use clickhouse::{Client, error::Error, test};
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn max_concurrent_requests() {
+    let mock = test::Mock::new();
+    let client = Client::default()
+        .with_mock(&mock)
+        .with_max_concurrent_requests(1)
+        .with_request_queue_timeout(Some(Duration::from_millis(50)));
+
+    // (max, active, queued)
+    let stats = |client: &Client| {
+        let stats = client.concurrency_stats().unwrap();
+        (stats.max, stats.active, stats.queued)
+    };
+    let sql = "SELECT ?fields FROM t";
+    let rows = vec![SimpleRow::new(1, "one"), SimpleRow::new(2, "two")];
+
+    assert_eq!(stats(&client), (1, 0, 0));
+
+    // The slot is occupied until the cursor is exhausted.
+    mock.add(test::handlers::provide(rows.clone()));
+    let mut cursor = client.query(sql).fetch::<SimpleRow>().unwrap();
+    assert_eq!(cursor.next().await.unwrap(), Some(rows[0].clone()));
+    assert_eq!(stats(&client), (1, 1, 0));
+
+    // No handler is consumed, because the request isn't sent.
+    let err = client
+        .query(sql)
+        .fetch_all::<SimpleRow>()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::RequestQueueTimedOut), "{err:?}");
+    assert_eq!(stats(&client), (1, 1, 0));
+
+    // Waits in the queue until the cursor is exhausted.
+    mock.add(test::handlers::provide(rows.clone()));
+    let queued = tokio::spawn({
+        let client = client.clone().with_request_queue_timeout(None);
+        async move { client.query(sql).fetch_all::<SimpleRow>().await }
+    });
+    while stats(&client) != (1, 1, 1) {
+        tokio::task::yield_now().await;
+    }
+
+    assert_eq!(cursor.next().await.unwrap(), Some(rows[1].clone()));
+    assert_eq!(cursor.next().await.unwrap(), None);
+    assert_eq!(queued.await.unwrap().unwrap(), rows);
+    assert_eq!(stats(&client), (1, 0, 0));
+
+    // Clones share the limit, unless it's reconfigured.
+    let other = client.clone().with_max_concurrent_requests(2);
+    assert_eq!(stats(&client.clone()), (1, 0, 0));
+    assert_eq!(stats(&other), (2, 0, 0));
+    assert_eq!(Client::default().concurrency_stats(), None);
+}
@@ -1,4 +1,4 @@
-use clickhouse::{Client, Compression};
+use clickhouse::{Client, Compression, error::Error};
 
 #[tokio::test]
 async fn wait_end_of_query() {
@@ -48,6 +48,20 @@ async fn max_execution_time(mut client: Client, wait_end_of_query: bool) -> u8 {
         "expected TIMEOUT_EXCEEDED in error string, got {err_s:?}; original: {err:?}"
     );
 
+    // Sent in the middle of the response.
+    if !wait_end_of_query {
+        assert!(
+            matches!(
+                err,
+                Error::ServerException {
+                    code: Some(159),
+                    ..
+                }
+            ),
+            "{err:?}"
+        );
+    }
+
     i
 }
 
@@ -1,4 +1,7 @@
-use clickhouse::{Client, Compression};
+#[cfg(feature = "test-util")]
+use clickhouse::test;
+use clickhouse::{Client, Compression, query::CursorStats};
+use std::time::Duration;
 
 use crate::{SimpleRow, create_simple_table};
 
@@ -20,14 +23,33 @@ async fn check(client: Client, expected_ratio: f64) {
     let mut decoded = cursor.decoded_bytes();
     assert_eq!(received, 0);
     assert_eq!(decoded, 0);
+    assert_eq!(cursor.stats(), CursorStats::default());
 
+    let mut prev_stats = cursor.stats();
     while cursor.next().await.unwrap().is_some() {
         assert!(cursor.received_bytes() >= received);
         assert!(cursor.decoded_bytes() >= decoded);
         received = cursor.received_bytes();
         decoded = cursor.decoded_bytes();
+
+        let stats = cursor.stats();
+        assert_eq!(stats.rows, prev_stats.rows + 1);
+        assert!(stats.blocks >= prev_stats.blocks);
+        assert!(stats.max_block_size >= prev_stats.max_block_size);
+        assert!(stats.decode_time >= prev_stats.decode_time);
+        prev_stats = stats;
     }
 
+    let stats = cursor.stats();
+    assert_eq!(stats.received_bytes, received);
+    assert_eq!(stats.decoded_bytes, decoded);
+    assert_eq!(stats.rows, 1_000);
+    assert_eq!(stats.skipped_rows, 0);
+    assert!(stats.blocks > 0);
+    assert!(stats.max_block_size > 0);
+    assert!(stats.max_block_size <= stats.received_bytes);
+    assert!(stats.decode_time > Duration::ZERO);
+
     assert_eq!(decoded, 15000 + 23); // 23 extra bytes for the RBWNAT header.
     assert_eq!(cursor.received_bytes(), dbg!(received));
     assert_eq!(cursor.decoded_bytes(), dbg!(decoded));
@@ -49,3 +71,26 @@ async fn lz4() {
     let client = prepare_database!().with_compression(Compression::Lz4);
     check(client, 3.7).await;
 }
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn rows_and_blocks() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let expected = vec![SimpleRow::new(1, "one"), SimpleRow::new(2, "two")];
+
+    mock.add(test::handlers::provide(expected.clone()));
+    let mut cursor = client.query("doesn't matter").fetch::<SimpleRow>().unwrap();
+    assert_eq!(cursor.stats().rows, 0);
+
+    cursor.next().await.unwrap().unwrap();
+    let stats = cursor.stats();
+    assert_eq!(stats.rows, 1);
+    assert_eq!(stats.blocks, 1);
+    assert_eq!(stats.decoded_bytes, cursor.decoded_bytes());
+
+    while cursor.next().await.unwrap().is_some() {}
+    let stats = cursor.stats();
+    assert_eq!(stats.rows, 2);
+    assert_eq!(stats.received_bytes, cursor.received_bytes());
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "test-util")]
+
+use clickhouse::{Client, error::Error, test};
+
+#[tokio::test]
+async fn ddl_on_cluster() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+
+    let recording = mock.add(test::handlers::record_ddl());
+    let report = client
+        .ddl("ALTER TABLE t UPDATE s = '?' WHERE 1")
+        .on_cluster("main")
+        .wait_all_replicas()
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        recording.query().await,
+        "ALTER TABLE t ON CLUSTER `main` UPDATE s = '?' WHERE 1"
+    );
+    assert!(report.hosts.is_empty());
+    assert!(report.is_success());
+
+    let err = client
+        .ddl("SELECT 1")
+        .on_cluster("main")
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidParams(_)), "{err:?}");
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "test-util")]
+
+use clickhouse::{Client, error::Error, test};
+use hyper::StatusCode;
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn error_context() {
+    let mock = test::Mock::new();
+    let client = Client::default()
+        .with_mock(&mock)
+        .with_setting("max_threads", "1")
+        .with_error_context(true);
+
+    mock.add(test::handlers::failure(StatusCode::BAD_REQUEST));
+    let err = client
+        .query("SELECT * FROM t WHERE name = ? AND id = {id:UInt32}")
+        .bind("secret")
+        .param("id", 42)
+        .with_setting("query_id", "q1")
+        .execute()
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err.without_context(), Error::BadResponse(_)),
+        "{err:?}"
+    );
+
+    let context = err.context().expect("no context");
+    assert_eq!(
+        context.sql,
+        "SELECT * FROM t WHERE name = '?' AND id = {id:UInt32}"
+    );
+    assert!(context.server.starts_with("http://"), "{}", context.server);
+    for setting in [("max_threads", "1"), ("query_id", "q1"), ("param_id", "?")] {
+        assert!(
+            context
+                .settings
+                .iter()
+                .any(|(n, v)| (n.as_str(), v.as_str()) == setting),
+            "{setting:?} not in {:?}",
+            context.settings
+        );
+    }
+    assert!(!err.to_string().contains("secret"), "{err}");
+    assert!(!err.to_string().contains("42"), "{err}");
+
+    mock.add(test::handlers::failure(StatusCode::BAD_REQUEST));
+    let err = client
+        .query("SELECT ?fields FROM t")
+        .fetch_all::<SimpleRow>()
+        .await
+        .unwrap_err();
+    assert_eq!(err.context().unwrap().sql, "SELECT `id`,`data` FROM t");
+
+    mock.add(test::handlers::failure(StatusCode::BAD_REQUEST));
+    let mut insert = client.insert::<SimpleRow>("t").await.unwrap();
+    let result = async move {
+        insert.write(&SimpleRow::new(1, "one")).await?;
+        insert.end().await
+    };
+    let err = result.await.unwrap_err();
+    assert_eq!(
+        err.context().unwrap().sql,
+        "INSERT INTO `t`(`id`,`data`) FORMAT RowBinary"
+    );
+
+    // Disabled by default.
+    mock.add(test::handlers::failure(StatusCode::BAD_REQUEST));
+    let err = Client::default()
+        .with_mock(&mock)
+        .query("SELECT 1")
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(err.context().is_none());
+    assert!(matches!(err, Error::BadResponse(_)), "{err:?}");
+}
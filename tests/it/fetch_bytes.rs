@@ -1,4 +1,6 @@
 use clickhouse::error::Error;
+#[cfg(feature = "test-util")]
+use clickhouse::{Client, test};
 use std::str::from_utf8;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt};
 
@@ -59,7 +61,13 @@ async fn error() {
 
     let err = bytes_cursor.next().await;
     println!("{err:?}");
-    assert!(matches!(err, Err(Error::BadResponse(_))));
+    assert!(matches!(
+        err,
+        Err(Error::ServerException {
+            code: Some(159),
+            ..
+        })
+    ));
 }
 
 #[tokio::test]
@@ -139,3 +147,91 @@ async fn async_read() {
     assert_eq!(cursor.decoded_bytes(), expected.len() as u64);
     assert_eq!(actual, expected);
 }
+
+#[cfg(feature = "futures03")]
+#[tokio::test]
+async fn stream() {
+    use futures_util::TryStreamExt;
+
+    let client = prepare_database!();
+    let query = || {
+        client
+            .query("SELECT number FROM system.numbers LIMIT 100")
+            .with_setting("max_block_size", "7")
+            .fetch_bytes("CSV")
+            .unwrap()
+    };
+
+    #[allow(clippy::format_collect)]
+    let expected = (0..100).map(|n| format!("{n}\n")).collect::<String>();
+
+    let chunks = query().try_collect::<Vec<_>>().await.unwrap();
+    assert!(chunks.len() > 1);
+    assert_eq!(chunks.concat(), expected.as_bytes());
+
+    let mut actual = String::new();
+    futures_util::AsyncReadExt::read_to_string(&mut query(), &mut actual)
+        .await
+        .unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[tokio::test]
+async fn parquet_to_file() {
+    let client = prepare_database!();
+    let query = || {
+        client
+            .query("SELECT number, toString(number) AS str FROM system.numbers LIMIT 100000")
+            .fetch_parquet()
+            .unwrap()
+    };
+
+    let mut data = Vec::new();
+    let written = query().write_to(&mut data).await.unwrap();
+    assert_eq!(written, data.len() as u64);
+    assert!(data.starts_with(b"PAR1") && data.ends_with(b"PAR1"));
+
+    let path = std::env::temp_dir().join(format!("{}.parquet", uuid::Uuid::new_v4()));
+    let written = query().write_to_file(&path).await.unwrap();
+    let file = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written, file.len() as u64);
+    assert_eq!(file, data);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn fetch_bytes_zero_copy() {
+    use bytes::Bytes;
+    use clickhouse::Row;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Row, Serialize, Deserialize)]
+    struct Log {
+        id: u64,
+        #[serde(with = "clickhouse::serde::bytes_zero_copy")]
+        message: Bytes,
+    }
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let expected = vec![
+        Log {
+            id: 1,
+            message: Bytes::from_static(b"one"),
+        },
+        Log {
+            id: 2,
+            message: Bytes::from_static(b"two"),
+        },
+    ];
+
+    mock.add(test::handlers::provide(expected.clone()));
+    let mut cursor = client.query("doesn't matter").fetch::<Log>().unwrap();
+    let mut actual = Vec::new();
+    // Rows are owned, so they can be kept after the next call.
+    while let Some(row) = cursor.next().await.unwrap() {
+        actual.push(row);
+    }
+    assert_eq!(actual, expected);
+}
@@ -0,0 +1,75 @@
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Partial {
+    id: u64,
+    tags: Vec<String>,
+    score: Option<f64>,
+}
+
+#[tokio::test]
+async fn fetch_json() {
+    let client = prepare_database!();
+
+    client
+        .query(
+            "
+            CREATE TABLE test(
+                id UInt64,
+                name String,
+                tags Array(String),
+                score Nullable(Float64)
+            )
+            ENGINE = MergeTree ORDER BY id
+            ",
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    client
+        .query("INSERT INTO test VALUES (1, 'a', ['x', 'y'], 0.5), (18446744073709551615, 'b', [], NULL)")
+        .execute()
+        .await
+        .unwrap();
+
+    let expected = vec![
+        Partial {
+            id: 1,
+            tags: vec!["x".into(), "y".into()],
+            score: Some(0.5),
+        },
+        Partial {
+            id: u64::MAX,
+            tags: vec![],
+            score: None,
+        },
+    ];
+
+    // Columns without fields are ignored.
+    let mut cursor = client
+        .query("SELECT * FROM test ORDER BY id")
+        .fetch_json::<Partial>()
+        .unwrap();
+    let mut actual = Vec::new();
+    while let Some(row) = cursor.next().await.unwrap() {
+        actual.push(row);
+    }
+    assert_eq!(actual, expected);
+    assert!(cursor.received_bytes() > 0);
+
+    let actual = client
+        .query("SELECT * FROM test ORDER BY id")
+        .fetch_json_all::<Partial>()
+        .await
+        .unwrap();
+    assert_eq!(actual, expected);
+
+    // Missing columns are reported.
+    let err = client
+        .query("SELECT id FROM test ORDER BY id")
+        .fetch_json_all::<Partial>()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing field"), "{err}");
+}
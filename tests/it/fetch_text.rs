@@ -0,0 +1,101 @@
+use clickhouse::{Client, Row, error::Error};
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Row, Deserialize)]
+struct Sample {
+    id: u64,
+    name: String,
+    score: Option<f64>,
+    day: String,
+}
+
+const QUERY: &str = r#"
+    SELECT ?fields FROM (
+        SELECT
+            number AS id,
+            concat('a,"\t', toString(number), '\n') AS name,
+            if(number = 1, NULL, number / 2) AS score,
+            toString(toDate('2024-01-01') + number) AS day
+        FROM system.numbers
+        LIMIT 3
+    )
+"#;
+
+fn expected() -> Vec<Sample> {
+    (0..3)
+        .map(|id| Sample {
+            id,
+            name: format!("a,\"\t{id}\n"),
+            score: (id != 1).then(|| id as f64 / 2.),
+            day: format!("2024-01-0{}", id + 1),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn fetch_csv() {
+    let client = prepare_database!();
+
+    let mut cursor = client.query(QUERY).fetch_csv::<Sample>().unwrap();
+    let mut actual = Vec::new();
+    while let Some(row) = cursor.next().await.unwrap() {
+        actual.push(row);
+    }
+
+    assert_eq!(actual, expected());
+}
+
+#[tokio::test]
+async fn fetch_tsv() {
+    let client = prepare_database!();
+
+    let mut cursor = client.query(QUERY).fetch_tsv::<Sample>().unwrap();
+    let mut actual = Vec::new();
+    while let Some(row) = cursor.next().await.unwrap() {
+        actual.push(row);
+    }
+
+    assert_eq!(actual, expected());
+}
+
+#[tokio::test]
+async fn fetch_primitives() {
+    let client = prepare_database!();
+
+    let mut cursor = client
+        .query("SELECT number FROM system.numbers LIMIT 3")
+        .fetch_tsv::<u64>()
+        .unwrap();
+    let mut actual = Vec::new();
+    while let Some(row) = cursor.next().await.unwrap() {
+        actual.push(row);
+    }
+
+    assert_eq!(actual, [0, 1, 2]);
+}
+
+#[tokio::test]
+async fn renamed_fields() {
+    #[derive(Debug, Row, Deserialize)]
+    struct Renamed {
+        #[clickhouse(rename = "sample.id")]
+        id: u64,
+    }
+
+    // Rejected before sending the query.
+    let client = Client::default();
+
+    let err = client
+        .query("SELECT ?fields FROM system.numbers LIMIT 1")
+        .fetch_csv::<Renamed>()
+        .err()
+        .expect("renamed fields must be rejected");
+    assert!(matches!(err, Error::Unsupported(_)));
+
+    let err = client
+        .query("SELECT ?fields FROM system.numbers LIMIT 1")
+        .fetch_tsv::<Renamed>()
+        .err()
+        .expect("renamed fields must be rejected");
+    assert!(matches!(err, Error::Unsupported(_)));
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use clickhouse::Row;
+#[cfg(feature = "test-util")]
+use clickhouse::{Client, test};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Row)]
+struct Audit {
+    created_by: String,
+    version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Row)]
+struct MyRow {
+    id: u64,
+    #[clickhouse(flatten)]
+    audit: Audit,
+    name: String,
+}
+
+#[tokio::test]
+async fn smoke() {
+    let client = prepare_database!();
+
+    assert_eq!(MyRow::COLUMN_NAMES, ["id", "created_by", "version", "name"]);
+
+    client
+        .query(
+            "
+        CREATE TABLE test(
+            name        String,
+            version     UInt32,
+            id          UInt64,
+            created_by  String
+        )
+        ENGINE = MergeTree ORDER BY id
+    ",
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    let original_row = MyRow {
+        id: 42,
+        audit: Audit {
+            created_by: "alice".into(),
+            version: 3,
+        },
+        name: "foo".into(),
+    };
+
+    let mut insert = client.insert::<MyRow>("test").await.unwrap();
+    insert.write(&original_row).await.unwrap();
+    insert.end().await.unwrap();
+
+    let row = client
+        .query("SELECT ?fields FROM test")
+        .fetch_one::<MyRow>()
+        .await
+        .unwrap();
+
+    assert_eq!(row, original_row);
+
+    // The embedded row can be fetched on its own.
+    let audit = client
+        .query("SELECT ?fields FROM test")
+        .fetch_one::<Audit>()
+        .await
+        .unwrap();
+
+    assert_eq!(audit, original_row.audit);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn mocked_roundtrip() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = vec![MyRow {
+        id: 1,
+        audit: Audit {
+            created_by: "alice".into(),
+            version: 2,
+        },
+        name: "one".into(),
+    }];
+
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client.insert::<MyRow>("t").await.unwrap();
+    insert.write(&rows[0]).await.unwrap();
+    insert.end().await.unwrap();
+    let recorded: Vec<(u64, String, u32, String)> = recording.collect().await;
+    assert_eq!(recorded, [(1, "alice".into(), 2, "one".into())]);
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let fetched = crate::fetch_rows::<MyRow>(&client, "doesn't matter").await;
+    assert_eq!(fetched, rows);
+}
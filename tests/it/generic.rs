@@ -0,0 +1,46 @@
+#![cfg(feature = "test-util")]
+
+use clickhouse::{Client, Row, test};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+#[tokio::test]
+async fn generic_row() {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Row)]
+    struct Event<T: Serialize + DeserializeOwned> {
+        id: u64,
+        #[serde(bound = "T: Serialize + DeserializeOwned")]
+        payload: T,
+    }
+
+    async fn roundtrip<T>(payload: T)
+    where
+        T: Clone
+            + std::fmt::Debug
+            + PartialEq
+            + Serialize
+            + DeserializeOwned
+            + Send
+            + Sync
+            + 'static,
+    {
+        let mock = test::Mock::new();
+        let client = Client::default().with_mock(&mock);
+        let rows = vec![Event { id: 1, payload }];
+
+        let recording = mock.add(test::handlers::record());
+        let mut insert = client.insert::<Event<T>>("t").await.unwrap();
+        insert.write(&rows[0]).await.unwrap();
+        insert.end().await.unwrap();
+        let recorded: Vec<Event<T>> = recording.collect().await;
+        assert_eq!(recorded, rows);
+
+        mock.add(test::handlers::provide(rows.clone()));
+        let fetched = crate::fetch_rows::<Event<T>>(&client, "doesn't matter").await;
+        assert_eq!(fetched, rows);
+    }
+
+    assert_eq!(Event::<u32>::COLUMN_NAMES, ["id", "payload"]);
+    roundtrip(42u32).await;
+    roundtrip(String::from("foo")).await;
+    roundtrip(vec![(1u8, 2i64)]).await;
+}
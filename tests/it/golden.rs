@@ -0,0 +1,252 @@
+#![cfg(feature = "test-util")]
+
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use clickhouse::{
+    Row,
+    test::golden::{self, FIXTURES, Fixture},
+};
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+fn fixture(name: &str) -> &'static Fixture {
+    golden::fixture(name).unwrap_or_else(|| panic!("no fixture {name}"))
+}
+
+macro_rules! check {
+    ($name:literal, $ty:ty, $value:expr) => {{
+        #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+        struct Value {
+            value: $ty,
+        }
+
+        golden::assert_compatible(fixture($name), &Value { value: $value });
+    }};
+}
+
+#[test]
+fn fixtures_are_unique_and_parsable() {
+    for (i, fixture) in FIXTURES.iter().enumerate() {
+        assert!(
+            FIXTURES[..i].iter().all(|f| f.name != fixture.name),
+            "duplicate fixture {}",
+            fixture.name
+        );
+        // Panics if the data type is invalid.
+        fixture.column("value");
+    }
+}
+
+#[test]
+fn integers() {
+    check!("uint8", u8, 200);
+    check!("uint16", u16, 0x1234);
+    check!("uint32", u32, 0xDEAD_BEEF);
+    check!("uint64", u64, 0x0102_0304_0506_0708);
+    check!("uint128", u128, (1 << 64) | 2);
+    check!("int8", i8, -1);
+    check!("int16", i16, -2);
+    check!("int32", i32, -3);
+    check!("int64", i64, -4);
+    check!("int128", i128, -5);
+    check!("bool", bool, true);
+}
+
+#[test]
+fn floats() {
+    check!("float32", f32, 1.5);
+    check!("float64", f64, -2.5);
+}
+
+#[test]
+fn strings() {
+    check!("string", String, "hello".into());
+    check!("string_empty", String, String::new());
+    check!("string_utf8", String, "日本".into());
+    check!("fixed_string", [u8; 4], *b"ab\0\0");
+    check!("low_cardinality", String, "x".into());
+    check!("low_cardinality_fixed_string", [u8; 2], *b"ab");
+    check!("low_cardinality_uint64", u64, 42);
+}
+
+#[test]
+fn nullable() {
+    check!("nullable_null", Option<u8>, None);
+    check!("nullable_value", Option<u8>, Some(7));
+    check!("low_cardinality_nullable", Option<String>, None);
+}
+
+#[test]
+fn compound() {
+    check!("array", Vec<u16>, vec![1, 2]);
+    check!("array_empty", Vec<String>, vec![]);
+    check!("array_nested", Vec<Vec<u8>>, vec![vec![1], vec![2, 3]]);
+    check!("map", HashMap<String, u8>, HashMap::from([("a".into(), 1)]));
+    check!("map", Vec<(String, u8)>, vec![("a".into(), 1)]);
+    check!("tuple", (u8, String), (1, "x".into()));
+    check!("point", (f64, f64), (1.0, 2.0));
+}
+
+#[test]
+fn nested_low_cardinality() {
+    check!(
+        "array_low_cardinality",
+        Vec<String>,
+        vec!["a".into(), "bc".into()]
+    );
+    check!(
+        "array_low_cardinality_nullable",
+        Vec<Option<String>>,
+        vec![None, Some("a".into())]
+    );
+    check!(
+        "array_low_cardinality_fixed_string",
+        Vec<[u8; 2]>,
+        vec![*b"ab"]
+    );
+    check!("map_low_cardinality", HashMap<String, u8>, HashMap::from([("a".into(), 1)]));
+    check!(
+        "map_low_cardinality",
+        Vec<(String, u8)>,
+        vec![("a".into(), 1)]
+    );
+    check!(
+        "tuple_low_cardinality",
+        (String, Option<u8>),
+        ("x".into(), Some(7))
+    );
+    check!(
+        "simple_aggregate_function_low_cardinality",
+        Option<String>,
+        Some("a".into())
+    );
+}
+
+#[test]
+fn variant() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum MyVariant {
+        String(String),
+        UInt8(u8),
+    }
+
+    #[derive(Debug, PartialEq, Row, Deserialize)]
+    struct NullableRow {
+        value: Option<MyVariant>,
+    }
+
+    check!("variant", MyVariant, MyVariant::UInt8(5));
+
+    // NULL is only produced by the server, check the decoding part.
+    let fixture = fixture("variant_null");
+    let row: NullableRow = golden::decode(fixture.bytes, Some(&[fixture.column("value")])).unwrap();
+    assert_eq!(row, NullableRow { value: None });
+}
+
+#[test]
+fn enums() {
+    #[derive(Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+    #[repr(i8)]
+    enum MyEnum8 {
+        A = 1,
+        B = 2,
+    }
+
+    #[derive(Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+    #[repr(i16)]
+    enum MyEnum16 {
+        X = -1000,
+    }
+
+    check!("enum8", MyEnum8, MyEnum8::B);
+    check!("enum16", MyEnum16, MyEnum16::X);
+}
+
+#[test]
+fn dates_and_times() {
+    check!("date", u16, 19000);
+    check!("date32", i32, -1);
+    check!("datetime", u32, 1_700_000_000);
+    check!("datetime64", i64, 1_700_000_000_123);
+    check!("time", i32, -3600);
+    check!("time64", i64, 3_723_456);
+}
+
+#[test]
+fn decimals() {
+    // A custom wrapper, as users usually do for fixed-point numbers.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Scaled<T>(T);
+
+    check!("decimal32", Scaled<i32>, Scaled(12345));
+    check!("decimal64", Scaled<i64>, Scaled(-1));
+    check!("decimal128", Scaled<i128>, Scaled(1_000_000_000_000));
+}
+
+#[test]
+fn ip() {
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    struct Ipv4Row {
+        #[serde(with = "clickhouse::serde::ipv4")]
+        ip: Ipv4Addr,
+    }
+
+    golden::assert_compatible(
+        fixture("ipv4"),
+        &Ipv4Row {
+            ip: Ipv4Addr::new(1, 2, 3, 4),
+        },
+    );
+    check!("ipv6", Ipv6Addr, Ipv6Addr::LOCALHOST);
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid() {
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    struct UuidRow {
+        #[serde(with = "clickhouse::serde::uuid")]
+        id: uuid::Uuid,
+    }
+
+    let id = uuid::Uuid::parse_str("61f0c404-5cb3-11e7-907b-a6006ad3dba0").unwrap();
+    golden::assert_compatible(fixture("uuid"), &UuidRow { id });
+}
+
+#[test]
+fn primitive_rows() {
+    golden::assert_compatible(fixture("uint32"), &0xDEAD_BEEF_u32);
+    golden::assert_compatible(fixture("string"), &String::from("hello"));
+}
+
+#[test]
+fn rbwnat() {
+    let fixture = fixture("uint8");
+    let rbwnat = fixture.to_rbwnat("value");
+    // 1 column, "value" of type "UInt8", then the data itself.
+    assert_eq!(rbwnat, b"\x01\x05value\x05UInt8\xC8");
+}
+
+#[test]
+fn encode_decode() {
+    let bytes = golden::encode(&42_u32, None).unwrap();
+    assert_eq!(bytes, [42, 0, 0, 0]);
+    assert_eq!(golden::decode::<u32>(&bytes, None).unwrap(), 42);
+
+    // Trailing bytes are not allowed.
+    assert!(golden::decode::<u16>(&bytes, None).is_err());
+}
+
+#[test]
+#[should_panic(expected = "encoding mismatch for fixture uint16")]
+fn mismatch() {
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    struct Value {
+        value: u16,
+    }
+
+    golden::assert_compatible(fixture("uint16"), &Value { value: 0x1235 });
+}
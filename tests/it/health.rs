@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+#[cfg(feature = "test-util")]
+use clickhouse::{Client, test};
+
+#[cfg(feature = "test-util")]
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn ping() {
+    let client = crate::get_client();
+    let health = client.ping().await.unwrap();
+    assert!(health.latency > Duration::ZERO);
+    assert_eq!(health.server_elapsed, None);
+
+    // Doesn't require authentication.
+    let client = client.with_password("definitely wrong password");
+    client.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn ready() {
+    let client = crate::get_client();
+    let health = client.ready(Duration::from_secs(10)).await.unwrap();
+    assert!(health.latency > Duration::ZERO);
+    assert!(health.server_elapsed.is_some());
+
+    let client = client.with_password("definitely wrong password");
+    assert!(client.ready(Duration::from_secs(10)).await.is_err());
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn health_checks() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+
+    mock.add(test::handlers::record_ddl());
+    let health = client.ping().await.unwrap();
+    assert_eq!(health.server_elapsed, None);
+
+    mock.add(test::handlers::failure(test::status::SERVICE_UNAVAILABLE));
+    assert!(client.ping().await.is_err());
+
+    mock.add(test::handlers::provide_with_summary(
+        Vec::<SimpleRow>::new(),
+        r#"{"elapsed_ns":"1500"}"#,
+    ));
+    let health = client.ready(Duration::from_secs(5)).await.unwrap();
+    assert_eq!(health.server_elapsed, Some(Duration::from_nanos(1500)));
+    assert!(health.latency > Duration::ZERO);
+
+    mock.add(test::handlers::exception(194));
+    assert!(client.ready(Duration::from_secs(5)).await.is_err());
+}
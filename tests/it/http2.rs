@@ -0,0 +1,27 @@
+#![cfg(all(
+    feature = "http2",
+    any(feature = "rustls-tls-aws-lc", feature = "rustls-tls-ring")
+))]
+
+use crate::get_client;
+use futures_util::future;
+
+#[tokio::test]
+async fn concurrent_queries() {
+    check_cloud_test_env!();
+    let client = get_client().with_http2(true);
+
+    // Establish a connection first, so the next requests can reuse it.
+    assert_eq!(client.query("SELECT 1").fetch_one::<u8>().await.unwrap(), 1);
+
+    let results = future::try_join_all((0..32u64).map(|i| {
+        client
+            .query("SELECT toUInt64(?) * 2")
+            .bind(i)
+            .fetch_one::<u64>()
+    }))
+    .await
+    .unwrap();
+
+    assert_eq!(results, (0..32).map(|i| i * 2).collect::<Vec<_>>());
+}
@@ -1,10 +1,14 @@
 use crate::{SimpleRow, create_simple_table, fetch_rows, flush_query_log, get_client};
 use clickhouse::insert::Insert;
+#[cfg(feature = "test-util")]
+use clickhouse::{Client, test};
 use clickhouse::{Row, sql::Identifier};
 use rand::distr::{Alphanumeric, SampleString};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::panic::AssertUnwindSafe;
+#[cfg(feature = "test-util")]
+use std::time::Duration;
 
 #[tokio::test]
 async fn keeps_client_settings() {
@@ -145,6 +149,59 @@ async fn empty_insert() {
     assert!(rows.is_empty())
 }
 
+#[tokio::test]
+async fn write_stats() {
+    let table_name = "insert_write_stats";
+    let query_id = uuid::Uuid::new_v4().to_string();
+
+    let client = prepare_database!();
+    create_simple_table(&client, table_name).await;
+
+    let mut insert = client
+        .insert::<SimpleRow>(table_name)
+        .await
+        .unwrap()
+        .with_setting("query_id", &query_id);
+    for i in 0..100 {
+        insert.write(&SimpleRow::new(i, "foo")).await.unwrap();
+    }
+    let stats = insert.end().await.unwrap();
+
+    assert_eq!(stats.rows, Some(100));
+    assert!(stats.bytes.unwrap() > 0);
+    assert!(stats.compressed_bytes > 0);
+    assert!(stats.duration.is_some());
+    assert_eq!(stats.query_id.as_deref(), Some(query_id.as_str()));
+}
+
+#[cfg(feature = "lz4")]
+#[tokio::test]
+async fn offload_serialization() {
+    let table_name = "insert_offload_serialization";
+
+    let client = prepare_database!().with_compression(clickhouse::Compression::Lz4);
+    create_simple_table(&client, table_name).await;
+
+    let rows = (0..10_000)
+        .map(|id| SimpleRow::new(id, format!("row {id}")))
+        .collect::<Vec<_>>();
+
+    // Many chunks are compressed on the blocking pool because of the small buffer.
+    let mut insert = client
+        .insert::<SimpleRow>(table_name)
+        .await
+        .unwrap()
+        .with_write_buffer_capacity(1024)
+        .with_offload_serialization(true);
+    for row in &rows {
+        insert.write(row).await.unwrap();
+    }
+    let stats = insert.end().await.unwrap();
+    assert_eq!(stats.rows, Some(rows.len() as u64));
+
+    assert_eq!(fetch_rows::<SimpleRow>(&client, table_name).await, rows);
+}
+
 #[tokio::test]
 async fn insert_with_json_hint() {
     #[derive(Serialize, Deserialize, Row, PartialEq)]
@@ -259,6 +316,105 @@ async fn rename_insert() {
     assert_eq!(rows, vec!(row))
 }
 
+#[tokio::test]
+async fn clickhouse_rename_insert() {
+    #[derive(Debug, Row, Serialize, Deserialize, PartialEq)]
+    struct RenameRow {
+        #[clickhouse(rename = "fix.id")]
+        fix_id: u64,
+        #[clickhouse(rename = "complex.id")]
+        #[serde(rename = "complexId")]
+        complex_id: String,
+    }
+
+    let table_name = "insert_clickhouse_rename";
+
+    let client = prepare_database!();
+    client
+        .query(
+            "
+            CREATE TABLE ?(
+              `fix.id` UInt64,
+              `complex.id` String
+            )
+            ENGINE = MergeTree
+            ORDER BY `fix.id`
+            ",
+        )
+        .bind(Identifier(table_name))
+        .execute()
+        .await
+        .unwrap();
+
+    let row = RenameRow {
+        fix_id: 42,
+        complex_id: String::from("foo"),
+    };
+
+    let mut insert = client.insert::<RenameRow>(table_name).await.unwrap();
+    insert.write(&row).await.unwrap();
+    insert.end().await.unwrap();
+
+    let rows = fetch_rows::<RenameRow>(&client, table_name).await;
+    assert_eq!(rows, vec!(row));
+
+    // The serde names are still used for other formats.
+    let json = serde_json::to_string(&rows[0]).unwrap();
+    assert_eq!(json, r#"{"fix_id":42,"complexId":"foo"}"#);
+}
+
+#[tokio::test]
+async fn skip_insert() {
+    #[derive(Debug, Row, Serialize, Deserialize, PartialEq)]
+    struct SkipRow {
+        id: u64,
+        #[clickhouse(skip_insert)]
+        doubled: u64,
+        #[clickhouse(skip_insert)]
+        status: String,
+    }
+
+    let table_name = "insert_skip_insert";
+
+    let client = prepare_database!();
+    client
+        .query(
+            "
+            CREATE TABLE ?(
+              id UInt64,
+              doubled UInt64 MATERIALIZED id * 2,
+              status String DEFAULT 'new'
+            )
+            ENGINE = MergeTree
+            ORDER BY id
+            ",
+        )
+        .bind(Identifier(table_name))
+        .execute()
+        .await
+        .unwrap();
+
+    let row = SkipRow {
+        id: 21,
+        doubled: 0,
+        status: String::from("ignored"),
+    };
+
+    let mut insert = client.insert::<SkipRow>(table_name).await.unwrap();
+    insert.write(&row).await.unwrap();
+    insert.end().await.unwrap();
+
+    let rows = fetch_rows::<SkipRow>(&client, table_name).await;
+    assert_eq!(
+        rows,
+        vec![SkipRow {
+            id: 21,
+            doubled: 42,
+            status: String::from("new"),
+        }]
+    );
+}
+
 #[tokio::test]
 async fn insert_from_cursor() {
     #[derive(Debug, Row, Serialize, Deserialize, PartialEq)]
@@ -503,6 +659,142 @@ async fn clear_cached_metadata() {
     assert_eq!(*rows, [Foo2 { bar: 1 }, Foo2 { bar: 3 }]);
 }
 
+#[tokio::test]
+async fn preloaded_metadata() {
+    #[derive(clickhouse::Row, serde::Serialize)]
+    struct Foo {
+        bar: i32,
+        baz: String,
+    }
+
+    #[derive(clickhouse::Row, serde::Serialize)]
+    struct Foo2 {
+        bar: i32,
+    }
+
+    let client = prepare_database!().with_validation(true);
+
+    client
+        .query("CREATE TABLE foo(bar Int32, baz String) ENGINE = MergeTree PRIMARY KEY(bar)")
+        .execute()
+        .await
+        .unwrap();
+
+    assert!(client.export_metadata_cache().await.is_empty());
+
+    let mut insert = client.insert::<Foo>("foo").await.unwrap();
+    insert
+        .write(&Foo {
+            bar: 1,
+            baz: "Hello, world!".to_string(),
+        })
+        .await
+        .unwrap();
+    insert.end().await.unwrap();
+
+    let cache = client.export_metadata_cache().await;
+    assert_eq!(cache.tables().collect::<Vec<_>>(), ["`foo`"]);
+
+    let json = serde_json::to_string(&cache).unwrap();
+    let cache: clickhouse::MetadataCache = serde_json::from_str(&json).unwrap();
+
+    client
+        .query("ALTER TABLE foo DROP COLUMN baz")
+        .execute()
+        .await
+        .unwrap();
+
+    // The preloaded schema is used without fetching, so it's stale now.
+    let preloaded = client.clone().with_preloaded_metadata(cache);
+    let Err(err) = preloaded.insert::<Foo2>("foo").await else {
+        panic!("the preloaded schema must be used");
+    };
+    assert!(err.to_string().contains("baz"), "{err}");
+
+    preloaded.clear_cached_metadata().await;
+    let mut insert = preloaded.insert::<Foo2>("foo").await.unwrap();
+    insert.write(&Foo2 { bar: 2 }).await.unwrap();
+    insert.end().await.unwrap();
+}
+
+#[tokio::test]
+async fn invalidate_table_metadata() {
+    #[derive(clickhouse::Row, serde::Serialize)]
+    struct Foo {
+        bar: i32,
+    }
+
+    #[derive(clickhouse::Row, serde::Serialize)]
+    struct Foo2 {
+        bar: i32,
+        baz: String,
+    }
+
+    #[derive(clickhouse::Row, serde::Serialize)]
+    struct Foo3 {
+        bar: i32,
+        baz: String,
+        qux: String,
+    }
+
+    let client = prepare_database!().with_validation(true);
+
+    client
+        .query("CREATE TABLE foo(bar Int32) ENGINE = MergeTree PRIMARY KEY(bar)")
+        .execute()
+        .await
+        .unwrap();
+
+    let mut insert = client.insert::<Foo>("foo").await.unwrap();
+    insert.write(&Foo { bar: 1 }).await.unwrap();
+    insert.end().await.unwrap();
+
+    client
+        .query("ALTER TABLE foo ADD COLUMN baz String")
+        .execute()
+        .await
+        .unwrap();
+
+    // The cached schema has no `baz` column.
+    assert!(client.insert::<Foo2>("foo").await.is_err());
+
+    // Other tables aren't affected.
+    client.invalidate_table_metadata("bar").await;
+    assert!(client.insert::<Foo2>("foo").await.is_err());
+
+    client.invalidate_table_metadata("foo").await;
+    let mut insert = client.insert::<Foo2>("foo").await.unwrap();
+    insert
+        .write(&Foo2 {
+            bar: 2,
+            baz: "two".into(),
+        })
+        .await
+        .unwrap();
+    insert.end().await.unwrap();
+
+    client
+        .query("ALTER TABLE foo ADD COLUMN qux String")
+        .execute()
+        .await
+        .unwrap();
+
+    assert!(client.insert::<Foo3>("foo").await.is_err());
+
+    // Expired entries are fetched again.
+    let client = client.with_metadata_ttl(std::time::Duration::ZERO);
+    let mut insert = client.insert::<Foo3>("foo").await.unwrap();
+    insert
+        .write(&Foo3 {
+            bar: 3,
+            baz: "three".into(),
+            qux: "three".into(),
+        })
+        .await
+        .unwrap();
+    insert.end().await.unwrap();
+}
+
 #[tokio::test]
 async fn insert_with_role() {
     #[derive(serde::Serialize, serde::Deserialize, clickhouse::Row)]
@@ -743,3 +1035,329 @@ async fn insert_unvalidated() {
 
     insert.end().await.unwrap_err();
 }
+
+#[tokio::test]
+async fn async_insert() {
+    use clickhouse::insert::AsyncInsertWait;
+    use std::time::Duration;
+
+    let client = prepare_database!();
+    create_simple_table(&client, "test").await;
+
+    for wait in [
+        AsyncInsertWait::Flush,
+        AsyncInsertWait::FlushWithTimeout(Duration::from_millis(2500)),
+    ] {
+        let mut insert = client
+            .insert::<SimpleRow>("test")
+            .await
+            .unwrap()
+            .with_async_insert(wait);
+        insert.write(&SimpleRow::new(1, "foo")).await.unwrap();
+        insert.write(&SimpleRow::new(2, "bar")).await.unwrap();
+        let stats = insert.end().await.unwrap();
+        assert_eq!(stats.rows, Some(2), "{wait:?}");
+    }
+
+    // Flushed inserts are visible immediately.
+    let rows = fetch_rows::<SimpleRow>(&client, "test").await;
+    assert_eq!(rows.len(), 4);
+
+    let mut insert = client
+        .insert::<SimpleRow>("test")
+        .await
+        .unwrap()
+        .with_async_insert(AsyncInsertWait::Enqueue);
+    insert.write(&SimpleRow::new(3, "baz")).await.unwrap();
+    insert.end().await.unwrap();
+}
+
+#[tokio::test]
+async fn schema_mismatch_before_sending() {
+    #[derive(Row, Serialize)]
+    struct WrongRow {
+        id: u64,
+        data: u32,
+    }
+
+    let client = prepare_database!().with_validation(true);
+    create_simple_table(&client, "test").await;
+
+    let query_id = uuid::Uuid::new_v4().to_string();
+    let mut insert = client
+        .insert::<WrongRow>("test")
+        .await
+        .unwrap()
+        .with_setting("query_id", &query_id);
+
+    let err = insert
+        .write(&WrongRow { id: 1, data: 42 })
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, clickhouse::error::Error::SchemaMismatch(msg)
+            if msg.contains("data") && msg.contains("String as u32")),
+        "{err:?}"
+    );
+
+    // The request hasn't even been started.
+    flush_query_log(&client).await;
+    let count = client
+        .query("SELECT count() FROM system.query_log WHERE query_id = ?")
+        .bind(&query_id)
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn different_field_order() {
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    struct TableRow {
+        id: u64,
+        name: String,
+        score: f32,
+    }
+
+    // The same columns, but in a different order than in the table.
+    #[derive(Row, Serialize)]
+    struct ShuffledRow<'a> {
+        score: f32,
+        id: u64,
+        name: &'a str,
+    }
+
+    for validation in [true, false] {
+        let client = prepare_database!().with_validation(validation);
+        client
+            .query("CREATE TABLE test(id UInt64, name String, score Float32) ENGINE = MergeTree ORDER BY id")
+            .execute()
+            .await
+            .unwrap();
+
+        let mut insert = client.insert::<ShuffledRow<'_>>("test").await.unwrap();
+        for (id, name) in [(1, "foo"), (2, "bar")] {
+            let row = ShuffledRow {
+                score: id as f32 / 2.,
+                id,
+                name,
+            };
+            insert.write(&row).await.unwrap();
+        }
+        insert.end().await.unwrap();
+
+        let rows = fetch_rows::<TableRow>(&client, "test").await;
+        assert_eq!(
+            rows,
+            vec![
+                TableRow {
+                    id: 1,
+                    name: "foo".into(),
+                    score: 0.5,
+                },
+                TableRow {
+                    id: 2,
+                    name: "bar".into(),
+                    score: 1.,
+                },
+            ],
+            "validation: {validation}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn qualified_table() {
+    let db_name = test_database_name!();
+    let client = prepare_database!();
+    create_simple_table(&client, "weird`table").await;
+
+    // The client isn't bound to the database, so the table must be qualified.
+    for validation in [true, false] {
+        let other = get_client().with_validation(validation);
+        let mut insert = other
+            .insert::<SimpleRow>((db_name.as_str(), "weird`table"))
+            .await
+            .unwrap();
+        insert.write(&SimpleRow::new(42, "foo")).await.unwrap();
+        insert.end().await.unwrap();
+    }
+
+    let rows = fetch_rows::<SimpleRow>(&client, "weird`table").await;
+    assert_eq!(
+        rows,
+        vec![SimpleRow::new(42, "foo"), SimpleRow::new(42, "foo")]
+    );
+}
+
+#[tokio::test]
+async fn with_header() {
+    let table_name = "insert_with_header";
+    let query_id = uuid::Uuid::new_v4().to_string();
+
+    let client = prepare_database!();
+    create_simple_table(&client, table_name).await;
+
+    let mut insert = client
+        .insert::<SimpleRow>(table_name)
+        .await
+        .unwrap()
+        .with_header("Referer", "https://insert.example")
+        .with_setting("query_id", &query_id);
+    insert.write(&SimpleRow::new(42, "foo")).await.unwrap();
+    insert.end().await.unwrap();
+
+    flush_query_log(&client).await;
+
+    let referer = client
+        .query("SELECT any(http_referer) FROM system.query_log WHERE query_id = ?")
+        .bind(&query_id)
+        .fetch_one::<String>()
+        .await
+        .unwrap();
+    assert_eq!(referer, "https://insert.example");
+}
+
+#[tokio::test]
+async fn insert_group() {
+    let client = prepare_database!();
+    create_simple_table(&client, "facts").await;
+    create_simple_table(&client, "dimensions").await;
+
+    let group = client.insert_group().await.unwrap();
+    for table in ["facts", "dimensions"] {
+        let mut insert = group.insert::<SimpleRow>(table).await.unwrap();
+        insert.write(&SimpleRow::new(1, table)).await.unwrap();
+        insert.end().await.unwrap();
+    }
+
+    // Rows are visible only in the session until committed.
+    if group.is_transactional() {
+        assert!(fetch_rows::<SimpleRow>(&client, "facts").await.is_empty());
+    }
+    let in_session = fetch_rows::<SimpleRow>(group.client(), "facts").await;
+    assert_eq!(in_session, [SimpleRow::new(1, "facts")]);
+
+    group.commit().await.unwrap();
+
+    for table in ["facts", "dimensions"] {
+        let rows = fetch_rows::<SimpleRow>(&client, table).await;
+        assert_eq!(rows, [SimpleRow::new(1, table)]);
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn buffer_sizes() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = (0..1000)
+        .map(|i| SimpleRow::new(i, "data"))
+        .collect::<Vec<_>>();
+
+    // Many chunks are sent because of the small buffer.
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client
+        .insert::<SimpleRow>("t")
+        .await
+        .unwrap()
+        .with_write_buffer_capacity(256);
+    for row in &rows {
+        insert.write(row).await.unwrap();
+    }
+    insert.end().await.unwrap();
+    let recorded: Vec<SimpleRow> = recording.collect().await;
+    assert_eq!(recorded, rows);
+
+    // Chunks are adapted to the target size.
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client
+        .insert::<SimpleRow>("t")
+        .await
+        .unwrap()
+        .with_target_block_bytes(512);
+    for row in &rows {
+        insert.write(row).await.unwrap();
+    }
+    insert.end().await.unwrap();
+    let recorded: Vec<SimpleRow> = recording.collect().await;
+    assert_eq!(recorded, rows);
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let mut cursor = client
+        .query("doesn't matter")
+        .with_read_buffer_size(1024)
+        .fetch::<SimpleRow>()
+        .unwrap();
+    let mut fetched = Vec::new();
+    while let Some(row) = cursor.next().await.unwrap() {
+        fetched.push(row);
+    }
+    assert_eq!(fetched, rows);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn insert_write_stats() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+
+    mock.add(test::handlers::provide_with_summary(
+        Vec::<SimpleRow>::new(),
+        r#"{"written_rows":"2","written_bytes":"32","elapsed_ns":"1500"}"#,
+    ));
+
+    let mut insert = client.insert::<SimpleRow>("t").await.unwrap();
+    insert.write(&SimpleRow::new(1, "one")).await.unwrap();
+    insert.write(&SimpleRow::new(2, "two")).await.unwrap();
+    let stats = insert.end().await.unwrap();
+
+    assert_eq!(stats.rows, Some(2));
+    assert_eq!(stats.bytes, Some(32));
+    assert_eq!(stats.duration, Some(Duration::from_nanos(1500)));
+    assert!(stats.compressed_bytes > 0);
+    assert_eq!(stats.query_id, None);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn insert_with_retries() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+
+    // Both smaller and larger than retained data.
+    for count in [10, 100_000] {
+        let rows = (0..count)
+            .map(|id| SimpleRow::new(id, format!("row {id}")))
+            .collect::<Vec<_>>();
+
+        let recording = mock.add(test::handlers::record());
+        let mut insert = client
+            .insert::<SimpleRow>("t")
+            .await
+            .unwrap()
+            .with_retries(3);
+        for row in &rows {
+            insert.write(row).await.unwrap();
+        }
+        insert.end().await.unwrap();
+
+        let recorded: Vec<SimpleRow> = recording.collect().await;
+        assert_eq!(recorded, rows);
+    }
+    // Retries are disabled, because the server could write smaller blocks.
+    let rows = vec![SimpleRow::new(1, "one")];
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client
+        .insert::<SimpleRow>("t")
+        .await
+        .unwrap()
+        .with_setting("min_insert_block_size_rows", "1")
+        .with_retries(3);
+    insert.write(&rows[0]).await.unwrap();
+    insert.end().await.unwrap();
+
+    let recorded: Vec<SimpleRow> = recording.collect().await;
+    assert_eq!(recorded, rows);
+}
@@ -1,6 +1,7 @@
 use crate::{SimpleRow, create_simple_table, fetch_rows};
 use bytes::{Buf, Bytes};
 use clickhouse::error::Error;
+use clickhouse::sql::Identifier;
 use clickhouse::{Client, Compression};
 use clickhouse_macros::Row;
 use serde::Deserialize;
@@ -388,3 +389,53 @@ async fn verify_insert(client: &Client) {
     assert_eq!(results.median_trip_distance, 1.7999999523162842);
     assert_eq!(results.count, 1000);
 }
+
+#[tokio::test]
+async fn insert_raw() {
+    let client = prepare_database!();
+    create_table(&client).await;
+
+    let stats = client
+        .insert_raw("nyc_taxi_trips_small", "TabSeparated", TAXI_DATA_TSV)
+        .await
+        .unwrap();
+    assert_eq!(stats.rows, Some(1000));
+
+    verify_insert(&client).await;
+
+    let err = client
+        .insert_raw("nyc_taxi_trips_small", "CSV; DROP TABLE test", &b""[..])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidParams(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn insert_raw_encoded_rows() {
+    let table_name = "insert_raw_encoded_rows";
+
+    let client = prepare_database!();
+    create_simple_table(&client, table_name).await;
+
+    let columns = client
+        .query("SELECT * FROM ?")
+        .bind(Identifier(table_name))
+        .describe()
+        .await
+        .unwrap();
+
+    let rows = vec![SimpleRow::new(1, "foo"), SimpleRow::new(2, "bar")];
+    let buffer = clickhouse::rowbinary::encode_rows(&rows, &columns).unwrap();
+    assert_eq!(
+        clickhouse::rowbinary::decode_rows::<SimpleRow>(&buffer).unwrap(),
+        rows
+    );
+
+    let stats = client
+        .insert_raw(table_name, "RowBinaryWithNamesAndTypes", &buffer[..])
+        .await
+        .unwrap();
+    assert_eq!(stats.rows, Some(2));
+
+    assert_eq!(fetch_rows::<SimpleRow>(&client, table_name).await, rows);
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "test-util")]
+
+use clickhouse::{Client, error::Error, test};
+use hyper::StatusCode;
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn insert_group() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = vec![SimpleRow::new(1, "one")];
+
+    let begin = mock.add(test::handlers::record_ddl());
+    let group = client.insert_group().await.unwrap();
+    assert_eq!(begin.query().await, "BEGIN TRANSACTION");
+    assert!(group.is_transactional());
+    assert!(group.client().get_setting("session_id").is_some());
+    assert_eq!(client.get_setting("session_id"), None);
+
+    let recording = mock.add(test::handlers::record());
+    let mut insert = group.insert::<SimpleRow>("t").await.unwrap();
+    insert.write(&rows[0]).await.unwrap();
+    insert.end().await.unwrap();
+    let recorded: Vec<SimpleRow> = recording.collect().await;
+    assert_eq!(recorded, rows);
+
+    let commit = mock.add(test::handlers::record_ddl());
+    group.commit().await.unwrap();
+    assert_eq!(commit.query().await, "COMMIT");
+
+    // Falls back to inserting without a transaction (`NOT_IMPLEMENTED`).
+    mock.add(test::handlers::exception(48));
+    let group = client.insert_group().await.unwrap();
+    assert!(!group.is_transactional());
+    let err = group.rollback().await.unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)), "{err}");
+
+    // Other errors are returned, e.g. `UNKNOWN_DATABASE`.
+    mock.add(test::handlers::exception(81));
+    let err = client.insert_group().await.err().unwrap();
+    assert!(matches!(err, Error::BadResponse(_)), "{err}");
+
+    mock.add(test::handlers::failure(StatusCode::FORBIDDEN));
+    let err = client.insert_group().await.err().unwrap();
+    assert!(matches!(err, Error::BadResponse(_)), "{err}");
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "test-util")]
+
+use clickhouse::{Client, test};
+use hyper::StatusCode;
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn insert_sink() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = (0..5)
+        .map(|id| SimpleRow::new(id, format!("row {id}")))
+        .collect::<Vec<_>>();
+
+    let recordings = [(); 3].map(|_| mock.add(test::handlers::record()));
+    let sink = client.insert_sink::<SimpleRow>("t", 1).with_max_rows(2);
+
+    let sender = sink.sender();
+    let producer = tokio::spawn({
+        let rows = rows.clone();
+        async move {
+            for row in rows {
+                sender.send(row).await.unwrap();
+            }
+        }
+    });
+    producer.await.unwrap();
+
+    let totals = sink.end().await.unwrap();
+    assert_eq!(totals.rows, 5);
+    assert_eq!(totals.transactions, 3);
+
+    let mut recorded = Vec::<SimpleRow>::new();
+    for recording in recordings {
+        recorded.extend(recording.collect::<Vec<SimpleRow>>().await);
+    }
+    assert_eq!(recorded, rows);
+
+    // Stopped by a non-retryable error.
+    mock.add(test::handlers::failure(StatusCode::BAD_REQUEST));
+    let sink = client.insert_sink::<SimpleRow>("t", 1);
+    sink.send(SimpleRow::new(1, "one")).await.unwrap();
+
+    let sender = sink.sender();
+    let err = loop {
+        if let Err(err) = sender.send(SimpleRow::new(2, "two")).await {
+            break err;
+        }
+    };
+    assert!(err.to_string().contains("insert sink is stopped"), "{err}");
+
+    drop(sender);
+    assert!(sink.end().await.is_err());
+}
@@ -3,8 +3,12 @@ use std::string::ToString;
 use serde::Serialize;
 
 use crate::{SimpleRow, create_simple_table, fetch_rows, flush_query_log};
+#[cfg(feature = "lz4")]
+use clickhouse::Compression;
 use clickhouse::inserter::Inserter;
 use clickhouse::sql::Identifier;
+#[cfg(feature = "test-util")]
+use clickhouse::test;
 use clickhouse::{Client, Row, inserter::Quantities};
 
 #[derive(Debug, Row, Serialize)]
@@ -78,6 +82,7 @@ async fn limited_by_rows() {
             assert_ne!(inserted.bytes, 0);
             assert_eq!(inserted.rows, 10);
             assert_eq!(inserted.transactions, 5);
+            assert_eq!(inserted.stats.unwrap().rows, Some(10));
             assert_eq!(pending, &Quantities::ZERO);
         } else {
             assert_eq!(inserted, Quantities::ZERO);
@@ -139,6 +144,51 @@ async fn limited_by_bytes() {
     assert_eq!(count, rows);
 }
 
+#[cfg(feature = "lz4")]
+#[tokio::test]
+async fn limited_by_compressed_bytes() {
+    let client = prepare_database!().with_compression(Compression::Lz4);
+    create_table(&client).await;
+
+    let max_compressed_bytes = 10_000;
+    let mut inserter = client
+        .inserter::<MyRow>("test")
+        .with_max_compressed_bytes(max_compressed_bytes);
+
+    // Highly compressible rows.
+    let row = MyRow::new("x".repeat(999));
+    let mut rows = 0;
+
+    let inserted = loop {
+        inserter.write(&row).await.unwrap();
+        rows += 1;
+
+        let compressed = inserter.pending_compressed_bytes();
+        let inserted = inserter.commit().await.unwrap();
+
+        if compressed < max_compressed_bytes {
+            assert_eq!(inserted, Quantities::ZERO);
+            continue;
+        }
+
+        assert_eq!(inserted.rows, rows);
+        assert_eq!(inserter.pending_compressed_bytes(), 0);
+        break inserted;
+    };
+
+    // Much more than the limit before compression.
+    assert!(inserted.bytes > 10 * max_compressed_bytes, "{inserted:?}");
+    assert_eq!(inserter.end().await.unwrap(), Quantities::ZERO);
+
+    let count = client
+        .query("SELECT count() FROM test")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+
+    assert_eq!(count, rows);
+}
+
 #[cfg(feature = "test-util")] // only with `tokio::time::Instant`
 #[tokio::test(start_paused = true)]
 async fn limited_by_time() {
@@ -185,6 +235,140 @@ async fn limited_by_time() {
     assert_eq!(sum, (1..=rows).sum::<u64>());
 }
 
+#[cfg(feature = "test-util")] // only with `tokio::time::Instant`
+#[tokio::test(start_paused = true)]
+async fn limited_by_batch_age() {
+    use std::time::Duration;
+
+    let client = prepare_database!();
+    create_table(&client).await;
+
+    let step = Duration::from_millis(300);
+    let mut inserter = client
+        .inserter::<MyRow>("test")
+        .with_max_batch_age(Some(Duration::from_secs(1)));
+    assert_eq!(inserter.time_left(), None);
+    assert_eq!(inserter.batch_age(), None);
+    let rows = 12;
+
+    for i in 1..=rows {
+        inserter.write(&MyRow::new(i)).await.unwrap();
+        assert!(inserter.time_left().is_some());
+
+        tokio::time::sleep(step).await;
+
+        // The batch is started by its first row,
+        // so every batch has 4 rows written 300ms apart.
+        let inserted = inserter.commit().await.unwrap();
+        if i % 4 == 0 {
+            assert_eq!(inserted.rows, 4);
+            assert_eq!(inserter.batch_age(), None);
+            assert_eq!(inserter.time_left(), None);
+            assert!(inserter.last_batch_age().unwrap() >= step * 4);
+        } else {
+            assert_eq!(inserted, Quantities::ZERO);
+            assert_eq!(inserter.batch_age(), Some(step * (i % 4) as u32));
+        }
+    }
+
+    assert_eq!(inserter.end().await.unwrap(), Quantities::ZERO);
+
+    let count = client
+        .query("SELECT count() FROM test")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+    assert_eq!(count, rows);
+}
+
+#[cfg(feature = "test-util")] // only with `tokio::time::Instant`
+#[tokio::test(start_paused = true)]
+async fn limited_by_rate() {
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    let client = prepare_database!();
+    create_table(&client).await;
+
+    let mut inserter = client
+        .inserter::<MyRow>("test")
+        .with_rate_limit(10, u64::MAX)
+        .with_rate_limit_burst(Duration::from_secs(2))
+        .with_max_rows(20);
+
+    let started_at = Instant::now();
+    let rows = 50;
+
+    for i in 1..=rows {
+        inserter.write(&MyRow::new(i)).await.unwrap();
+        inserter.commit().await.unwrap();
+    }
+
+    assert_eq!(inserter.end().await.unwrap().rows, 10);
+
+    // Up to 20 rows are written immediately, then 10 rows per second.
+    let elapsed = started_at.elapsed();
+    assert!(elapsed >= Duration::from_secs(2), "{elapsed:?}");
+
+    let count = client
+        .query("SELECT count() FROM test")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+
+    assert_eq!(count, rows);
+}
+
+#[tokio::test]
+async fn recycles_idle_connection() {
+    use std::time::Duration;
+
+    let client = prepare_database!();
+    create_table(&client).await;
+
+    let log_comment = uuid::Uuid::new_v4().to_string();
+    let mut inserter = client
+        .inserter::<MyRow>("test")
+        .with_keep_alive(
+            Some(Duration::from_millis(1000)),
+            Duration::from_millis(500),
+        )
+        .with_setting("log_comment", &log_comment);
+
+    inserter.write(&MyRow::new(1)).await.unwrap();
+    inserter.force_commit().await.unwrap();
+
+    // Less than the pool's idle timeout, but more than `keep_alive - margin`.
+    tokio::time::sleep(Duration::from_millis(700)).await;
+
+    inserter.write(&MyRow::new(2)).await.unwrap();
+    inserter.end().await.unwrap();
+
+    flush_query_log(&client).await;
+
+    let ports = client
+        .query(
+            "
+            SELECT port
+            FROM system.query_log
+            WHERE log_comment = ?
+            AND type = 'QueryFinish'
+            AND query_kind = 'Insert'
+            ORDER BY event_time_microseconds
+            ",
+        )
+        .bind(&log_comment)
+        .fetch_all::<u16>()
+        .await
+        .unwrap();
+
+    assert_eq!(ports.len(), 2);
+    assert_ne!(
+        ports[0], ports[1],
+        "the idle connection should not be reused"
+    );
+}
+
 /// Similar to [`crate::insert::keeps_client_settings`] with minor differences.
 #[tokio::test]
 async fn keeps_client_settings() {
@@ -390,3 +574,106 @@ async fn inserter_with_role() {
     .await
     .expect_err("user should not be able to insert into `foo`");
 }
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn flush_interval() {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    tokio::time::pause();
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let committed = Arc::new(Mutex::new(Vec::new()));
+    let rows = vec![SimpleRow::new(1, "one"), SimpleRow::new(2, "two")];
+
+    let recording = mock.add(test::handlers::record());
+    let mut inserter = client
+        .inserter::<SimpleRow>("t")
+        .with_flush_interval(Duration::from_secs(5))
+        .with_commit_callback({
+            let committed = committed.clone();
+            move |quantities| committed.lock().unwrap().push(quantities.rows)
+        });
+
+    for row in &rows {
+        inserter.write(row).await.unwrap();
+    }
+    assert_eq!(inserter.commit().await.unwrap().rows, 0);
+
+    // Flushed without calling the inserter.
+    let recorded: Vec<SimpleRow> = recording.collect().await;
+    assert_eq!(recorded, rows);
+    assert!(committed.lock().unwrap().is_empty());
+
+    // Reported on the next call.
+    tokio::time::sleep(Duration::from_millis(1)).await;
+    let recording = mock.add(test::handlers::record());
+    inserter.write(&SimpleRow::new(3, "three")).await.unwrap();
+    assert_eq!(*committed.lock().unwrap(), [2]);
+    assert_eq!(inserter.pending().rows, 1);
+
+    // Not flushed if the inserter isn't idle.
+    tokio::time::advance(Duration::from_secs(3)).await;
+    inserter.write(&SimpleRow::new(4, "four")).await.unwrap();
+    tokio::time::advance(Duration::from_secs(3)).await;
+    inserter.write(&SimpleRow::new(5, "five")).await.unwrap();
+
+    let quantities = inserter.end().await.unwrap();
+    assert_eq!(quantities.rows, 3);
+    assert_eq!(*committed.lock().unwrap(), [2, 3]);
+
+    let recorded: Vec<SimpleRow> = recording.collect().await;
+    assert_eq!(recorded.len(), 3);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn on_committed() {
+    use hyper::StatusCode;
+    use std::sync::{Arc, Mutex};
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let committed = Arc::new(Mutex::new(Vec::new()));
+
+    let mut inserter = client
+        .inserter::<SimpleRow>("t")
+        .with_max_rows(2)
+        .on_committed({
+            let committed = committed.clone();
+            move |quantities, offset: Option<u64>| {
+                committed.lock().unwrap().push((quantities.rows, offset))
+            }
+        });
+
+    let recording = mock.add(test::handlers::record());
+    for offset in 0..2 {
+        inserter
+            .write(&SimpleRow::new(offset, "foo"))
+            .await
+            .unwrap();
+        inserter.set_batch_token(offset);
+        inserter.commit().await.unwrap();
+    }
+    recording.collect::<Vec<SimpleRow>>().await;
+    assert_eq!(*committed.lock().unwrap(), [(2, Some(1))]);
+
+    // The token of a failed batch is dropped.
+    mock.add(test::handlers::failure(StatusCode::INTERNAL_SERVER_ERROR));
+    inserter.write(&SimpleRow::new(2, "foo")).await.unwrap();
+    inserter.set_batch_token(2u64);
+    inserter.force_commit().await.unwrap_err();
+    assert_eq!(committed.lock().unwrap().len(), 1);
+
+    // Kept until the next batch if there are no rows.
+    inserter.set_batch_token(3u64);
+    assert_eq!(inserter.force_commit().await.unwrap().rows, 0);
+
+    let recording = mock.add(test::handlers::record());
+    inserter.write(&SimpleRow::new(4, "foo")).await.unwrap();
+    assert_eq!(inserter.end().await.unwrap().rows, 1);
+    recording.collect::<Vec<SimpleRow>>().await;
+    assert_eq!(*committed.lock().unwrap(), [(2, Some(1)), (1, Some(3))]);
+}
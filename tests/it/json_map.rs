@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use clickhouse::Row;
+
+#[tokio::test]
+async fn smoke() {
+    let client = prepare_database!();
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize, Row)]
+    struct MyRow {
+        id: u64,
+        #[serde(with = "clickhouse::serde::json_map")]
+        attrs: HashMap<String, Value>,
+        #[serde(with = "clickhouse::serde::json_map::option")]
+        attrs_opt: Option<serde_json::Map<String, Value>>,
+        #[serde(with = "clickhouse::serde::json_map::vec")]
+        attrs_vec: Vec<HashMap<String, Value>>,
+    }
+
+    client
+        .query(
+            "
+            CREATE TABLE test(
+                id UInt64,
+                attrs Map(String, String),
+                attrs_opt Map(String, String),
+                attrs_vec Array(Map(String, String))
+            ) ENGINE = MergeTree ORDER BY id
+        ",
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    let object = |value: Value| match value {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    };
+
+    let original_rows = vec![
+        MyRow {
+            id: 1,
+            attrs: object(json!({"user": {"id": 42, "tags": ["a", "b"]}, "ok": true}))
+                .into_iter()
+                .collect(),
+            attrs_opt: Some(object(json!({"ratio": 0.5}))),
+            attrs_vec: vec![HashMap::new(), HashMap::from([("n".into(), json!(null))])],
+        },
+        MyRow {
+            id: 2,
+            attrs: HashMap::new(),
+            attrs_opt: None,
+            attrs_vec: Vec::new(),
+        },
+    ];
+
+    let mut insert = client.insert::<MyRow>("test").await.unwrap();
+    for row in &original_rows {
+        insert.write(row).await.unwrap();
+    }
+    insert.end().await.unwrap();
+
+    let rows = client
+        .query("SELECT ?fields FROM test ORDER BY id")
+        .fetch_all::<MyRow>()
+        .await
+        .unwrap();
+    assert_eq!(rows, original_rows);
+
+    // Values are stored as JSON text, so they can be queried on the server.
+    let user_id = client
+        .query("SELECT JSONExtractUInt(attrs['user'], 'id') FROM test WHERE id = 1")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+    assert_eq!(user_id, 42);
+}
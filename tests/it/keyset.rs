@@ -0,0 +1,83 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use clickhouse::{Client, query::KeysetCheckpoint, test};
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn fetch_keyset() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = vec![
+        SimpleRow::new(1, "one"),
+        SimpleRow::new(2, "two"),
+        SimpleRow::new(3, "three"),
+    ];
+
+    mock.add(test::handlers::provide(rows[..2].to_vec()));
+    mock.add(test::handlers::provide(rows[2..].to_vec()));
+
+    let mut stream =
+        client
+            .query("SELECT ?fields FROM t")
+            .fetch_keyset("id", 2, |row: &SimpleRow| row.id);
+
+    let mut actual = Vec::new();
+    while let Some(row) = stream.next().await.unwrap() {
+        actual.push(row);
+    }
+    assert_eq!(actual, rows);
+    assert_eq!(
+        *stream.checkpoint(),
+        KeysetCheckpoint {
+            last_key: Some("3".into()),
+            rows: 3,
+        }
+    );
+
+    // Resumes after the checkpoint.
+    let recording = mock.add(test::handlers::record_ddl());
+    let mut stream = client
+        .query("SELECT ?fields FROM t WHERE data != 'a??'")
+        .fetch_keyset("id", 2, |row: &SimpleRow| row.id)
+        .with_checkpoint(stream.checkpoint().clone());
+
+    assert_eq!(stream.next().await.unwrap(), None);
+    assert_eq!(
+        recording.query().await,
+        "SELECT * FROM (SELECT `id`,`data` FROM t WHERE data != 'a?') WHERE id > 3 ORDER BY id LIMIT 2"
+    );
+    assert_eq!(stream.checkpoint().rows, 3);
+}
+
+#[tokio::test]
+async fn subscribe() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = (1..=4).map(|id| SimpleRow::new(id, id)).collect::<Vec<_>>();
+
+    let mut subscription = client.subscribe(
+        "SELECT ?fields FROM t",
+        "id",
+        Duration::from_millis(10),
+        |row: &SimpleRow| row.id,
+    );
+
+    mock.add(test::handlers::provide(rows[..3].to_vec()));
+    for expected in &rows[..3] {
+        assert_eq!(subscription.next().await.unwrap(), *expected);
+    }
+    assert_eq!(subscription.checkpoint().last_key.as_deref(), Some("3"));
+
+    // No new rows, then polls again.
+    let recording = mock.add(test::handlers::record_ddl());
+    mock.add(test::handlers::provide(rows[3..].to_vec()));
+    assert_eq!(subscription.next().await.unwrap(), rows[3]);
+    assert_eq!(
+        recording.query().await,
+        "SELECT * FROM (SELECT `id`,`data` FROM t) WHERE id > 3 ORDER BY id LIMIT 65536"
+    );
+    assert_eq!(subscription.checkpoint().rows, 4);
+}
@@ -247,25 +247,48 @@ pub(crate) mod decimals {
 }
 
 mod arrow;
+mod audit;
 mod bfloat16;
 mod chrono;
 mod cloud_jwt;
 mod compression;
+mod concurrency;
 mod cursor_error;
 mod cursor_stats;
+mod ddl;
+mod error_context;
 mod fetch_bytes;
+mod fetch_json;
+mod fetch_text;
+mod flatten;
+mod generic;
+mod golden;
+mod health;
+mod http2;
 mod https_errors;
 mod insert;
 mod insert_formatted;
+mod insert_group;
+#[cfg(feature = "inserter")]
+mod insert_sink;
 #[cfg(feature = "inserter")]
 mod inserter;
 mod int128;
 mod int256;
 mod ip;
+mod json_map;
+mod keyset;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "migrations")]
+mod migrations;
 mod mock;
 mod nested;
+mod newtype;
+mod observer;
 #[cfg(feature = "opentelemetry")]
 mod opentelemetry;
+mod pool;
 mod query;
 mod query_readonly;
 mod query_summary;
@@ -273,7 +296,13 @@ mod query_syntax;
 mod rbwnat_header;
 mod rbwnat_smoke;
 mod rbwnat_validation;
+#[cfg(feature = "deadpool")]
+mod session_pool;
+#[cfg(feature = "tracing")]
+mod spans;
 mod time;
+mod timeouts;
+mod ulid;
 mod user_agent;
 mod uuid;
 mod variant;
@@ -0,0 +1,95 @@
+#![cfg(feature = "test-util")]
+
+use clickhouse::{Client, test};
+use hyper::StatusCode;
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn query_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    // The test runtime is single-threaded, so all queries are finished here.
+    let _guard = metrics::set_default_local_recorder(&recorder);
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock).with_database("db");
+
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client.insert::<SimpleRow>("t").await.unwrap();
+    insert.write(&SimpleRow::new(1, "one")).await.unwrap();
+    insert.write(&SimpleRow::new(2, "two")).await.unwrap();
+    insert.end().await.unwrap();
+    let _: Vec<SimpleRow> = recording.collect().await;
+
+    mock.add(test::handlers::provide(vec![SimpleRow::new(1, "one")]));
+    client
+        .query("SELECT ?fields FROM t")
+        .fetch_all::<SimpleRow>()
+        .await
+        .unwrap();
+
+    mock.add(test::handlers::failure(StatusCode::BAD_REQUEST));
+    client.query("DROP TABLE t").execute().await.unwrap_err();
+
+    let metrics = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let (_, key) = key.into_parts();
+            let (name, labels) = key.into_parts();
+            let mut labels = labels
+                .into_iter()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect::<Vec<_>>();
+            labels.sort();
+            (format!("{}{{{}}}", name.as_str(), labels.join(",")), value)
+        })
+        .collect::<std::collections::HashMap<_, _>>();
+
+    let counter = |key: &str| match metrics.get(key) {
+        Some(DebugValue::Counter(value)) => *value,
+        other => panic!("unexpected {key}: {other:?}"),
+    };
+
+    let insert = "database=db,operation=insert,table=t";
+    assert_eq!(
+        counter(&format!("clickhouse_client_queries_total{{{insert}}}")),
+        1
+    );
+    assert_eq!(
+        counter(&format!(
+            "clickhouse_client_inserted_rows_total{{{insert}}}"
+        )),
+        2
+    );
+    assert!(
+        counter(&format!(
+            "clickhouse_client_inserted_bytes_total{{{insert}}}"
+        )) > 0
+    );
+
+    let fetch = "database=db,operation=fetch";
+    assert_eq!(
+        counter(&format!("clickhouse_client_queries_total{{{fetch}}}")),
+        1
+    );
+    assert_eq!(
+        counter(&format!("clickhouse_client_fetched_rows_total{{{fetch}}}")),
+        1
+    );
+
+    let execute = "database=db,operation=execute,table=t";
+    assert_eq!(
+        counter(
+            "clickhouse_client_errors_total{database=db,error=BadResponse,operation=execute,table=t}"
+        ),
+        1
+    );
+    assert!(matches!(
+        metrics.get(&format!("clickhouse_client_query_duration_seconds{{{execute}}}")),
+        Some(DebugValue::Histogram(values)) if values.len() == 1
+    ));
+}
@@ -0,0 +1,138 @@
+use clickhouse::{
+    error::Error,
+    migrations::{Migration, Migrator},
+};
+
+fn migrations() -> Vec<Migration> {
+    vec![
+        Migration::new(
+            1,
+            "create_events",
+            "CREATE TABLE events (id UInt64) ENGINE = MergeTree ORDER BY id;
+            -- Multiple statements are allowed.
+            INSERT INTO events VALUES (1);",
+        ),
+        Migration::new(
+            2,
+            "add_payload",
+            "ALTER TABLE events ADD COLUMN payload String DEFAULT 'x';",
+        ),
+    ]
+}
+
+#[tokio::test]
+async fn applies_pending() {
+    let client = prepare_database!();
+
+    // Nothing is created in the dry-run mode.
+    let pending = Migrator::new(&client, migrations())
+        .with_dry_run(true)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(pending, [1, 2]);
+    let exists = client
+        .query("EXISTS TABLE schema_migrations")
+        .fetch_one::<u8>()
+        .await
+        .unwrap();
+    assert_eq!(exists, 0);
+
+    // Provided in any order.
+    let applied = Migrator::new(&client, migrations().into_iter().rev())
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(applied, [1, 2]);
+
+    let rows = client
+        .query("SELECT id, payload FROM events")
+        .fetch_all::<(u64, String)>()
+        .await
+        .unwrap();
+    assert_eq!(rows, [(1, "x".into())]);
+
+    // Idempotent.
+    let applied = Migrator::new(&client, migrations()).run().await.unwrap();
+    assert!(applied.is_empty());
+
+    // Only new migrations are applied.
+    let mut next = migrations();
+    next.push(Migration::new(
+        3,
+        "insert",
+        "INSERT INTO events VALUES (2, 'y')",
+    ));
+
+    let pending = Migrator::new(&client, next.clone())
+        .with_dry_run(true)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(pending, [3]);
+
+    let applied = Migrator::new(&client, next).run().await.unwrap();
+    assert_eq!(applied, [3]);
+
+    let recorded = client
+        .query("SELECT version, name FROM schema_migrations ORDER BY version")
+        .fetch_all::<(u64, String)>()
+        .await
+        .unwrap();
+    assert_eq!(
+        recorded,
+        [
+            (1, "create_events".into()),
+            (2, "add_payload".into()),
+            (3, "insert".into())
+        ]
+    );
+}
+
+#[tokio::test]
+async fn verifies_applied() {
+    let client = prepare_database!();
+
+    let migrator = Migrator::new(&client, migrations()).with_table("migrations_log");
+    migrator.run().await.unwrap();
+
+    // Changed after being applied.
+    let mut changed = migrations();
+    changed[1] = Migration::new(2, "add_payload", "SELECT 1");
+    let err = Migrator::new(&client, changed)
+        .with_table("migrations_log")
+        .run()
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::Migration(msg) if msg.contains("has been changed")),
+        "{err:?}"
+    );
+
+    // Removed after being applied.
+    let err = Migrator::new(&client, migrations().into_iter().skip(1))
+        .with_table("migrations_log")
+        .with_dry_run(true)
+        .run()
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::Migration(msg) if msg.contains("is missing")),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn fails_on_duplicate_versions() {
+    // Fails before sending any request.
+    let client = clickhouse::Client::default().with_url("http://127.0.0.1:1");
+
+    let mut migrations = migrations();
+    migrations.push(Migration::new(1, "other", "SELECT 1"));
+
+    let err = Migrator::new(&client, migrations).run().await.unwrap_err();
+    assert!(
+        matches!(&err, Error::Migration(msg) if msg.contains("duplicate version 1")),
+        "{err:?}"
+    );
+}
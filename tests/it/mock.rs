@@ -60,6 +60,7 @@ async fn summary_header() {
     assert_eq!(summary.result_bytes(), Some(24));
     assert_eq!(summary.elapsed_ns(), Some(12345));
     assert_eq!(summary.memory_usage(), Some(1024));
+    assert_eq!(summary.query_cache_hit(), Some(false));
 }
 
 #[tokio::test]
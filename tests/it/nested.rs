@@ -50,3 +50,75 @@ async fn smoke() {
 
     assert_eq!(row, original_row);
 }
+
+#[tokio::test]
+async fn nested_attribute() {
+    let client = prepare_database!();
+
+    #[derive(Debug, PartialEq, Eq, Row)]
+    #[clickhouse(nested)]
+    struct Item {
+        name: String,
+        count: u32,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Row)]
+    struct MyRow {
+        no: i32,
+        #[clickhouse(nested)]
+        #[serde(with = "clickhouse::serde::nested")]
+        items: Vec<Item>,
+    }
+
+    client
+        .query(
+            "
+        CREATE TABLE test(
+            no      Int32,
+            items   Nested(
+                name    String,
+                count   UInt32
+            )
+        )
+        ENGINE = MergeTree ORDER BY no
+    ",
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    let original_row = MyRow {
+        no: 42,
+        items: vec![
+            Item {
+                name: "foo".into(),
+                count: 1,
+            },
+            Item {
+                name: "bar".into(),
+                count: 5,
+            },
+        ],
+    };
+
+    let mut insert = client.insert::<MyRow>("test").await.unwrap();
+    insert.write(&original_row).await.unwrap();
+    insert.end().await.unwrap();
+
+    let row = client
+        .query("SELECT ?fields FROM test")
+        .fetch_one::<MyRow>()
+        .await
+        .unwrap();
+
+    assert_eq!(row, original_row);
+
+    // Columns are still available separately.
+    let names = client
+        .query("SELECT items.name FROM test")
+        .fetch_one::<Vec<String>>()
+        .await
+        .unwrap();
+
+    assert_eq!(names, ["foo", "bar"]);
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "test-util")]
+
+use clickhouse::{Client, Row, test};
+use serde::{Deserialize, Serialize};
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn newtype_row() {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Row)]
+    struct Wrapper(SimpleRow);
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = vec![Wrapper(SimpleRow::new(1, "one"))];
+
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client.insert::<Wrapper>("t").await.unwrap();
+    insert.write(&rows[0]).await.unwrap();
+    insert.end().await.unwrap();
+    let recorded: Vec<SimpleRow> = recording.collect().await;
+    assert_eq!(recorded, [SimpleRow::new(1, "one")]);
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let fetched = crate::fetch_rows::<Wrapper>(&client, "doesn't matter").await;
+    assert_eq!(fetched, rows);
+}
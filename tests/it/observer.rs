@@ -0,0 +1,86 @@
+#![cfg(feature = "test-util")]
+
+use std::sync::{Arc, Mutex};
+
+use clickhouse::{
+    Client,
+    query::{QueryEvent, QueryKind},
+    test,
+};
+use hyper::StatusCode;
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn query_observer() {
+    #[derive(Debug, PartialEq)]
+    struct Observed {
+        kind: QueryKind,
+        sql: String,
+        query_id: Option<String>,
+        rows: Option<u64>,
+        failed: bool,
+    }
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock).with_query_observer({
+        let observed = observed.clone();
+        move |event: &QueryEvent<'_>| {
+            observed.lock().unwrap().push(Observed {
+                kind: event.kind,
+                sql: event.sql.to_string(),
+                query_id: event.query_id.map(Into::into),
+                rows: event.rows,
+                failed: event.error.is_some(),
+            });
+        }
+    });
+
+    mock.add(test::handlers::provide(vec![SimpleRow::new(1, "one")]));
+    client
+        .query("SELECT ?fields FROM t WHERE id = ?")
+        .bind(1)
+        .with_setting("query_id", "q1")
+        .fetch_all::<SimpleRow>()
+        .await
+        .unwrap();
+
+    mock.add(test::handlers::failure(StatusCode::BAD_REQUEST));
+    client.query("DROP TABLE t").execute().await.unwrap_err();
+
+    let recording = mock.add(test::handlers::record());
+    let mut insert = client.insert::<SimpleRow>("t").await.unwrap();
+    insert.write(&SimpleRow::new(1, "one")).await.unwrap();
+    insert.write(&SimpleRow::new(2, "two")).await.unwrap();
+    insert.end().await.unwrap();
+    let _: Vec<SimpleRow> = recording.collect().await;
+
+    let observed = observed.lock().unwrap();
+    assert_eq!(
+        *observed,
+        [
+            Observed {
+                kind: QueryKind::Fetch,
+                sql: "SELECT `id`,`data` FROM t WHERE id = 1".into(),
+                query_id: Some("q1".into()),
+                rows: Some(1),
+                failed: false,
+            },
+            Observed {
+                kind: QueryKind::Execute,
+                sql: "DROP TABLE t".into(),
+                query_id: None,
+                rows: None,
+                failed: true,
+            },
+            Observed {
+                kind: QueryKind::Insert,
+                sql: "INSERT INTO `t`(`id`,`data`) FORMAT RowBinary".into(),
+                query_id: None,
+                rows: Some(2),
+                failed: false,
+            },
+        ]
+    );
+}
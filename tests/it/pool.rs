@@ -0,0 +1,61 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use clickhouse::{Client, test};
+use hyper_util::{client::legacy::Client as HyperClient, rt::TokioExecutor};
+
+use crate::SimpleRow;
+
+#[tokio::test]
+async fn pool_stats() {
+    let mock = test::Mock::new();
+    let client = Client::default()
+        .with_mock(&mock)
+        .with_pool_max_idle_per_host(4)
+        .with_pool_idle_timeout(Some(Duration::from_secs(1)))
+        .with_tcp_keepalive(None);
+
+    // (idle, in_flight)
+    let stats = |client: &Client| {
+        let stats = client.pool_stats().unwrap();
+        assert_eq!(stats.idle, stats.open.saturating_sub(stats.in_flight));
+        (stats.idle, stats.in_flight)
+    };
+    let sql = "SELECT ?fields FROM t";
+    let rows = vec![SimpleRow::new(1, "one"), SimpleRow::new(2, "two")];
+
+    assert_eq!(stats(&client), (0, 0));
+
+    // The request is in flight until the cursor is exhausted.
+    mock.add(test::handlers::provide(rows.clone()));
+    let mut cursor = client.query(sql).fetch::<SimpleRow>().unwrap();
+    assert_eq!(cursor.next().await.unwrap(), Some(rows[0].clone()));
+    assert_eq!(stats(&client), (0, 1));
+    assert_eq!(stats(&client.clone()), (0, 1));
+
+    assert_eq!(cursor.next().await.unwrap(), Some(rows[1].clone()));
+    assert_eq!(cursor.next().await.unwrap(), None);
+    assert_eq!(stats(&client).1, 0);
+
+    // The mock server closes connections after every response.
+    while client.pool_stats().unwrap().open != 0 {
+        tokio::task::yield_now().await;
+    }
+
+    // Reconfiguring creates a new pool.
+    let other = client.clone().with_pool_max_idle_per_host(1);
+    mock.add(test::handlers::provide(rows.clone()));
+    let mut cursor = other.query(sql).fetch::<SimpleRow>().unwrap();
+    assert_eq!(cursor.next().await.unwrap(), Some(rows[0].clone()));
+    assert_eq!(stats(&other), (0, 1));
+    assert_eq!(stats(&client), (0, 0));
+    drop(cursor);
+    assert_eq!(stats(&other), (0, 0));
+}
+
+#[test]
+fn pool_stats_custom_http_client() {
+    let http = HyperClient::builder(TokioExecutor::new()).build_http();
+    assert_eq!(Client::with_http_client(http).pool_stats(), None);
+}
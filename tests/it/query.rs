@@ -1,8 +1,13 @@
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 use clickhouse::sql::Identifier;
-use clickhouse::{Row, error::Error};
+#[cfg(feature = "test-util")]
+use clickhouse::test;
+use clickhouse::{Client, Row, error::Error};
+
+use crate::{SimpleRow, create_simple_table, flush_query_log};
 
 #[tokio::test]
 async fn smoke() {
@@ -87,6 +92,70 @@ async fn fetch_one_and_optional() {
     assert_eq!(got_string, "bar");
 }
 
+#[tokio::test]
+async fn fetch_all_lossy() {
+    let client = prepare_database!();
+
+    #[derive(Debug, PartialEq, Deserialize, Row)]
+    struct Data {
+        id: u64,
+        payload: String,
+    }
+
+    // Every third payload is invalid UTF-8.
+    let (rows, errors) = client
+        .query(
+            "SELECT number AS id, if(number % 3 = 1, unhex('FF'), toString(number)) AS payload
+             FROM system.numbers LIMIT 6",
+        )
+        .fetch_all_lossy::<Data>()
+        .await
+        .unwrap();
+
+    let ids = rows.iter().map(|row| row.id).collect::<Vec<_>>();
+    assert_eq!(ids, [0, 2, 3, 5]);
+    assert_eq!(rows[1].payload, "2");
+
+    assert_eq!(errors.len(), 2);
+    for (error, id) in errors.iter().zip([1u64, 4]) {
+        assert_eq!(error.index, id);
+        assert!(
+            matches!(error.error, Error::InvalidUtf8Encoding(_)),
+            "{error}"
+        );
+
+        let mut raw = id.to_le_bytes().to_vec();
+        raw.extend([1, 0xFF]);
+        assert_eq!(error.raw, raw);
+    }
+}
+
+#[tokio::test]
+async fn fetch_keyset() {
+    let client = prepare_database!();
+
+    #[derive(Debug, PartialEq, Deserialize, Row)]
+    struct Data {
+        bucket: u64,
+        id: u64,
+    }
+
+    // The key is not unique without `id`.
+    let mut stream = client
+        .query("SELECT intDiv(number, 4) AS bucket, number AS id FROM system.numbers LIMIT 10")
+        .fetch_keyset("(bucket, id)", 3, |row: &Data| (row.bucket, row.id));
+
+    let mut ids = Vec::new();
+    while let Some(row) = stream.next().await.unwrap() {
+        assert_eq!(row.bucket, row.id / 4);
+        ids.push(row.id);
+    }
+
+    assert_eq!(ids, (0..10).collect::<Vec<_>>());
+    assert_eq!(stream.checkpoint().last_key.as_deref(), Some("(2,9)"));
+    assert_eq!(stream.checkpoint().rows, 10);
+}
+
 #[tokio::test]
 async fn server_side_param() {
     let client = prepare_database!();
@@ -143,6 +212,45 @@ async fn server_side_param() {
     assert_eq!(result, bytes);
 }
 
+#[tokio::test]
+async fn server_side_fields_values() {
+    #[derive(Debug, PartialEq, Row, Serialize, Deserialize)]
+    struct Event {
+        id: u64,
+        name: String,
+        tags: Vec<String>,
+        score: Option<f64>,
+    }
+
+    let client = prepare_database!();
+
+    let event = Event {
+        id: 42,
+        name: "it's \\ \"quoted\"".into(),
+        tags: vec!["a".into(), "b'c".into()],
+        score: None,
+    };
+
+    let result = client
+        .query(
+            "SELECT {id: UInt64} AS id, {name: String} AS name,
+                    {tags: Array(String)} AS tags, {score: Nullable(Float64)} AS score",
+        )
+        .bind_fields_values(&event)
+        .fetch_one::<Event>()
+        .await
+        .expect("failed to fetch event");
+    assert_eq!(result, event);
+
+    let err = client
+        .query("SELECT {val: UInt64}")
+        .bind_fields_values(&42u64)
+        .fetch_one::<u64>()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("only structs"), "{err}");
+}
+
 // See #19.
 #[tokio::test]
 async fn long_query() {
@@ -273,6 +381,22 @@ async fn overrides_client_settings() {
     assert_eq!(value, override_value);
 }
 
+#[tokio::test]
+async fn typed_settings() {
+    use clickhouse::Settings;
+
+    let client = prepare_database!().with_settings(Settings::new().max_threads(3).priority(7));
+    let sql = "SELECT toString(getSetting('max_threads')), toString(getSetting('priority'))";
+
+    let (max_threads, priority) = client
+        .query(sql)
+        .with_settings(Settings::new().priority(5))
+        .fetch_one::<(String, String)>()
+        .await
+        .unwrap();
+    assert_eq!((max_threads.as_str(), priority.as_str()), ("3", "5"));
+}
+
 #[tokio::test]
 async fn prints_query() {
     let client = prepare_database!();
@@ -363,3 +487,973 @@ async fn query_with_role() {
         .await
         .expect("user should be able to query `foo` now");
 }
+
+#[tokio::test]
+async fn pool_stats() {
+    let client = prepare_database!();
+
+    let mut cursor = client
+        .query("SELECT number FROM system.numbers LIMIT 3")
+        .fetch::<u64>()
+        .unwrap();
+    assert_eq!(cursor.next().await.unwrap(), Some(0));
+    let stats = client.pool_stats().unwrap();
+    assert_eq!(stats.in_flight, 1);
+    assert!(stats.open >= 1, "{stats:?}");
+
+    while cursor.next().await.unwrap().is_some() {}
+    drop(cursor);
+
+    // The connection is returned to the pool in the background.
+    let stats = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let stats = client.pool_stats().unwrap();
+            if stats.idle > 0 {
+                break stats;
+            }
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("the connection isn't returned to the pool");
+
+    assert_eq!(stats.in_flight, 0);
+    assert_eq!(stats.idle, stats.open);
+}
+
+#[tokio::test]
+async fn cursor_drain_limit() {
+    let client = prepare_database!().with_cursor_drain_limit(1024 * 1024);
+
+    let mut cursor = client
+        .query("SELECT number FROM system.numbers LIMIT 1000")
+        .fetch::<u64>()
+        .unwrap();
+    assert_eq!(cursor.next().await.unwrap(), Some(0));
+    drop(cursor);
+
+    // The rest of the response is drained in the background,
+    // so the connection is returned to the pool instead of being closed.
+    let stats = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let stats = client.pool_stats().unwrap();
+            if stats.idle > 0 {
+                break stats;
+            }
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("the connection isn't returned to the pool");
+
+    assert_eq!(stats.in_flight, 0);
+    assert_eq!(stats.idle, stats.open);
+}
+
+#[tokio::test]
+async fn refresh_dns() {
+    if crate::test_env() == crate::TestEnv::Cloud {
+        eprintln!("Skipping test as client ports are hidden by a proxy");
+        return;
+    }
+
+    let client = prepare_database!();
+
+    // The client's port identifies the connection.
+    async fn port(client: &Client) -> u16 {
+        client
+            .query("SELECT port FROM system.processes WHERE query_id = queryID()")
+            .fetch_all::<u16>()
+            .await
+            .unwrap()[0]
+    }
+
+    let first = port(&client).await;
+    assert_eq!(port(&client).await, first);
+
+    client.refresh_dns();
+    let second = port(&client).await;
+    assert_ne!(second, first);
+    assert_eq!(port(&client).await, second);
+
+    // Connections aren't reused after the interval.
+    let client = client.with_dns_refresh_interval(Some(Duration::ZERO));
+    let first = port(&client).await;
+    assert_ne!(port(&client).await, first);
+}
+
+#[tokio::test]
+async fn read_hint() {
+    use clickhouse::query::ReadHint;
+
+    let client = prepare_database!();
+    let sql = "SELECT toString(getSetting('max_threads')), toString(getSetting('priority'))";
+
+    let (max_threads, priority) = client
+        .query(sql)
+        .hint(ReadHint::PointLookup)
+        .fetch_one::<(String, String)>()
+        .await
+        .unwrap();
+    assert_eq!((max_threads.as_str(), priority.as_str()), ("1", "1"));
+
+    // Explicit settings take precedence.
+    let (max_threads, priority) = client
+        .clone()
+        .with_setting("priority", "5")
+        .query(sql)
+        .hint(ReadHint::PointLookup)
+        .with_setting("max_threads", "2")
+        .fetch_one::<(String, String)>()
+        .await
+        .unwrap();
+    assert_eq!((max_threads.as_str(), priority.as_str()), ("2", "5"));
+
+    // The last hint wins.
+    let (_, priority) = client
+        .query(sql)
+        .hint(ReadHint::PointLookup)
+        .hint(ReadHint::FullScanExport)
+        .fetch_one::<(String, String)>()
+        .await
+        .unwrap();
+    assert_eq!(priority, "10");
+}
+
+#[tokio::test]
+async fn execute_batch() {
+    let client = prepare_database!();
+
+    client
+        .execute_batch(
+            "CREATE TABLE test(no UInt32, name String) ENGINE = MergeTree ORDER BY no;
+            /* Semicolons; in comments */ INSERT INTO test VALUES (1, 'a;b'), (2, '?');
+            -- and in strings are ignored;
+            ",
+        )
+        .await
+        .unwrap();
+
+    let rows = client
+        .query("SELECT ?fields FROM test ORDER BY no")
+        .fetch_all::<(u32, String)>()
+        .await
+        .unwrap();
+    assert_eq!(rows, [(1, "a;b".into()), (2, "?".into())]);
+
+    // Stops at the first failed statement.
+    let err = client
+        .execute_batch(
+            "INSERT INTO test VALUES (3, 'c'); SELECT unknown; INSERT INTO test VALUES (4, 'd')",
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::BadResponse(_)), "{err:?}");
+
+    let count = client
+        .query("SELECT count() FROM test")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+}
+
+#[tokio::test]
+async fn update_diff() {
+    let client = prepare_database!();
+    create_simple_table(&client, "test").await;
+
+    let old = SimpleRow::new(1, "foo");
+    let mut insert = client.insert::<SimpleRow>("test").await.unwrap();
+    insert.write(&old).await.unwrap();
+    insert.write(&SimpleRow::new(2, "baz")).await.unwrap();
+    insert.end().await.unwrap();
+
+    let new = SimpleRow::new(1, "it's bar?");
+    client
+        .update_diff("test", &["id"], &old, &new)
+        .unwrap()
+        .unwrap()
+        .with_setting("mutations_sync", "1")
+        .execute()
+        .await
+        .unwrap();
+
+    let rows = client
+        .query("SELECT ?fields FROM test ORDER BY id")
+        .fetch_all::<SimpleRow>()
+        .await
+        .unwrap();
+    assert_eq!(rows, [new, SimpleRow::new(2, "baz")]);
+}
+
+#[tokio::test]
+async fn audit_sink() {
+    use clickhouse::audit::{AuditRecord, StatementKind};
+    use std::sync::{Arc, Mutex};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let client = prepare_database!().with_audit_sink({
+        let records = records.clone();
+        move |record: &AuditRecord<'_>| {
+            records
+                .lock()
+                .unwrap()
+                .push((record.kind, record.query_id.to_string()));
+        }
+    });
+
+    create_simple_table(&client, "test").await;
+    client
+        .query("SELECT count() FROM test")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+
+    let (kind, query_id) = records.lock().unwrap().pop().unwrap();
+    assert_eq!(kind, StatementKind::Create);
+    assert!(records.lock().unwrap().is_empty());
+
+    // The generated `query_id` is sent to the server.
+    flush_query_log(&client).await;
+    let count = client
+        .query("SELECT count() FROM system.query_log WHERE query_id = ? AND query_kind = 'Create'")
+        .bind(&query_id)
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+    assert!(count > 0);
+}
+
+#[tokio::test]
+async fn quota_key() {
+    let client = prepare_database!().with_quota_key("client-key");
+
+    let query_id = uuid::Uuid::new_v4().to_string();
+    client
+        .query("SELECT 1")
+        .with_setting("query_id", &query_id)
+        .fetch_one::<u8>()
+        .await
+        .unwrap();
+
+    let overridden_query_id = uuid::Uuid::new_v4().to_string();
+    client
+        .query("SELECT 1")
+        .with_setting("query_id", &overridden_query_id)
+        .with_quota_key("query-key")
+        .fetch_one::<u8>()
+        .await
+        .unwrap();
+
+    flush_query_log(&client).await;
+
+    let quota_key = async |query_id: &str| {
+        client
+            .query("SELECT any(quota_key) FROM system.query_log WHERE query_id = ?")
+            .bind(query_id)
+            .fetch_one::<String>()
+            .await
+            .unwrap()
+    };
+
+    assert_eq!(quota_key(&query_id).await, "client-key");
+    assert_eq!(quota_key(&overridden_query_id).await, "query-key");
+}
+
+#[tokio::test]
+async fn tags() {
+    let client = prepare_database!();
+    create_simple_table(&client, "test").await;
+
+    let query_id = uuid::Uuid::new_v4().to_string();
+    client
+        .query("SELECT count() FROM test")
+        .with_setting("query_id", &query_id)
+        .with_tag("feature", "search")
+        .with_tag("endpoint", "/users?q=\"it's\"")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+
+    let insert_query_id = uuid::Uuid::new_v4().to_string();
+    let mut insert = client
+        .insert::<SimpleRow>("test")
+        .await
+        .unwrap()
+        .with_setting("query_id", &insert_query_id)
+        .with_tag("feature", "import");
+    insert.write(&SimpleRow::new(1, "one")).await.unwrap();
+    insert.end().await.unwrap();
+
+    flush_query_log(&client).await;
+
+    let tags = async |query_id: &str| {
+        client
+            .query(
+                "SELECT any(JSONExtractString(log_comment, 'feature')), \
+                        any(JSONExtractString(log_comment, 'endpoint')) \
+                 FROM system.query_log WHERE query_id = ?",
+            )
+            .bind(query_id)
+            .fetch_one::<(String, String)>()
+            .await
+            .unwrap()
+    };
+
+    assert_eq!(
+        tags(&query_id).await,
+        ("search".into(), "/users?q=\"it's\"".into())
+    );
+    assert_eq!(
+        tags(&insert_query_id).await,
+        ("import".into(), String::new())
+    );
+}
+
+#[tokio::test]
+async fn describe() {
+    use clickhouse::types::{Column, DataTypeNode};
+
+    let client = prepare_database!();
+    create_simple_table(&client, "test").await;
+
+    let columns = client
+        .query("SELECT id, toNullable(data) AS data, ? AS number FROM test WHERE id > ?")
+        .bind(42u32)
+        .bind(1)
+        .describe()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        columns,
+        [
+            Column::new("id".into(), DataTypeNode::UInt64),
+            Column::new(
+                "data".into(),
+                DataTypeNode::Nullable(Box::new(DataTypeNode::String))
+            ),
+            Column::new("number".into(), DataTypeNode::UInt8),
+        ]
+    );
+
+    // Invalid queries fail without fetching anything.
+    let err = client
+        .query("SELECT unknown_column FROM test")
+        .describe()
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::BadResponse(_) | Error::ServerException { .. }),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn response_headers() {
+    let client = prepare_database!();
+
+    let query_id = uuid::Uuid::new_v4().to_string();
+    let mut cursor = client
+        .query("SELECT 1")
+        .with_setting("query_id", &query_id)
+        .with_setting("session_timezone", "Asia/Tokyo")
+        .fetch::<u8>()
+        .unwrap();
+
+    assert!(cursor.headers().is_none());
+    assert_eq!(cursor.next().await.unwrap(), Some(1));
+
+    let headers = cursor.headers().unwrap();
+    assert_eq!(headers.query_id(), Some(query_id.as_str()));
+    assert_eq!(headers.timezone(), Some("Asia/Tokyo"));
+    assert_eq!(headers.format(), Some("RowBinaryWithNamesAndTypes"));
+
+    let query_id = uuid::Uuid::new_v4().to_string();
+    let headers = client
+        .query("SELECT 1")
+        .with_setting("query_id", &query_id)
+        .execute_with_headers()
+        .await
+        .unwrap();
+    assert_eq!(headers.query_id(), Some(query_id.as_str()));
+}
+
+#[tokio::test]
+async fn fetch_with_totals() {
+    #[derive(Debug, PartialEq, Row, Deserialize)]
+    struct Stats {
+        parity: u64,
+        sum: u64,
+    }
+
+    let client = prepare_database!();
+
+    let mut cursor = client
+        .query(
+            "SELECT number % 2 AS parity, sum(number) AS sum FROM numbers(10)
+             GROUP BY parity WITH TOTALS ORDER BY parity",
+        )
+        .with_setting("extremes", "1")
+        .fetch_with_totals::<Stats>()
+        .unwrap();
+
+    let mut rows = Vec::new();
+    while let Some(row) = cursor.next().await.unwrap() {
+        rows.push(row);
+    }
+    assert_eq!(
+        rows,
+        [Stats { parity: 0, sum: 20 }, Stats { parity: 1, sum: 25 }]
+    );
+
+    assert_eq!(cursor.totals(), Some(&Stats { parity: 0, sum: 45 }));
+    let extremes = cursor.extremes().unwrap();
+    assert_eq!(extremes.min, Stats { parity: 0, sum: 20 });
+    assert_eq!(extremes.max, Stats { parity: 1, sum: 25 });
+
+    // Without totals and extremes.
+    let mut cursor = client
+        .query("SELECT ?fields FROM (SELECT 1 AS parity, 2 AS sum)")
+        .fetch_with_totals::<Stats>()
+        .unwrap();
+    assert_eq!(
+        cursor.next().await.unwrap(),
+        Some(Stats { parity: 1, sum: 2 })
+    );
+    assert_eq!(cursor.next().await.unwrap(), None);
+    assert_eq!(cursor.totals(), None);
+    assert!(cursor.extremes().is_none());
+}
+
+#[tokio::test]
+async fn with_header() {
+    let client = prepare_database!().with_header("Referer", "https://client.example");
+
+    let query_id = uuid::Uuid::new_v4().to_string();
+    client
+        .query("SELECT 1")
+        .with_setting("query_id", &query_id)
+        .fetch_one::<u8>()
+        .await
+        .unwrap();
+
+    let overridden_query_id = uuid::Uuid::new_v4().to_string();
+    client
+        .query("SELECT 1")
+        .with_setting("query_id", &overridden_query_id)
+        .with_header("referer", "https://query.example")
+        .fetch_one::<u8>()
+        .await
+        .unwrap();
+
+    flush_query_log(&client).await;
+
+    let referer = async |query_id: &str| {
+        client
+            .query("SELECT any(http_referer) FROM system.query_log WHERE query_id = ?")
+            .bind(query_id)
+            .fetch_one::<String>()
+            .await
+            .unwrap()
+    };
+
+    assert_eq!(referer(&query_id).await, "https://client.example");
+    assert_eq!(referer(&overridden_query_id).await, "https://query.example");
+}
+
+#[tokio::test]
+async fn allow_extra_columns() {
+    let client = prepare_database!();
+
+    #[derive(Debug, PartialEq, Row, Deserialize)]
+    struct Partial {
+        name: String,
+        no: u32,
+    }
+
+    client
+        .query(
+            "
+            CREATE TABLE test(
+                created DateTime,
+                no UInt32,
+                tags Array(String),
+                name String,
+                value Float64
+            )
+            ENGINE = MergeTree
+            ORDER BY no
+        ",
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    client
+        .query("INSERT INTO test VALUES (now(), 1, ['a'], 'foo', 0.5), (now(), 2, [], 'bar', 1)")
+        .execute()
+        .await
+        .unwrap();
+
+    let err = client
+        .query("SELECT * FROM test ORDER BY no")
+        .fetch_all::<Partial>()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::SchemaMismatch(_)), "{err}");
+
+    let rows = client
+        .query("SELECT * FROM test ORDER BY no")
+        .allow_extra_columns()
+        .fetch_all::<Partial>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows,
+        [
+            Partial {
+                name: "foo".into(),
+                no: 1
+            },
+            Partial {
+                name: "bar".into(),
+                no: 2
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn fetch_map() {
+    use clickhouse::query::DuplicateKeys;
+    use std::collections::{BTreeMap, HashMap};
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let pairs = || {
+        vec![
+            (1_u64, "one".to_string()),
+            (2, "two".into()),
+            (1, "uno".into()),
+        ]
+    };
+
+    mock.add(test::handlers::provide(pairs()));
+    let err = client
+        .query("doesn't matter")
+        .fetch_map::<HashMap<u64, String>>(DuplicateKeys::Error)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::DuplicateKey(key) if key == "1"));
+
+    mock.add(test::handlers::provide(pairs()));
+    let first: BTreeMap<u64, String> = client
+        .query("doesn't matter")
+        .fetch_map(DuplicateKeys::First)
+        .await
+        .unwrap();
+    assert_eq!(
+        first,
+        BTreeMap::from([(1, "one".into()), (2, "two".into())])
+    );
+
+    mock.add(test::handlers::provide(pairs()));
+    let last: HashMap<u64, String> = client
+        .query("doesn't matter")
+        .fetch_map(DuplicateKeys::Last)
+        .await
+        .unwrap();
+    assert_eq!(last, HashMap::from([(1, "uno".into()), (2, "two".into())]));
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn fetch_map_by() {
+    use clickhouse::query::DuplicateKeys;
+    use std::collections::HashMap;
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = vec![SimpleRow::new(1, "one"), SimpleRow::new(2, "two")];
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let actual: HashMap<u64, SimpleRow> = client
+        .query("doesn't matter")
+        .fetch_map_by(DuplicateKeys::Error, |row: &SimpleRow| row.id)
+        .await
+        .unwrap();
+
+    let expected = rows.into_iter().map(|row| (row.id, row)).collect();
+    assert_eq!(actual, expected);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn execute_batch_splits_statements() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+
+    let first = mock.add(test::handlers::record_ddl());
+    let second = mock.add(test::handlers::record_ddl());
+
+    client
+        .execute_batch(
+            "CREATE TABLE t (s String) ENGINE = Memory;
+            -- Insert a value; semicolons in comments are ignored.
+            INSERT INTO t VALUES ('a;b?');
+            ",
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        first.query().await,
+        "CREATE TABLE t (s String) ENGINE = Memory"
+    );
+    assert_eq!(
+        second.query().await,
+        "-- Insert a value; semicolons in comments are ignored.\n            INSERT INTO t VALUES ('a;b?')"
+    );
+
+    // Stops at the first failed statement.
+    mock.add(test::handlers::exception(209));
+    let err = client
+        .execute_batch("SELECT 1; SELECT 2")
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, clickhouse::error::Error::BadResponse(_)),
+        "{err:?}"
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn fetch_all_spilled() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let dir = std::env::temp_dir().join(format!("clickhouse-spill-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let sql = "SELECT ?fields FROM t";
+    let rows = (0..1000)
+        .map(|i| SimpleRow::new(i, format!("row {i}")))
+        .collect::<Vec<_>>();
+
+    let is_empty = |dir: &std::path::Path| std::fs::read_dir(dir).unwrap().next().is_none();
+
+    // Fits into the budget.
+    mock.add(test::handlers::provide(rows.clone()));
+    let spilled = client
+        .query(sql)
+        .fetch_all_spilled::<SimpleRow>(1 << 20, &dir)
+        .await
+        .unwrap();
+    assert!(!spilled.is_spilled());
+    assert!(is_empty(&dir));
+    assert_eq!(spilled.collect::<Result<Vec<_>, _>>().unwrap(), rows);
+
+    // Spilled in several batches.
+    mock.add(test::handlers::provide(rows.clone()));
+    let spilled = client
+        .query(sql)
+        .fetch_all_spilled::<SimpleRow>(1024, &dir)
+        .await
+        .unwrap();
+    assert!(spilled.is_spilled());
+    assert!(!is_empty(&dir));
+    assert_eq!(spilled.collect::<Result<Vec<_>, _>>().unwrap(), rows);
+
+    // The file is removed once the iterator is dropped.
+    assert!(is_empty(&dir));
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let mut spilled = client
+        .query(sql)
+        .fetch_all_spilled::<SimpleRow>(0, &dir)
+        .await
+        .unwrap();
+    assert_eq!(spilled.next().unwrap().unwrap(), rows[0]);
+    drop(spilled);
+    assert!(is_empty(&dir));
+
+    std::fs::remove_dir(&dir).unwrap();
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn update_diff_statement() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+
+    let old = SimpleRow::new(1, "foo");
+    let new = SimpleRow::new(1, "bar?");
+
+    assert!(
+        client
+            .update_diff("t", &["id"], &old, &old)
+            .unwrap()
+            .is_none()
+    );
+
+    let recording = mock.add(test::handlers::record_ddl());
+    client
+        .update_diff("t", &["id"], &old, &new)
+        .unwrap()
+        .unwrap()
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        recording.query().await,
+        "ALTER TABLE t UPDATE `data` = 'bar?' WHERE `id` = 1"
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn next_with_bytes() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = vec![SimpleRow::new(1, "one"), SimpleRow::new(2, "two")];
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let mut cursor = client
+        .query("SELECT ?fields FROM t")
+        .fetch::<SimpleRow>()
+        .unwrap();
+
+    for expected in rows {
+        let (row, raw) = cursor.next_with_bytes().await.unwrap().unwrap();
+        let mut encoded = expected.id.to_le_bytes().to_vec();
+        encoded.push(expected.data.len() as u8);
+        encoded.extend_from_slice(expected.data.as_bytes());
+
+        assert_eq!(row, expected);
+        assert_eq!(raw, encoded);
+    }
+
+    assert!(cursor.next_with_bytes().await.unwrap().is_none());
+    assert_eq!(cursor.returned_rows(), 2);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn strict_single_row() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = vec![SimpleRow::new(1, "one"), SimpleRow::new(2, "two")];
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let row = client
+        .query("doesn't matter")
+        .fetch_one::<SimpleRow>()
+        .await
+        .unwrap();
+    assert_eq!(row, rows[0]);
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let err = client
+        .query("doesn't matter")
+        .with_strict_single_row()
+        .fetch_one::<SimpleRow>()
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, clickhouse::error::Error::TooManyRows),
+        "{err:?}"
+    );
+
+    mock.add(test::handlers::provide(rows[..1].to_vec()));
+    let row = client
+        .query("doesn't matter")
+        .with_strict_single_row()
+        .fetch_optional::<SimpleRow>()
+        .await
+        .unwrap();
+    assert_eq!(row, Some(rows[0].clone()));
+
+    mock.add(test::handlers::provide(Vec::<SimpleRow>::new()));
+    let row = client
+        .query("doesn't matter")
+        .with_strict_single_row()
+        .fetch_optional::<SimpleRow>()
+        .await
+        .unwrap();
+    assert_eq!(row, None);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn response_headers_summary() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let summary_json = r#"{"read_rows":"1"}"#;
+
+    mock.add(test::handlers::provide_with_summary(
+        vec![SimpleRow::new(1, "one")],
+        summary_json,
+    ));
+
+    let mut cursor = client
+        .query("doesn't matter")
+        .fetch_bytes("RowBinary")
+        .unwrap();
+    assert!(cursor.headers().is_none());
+    cursor.collect().await.unwrap();
+
+    let headers = cursor.headers().expect("headers should be present");
+    assert_eq!(headers.get("X-ClickHouse-Summary"), Some(summary_json));
+    assert_eq!(headers.summary().unwrap().read_rows(), Some(1));
+    assert_eq!(headers.query_id(), None);
+
+    mock.add(test::handlers::provide_with_summary(
+        Vec::<SimpleRow>::new(),
+        summary_json,
+    ));
+
+    let headers = client
+        .query("doesn't matter")
+        .execute_with_headers()
+        .await
+        .unwrap();
+    assert_eq!(headers.summary().unwrap().read_rows(), Some(1));
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn fetch_partitioned() {
+    use clickhouse::query::Partitioning;
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = (1..=4).map(|id| SimpleRow::new(id, id)).collect::<Vec<_>>();
+
+    // By hash.
+    mock.add(test::handlers::provide(rows[..1].to_vec()));
+    mock.add(test::handlers::provide(rows[1..].to_vec()));
+
+    let mut stream = client
+        .query("SELECT ?fields FROM t")
+        .fetch_partitioned::<SimpleRow>(2, Partitioning::hash("id"));
+
+    let mut actual = Vec::new();
+    while let Some(row) = stream.next().await.unwrap() {
+        actual.push(row);
+    }
+    actual.sort_by_key(|row| row.id);
+    assert_eq!(actual, rows);
+
+    // By ranges.
+    mock.add(test::handlers::provide(vec![(1_i128, 10_i128, 8_u64)]));
+    let recordings = [
+        mock.add(test::handlers::record_ddl()),
+        mock.add(test::handlers::record_ddl()),
+        mock.add(test::handlers::record_ddl()),
+    ];
+
+    let mut stream = client
+        .query("SELECT ?fields FROM t")
+        .fetch_partitioned::<SimpleRow>(3, Partitioning::range("id"));
+    assert_eq!(stream.next().await.unwrap(), None);
+
+    let mut queries = Vec::new();
+    for recording in recordings {
+        queries.push(recording.query().await);
+    }
+    queries.sort();
+    assert_eq!(
+        queries,
+        [
+            "SELECT * FROM (SELECT `id`,`data` FROM t) WHERE toInt128(id) >= 1 AND toInt128(id) < 5",
+            "SELECT * FROM (SELECT `id`,`data` FROM t) WHERE toInt128(id) >= 5 AND toInt128(id) < 9",
+            "SELECT * FROM (SELECT `id`,`data` FROM t) WHERE toInt128(id) >= 9 AND toInt128(id) < 13",
+        ]
+    );
+
+    // No queries for an empty result.
+    mock.add(test::handlers::provide(vec![(0_i128, 0_i128, 0_u64)]));
+    let mut stream = client
+        .query("SELECT ?fields FROM t")
+        .fetch_partitioned::<SimpleRow>(3, Partitioning::range("id"));
+    assert_eq!(stream.next().await.unwrap(), None);
+
+    // The last range is unbounded if its end overflows.
+    let (min, max) = (i128::MAX - 2, i128::MAX);
+    mock.add(test::handlers::provide(vec![(min, max, 3_u64)]));
+    let recordings = [
+        mock.add(test::handlers::record_ddl()),
+        mock.add(test::handlers::record_ddl()),
+    ];
+
+    let mut stream = client
+        .query("SELECT ?fields FROM t")
+        .fetch_partitioned::<SimpleRow>(2, Partitioning::range("id"));
+    assert_eq!(stream.next().await.unwrap(), None);
+
+    let mut queries = Vec::new();
+    for recording in recordings {
+        queries.push(recording.query().await);
+    }
+    queries.sort();
+    assert_eq!(
+        queries,
+        [
+            format!(
+                "SELECT * FROM (SELECT `id`,`data` FROM t) WHERE toInt128(id) >= {min} AND toInt128(id) < {max}"
+            ),
+            format!("SELECT * FROM (SELECT `id`,`data` FROM t) WHERE toInt128(id) >= {max}"),
+        ]
+    );
+
+    // Too wide ranges are rejected.
+    mock.add(test::handlers::provide(vec![(i128::MIN, i128::MAX, 2_u64)]));
+    let mut stream = client
+        .query("SELECT ?fields FROM t")
+        .fetch_partitioned::<SimpleRow>(2, Partitioning::range("id"));
+    assert!(matches!(stream.next().await, Err(Error::InvalidParams(_))));
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn fetch_resumable() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = (1..=3).map(|id| SimpleRow::new(id, id)).collect::<Vec<_>>();
+
+    let recording = mock.add(test::handlers::record_ddl());
+    let mut stream = client
+        .query("SELECT ?fields FROM t ORDER BY id")
+        .fetch_resumable::<SimpleRow>();
+    assert_eq!(stream.next().await.unwrap(), None);
+    assert_eq!(
+        recording.query().await,
+        "SELECT `id`,`data` FROM t ORDER BY id"
+    );
+
+    mock.add(test::handlers::provide(rows.clone()));
+    let mut stream = client
+        .query("SELECT ?fields FROM t ORDER BY id")
+        .fetch_resumable::<SimpleRow>();
+
+    let mut actual = Vec::new();
+    while let Some(row) = stream.next().await.unwrap() {
+        actual.push(row);
+    }
+    assert_eq!(actual, rows);
+    assert_eq!(stream.rows(), 3);
+    assert_eq!(stream.resumptions(), 0);
+
+    // Other errors aren't retried.
+    mock.add(test::handlers::exception(241));
+    let mut stream = client
+        .query("SELECT ?fields FROM t ORDER BY id")
+        .fetch_resumable::<SimpleRow>();
+    assert!(stream.next().await.is_err());
+    assert_eq!(stream.resumptions(), 0);
+}
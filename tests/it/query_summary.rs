@@ -1,3 +1,8 @@
+use std::time::Duration;
+
+#[cfg(feature = "test-util")]
+use clickhouse::{Client, test};
+
 use crate::{SimpleRow, create_simple_table};
 
 #[tokio::test]
@@ -129,3 +134,46 @@ async fn summary_with_fetch_bytes() {
     assert_eq!(summary.result_rows(), Some(50));
     assert!(summary.elapsed_ns().unwrap() > 0);
 }
+
+#[tokio::test]
+async fn summary_query_cache_hit() {
+    let client = prepare_database!();
+
+    // A unique tag to avoid results cached by previous runs.
+    let tag = format!("{:x}", rand::random::<u64>());
+
+    for expected in [false, true] {
+        let mut cursor = client
+            .query("SELECT sum(number) FROM numbers(1000)")
+            .use_server_query_cache(Duration::from_secs(60), Some(&tag))
+            .with_setting("send_progress_in_http_headers", "1")
+            .with_setting("wait_end_of_query", "1")
+            .fetch::<u64>()
+            .unwrap();
+
+        assert_eq!(cursor.next().await.unwrap(), Some(499_500));
+        assert!(cursor.next().await.unwrap().is_none());
+
+        let summary = cursor.summary().expect("summary should be present");
+        assert_eq!(summary.query_cache_hit(), Some(expected));
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn summary_query_cache_hit_header() {
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+    let rows = vec![SimpleRow::new(1, "one")];
+
+    mock.add(test::handlers::provide_with_summary(
+        rows,
+        r#"{"read_rows":"0","read_bytes":"0","result_rows":"1","result_bytes":"8"}"#,
+    ));
+
+    let mut cursor = client.query("doesn't matter").fetch::<SimpleRow>().unwrap();
+    while cursor.next().await.unwrap().is_some() {}
+
+    let summary = cursor.summary().expect("summary should be present");
+    assert_eq!(summary.query_cache_hit(), Some(true));
+}
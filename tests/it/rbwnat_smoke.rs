@@ -92,6 +92,33 @@ async fn several_simple_rows() {
     assert_eq!(result, rows);
 }
 
+#[tokio::test]
+async fn newtype_rows() {
+    #[derive(Clone, Debug, Row, Serialize, Deserialize, PartialEq)]
+    struct Wrapper(SimpleRow);
+
+    #[derive(Clone, Debug, Row, Serialize, Deserialize, PartialEq)]
+    struct Id(u64);
+
+    let client = prepare_database!();
+    create_simple_table(&client, "test").await;
+
+    let rows = vec![
+        Wrapper(SimpleRow::new(42, "foo".to_string())),
+        Wrapper(SimpleRow::new(144, "bar".to_string())),
+    ];
+
+    let result = insert_and_select(&client, "test", rows.clone()).await;
+    assert_eq!(result, rows);
+
+    let ids = client
+        .query("SELECT id FROM test ORDER BY id")
+        .fetch_all::<Id>()
+        .await
+        .unwrap();
+    assert_eq!(ids, [Id(42), Id(144)]);
+}
+
 #[tokio::test]
 async fn many_numbers() {
     #[derive(Row, Serialize, Deserialize)]
@@ -287,6 +314,62 @@ async fn geo() {
     assert_eq!(result, rows);
 }
 
+#[tokio::test]
+async fn geo_newtypes() {
+    use clickhouse::types::{LineString, MultiLineString, MultiPolygon, Point, Polygon, Ring};
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[derive(Row, serde::Serialize, serde::Deserialize)]
+    struct Data {
+        id: u32,
+        point: Point,
+        ring: Ring,
+        polygon: Polygon,
+        multi_polygon: MultiPolygon,
+        line_string: LineString,
+        multi_line_string: MultiLineString,
+        points: Vec<Point>,
+    }
+
+    let client = prepare_database!();
+    client
+        .query(
+            "
+            CREATE TABLE IF NOT EXISTS test (
+                id                UInt32,
+                point             Point,
+                ring              Ring,
+                polygon           Polygon,
+                multi_polygon     MultiPolygon,
+                line_string       LineString,
+                multi_line_string MultiLineString,
+                points            Array(Point)
+            )
+            ENGINE = MergeTree
+            ORDER BY id
+            ",
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    let ring = Ring(vec![Point::new(3.0, 4.0), Point::new(5.0, 6.0)]);
+    let line = LineString(vec![Point::new(19.0, 20.0), Point::new(21.0, 22.0)]);
+    let rows = vec![Data {
+        id: 42,
+        point: Point::new(1.0, 2.0),
+        ring: ring.clone(),
+        polygon: Polygon(vec![ring.clone(), Ring(vec![Point::new(11.0, 12.0)])]),
+        multi_polygon: MultiPolygon(vec![Polygon(vec![ring])]),
+        line_string: line.clone(),
+        multi_line_string: MultiLineString(vec![line]),
+        points: vec![Point::new(7.0, 8.0)],
+    }];
+
+    let result = insert_and_select(&client, "test", rows.clone()).await;
+    assert_eq!(result, rows);
+}
+
 #[tokio::test]
 async fn maps() {
     #[derive(Clone, Debug, Row, Serialize, Deserialize, PartialEq)]
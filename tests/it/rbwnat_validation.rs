@@ -401,6 +401,23 @@ async fn geo_invalid_point() {
     );
 }
 
+#[tokio::test]
+async fn geo_newtype_mismatch() {
+    #[derive(Debug, Row, Serialize, Deserialize, PartialEq)]
+    struct Data {
+        id: u32,
+        ring: clickhouse::types::Ring,
+    }
+    assert_err_on_fetch!(
+        &["Data.ring", "Polygon", "clickhouse::types::Ring"],
+        "
+            SELECT
+                42                        :: UInt32  AS id,
+                [[(1.0, 2.0), (3.0, 4.0)]] :: Polygon AS ring
+        "
+    );
+}
+
 /// See https://github.com/ClickHouse/clickhouse-rs/issues/57
 #[tokio::test]
 async fn issue_57() {
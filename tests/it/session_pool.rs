@@ -0,0 +1,51 @@
+use clickhouse::pool::{SessionManager, SessionPool};
+
+use crate::get_client;
+
+#[tokio::test]
+async fn temporary_tables() {
+    let client = get_client();
+    let pool = SessionPool::builder(SessionManager::new(client))
+        .max_size(2)
+        .build()
+        .unwrap();
+
+    let session = pool.get().await.unwrap();
+    session
+        .query("CREATE TEMPORARY TABLE ids (id UInt64)")
+        .execute()
+        .await
+        .unwrap();
+    session
+        .query("INSERT INTO ids VALUES (1), (2)")
+        .execute()
+        .await
+        .unwrap();
+
+    // Another session doesn't see the table.
+    let other = pool.get().await.unwrap();
+    let err = other
+        .query("SELECT id FROM ids")
+        .fetch_all::<u64>()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("UNKNOWN_TABLE"), "{err}");
+    drop(other);
+
+    let ids = session
+        .query("SELECT id FROM ids ORDER BY id")
+        .fetch_all::<u64>()
+        .await
+        .unwrap();
+    assert_eq!(ids, [1, 2]);
+
+    // The session is kept with its state once returned to the pool.
+    drop(session);
+    let session = pool.get().await.unwrap();
+    let count = session
+        .query("SELECT count() FROM ids")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "test-util")]
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use clickhouse::{Client, test};
+use tracing::{
+    Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
+use tracing_subscriber::{
+    layer::{Context, Layer, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+/// Collects recorded fields of spans by names of spans.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<HashMap<String, HashMap<String, String>>>>);
+
+impl Recorder {
+    fn fields(&self, span: &str) -> HashMap<String, String> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(span)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+struct Visitor<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Visitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().into(), format!("{value:?}"));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        let mut spans = self.0.lock().unwrap();
+        let fields = spans.entry(attrs.metadata().name().into()).or_default();
+        attrs.record(&mut Visitor(fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let name = ctx.span(id).expect("unknown span").name();
+        let mut spans = self.0.lock().unwrap();
+        values.record(&mut Visitor(spans.entry(name.into()).or_default()));
+    }
+}
+
+#[tokio::test]
+async fn query_span() {
+    let recorder = Recorder::default();
+    let _guard = tracing_subscriber::registry()
+        .with(recorder.clone())
+        .set_default();
+
+    let mock = test::Mock::new();
+    let client = Client::default()
+        .with_mock(&mock)
+        .with_setting("query_id", "q1")
+        .with_statement_redaction(|_| "***".into());
+
+    let recording = mock.add(test::handlers::record_ddl());
+    client
+        .query("ALTER TABLE t DELETE WHERE token = ?")
+        .bind("secret")
+        .execute()
+        .await
+        .unwrap();
+
+    // Only reported statements are redacted.
+    assert_eq!(
+        recording.query().await,
+        "ALTER TABLE t DELETE WHERE token = 'secret'"
+    );
+
+    let fields = recorder.fields("clickhouse.query");
+    assert_eq!(
+        fields["db.query.text"],
+        "ALTER TABLE t DELETE WHERE token = ***"
+    );
+    assert_eq!(fields["clickhouse.request.query_id"], "q1");
+    assert!(fields.contains_key("clickhouse.query.duration"));
+}
+
+#[cfg(feature = "inserter")]
+#[tokio::test]
+async fn inserter_commit_span() {
+    use crate::SimpleRow;
+
+    let recorder = Recorder::default();
+    let _guard = tracing_subscriber::registry()
+        .with(recorder.clone())
+        .set_default();
+
+    let mock = test::Mock::new();
+    let client = Client::default().with_mock(&mock);
+
+    let recording = mock.add(test::handlers::record());
+    let mut inserter = client.inserter::<SimpleRow>("t");
+    inserter.write(&SimpleRow::new(1, "one")).await.unwrap();
+    inserter.write(&SimpleRow::new(2, "two")).await.unwrap();
+    inserter.end().await.unwrap();
+    let _: Vec<SimpleRow> = recording.collect().await;
+
+    let fields = recorder.fields("clickhouse.insert");
+    assert_eq!(
+        fields["db.query.text"],
+        "INSERT INTO `t`(`id`,`data`) FORMAT RowBinary"
+    );
+    assert!(fields.contains_key("clickhouse.query.duration"));
+
+    let fields = recorder.fields("clickhouse.inserter.commit");
+    assert_eq!(fields["db.collection.name"], "t");
+    assert_eq!(fields["clickhouse.inserter.rows"], "2");
+    assert!(fields.contains_key("clickhouse.query.duration"));
+}
@@ -0,0 +1,157 @@
+//! Timeouts are checked against fake servers that never finish responses
+//! or never read requests.
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use clickhouse::{Client, Compression, error::Error};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+/// Accepts connections, sends `response` and keeps connections open.
+/// Heads of requests are sent to the returned channel.
+async fn stalled_server(response: &'static [u8]) -> (Client, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut streams = Vec::<TcpStream>::new();
+
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(stream.read_u8().await.unwrap());
+            }
+
+            let _ = tx.send(String::from_utf8(head).unwrap());
+            stream.write_all(response).await.unwrap();
+            streams.push(stream);
+        }
+    });
+
+    // Responses are sent as is, so they must not be decompressed.
+    let client = Client::default()
+        .with_url(url)
+        .with_compression(Compression::None);
+    (client, rx)
+}
+
+#[tokio::test]
+async fn request_timeout() {
+    let (client, mut heads) = stalled_server(b"").await;
+
+    let err = client
+        .query("SELECT 1")
+        .with_timeout(Duration::from_millis(1500))
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::TimedOut), "{err:?}");
+
+    // Rounded up to seconds.
+    let head = heads.recv().await.unwrap();
+    assert!(head.contains("max_execution_time=2"), "{head}");
+
+    // The client-level default is used, unless the setting is set explicitly.
+    let client = client.with_request_timeout(Some(Duration::from_millis(100)));
+    let err = client
+        .query("SELECT 1")
+        .with_setting("max_execution_time", "60")
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::TimedOut), "{err:?}");
+
+    let head = heads.recv().await.unwrap();
+    assert!(head.contains("max_execution_time=60"), "{head}");
+    assert!(!head.contains("max_execution_time=1"), "{head}");
+}
+
+#[tokio::test]
+async fn read_timeout() {
+    let (client, _heads) =
+        stalled_server(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n")
+            .await;
+
+    let mut cursor = client
+        .with_read_timeout(Some(Duration::from_millis(100)))
+        .query("SELECT 1")
+        .fetch_bytes("RowBinary")
+        .unwrap();
+
+    assert_eq!(cursor.next().await.unwrap().unwrap(), "hello");
+    let err = cursor.next().await.unwrap_err();
+    assert!(matches!(err, Error::TimedOut), "{err:?}");
+
+    // The stream is terminated after the error.
+    assert!(cursor.next().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn no_timeouts() {
+    let (client, _heads) = stalled_server(b"").await;
+
+    let query = client.query("SELECT 1").execute();
+    let res = tokio::time::timeout(Duration::from_millis(100), query).await;
+    assert!(res.is_err(), "the query must not be finished: {res:?}");
+}
+
+#[tokio::test]
+async fn insert_stall_timeout() {
+    // Accepts connections, but never reads, like a frozen server.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    // Sent data must not shrink, so writes are blocked soon.
+    let client = Client::default()
+        .with_url(format!("http://{}", listener.local_addr().unwrap()))
+        .with_compression(Compression::None);
+    let server = tokio::spawn(async move {
+        let mut streams = Vec::new();
+        loop {
+            streams.push(listener.accept().await.unwrap());
+        }
+    });
+
+    // Stalled connections are closed instead of being stuck in writing.
+    let wait_closed = || async {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while client.pool_stats().unwrap().open != 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the stalled connection isn't closed");
+    };
+
+    // Stalled while data is sent.
+    let mut insert = client
+        .insert_formatted_with("INSERT INTO t FORMAT TSV")
+        .with_stall_timeout(Some(Duration::from_millis(200)));
+    let chunk = Bytes::from(vec![b'a'; 1 << 20]);
+    let err = loop {
+        if let Err(err) = insert.send(chunk.clone()).await {
+            break err;
+        }
+    };
+    assert!(matches!(err, Error::WriteStalled), "{err}");
+    drop(insert);
+    wait_closed().await;
+
+    // Stalled while the insert is ended.
+    let mut insert = client
+        .insert_formatted_with("INSERT INTO t FORMAT TSV")
+        .with_stall_timeout(Some(Duration::from_millis(200)));
+    insert
+        .send(Bytes::from(vec![b'a'; 64 << 20]))
+        .await
+        .unwrap();
+    let err = insert.end().await.unwrap_err();
+    assert!(matches!(err, Error::WriteStalled), "{err}");
+    wait_closed().await;
+
+    server.abort();
+}
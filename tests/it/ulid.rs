@@ -0,0 +1,86 @@
+#![cfg(feature = "ulid")]
+
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use clickhouse::Row;
+
+#[tokio::test]
+async fn smoke() {
+    let client = prepare_database!();
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Row)]
+    struct MyRow {
+        #[serde(with = "clickhouse::serde::ulid")]
+        ulid: Ulid,
+        #[serde(with = "clickhouse::serde::ulid::option")]
+        ulid_opt: Option<Ulid>,
+        #[serde(with = "clickhouse::serde::ulid::fixed_string")]
+        ulid_bytes: Ulid,
+        #[serde(with = "clickhouse::serde::ulid::fixed_string::option")]
+        ulid_bytes_opt: Option<Ulid>,
+    }
+
+    client
+        .query(
+            "
+            CREATE TABLE test(
+                ulid FixedString(26),
+                ulid_opt Nullable(FixedString(26)),
+                ulid_bytes FixedString(16),
+                ulid_bytes_opt Nullable(FixedString(16))
+            ) ENGINE = MergeTree ORDER BY ulid
+        ",
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    let ulid = Ulid::from_parts(1_700_000_000_000, 0x0123_4567_89AB_CDEF_0123);
+
+    let original_row = MyRow {
+        ulid,
+        ulid_opt: Some(ulid),
+        ulid_bytes: ulid,
+        ulid_bytes_opt: None,
+    };
+
+    let mut insert = client.insert::<MyRow>("test").await.unwrap();
+    insert.write(&original_row).await.unwrap();
+    insert.end().await.unwrap();
+
+    let (row, row_ulid_str) = client
+        .query("SELECT ?fields, toString(ulid) FROM test")
+        .fetch_one::<(MyRow, String)>()
+        .await
+        .unwrap();
+
+    assert_eq!(row, original_row);
+    assert_eq!(row_ulid_str, original_row.ulid.to_string());
+}
+
+#[tokio::test]
+async fn human_readable_smoke() {
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Row)]
+    struct MyRow {
+        #[serde(with = "clickhouse::serde::ulid")]
+        ulid: Ulid,
+        #[serde(with = "clickhouse::serde::ulid::fixed_string")]
+        ulid_bytes: Ulid,
+    }
+
+    let ulid = Ulid::from_string("01D39ZY06FGSCTVN4T2V9PKHFZ").unwrap();
+    let row = MyRow {
+        ulid,
+        ulid_bytes: ulid,
+    };
+
+    let json = serde_json::to_string(&row).unwrap();
+    assert_eq!(
+        json,
+        r#"{"ulid":"01D39ZY06FGSCTVN4T2V9PKHFZ","ulid_bytes":"01D39ZY06FGSCTVN4T2V9PKHFZ"}"#
+    );
+
+    let parsed: MyRow = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, row);
+}
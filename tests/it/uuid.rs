@@ -172,3 +172,48 @@ async fn vec_human_readable_smoke() {
     let new_row: OursRow = serde_json::from_str(&s2).unwrap();
     assert_eq!(new_row, row);
 }
+
+#[tokio::test]
+async fn fixed_string() {
+    let client = prepare_database!();
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Row)]
+    struct MyRow {
+        #[serde(with = "clickhouse::serde::uuid::fixed_string")]
+        uuid: Uuid,
+        #[serde(with = "clickhouse::serde::uuid::fixed_string::option")]
+        uuid_opt: Option<Uuid>,
+    }
+
+    client
+        .query(
+            "
+            CREATE TABLE test(
+                uuid FixedString(16),
+                uuid_opt Nullable(FixedString(16))
+            ) ENGINE = MergeTree ORDER BY uuid
+        ",
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    let uuid = Uuid::new_v4();
+    let original_row = MyRow {
+        uuid,
+        uuid_opt: None,
+    };
+
+    let mut insert = client.insert::<MyRow>("test").await.unwrap();
+    insert.write(&original_row).await.unwrap();
+    insert.end().await.unwrap();
+
+    let (row, row_uuid_str) = client
+        .query("SELECT ?fields, UUIDNumToString(uuid) FROM test")
+        .fetch_one::<(MyRow, String)>()
+        .await
+        .unwrap();
+
+    assert_eq!(row, original_row);
+    assert_eq!(row_uuid_str, original_row.uuid.to_string());
+}
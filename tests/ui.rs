@@ -4,10 +4,14 @@
 //! produces expected errors when used incorrectly. Test cases must be
 //! added to the `tests/ui/` directory (use existing ones as an example).
 //!
+//! Supported struct shapes are in the `tests/ui/pass/` directory,
+//! their `main()` checks the derived column names.
+//!
 //! Run with `TRYBUILD=overwrite` to update snapshots (*.stderr files).
 
 #[test]
 fn ui() {
     let t = trybuild::TestCases::new();
     t.compile_fail("tests/ui/*.rs");
+    t.pass("tests/ui/pass/*.rs");
 }
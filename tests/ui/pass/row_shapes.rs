@@ -0,0 +1,159 @@
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+
+#[derive(Row, Serialize, Deserialize)]
+struct Plain {
+    a: u32,
+    b: String,
+}
+
+#[derive(Row, Deserialize)]
+struct Borrowed<'a> {
+    a: &'a str,
+    b: &'a [u8],
+}
+
+#[derive(Row, Serialize, Deserialize)]
+struct Generic<T> {
+    id: u64,
+    value: T,
+}
+
+#[derive(Row, Deserialize)]
+struct GenericBorrowed<'a, T> {
+    name: &'a str,
+    value: T,
+}
+
+#[derive(Row, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Renamed {
+    first_name: String,
+    #[serde(rename = "LAST")]
+    last_name: String,
+    #[clickhouse(rename = "t.created_at")]
+    created_at: u32,
+}
+
+#[derive(Row, Serialize, Deserialize)]
+struct Skipped {
+    a: u32,
+    #[serde(skip)]
+    b: u32,
+    #[serde(skip_deserializing)]
+    c: u32,
+    #[clickhouse(skip_insert)]
+    d: u32,
+}
+
+#[derive(Row, Serialize, Deserialize)]
+#[clickhouse(nested)]
+struct Item {
+    name: String,
+    #[serde(rename = "count")]
+    qty: u32,
+}
+
+#[derive(Row, Serialize, Deserialize)]
+struct WithNested {
+    id: u32,
+    #[clickhouse(nested)]
+    #[serde(with = "clickhouse::serde::nested")]
+    items: Vec<Item>,
+}
+
+#[derive(Row, Serialize, Deserialize)]
+struct Audit {
+    created_by: String,
+}
+
+#[derive(Row, Serialize, Deserialize)]
+struct GenericBounded<T: Serialize + Clone>
+where
+    T: std::fmt::Debug,
+{
+    id: u64,
+    #[serde(bound = "T: Serialize + serde::de::DeserializeOwned")]
+    value: T,
+    #[clickhouse(flatten)]
+    audit: Audit,
+    #[clickhouse(nested)]
+    #[serde(with = "clickhouse::serde::nested")]
+    items: Vec<Item>,
+}
+
+#[derive(Row, Deserialize)]
+struct GenericOutliving<'a, T: 'a, U>
+where
+    U: 'a,
+{
+    name: &'a str,
+    value: T,
+    other: U,
+}
+
+#[derive(Row, Serialize, Deserialize)]
+struct Wrapper(Skipped);
+
+#[derive(Row, Serialize, Deserialize)]
+struct GenericWrapper<T>(T);
+
+#[derive(Row, Deserialize)]
+struct BorrowedWrapper<'a>(#[serde(borrow)] Borrowed<'a>);
+
+#[derive(Row, Serialize, Deserialize)]
+struct Id(u64);
+
+mod renamed_crate {
+    pub use clickhouse as ch;
+}
+
+#[derive(Row, Deserialize)]
+#[clickhouse(crate = "renamed_crate::ch")]
+struct CratePath {
+    a: u32,
+}
+
+fn main() {
+    assert_eq!(Plain::COLUMN_NAMES, ["a", "b"]);
+    assert_eq!(<Borrowed<'_> as Row>::COLUMN_NAMES, ["a", "b"]);
+    assert_eq!(<Generic<f64> as Row>::COLUMN_NAMES, ["id", "value"]);
+    assert_eq!(
+        <GenericBorrowed<'_, u8> as Row>::COLUMN_NAMES,
+        ["name", "value"]
+    );
+    assert_eq!(
+        <GenericBounded<u32> as Row>::COLUMN_NAMES,
+        ["id", "value", "created_by", "items.name", "items.count"]
+    );
+    assert_eq!(
+        <GenericOutliving<'_, u8, String> as Row>::COLUMN_NAMES,
+        ["name", "value", "other"]
+    );
+    assert_eq!(
+        Renamed::COLUMN_NAMES,
+        ["firstName", "LAST", "t.created_at"]
+    );
+
+    assert_eq!(Skipped::COLUMN_NAMES, ["a", "d"]);
+    assert_eq!(Skipped::INSERT_COLUMN_NAMES, ["a"]);
+
+    assert_eq!(
+        WithNested::COLUMN_NAMES,
+        ["id", "items.name", "items.count"]
+    );
+    assert_eq!(WithNested::COLUMN_COUNT, 3);
+
+    assert_eq!(Wrapper::NAME, "Wrapper");
+    assert_eq!(Wrapper::COLUMN_NAMES, ["a", "d"]);
+    assert_eq!(Wrapper::INSERT_COLUMN_NAMES, ["a"]);
+    assert_eq!(Wrapper::INSERT_SKIPPED_FIELDS, ["d"]);
+    assert_eq!(<GenericWrapper<Plain> as Row>::COLUMN_NAMES, ["a", "b"]);
+    assert_eq!(<GenericWrapper<(Plain, u64)> as Row>::COLUMN_COUNT, 3);
+    assert_eq!(<BorrowedWrapper<'_> as Row>::COLUMN_NAMES, ["a", "b"]);
+    assert_eq!(Id::COLUMN_NAMES, [] as [&str; 0]);
+    assert_eq!(Id::COLUMN_COUNT, 1);
+
+    assert_eq!(CratePath::NAME, "CratePath");
+    assert_eq!(CratePath::COLUMN_NAMES, ["a"]);
+}
@@ -0,0 +1,31 @@
+#[derive(clickhouse::Row)]
+#[clickhouse(unknown)]
+struct UnknownContainerArgument {
+    a: u32,
+}
+
+#[derive(clickhouse::Row)]
+struct UnknownFieldArgument {
+    #[clickhouse(skip)]
+    a: u32,
+}
+
+#[derive(clickhouse::Row)]
+struct DuplicateRename {
+    #[clickhouse(rename = "b", rename = "c")]
+    a: u32,
+}
+
+#[derive(clickhouse::Row)]
+struct RenameNotString {
+    #[clickhouse(rename = b)]
+    a: u32,
+}
+
+#[derive(clickhouse::Row)]
+#[clickhouse(crate = clickhouse)]
+struct CratePathNotString {
+    a: u32,
+}
+
+fn main() {}
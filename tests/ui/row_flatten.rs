@@ -0,0 +1,27 @@
+#[derive(serde::Deserialize)]
+struct Inner {
+    b: u32,
+}
+
+#[derive(clickhouse::Row, serde::Deserialize)]
+struct Flatten {
+    a: u32,
+    #[serde(flatten)]
+    inner: Inner,
+}
+
+#[derive(clickhouse::Row, serde::Deserialize)]
+struct FlattenRenamed {
+    a: u32,
+    #[clickhouse(flatten, rename = "inner")]
+    inner: Inner,
+}
+
+#[derive(clickhouse::Row, serde::Deserialize)]
+struct FlattenGeneric<T> {
+    a: u32,
+    #[clickhouse(flatten)]
+    inner: T,
+}
+
+fn main() {}
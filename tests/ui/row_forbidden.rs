@@ -16,4 +16,7 @@ pub struct UnitStruct;
 #[derive(clickhouse::Row)]
 pub struct EmptyStruct {}
 
+#[derive(clickhouse::Row)]
+pub struct NewtypeWithAttribute(#[clickhouse(rename = "a")] u32);
+
 fn main() {}
@@ -0,0 +1,19 @@
+#[derive(clickhouse::Row)]
+#[clickhouse(nested)]
+struct Generic<T> {
+    value: T,
+}
+
+#[derive(clickhouse::Row)]
+struct MissingWith {
+    #[clickhouse(nested)]
+    items: Vec<Item>,
+}
+
+#[derive(clickhouse::Row)]
+#[clickhouse(nested)]
+struct Item {
+    name: String,
+}
+
+fn main() {}
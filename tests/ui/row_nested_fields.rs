@@ -0,0 +1,26 @@
+#[derive(clickhouse::Row, serde::Serialize, serde::Deserialize)]
+#[clickhouse(nested)]
+struct SkipInsert {
+    #[clickhouse(skip_insert)]
+    a: u32,
+}
+
+#[derive(clickhouse::Row, serde::Serialize, serde::Deserialize)]
+#[clickhouse(nested)]
+struct With {
+    #[serde(with = "clickhouse::serde::ipv4")]
+    a: std::net::Ipv4Addr,
+}
+
+#[derive(clickhouse::Row, serde::Serialize, serde::Deserialize)]
+#[clickhouse(nested)]
+struct Unnamed(u32);
+
+#[derive(clickhouse::Row, serde::Serialize, serde::Deserialize)]
+#[clickhouse(nested)]
+struct AllSkipped {
+    #[serde(skip)]
+    a: u32,
+}
+
+fn main() {}